@@ -0,0 +1,98 @@
+//! The attribute macros of [carlog](https://docs.rs/carlog), re-exported by it with the `macros`
+//! feature. Use them through carlog, i.e. `#[carlog::timed]`, rather than from this crate.
+
+use proc_macro::{Delimiter, Group, Literal, TokenStream, TokenTree};
+
+/// Time every call of a function as a step, like wrapping its body in `carlog_time!`.
+///
+/// The step is named after the function, or after the string given to the attribute. It's
+/// reported when the call returns, whichever `return` or `?` it returns by, and follows the
+/// [slow](https://docs.rs/carlog/latest/carlog/fn.set_slow_threshold.html) and
+/// [warn](https://docs.rs/carlog/latest/carlog/fn.set_warn_threshold.html) thresholds. A call that
+/// panics isn't reported. The completion line goes to the default stream.
+///
+/// ## Example
+/// ```ignore
+/// #[carlog::timed]
+/// fn build_index() -> usize {
+///     // ...
+/// #   0
+/// }
+///
+/// #[carlog::timed("fetch-crates")]
+/// fn fetch(names: &[&str]) -> std::io::Result<()> {
+///     // ...
+/// #   Ok(())
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn timed(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut tokens: Vec<TokenTree> = item.clone().into_iter().collect();
+    let name = match step_name(attr, &tokens) {
+        Ok(name) => name,
+        Err(message) => return error(message, item),
+    };
+    let body = match tokens.last() {
+        Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body.clone(),
+        _ => {
+            return error(
+                "`#[carlog::timed]` only applies to functions with a body",
+                item,
+            )
+        }
+    };
+    let mut stream: Vec<TokenTree> = body.stream().into_iter().collect();
+    // The inner attributes of the body must stay at its start.
+    let mut start = 0;
+    while let [TokenTree::Punct(hash), TokenTree::Punct(bang), TokenTree::Group(attr), ..] =
+        &stream[start..]
+    {
+        if hash.as_char() != '#' || bang.as_char() != '!' || attr.delimiter() != Delimiter::Bracket
+        {
+            break;
+        }
+        start += 3;
+    }
+    let guard: TokenStream = format!("let __carlog_timed = ::carlog::__Timed::start({});", name)
+        .parse()
+        .unwrap();
+    let rest = stream.split_off(start);
+    let mut timed: TokenStream = stream.into_iter().collect();
+    timed.extend(guard);
+    timed.extend(rest);
+    let mut wrapped = Group::new(Delimiter::Brace, timed);
+    wrapped.set_span(body.span());
+    *tokens.last_mut().unwrap() = TokenTree::Group(wrapped);
+    tokens.into_iter().collect()
+}
+
+/// The string literal naming the step, from the attribute or from the name of the function.
+fn step_name(attr: TokenStream, item: &[TokenTree]) -> Result<Literal, &'static str> {
+    let mut attr = attr.into_iter();
+    match (attr.next(), attr.next()) {
+        (None, _) => {}
+        (Some(TokenTree::Literal(name)), None) if name.to_string().starts_with('"') => {
+            return Ok(name);
+        }
+        _ => return Err("expected the name of the step, i.e. `#[carlog::timed(\"build-index\")]`"),
+    }
+    let mut tokens = item.iter();
+    while let Some(token) = tokens.next() {
+        if matches!(token, TokenTree::Ident(ident) if ident.to_string() == "fn") {
+            if let Some(TokenTree::Ident(name)) = tokens.next() {
+                let name = name.to_string();
+                return Ok(Literal::string(name.trim_start_matches("r#")));
+            }
+        }
+    }
+    Err("`#[carlog::timed]` only applies to functions")
+}
+
+/// A `compile_error!` with `message`, followed by the item unchanged.
+fn error(message: &str, item: TokenStream) -> TokenStream {
+    let mut tokens: TokenStream = format!("::core::compile_error!({:?});", message)
+        .parse()
+        .unwrap();
+    tokens.extend(item);
+    tokens
+}
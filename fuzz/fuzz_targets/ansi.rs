@@ -0,0 +1,35 @@
+//! Feeds arbitrary text to everything that parses ANSI escape sequences, run with
+//! `cargo +nightly fuzz run ansi`. The seeds of `tests/fixtures/ansi-conformance.txt` make a good
+//! starting corpus.
+
+#![no_main]
+#![deny(warnings)]
+
+use carlog::ansi::{self, Token};
+use carlog::prelude::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    carlog::color::set_override(true);
+    carlog::max_message_len(64);
+    let mut output = Vec::new();
+    let _ = Status::new().status("out").print_bytes(&mut output, data);
+
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let rebuilt: String = ansi::tokens(text)
+        .map(|token| match token {
+            Token::Text(text) | Token::Escape(text) => text,
+        })
+        .collect();
+    assert_eq!(rebuilt, text);
+    assert!(!ansi::strip(text).contains('\u{1b}'));
+    for max in [0, 1, 7, 40] {
+        let _ = carlog::fmt::ellipsize_middle(text, max);
+    }
+    let _ = Status::new()
+        .justify()
+        .status(text)
+        .print(&mut output, text);
+});
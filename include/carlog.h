@@ -0,0 +1,38 @@
+/* C interface of carlog, available with the `ffi` feature. */
+
+#ifndef CARLOG_H
+#define CARLOG_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Let colors be decided from the terminal. */
+#define CARLOG_COLOR_AUTO 0
+/* Always print colors. */
+#define CARLOG_COLOR_ALWAYS 1
+/* Never print colors. */
+#define CARLOG_COLOR_NEVER 2
+
+/*
+ * Every function accepts null pointers, printed as empty strings, and invalid UTF-8, shown with
+ * the replacement character. They return 0 on success and -1 on failure.
+ */
+
+/* Print an info like cargo message to stdout, i.e. `   Compiling carlog v0.1.0`. */
+int carlog_info(const char *status, const char *msg);
+
+/* Print a warning like cargo message to stderr, i.e. `warning: unused variable`. */
+int carlog_warning(const char *msg);
+
+/* Print an error like cargo message to stderr, i.e. `error: could not compile`. */
+int carlog_error(const char *msg);
+
+/* Choose whether colors are printed: one of the CARLOG_COLOR_* values. */
+int carlog_set_color_choice(int choice);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* CARLOG_H */
@@ -0,0 +1,210 @@
+//! Cargo like completion summaries for batches of items.
+
+use crate::{fmt, CargoColor, CarlogStream, Error, Status};
+use std::time::{Duration, Instant};
+
+/// Aggregates a batch of items into a single cargo like completion line.
+///
+/// After recording every item, [`finish`](Self::finish) prints a summary like
+/// `  Downloaded 12 crates (3.4 MB) in 1.02s`. Nothing is printed when no item was recorded.
+///
+/// Items can optionally be reported one by one with the present tense verb as they are recorded,
+/// i.e. ` Downloading serde v1.0.0`.
+///
+/// ## Example
+/// ```
+/// use carlog::Aggregator;
+///
+/// let mut downloads = Aggregator::new(("Downloading", "Downloaded")).noun("crate");
+/// downloads.record("serde v1.0.0", 75_000).unwrap();
+/// downloads.record("colored v2.0.0", 25_000).unwrap();
+/// downloads.finish().unwrap(); // "  Downloaded 2 crates (100.0 KB) in 0.00s"
+/// ```
+pub struct Aggregator<'a> {
+    present: String,
+    past: String,
+    noun: String,
+    plural: Option<String>,
+    print_items: bool,
+    stream: CarlogStream<'a>,
+    count: usize,
+    bytes: u64,
+    start: Instant,
+    elapsed: Box<dyn Fn(Instant) -> Duration + 'a>,
+}
+
+impl<'a> Aggregator<'a> {
+    /// Create an aggregator from a (present, past) verb pair, i.e. `("Downloading", "Downloaded")`.
+    ///
+    /// The summary is printed to stdout and items are counted with the noun `item` unless told
+    /// otherwise.
+    pub fn new<P, D>((present, past): (P, D)) -> Self
+    where
+        P: AsRef<str>,
+        D: AsRef<str>,
+    {
+        Self {
+            present: present.as_ref().to_string(),
+            past: past.as_ref().to_string(),
+            noun: "item".to_string(),
+            plural: None,
+            print_items: false,
+            stream: CarlogStream::default(),
+            count: 0,
+            bytes: 0,
+            start: Instant::now(),
+            elapsed: Box::new(|start| start.elapsed()),
+        }
+    }
+
+    /// Set the noun of the aggregated items, i.e. `crate`.
+    ///
+    /// The plural is formed by appending an `s`, see [`plural`](Self::plural) for irregular nouns.
+    pub fn noun<S>(mut self, noun: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.noun = noun.as_ref().to_string();
+        self
+    }
+
+    /// Set the plural of the noun, i.e. `dependencies`.
+    pub fn plural<S>(mut self, plural: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.plural = Some(plural.as_ref().to_string());
+        self
+    }
+
+    /// Print a line with the present tense verb and the item name for every recorded item.
+    pub fn print_items(mut self) -> Self {
+        self.print_items = true;
+        self
+    }
+
+    /// Set the stream where the item lines and the summary are printed.
+    pub fn stream(mut self, stream: CarlogStream<'a>) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn clock<F>(mut self, elapsed: F) -> Self
+    where
+        F: Fn(Instant) -> Duration + 'a,
+    {
+        self.elapsed = Box::new(elapsed);
+        self
+    }
+
+    /// Record an item of the batch.
+    ///
+    /// * `name`: The name of the item, only printed when [`print_items`](Self::print_items) is set.
+    /// * `bytes`: The size of the item.
+    pub fn record<S>(&mut self, name: S, bytes: u64) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        self.count += 1;
+        self.bytes += bytes;
+        if self.print_items {
+            let status = Self::status(&self.present);
+            status.print_stream(&mut self.stream, format!(" {}", name.as_ref()))?;
+        }
+        Ok(())
+    }
+
+    /// Print the summary line of the batch, if any item was recorded.
+    pub fn finish(mut self) -> Result<(), Error> {
+        if self.count == 0 {
+            return Ok(());
+        }
+        let count = match &self.plural {
+            Some(plural) => fmt::count_with(self.count, &self.noun, plural),
+            None => fmt::count(self.count, &self.noun),
+        };
+        let msg = format!(
+            " {} ({}) in {}",
+            count,
+            fmt::bytes(self.bytes),
+            fmt::elapsed((self.elapsed)(self.start))
+        );
+        Self::status(&self.past).print_stream(&mut self.stream, msg)
+    }
+
+    fn status(verb: &str) -> Status {
+        Status::plain()
+            .bold()
+            .justify()
+            .color(CargoColor::Green)
+            .status(verb)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Aggregator;
+    use crate::test::{force_colors, global_lock};
+    use crate::CarlogStream;
+    use std::time::Duration;
+
+    fn downloads(output: &mut Vec<u8>) -> Aggregator<'_> {
+        Aggregator::new(("Downloading", "Downloaded"))
+            .noun("crate")
+            .stream(CarlogStream::Custom(output))
+            .clock(|_| Duration::from_millis(1020))
+    }
+
+    #[test]
+    fn test_summary() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        let mut aggregator = downloads(&mut output);
+        for n in 0..12 {
+            aggregator.record(format!("crate{}", n), 283_334).unwrap();
+        }
+        aggregator.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "  \u{1b}[1;32mDownloaded\u{1b}[0m 12 crates (3.4 MB) in 1.02s\n"
+        );
+    }
+
+    #[test]
+    fn test_single_and_empty() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        downloads(&mut output).finish().unwrap();
+        assert!(output.is_empty());
+        let mut aggregator = downloads(&mut output).print_items();
+        aggregator.record("serde v1.0.0", 512).unwrap();
+        aggregator.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            " \u{1b}[1;32mDownloading\u{1b}[0m serde v1.0.0\n  \u{1b}[1;32mDownloaded\u{1b}[0m \
+             1 crate (512 B) in 1.02s\n"
+        );
+    }
+
+    #[test]
+    fn test_irregular_plural() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        let mut aggregator = Aggregator::new(("Locking", "Locked"))
+            .noun("dependency")
+            .plural("dependencies")
+            .stream(CarlogStream::Custom(&mut output))
+            .clock(|_| Duration::from_millis(7));
+        aggregator.record("a", 0).unwrap();
+        aggregator.record("b", 0).unwrap();
+        aggregator.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "      \u{1b}[1;32mLocked\u{1b}[0m 2 dependencies (0 B) in 0.01s\n"
+        );
+    }
+}
@@ -0,0 +1,475 @@
+//! The tokenizer of ANSI escape sequences, shared by everything that strips, measures or cuts
+//! styled text.
+//!
+//! Text relayed from other processes can hold anything, so the tokenizer never fails: every input
+//! is split into text and escape sequences in linear time. The sequences recognized are:
+//! * CSI: `ESC [` or the 8-bit `U+009B`, then parameter bytes (`0` to `?`), intermediate bytes
+//!   (space to `/`) and a final byte (`@` to `~`), i.e. the colors `ESC [ 1 ; 3 2 m`.
+//! * OSC: `ESC ]` or `U+009D`, then a string up to a `BEL` or a string terminator, `ESC \` or
+//!   `U+009C`, i.e. the hyperlinks `ESC ] 8 ; ; https://crates.io ESC \`.
+//! * DCS, SOS, PM and APC: `ESC P`, `ESC X`, `ESC ^` and `ESC _` or `U+0090`, `U+0098`, `U+009E`
+//!   and `U+009F`, then a string up to a string terminator.
+//! * The other escapes: `ESC`, then intermediate bytes and a final byte (`0` to `~`), i.e. `ESC 7`.
+//! * The other 8-bit C1 controls, `U+0080` to `U+009F`, on their own.
+//!
+//! Malformed input is tokenized as follows:
+//! * A sequence truncated by the end of the text, i.e. `ESC [ 3 1`, takes the rest of the text.
+//! * A byte that can't continue a sequence, i.e. a `NUL`, another control character or a
+//!   non-ASCII character in a CSI, ends the sequence before it. The byte is then read as text or as
+//!   the start of the next sequence, so `ESC [ 3 NUL 1 m` is the escape `ESC [ 3` followed by the
+//!   text `NUL 1 m`.
+//! * An `ESC` that doesn't start a sequence, i.e. followed by `é`, is an escape on its own.
+//! * Parameter lists are never parsed into numbers, so an overlong list is just a long sequence.
+//! * `NUL` and the other C0 controls outside of sequences are text, without width.
+//!
+//! [`strip_ansi`] removes the sequences from a stream of bytes the same way, even when they are
+//! split between writes.
+//!
+//! The cases of `tests/fixtures/ansi-conformance.txt` pin this behavior down, and every feature
+//! using the tokenizer runs against them.
+//!
+//! ## Example
+//! ```
+//! use carlog::ansi::{self, Token};
+//!
+//! let tokens: Vec<Token> = ansi::tokens("\u{1b}[32mok\u{1b}[0").collect();
+//! assert_eq!(
+//!     tokens,
+//!     [Token::Escape("\u{1b}[32m"), Token::Text("ok"), Token::Escape("\u{1b}[0")]
+//! );
+//! assert_eq!(ansi::strip("\u{1b}[1mCompiled\u{1b}[0m carlog"), "Compiled carlog");
+//! ```
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+/// A piece of text, see [`tokens`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// Text without escape sequences, which may still hold other control characters.
+    Text(&'a str),
+    /// An escape sequence, whole or malformed.
+    Escape(&'a str),
+}
+
+/// The tokens of a text, see [`tokens`].
+#[derive(Clone, Debug)]
+pub struct Tokens<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let bytes = self.rest.as_bytes();
+        let (len, escape) = match sequence_len(bytes) {
+            Some(len) => (len, true),
+            None => {
+                let len = (1..bytes.len())
+                    .find(|&i| starts_sequence(&bytes[i..]))
+                    .unwrap_or(bytes.len());
+                (len, false)
+            }
+        };
+        // Sequences end on ASCII bytes or before the first byte of a character.
+        let (token, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Some(match escape {
+            true => Token::Escape(token),
+            false => Token::Text(token),
+        })
+    }
+}
+
+/// Split a text into its escape sequences and the text between them, see the [module](self).
+///
+/// The tokens put together give the text back.
+pub fn tokens(text: &str) -> Tokens<'_> {
+    Tokens { rest: text }
+}
+
+/// The text without its escape sequences.
+pub fn strip(text: &str) -> Cow<'_, str> {
+    if !contains_escape(text.as_bytes()) {
+        return Cow::Borrowed(text);
+    }
+    let mut plain = String::with_capacity(text.len());
+    for token in tokens(text) {
+        if let Token::Text(text) = token {
+            plain.push_str(text);
+        }
+    }
+    Cow::Owned(plain)
+}
+
+/// Whether `bytes` hold an escape sequence.
+pub(crate) fn contains_escape(bytes: &[u8]) -> bool {
+    (0..bytes.len()).any(|i| starts_sequence(&bytes[i..]))
+}
+
+/// The bounds of the escape sequence that `pos` falls inside of, if any, i.e. to cut `bytes` at
+/// `pos` without splitting a sequence.
+///
+/// `bytes` needn't be UTF-8, the sequences are found the same way.
+pub(crate) fn sequence_around(bytes: &[u8], pos: usize) -> Option<(usize, usize)> {
+    let mut i = 0;
+    while i < pos {
+        match sequence_len(&bytes[i..]) {
+            Some(len) if i + len > pos => return Some((i, i + len)),
+            Some(len) => i += len,
+            None => i += 1,
+        }
+    }
+    None
+}
+
+/// Whether `bytes` start with an `ESC` or an 8-bit C1 control.
+fn starts_sequence(bytes: &[u8]) -> bool {
+    matches!(bytes, [ESC, ..] | [0xc2, 0x80..=0x9f, ..])
+}
+
+/// The length of the escape sequence at the start of `bytes`, if any.
+fn sequence_len(bytes: &[u8]) -> Option<usize> {
+    let len = match bytes {
+        [ESC, b'[', rest @ ..] | [0xc2, 0x9b, rest @ ..] => 2 + csi(rest),
+        [ESC, b']', rest @ ..] | [0xc2, 0x9d, rest @ ..] => 2 + string(rest, true),
+        [ESC, b'P' | b'X' | b'^' | b'_', rest @ ..] => 2 + string(rest, false),
+        [0xc2, 0x90 | 0x98 | 0x9e | 0x9f, rest @ ..] => 2 + string(rest, false),
+        [ESC, rest @ ..] => 1 + escape(rest),
+        [0xc2, 0x80..=0x9f, ..] => 2,
+        _ => return None,
+    };
+    Some(len)
+}
+
+/// The length of the rest of a CSI sequence.
+fn csi(bytes: &[u8]) -> usize {
+    for (i, &byte) in bytes.iter().enumerate() {
+        match byte {
+            0x20..=0x3f => {}
+            0x40..=0x7e => return i + 1,
+            _ => return i,
+        }
+    }
+    bytes.len()
+}
+
+/// The length of the rest of a string sequence, ended by a `BEL` too if `bel`.
+fn string(bytes: &[u8], bel: bool) -> usize {
+    for (i, &byte) in bytes.iter().enumerate() {
+        let next = bytes.get(i + 1).copied();
+        match (byte, next) {
+            (BEL, _) if bel => return i + 1,
+            (ESC, Some(b'\\')) | (0xc2, Some(0x9c)) => return i + 2,
+            // A terminator cut by the end of the text.
+            (ESC | 0xc2, None) => return bytes.len(),
+            (0xc2, Some(0x80..=0x9f)) | (0x00..=0x1f | 0x7f, _) => return i,
+            _ => {}
+        }
+    }
+    bytes.len()
+}
+
+/// The length of the rest of an escape sequence other than CSI and the strings.
+fn escape(bytes: &[u8]) -> usize {
+    for (i, &byte) in bytes.iter().enumerate() {
+        match byte {
+            0x20..=0x2f => {}
+            0x30..=0x7e => return i + 1,
+            _ => return i,
+        }
+    }
+    bytes.len()
+}
+
+/// A writer removing the escape sequences of everything written through it, see [`strip_ansi`].
+#[derive(Debug)]
+pub struct StripAnsi<W> {
+    writer: W,
+    /// The start of an escape sequence cut by the end of the last write.
+    pending: Vec<u8>,
+}
+
+/// Wrap `writer` so the escape sequences written through it are removed, i.e. to print colored
+/// lines to a socket that doesn't want them.
+///
+/// A sequence split between writes is held back until it ends, so it's removed whole. The bytes
+/// of a sequence that never ends aren't written.
+///
+/// ## Example
+/// ```
+/// use std::io::Write;
+///
+/// let mut plain = carlog::strip_ansi(Vec::<u8>::new());
+/// plain.write_all(b"\x1b[1;32mCompiled\x1b[").unwrap();
+/// plain.write_all(b"0m carlog v0.1.0\n").unwrap();
+/// assert_eq!(plain.into_inner(), b"Compiled carlog v0.1.0\n");
+/// ```
+pub fn strip_ansi<W>(writer: W) -> StripAnsi<W>
+where
+    W: Write,
+{
+    StripAnsi {
+        writer,
+        pending: Vec::new(),
+    }
+}
+
+impl<W> StripAnsi<W> {
+    /// The wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W> Write for StripAnsi<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut bytes = std::mem::take(&mut self.pending);
+        bytes.extend_from_slice(buf);
+        let mut plain = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let rest = &bytes[i..];
+            match sequence_len(rest) {
+                Some(len) if len == rest.len() && !complete(rest) => break,
+                Some(len) => i += len,
+                // The first byte of an 8-bit control or of a character.
+                None if rest == [0xc2] => break,
+                None => {
+                    plain.push(rest[0]);
+                    i += 1;
+                }
+            }
+        }
+        self.pending = bytes.split_off(i);
+        self.writer.write_all(&plain)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Whether the sequence taking the whole of `bytes` ends there, so that no byte written next
+/// could change it.
+fn complete(bytes: &[u8]) -> bool {
+    // A parameter, an intermediate and a string byte continue the unfinished sequences.
+    [b'0', b' ', b'a'].into_iter().all(|next| {
+        let continued = [bytes, &[next]].concat();
+        sequence_len(&continued) == Some(bytes.len())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{sequence_around, strip, strip_ansi, tokens, Token};
+    use crate::fmt;
+    use std::collections::HashSet;
+    use std::io::Write;
+
+    /// The conformance cases, see the header of the file for the format.
+    const CORPUS: &str = include_str!("../tests/fixtures/ansi-conformance.txt");
+
+    struct Case {
+        name: String,
+        input: String,
+        tokens: Vec<Token<'static>>,
+        width: usize,
+    }
+
+    /// Split a field at its unescaped `|`.
+    fn split(field: &str) -> Vec<String> {
+        let mut parts = vec![String::new()];
+        let mut chars = field.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    let last = parts.last_mut().unwrap();
+                    last.push(c);
+                    last.extend(chars.next());
+                }
+                '|' => parts.push(String::new()),
+                _ => parts.last_mut().unwrap().push(c),
+            }
+        }
+        parts
+    }
+
+    fn unescape(text: &str) -> String {
+        let mut unescaped = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            match chars.next().unwrap() {
+                'e' => unescaped.push('\u{1b}'),
+                '0' => unescaped.push('\0'),
+                't' => unescaped.push('\t'),
+                'n' => unescaped.push('\n'),
+                'x' => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    unescaped.push(char::from(u8::from_str_radix(&hex, 16).unwrap()));
+                }
+                'u' => {
+                    let hex: String = chars.by_ref().take_while(|&c| c != '}').skip(1).collect();
+                    let code = u32::from_str_radix(&hex, 16).unwrap();
+                    unescaped.push(char::from_u32(code).unwrap());
+                }
+                c => unescaped.push(c),
+            }
+        }
+        unescaped
+    }
+
+    fn corpus() -> Vec<Case> {
+        let lines = CORPUS
+            .lines()
+            .filter(|l| !l.is_empty() && !l.starts_with('#'));
+        lines
+            .map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                let [name, input, tokens, width] = fields[..] else {
+                    panic!("malformed case: {}", line);
+                };
+                let tokens = split(tokens)
+                    .iter()
+                    .map(|token| {
+                        let text: &'static str = unescape(&token[1..]).leak();
+                        match &token[..1] {
+                            "E" => Token::Escape(text),
+                            "T" => Token::Text(text),
+                            kind => panic!("unknown token kind {} in {}", kind, name),
+                        }
+                    })
+                    .collect();
+                Case {
+                    name: name.to_string(),
+                    input: unescape(input),
+                    tokens,
+                    width: width.parse().unwrap(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        for case in corpus() {
+            let tokens: Vec<Token> = tokens(&case.input).collect();
+            assert_eq!(tokens, case.tokens, "{}", case.name);
+            let plain: String = tokens
+                .iter()
+                .filter_map(|token| match token {
+                    Token::Text(text) => Some(*text),
+                    Token::Escape(_) => None,
+                })
+                .collect();
+            assert_eq!(strip(&case.input), plain, "{}", case.name);
+            assert_eq!(fmt::strip_ansi(&case.input), plain, "{}", case.name);
+        }
+    }
+
+    #[test]
+    fn test_consumers() {
+        for case in corpus() {
+            let (input, name) = (&case.input, &case.name);
+            assert_eq!(fmt::display_width(input), case.width, "{}", name);
+            let pieces = fmt::pieces(input);
+            assert_eq!(pieces.iter().map(|(p, _)| *p).collect::<String>(), *input);
+            assert_eq!(pieces.iter().map(|(_, w)| w).sum::<usize>(), case.width);
+            assert_eq!(
+                strip(&fmt::expand_tabs(input, 4)),
+                fmt::expand_tabs(&strip(input), 4),
+                "{}",
+                name
+            );
+            for max in 0..=case.width + 1 {
+                let shortened = fmt::ellipsize_middle(input, max);
+                assert!(fmt::display_width(&shortened) <= max, "{} at {}", name, max);
+            }
+            // The cuts keep the sequences whole.
+            let escapes: HashSet<&str> = case
+                .tokens
+                .iter()
+                .filter_map(|token| match token {
+                    Token::Escape(escape) => Some(*escape),
+                    Token::Text(_) => None,
+                })
+                .chain(["\u{1b}[0m"])
+                .collect();
+            for max in 0..input.len() {
+                let truncated = fmt::truncate_middle(input.as_bytes(), max);
+                let truncated = std::str::from_utf8(&truncated).unwrap();
+                for token in tokens(truncated) {
+                    if let Token::Escape(escape) = token {
+                        assert!(escapes.contains(escape), "{} at {}", name, max);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_adversarial() {
+        // Inputs that would take quadratic time with a tokenizer that rescans.
+        for input in [
+            "\u{1b}[".repeat(100_000),
+            format!("\u{1b}[{}", "1;".repeat(200_000)),
+            format!("\u{1b}]{}", "x".repeat(200_000)),
+            "\u{1b}".repeat(200_000),
+            "\u{9b}\0".repeat(100_000),
+        ] {
+            let count = tokens(&input).count();
+            assert!(count > 0);
+            assert!(strip(&input).len() < input.len());
+            sequence_around(input.as_bytes(), input.len() / 2);
+            fmt::truncate_middle(input.as_bytes(), input.len() / 3);
+            fmt::ellipsize_middle(&input, 40);
+        }
+    }
+
+    #[test]
+    fn test_bytes() {
+        // Not UTF-8, i.e. what `print_bytes` relays.
+        let bytes = b"\xff\x1b[31m\xfe\x1b[0";
+        assert_eq!(sequence_around(bytes, 3), Some((1, 6)));
+        assert_eq!(sequence_around(bytes, 6), None);
+        assert_eq!(sequence_around(bytes, 9), Some((7, 10)));
+    }
+
+    #[test]
+    fn test_strip_writer() {
+        let line = "   \u{1b}[1;32mCompiling\u{1b}[0m \u{1b}]8;;https://crates.io\u{1b}\\carlog\u{1b}]8;;\u{1b}\\ \u{9b}2mv0.1.0\n";
+        let mut plain = strip_ansi(Vec::<u8>::new());
+        for byte in line.bytes() {
+            plain.write_all(&[byte]).unwrap();
+        }
+        plain.flush().unwrap();
+        assert_eq!(plain.into_inner(), b"   Compiling carlog v0.1.0\n");
+
+        for case in corpus() {
+            let input = case.input.as_bytes();
+            for size in [1, 2, 3, 7] {
+                let mut plain = strip_ansi(Vec::<u8>::new());
+                for chunk in input.chunks(size) {
+                    assert_eq!(plain.write(chunk).unwrap(), chunk.len());
+                }
+                let plain = String::from_utf8(plain.into_inner()).unwrap();
+                assert_eq!(plain, strip(&case.input), "{} by {}", case.name, size);
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Printing to tokio `AsyncWrite` streams.
+//!
+//! Enabled with the `async` feature.
+
+use crate::{Loggable, Status};
+use std::io::{self, Write};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+impl Status {
+    /// Print the status to the specified async stream.
+    ///
+    /// The line is rendered exactly like [`print`](Self::print) before anything is written, and
+    /// written at once, so cancelling the future never leaves half a line behind unless the
+    /// stream itself accepted part of the write.
+    ///
+    /// `stream`: The stream where the status and message will be written.
+    /// `msg`: The message to be printed alongside the status, see [`Loggable`].
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
+    /// let mut output = Vec::<u8>::new();
+    /// status.print_async(&mut output, "carlog v0.1.0").await.unwrap();
+    /// # });
+    /// ```
+    pub async fn print_async<W, S>(&self, stream: &mut W, msg: S) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin + ?Sized,
+        S: Loggable,
+    {
+        let line = self.line(&msg.to_log_string());
+        stream.write_all(line.as_bytes()).await?;
+        stream.flush().await
+    }
+
+    /// Print the status to the async stdout of tokio.
+    ///
+    /// `msg`: The message to be printed alongside the status.
+    pub async fn print_stdout_async<S>(&self, msg: S) -> io::Result<()>
+    where
+        S: Loggable,
+    {
+        self.print_async(&mut tokio::io::stdout(), msg).await
+    }
+
+    /// Print the status to the async stderr of tokio.
+    ///
+    /// `msg`: The message to be printed alongside the status.
+    pub async fn print_stderr_async<S>(&self, msg: S) -> io::Result<()>
+    where
+        S: Loggable,
+    {
+        self.print_async(&mut tokio::io::stderr(), msg).await
+    }
+}
+
+/// A blocking stream that hands its output to an async task.
+///
+/// Writing never blocks: the bytes are sent through a channel to the [`AsyncDrain`], which writes
+/// them to an `AsyncWrite` stream. Every [`Status::print`] is a single write, so lines stay whole.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::AsyncSink;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let (mut sink, drain) = AsyncSink::new();
+/// let task = tokio::spawn(drain.drain(tokio::io::stderr()));
+/// let status = Status::new().bold().justify().color(CargoColor::Cyan).status("Compiling");
+/// status.print(&mut sink, " carlog v0.1.0").unwrap();
+/// drop(sink);
+/// task.await.unwrap().unwrap();
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct AsyncSink {
+    sender: UnboundedSender<Vec<u8>>,
+}
+
+/// The receiving end of an [`AsyncSink`].
+pub struct AsyncDrain {
+    receiver: UnboundedReceiver<Vec<u8>>,
+}
+
+impl AsyncSink {
+    /// Create a sink and the drain that receives its output.
+    pub fn new() -> (Self, AsyncDrain) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, AsyncDrain { receiver })
+    }
+}
+
+impl Write for AsyncSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.sender.send(buf.to_vec()).map_err(|_| {
+            io::Error::new(io::ErrorKind::BrokenPipe, "the async drain was dropped")
+        })?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsyncDrain {
+    /// Write everything sent to the sink to `stream`, until every clone of the sink is dropped.
+    pub async fn drain<W>(mut self, mut stream: W) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        while let Some(bytes) = self.receiver.recv().await {
+            stream.write_all(&bytes).await?;
+            stream.flush().await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AsyncSink;
+    use crate::test::{force_colors, global_lock};
+    use crate::{CargoColor, Status};
+    use tokio::io::AsyncReadExt;
+
+    fn status() -> Status {
+        Status::new()
+            .bold()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Compiled")
+    }
+
+    // The runtime of the test is single threaded, the lock is only held against other tests.
+    #[allow(clippy::await_holding_lock)]
+    #[tokio::test]
+    async fn test_print_async() {
+        let _lock = global_lock();
+        force_colors();
+        let (mut writer, mut reader) = tokio::io::duplex(64);
+        status()
+            .print_async(&mut writer, "carlog v0.1.0")
+            .await
+            .unwrap();
+        drop(writer);
+        let mut output = Vec::<u8>::new();
+        reader.read_to_end(&mut output).await.unwrap();
+        let mut expected = Vec::<u8>::new();
+        status().print(&mut expected, "carlog v0.1.0").unwrap();
+        assert_eq!(output, expected);
+    }
+
+    // The runtime of the test is single threaded, the lock is only held against other tests.
+    #[allow(clippy::await_holding_lock)]
+    #[tokio::test]
+    async fn test_sink() {
+        let _lock = global_lock();
+        force_colors();
+        let (mut sink, drain) = AsyncSink::new();
+        let compiling = Status::new().color(CargoColor::Cyan).status("Compiling");
+        compiling.print(&mut sink, " a").unwrap();
+        let mut clone = sink.clone();
+        status().print(&mut clone, " b").unwrap();
+        drop((sink, clone));
+        let mut output = Vec::<u8>::new();
+        drain.drain(&mut output).await.unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\u{1b}[36mCompiling\u{1b}[0m a\n    \u{1b}[1;32mCompiled\u{1b}[0m b\n"
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! The text attributes of a status, i.e. bold or underlined.
+
+use std::fmt::{self, Debug};
+use std::ops::{BitOr, BitOrAssign};
+
+/// A set of text attributes, combined with `|`.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub(crate) struct Attributes(u8);
+
+impl Attributes {
+    pub(crate) const NONE: Self = Self(0);
+    pub(crate) const BOLD: Self = Self(1);
+    pub(crate) const DIMMED: Self = Self(1 << 1);
+    pub(crate) const ITALIC: Self = Self(1 << 2);
+    pub(crate) const UNDERLINE: Self = Self(1 << 3);
+    pub(crate) const STRIKETHROUGH: Self = Self(1 << 4);
+
+    /// Every attribute with its name, in the order of their codes.
+    const ALL: [(Self, &'static str); 5] = [
+        (Self::BOLD, "BOLD"),
+        (Self::DIMMED, "DIMMED"),
+        (Self::ITALIC, "ITALIC"),
+        (Self::UNDERLINE, "UNDERLINE"),
+        (Self::STRIKETHROUGH, "STRIKETHROUGH"),
+    ];
+
+    /// Whether every attribute of `other` is set.
+    pub(crate) fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// The SGR codes of the attributes, in increasing order.
+    pub(crate) fn codes(self) -> impl Iterator<Item = &'static str> {
+        Self::ALL
+            .into_iter()
+            .filter(move |(attribute, _)| self.contains(*attribute))
+            .map(|(attribute, _)| match attribute {
+                Self::BOLD => "1",
+                Self::DIMMED => "2",
+                Self::ITALIC => "3",
+                Self::UNDERLINE => "4",
+                _ => "9",
+            })
+    }
+}
+
+impl BitOr for Attributes {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for Attributes {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+impl Debug for Attributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = Self::ALL
+            .iter()
+            .filter(|(attribute, _)| self.contains(*attribute))
+            .map(|(_, name)| *name)
+            .collect();
+        match names.is_empty() {
+            true => write!(f, "Attributes(NONE)"),
+            false => write!(f, "Attributes({})", names.join(" | ")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Attributes;
+    use crate::color::{paint, write_painted, Shade};
+    use colored::{Color, Colorize};
+
+    #[test]
+    fn test_attributes() {
+        let _lock = crate::test::global_lock();
+        crate::test::force_colors();
+        let attributes = Attributes::BOLD | Attributes::UNDERLINE;
+        assert!(attributes.contains(Attributes::BOLD));
+        assert!(!attributes.contains(Attributes::BOLD | Attributes::DIMMED));
+        assert!(attributes.contains(Attributes::NONE));
+        assert_eq!(format!("{:?}", attributes), "Attributes(BOLD | UNDERLINE)");
+        assert_eq!(format!("{:?}", Attributes::NONE), "Attributes(NONE)");
+        let all = Attributes::ALL
+            .iter()
+            .fold(Attributes::NONE, |all, (attribute, _)| all | *attribute);
+        let painted = |attributes| {
+            let mut line = String::new();
+            write_painted(&mut line, "a", attributes, Shade::Named(Color::Green));
+            line
+        };
+        assert_eq!(painted(all), "\u{1b}[1;2;3;4;9;32ma\u{1b}[0m");
+        assert_eq!(painted(Attributes::NONE), paint("a".green()));
+    }
+}
@@ -0,0 +1,113 @@
+//! Startup banner with the name, version and build information of the application.
+
+use crate::{color, theme, CarlogStream, Error, Level, Metadata, Status};
+use colored::Colorize;
+
+/// Print the banner of the application to stdout.
+///
+/// See [`banner_to`].
+pub fn banner(metadata: Metadata) -> Result<(), Error> {
+    banner_to(&metadata, CarlogStream::default())
+}
+
+/// Print the banner of the application to the specified stream.
+///
+/// The status line shows the name in bold with the [theme](crate::theme) color of info messages,
+/// followed by the version. The build information is listed underneath, dimmed, with the values
+/// aligned. The banner is printed at once, without other threads printing in between.
+///
+/// Nothing is printed when [info messages are suppressed](crate::set_max_level), i.e. in quiet
+/// mode. Structured logs can keep the banner as a single record, see [`Metadata::record`].
+///
+/// * `metadata`: The application to describe.
+/// * `stream`: The stream where the banner will be written.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::Metadata;
+///
+/// let metadata = Metadata::new("mytool", "1.2.0").git_hash("3f2a91c").build_date("2024-05-01");
+/// let mut output = Vec::<u8>::new();
+/// carlog::banner_to(&metadata, CarlogStream::Custom(&mut output)).unwrap();
+/// ```
+pub fn banner_to(metadata: &Metadata, mut stream: CarlogStream<'_>) -> Result<(), Error> {
+    if !crate::__enabled(Level::Info) {
+        return Ok(());
+    }
+    let details = metadata.details();
+    let width = details
+        .iter()
+        .map(|(key, _)| key.len() + 1)
+        .max()
+        .unwrap_or(0);
+    let mut message = format!(" {}", metadata.version);
+    for (key, value) in details {
+        let line = format!("{:<width$} {}", format!("{}:", key), value, width = width);
+        message.push_str(&format!("\n{:13}{}", "", color::paint(line.dimmed())));
+    }
+    Status::plain()
+        .bold()
+        .justify()
+        .color(theme().info)
+        .status(&metadata.name)
+        .print_stream(&mut stream, message)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::banner_to;
+    use crate::test::{auto_colors, force_colors, global_lock};
+    use crate::{set_max_level, CarlogStream, Level, Metadata};
+
+    fn banner(metadata: &Metadata) -> String {
+        let mut output = Vec::<u8>::new();
+        banner_to(metadata, CarlogStream::Custom(&mut output)).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_banner() {
+        let _lock = global_lock();
+        auto_colors();
+        let full = Metadata::new("mytool", "1.2.0")
+            .git_hash("3f2a91c")
+            .build_date("2024-05-01")
+            .extra("target", "x86_64-unknown-linux-gnu");
+        let minimal = Metadata::new("mytool", "1.2.0");
+        let outputs = [banner(&full), banner(&minimal)];
+        set_max_level(Level::Warning);
+        let quiet = banner(&full);
+        crate::settings::reset();
+        force_colors();
+        assert_eq!(
+            outputs[0],
+            "      mytool 1.2.0\n\
+             \x20            git hash:   3f2a91c\n\
+             \x20            build date: 2024-05-01\n\
+             \x20            target:     x86_64-unknown-linux-gnu\n"
+        );
+        assert_eq!(outputs[1], "      mytool 1.2.0\n");
+        assert_eq!(quiet, "");
+        assert_eq!(
+            banner(&minimal.git_hash("3f2a91c")),
+            "      \u{1b}[1;36mmytool\u{1b}[0m 1.2.0\n\
+             \x20            \u{1b}[2mgit hash: 3f2a91c\u{1b}[0m\n"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_structured() {
+        let metadata = Metadata::new("mytool", "1.2.0")
+            .build_date("2024-05-01")
+            .extra("target", "x86_64");
+        assert_eq!(
+            crate::wire::encode(&metadata.record()),
+            r#"{"format_version":1,"status":"mytool","message":"1.2.0","level":"Info","#
+                .to_string()
+                + r#""fields":{"build_date":"2024-05-01","target":"x86_64"}}"#
+        );
+    }
+}
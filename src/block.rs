@@ -0,0 +1,178 @@
+//! Re-emission of captured multi-line text under a status.
+
+use crate::{color, fmt, output, Status, Target};
+use colored::Colorize;
+use std::io::{self, stdout, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static MAX_LINES: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// How the lines of a block are shown.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum BlockStyle {
+    /// Dimmed lines indented by four spaces.
+    #[default]
+    Dimmed,
+    /// Lines prefixed with `> `.
+    Quoted,
+    /// Lines prefixed with a rustc like `  | ` gutter.
+    Gutter,
+    /// Lines indented under the message of a justified status, keeping their own colors.
+    Indented,
+}
+
+impl BlockStyle {
+    fn line(self, line: &str) -> String {
+        match self {
+            BlockStyle::Dimmed => format!("    {}", color::paint(line.dimmed())),
+            BlockStyle::Quoted => format!("> {}", line),
+            BlockStyle::Gutter => format!("  {} {}", color::paint("|".bold()), line),
+            BlockStyle::Indented => format!("{:13}{}", "", line),
+        }
+    }
+}
+
+/// Only show the first and last lines of blocks longer than `max`, replacing the others with a
+/// line like `… 214 lines omitted …`.
+///
+/// `None`, the default, shows every line.
+pub fn set_block_max_lines(max: Option<usize>) {
+    MAX_LINES.store(max.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+/// The number of lines above which blocks are elided, see [`set_block_max_lines`].
+pub(crate) fn block_max_lines() -> Option<usize> {
+    match MAX_LINES.load(Ordering::Relaxed) {
+        usize::MAX => None,
+        max => Some(max),
+    }
+}
+
+/// Print a status followed by a block of text to stdout.
+///
+/// See [`emit_block_to`].
+pub fn emit_block(status: &Status, text: &str, style: BlockStyle) -> io::Result<()> {
+    let _output = output::lock();
+    color::with_target(Target::Stdout, || {
+        emit_block_to(stdout().lock(), status, text, style)
+    })
+}
+
+/// Print a status followed by a block of text, i.e. the captured output of a process, to the
+/// specified stream.
+///
+/// The status is printed on its own line and every line of the text below it, in the given style.
+/// The whole block is written at once so it isn't interleaved with other output.
+///
+/// * `stream`: The stream where the block will be written.
+/// * `status`: The status printed before the block.
+/// * `text`: The text of the block.
+/// * `style`: How the lines of the block are shown.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::BlockStyle;
+///
+/// let status = Status::new().color(CargoColor::Red).status("error: build script failed");
+/// let mut output = Vec::<u8>::new();
+/// carlog::emit_block_to(&mut output, &status, "line 1\nline 2", BlockStyle::Gutter).unwrap();
+/// ```
+pub fn emit_block_to<W>(
+    mut stream: W,
+    status: &Status,
+    text: &str,
+    style: BlockStyle,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    let mut block = status.prefix();
+    block.push('\n');
+    let lines: Vec<&str> = text.lines().collect();
+    let (head, omitted) = elide(lines.len(), MAX_LINES.load(Ordering::Relaxed));
+    for line in &lines[..head] {
+        block.push_str(&style.line(line));
+        block.push('\n');
+    }
+    if omitted > 0 {
+        let omission = format!("\u{2026} {} omitted \u{2026}", fmt::count(omitted, "line"));
+        block.push_str(&style.line(&omission));
+        block.push('\n');
+        for line in &lines[head + omitted..] {
+            block.push_str(&style.line(line));
+            block.push('\n');
+        }
+    }
+    let _output = output::lock();
+    stream.write_all(block.as_bytes())?;
+    crate::stream::flush_line(&mut stream)
+}
+
+/// The number of leading lines shown and of lines omitted from a block of `len` lines showing at
+/// most `max`. The remaining lines are shown at the end.
+fn elide(len: usize, max: usize) -> (usize, usize) {
+    if len <= max {
+        (len, 0)
+    } else {
+        (max - max / 2, len - max)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{elide, emit_block_to, set_block_max_lines, BlockStyle};
+    use crate::test::{force_colors, global_lock};
+    use crate::{CargoColor, Status};
+
+    fn block(style: BlockStyle) -> String {
+        let mut output = Vec::<u8>::new();
+        let status = Status::new().color(CargoColor::BrightRed).status("error");
+        emit_block_to(&mut output, &status, "first\nsecond\n", style).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_styles() {
+        let _lock = global_lock();
+        force_colors();
+        assert_eq!(
+            block(BlockStyle::Dimmed),
+            "\u{1b}[91merror\u{1b}[0m\n    \u{1b}[2mfirst\u{1b}[0m\n    \u{1b}[2msecond\u{1b}[0m\n"
+        );
+        assert_eq!(
+            block(BlockStyle::Quoted),
+            "\u{1b}[91merror\u{1b}[0m\n> first\n> second\n"
+        );
+        assert_eq!(
+            block(BlockStyle::Gutter),
+            "\u{1b}[91merror\u{1b}[0m\n  \u{1b}[1m|\u{1b}[0m first\n  \u{1b}[1m|\u{1b}[0m second\n"
+        );
+        assert_eq!(
+            block(BlockStyle::Indented),
+            "\u{1b}[91merror\u{1b}[0m\n             first\n             second\n"
+        );
+    }
+
+    #[test]
+    fn test_elision() {
+        assert_eq!(elide(3, usize::MAX), (3, 0));
+        assert_eq!(elide(10, 10), (10, 0));
+        assert_eq!(elide(224, 10), (5, 214));
+        assert_eq!(elide(5, 3), (2, 2));
+        assert_eq!(elide(5, 0), (0, 5));
+
+        let _lock = global_lock();
+        force_colors();
+        set_block_max_lines(Some(3));
+        let text: Vec<String> = (1..=6).map(|n| n.to_string()).collect();
+        let mut output = Vec::<u8>::new();
+        let status = Status::new().status("log");
+        emit_block_to(&mut output, &status, &text.join("\n"), BlockStyle::Quoted).unwrap();
+        set_block_max_lines(None);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\u{1b}[37mlog\u{1b}[0m\n> 1\n> 2\n> \u{2026} 3 lines omitted \u{2026}\n> 6\n"
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Cache status reporting for incremental build tools.
+//!
+//! Mirrors the `Fresh` and `Dirty` lines printed by `cargo -v` and keeps a tally of both so a
+//! summary line can be printed at the end of the build.
+//!
+//! ## Example
+//! ```
+//! #[macro_use] extern crate carlog;
+//!
+//! carlog::set_verbosity(1);
+//! carlog_fresh!("carlog v0.1.0");
+//! carlog_dirty!("colored v2.0.0", "the file `src/lib.rs` has changed");
+//! carlog::emit_summary().unwrap(); // "    Finished 1 fresh, 1 rebuilt"
+//! ```
+
+use crate::{color, record_suppressed, verbosity, CargoColor, CarlogStream, Error, Status};
+use colored::Colorize;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The verbosity from which fresh and dirty lines are printed, like `cargo -v`.
+pub const CACHE_STATUS_VERBOSITY: u8 = 1;
+
+static FRESH: AtomicUsize = AtomicUsize::new(0);
+static DIRTY: AtomicUsize = AtomicUsize::new(0);
+
+/// Report a unit that is up to date.
+///
+/// Prints a dimmed, justified `Fresh` status when the verbosity is at least
+/// [`CACHE_STATUS_VERBOSITY`]. The unit is counted either way.
+///
+/// * `name`: The name of the unit, i.e. `carlog v0.1.0`.
+/// * `stream`: The stream where the status is printed.
+pub fn fresh<S>(name: S, mut stream: CarlogStream<'_>) -> Result<(), Error>
+where
+    S: AsRef<str>,
+{
+    FRESH.fetch_add(1, Ordering::Relaxed);
+    if verbosity() < CACHE_STATUS_VERBOSITY {
+        record_suppressed();
+        return Ok(());
+    }
+    let status = Status::plain().justify().dimmed().status("Fresh");
+    status.print_stream(&mut stream, format!(" {}", name.as_ref()))
+}
+
+/// Report a unit that has to be rebuilt.
+///
+/// Prints a cyan, justified `Dirty` status followed by the dimmed reason when the verbosity is at
+/// least [`CACHE_STATUS_VERBOSITY`]. The unit is counted either way.
+///
+/// * `name`: The name of the unit, i.e. `carlog v0.1.0`.
+/// * `reason`: Why the unit has to be rebuilt.
+/// * `stream`: The stream where the status is printed.
+pub fn dirty<S, R>(name: S, reason: R, mut stream: CarlogStream<'_>) -> Result<(), Error>
+where
+    S: AsRef<str>,
+    R: AsRef<str>,
+{
+    DIRTY.fetch_add(1, Ordering::Relaxed);
+    if verbosity() < CACHE_STATUS_VERBOSITY {
+        record_suppressed();
+        return Ok(());
+    }
+    let status = Status::plain()
+        .justify()
+        .color(CargoColor::Cyan)
+        .status("Dirty");
+    let reason = color::paint(format!(": {}", reason.as_ref()).dimmed());
+    status.print_stream(&mut stream, format!(" {}{}", name.as_ref(), reason))
+}
+
+/// The number of units reported as fresh.
+pub fn fresh_count() -> usize {
+    FRESH.load(Ordering::Relaxed)
+}
+
+/// The number of units reported as dirty.
+pub fn dirty_count() -> usize {
+    DIRTY.load(Ordering::Relaxed)
+}
+
+/// Reset the fresh and dirty tallies to zero.
+pub fn reset_cache_counts() {
+    FRESH.store(0, Ordering::Relaxed);
+    DIRTY.store(0, Ordering::Relaxed);
+}
+
+/// Print the summary of the reported units to stdout.
+///
+/// See [`emit_summary_to`].
+pub fn emit_summary() -> Result<(), Error> {
+    summary(&mut CarlogStream::Stdout)
+}
+
+/// Print the summary of the reported units to the specified stream.
+///
+/// The summary is a cargo-like `Finished` line, i.e. `    Finished 42 fresh, 7 rebuilt`. Nothing is
+/// printed when no unit was reported.
+///
+/// * `stream`: The stream where the summary will be written.
+pub fn emit_summary_to<W>(mut stream: W) -> Result<(), Error>
+where
+    W: Write,
+{
+    summary(&mut CarlogStream::Custom(&mut stream))
+}
+
+fn summary(stream: &mut CarlogStream<'_>) -> Result<(), Error> {
+    let (fresh, dirty) = (fresh_count(), dirty_count());
+    if fresh == 0 && dirty == 0 {
+        return Ok(());
+    }
+    Status::plain()
+        .bold()
+        .justify()
+        .color(CargoColor::Green)
+        .status("Finished")
+        .print_stream(stream, format!(" {} fresh, {} rebuilt", fresh, dirty))
+}
+
+/// Print a cargo like `Fresh` line for a unit that is up to date.
+///
+/// Only printed from verbosity [`CACHE_STATUS_VERBOSITY`], but always counted for the summary.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog_fresh!("carlog v0.1.0");
+/// let mut output = Vec::<u8>::new();
+/// carlog_fresh!("carlog v0.1.0", CarlogStream::Custom(&mut output));
+/// ```
+#[macro_export]
+macro_rules! carlog_fresh {
+    ($name:expr) => {
+        $crate::carlog_fresh!($name, $crate::CarlogStream::default())
+    };
+    ($name:expr, $stream:expr) => {
+        $crate::__printed($crate::cache::fresh($name, $stream))
+    };
+}
+
+/// Print a cargo like `Dirty` line for a unit that has to be rebuilt.
+///
+/// Only printed from verbosity [`CACHE_STATUS_VERBOSITY`], but always counted for the summary.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog_dirty!("carlog v0.1.0", "the file `src/lib.rs` has changed");
+/// let mut output = Vec::<u8>::new();
+/// carlog_dirty!("carlog v0.1.0", "the file `src/lib.rs` has changed", CarlogStream::Custom(&mut output));
+/// ```
+#[macro_export]
+macro_rules! carlog_dirty {
+    ($name:expr, $reason:expr) => {
+        $crate::carlog_dirty!($name, $reason, $crate::CarlogStream::default())
+    };
+    ($name:expr, $reason:expr, $stream:expr) => {
+        $crate::__printed($crate::cache::dirty($name, $reason, $stream))
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::{dirty_count, emit_summary_to, fresh_count, reset_cache_counts};
+    use crate::test::{force_colors, global_lock};
+    use crate::{set_verbosity, CarlogStream};
+
+    #[test]
+    fn test_verbosity_gating() {
+        let _lock = global_lock();
+        force_colors();
+        reset_cache_counts();
+        let mut output = Vec::<u8>::new();
+        set_verbosity(0);
+        carlog_fresh!("carlog v0.1.0", CarlogStream::Custom(&mut output));
+        carlog_dirty!(
+            "carlog v0.1.0",
+            "changed",
+            CarlogStream::Custom(&mut output)
+        );
+        assert!(output.is_empty());
+        assert_eq!((fresh_count(), dirty_count()), (1, 1));
+        reset_cache_counts();
+    }
+
+    #[test]
+    fn test_fresh_dirty_rendering() {
+        let _lock = global_lock();
+        force_colors();
+        reset_cache_counts();
+        set_verbosity(1);
+        let mut output = Vec::<u8>::new();
+        carlog_fresh!("carlog v0.1.0", CarlogStream::Custom(&mut output));
+        carlog_dirty!(
+            "colored v2.0.0",
+            "the file `src/lib.rs` has changed",
+            CarlogStream::Custom(&mut output)
+        );
+        set_verbosity(0);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "       \u{1b}[2;37mFresh\u{1b}[0m carlog v0.1.0\n       \u{1b}[36mDirty\u{1b}[0m \
+             colored v2.0.0\u{1b}[2m: the file `src/lib.rs` has changed\u{1b}[0m\n"
+        );
+        reset_cache_counts();
+    }
+
+    #[test]
+    fn test_summary() {
+        let _lock = global_lock();
+        force_colors();
+        reset_cache_counts();
+        let mut output = Vec::<u8>::new();
+        emit_summary_to(&mut output).unwrap();
+        assert!(output.is_empty());
+        for _ in 0..42 {
+            carlog_fresh!("unit", CarlogStream::Custom(&mut output));
+        }
+        for _ in 0..7 {
+            carlog_dirty!("unit", "changed", CarlogStream::Custom(&mut output));
+        }
+        emit_summary_to(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "    \u{1b}[1;32mFinished\u{1b}[0m 42 fresh, 7 rebuilt\n"
+        );
+        reset_cache_counts();
+    }
+}
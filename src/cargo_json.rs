@@ -0,0 +1,269 @@
+//! Diagnostics in the JSON form of `cargo --message-format=json`, for the editors and CI tools
+//! reading the output of cargo.
+//!
+//! A diagnostic is one line like:
+//! ```json
+//! {"reason":"compiler-message","message":{"$message_type":"diagnostic","message":"unused import",
+//!  "code":{"code":"unused_imports","explanation":null},"level":"warning","spans":[...],
+//!  "children":[],"rendered":"warning: unused import [unused_imports]\n"}}
+//! ```
+//! The `rendered` field is the line the macros print in the [human format](crate::Format::Human),
+//! without colors, so both forms always say the same. With
+//! [`Format::CargoJson`], `carlog_warning!` and `carlog_error!` print
+//! their messages this way.
+//!
+//! ## Example
+//! ```
+//! use carlog::cargo_json::CargoDiagnostic;
+//! use carlog::prelude::*;
+//!
+//! CargoDiagnostic::new(Level::Warning, "unused variable: `x`")
+//!     .code("unused_variables")
+//!     .span("src/main.rs", 3, 9)
+//!     .emit(CarlogStream::Stdout)
+//!     .unwrap();
+//! ```
+
+use crate::{json, stream, theme, CarlogStream, Error, Format, Level, Record};
+
+/// A diagnostic of `cargo --message-format=json`, see the [module](self).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CargoDiagnostic {
+    level: Level,
+    message: String,
+    code: Option<String>,
+    spans: Vec<Span>,
+}
+
+/// Where a diagnostic points to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Span {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+impl CargoDiagnostic {
+    /// Creates a diagnostic of `level`, a `note` for the levels below [`Level::Warning`].
+    pub fn new<M>(level: Level, message: M) -> Self
+    where
+        M: AsRef<str>,
+    {
+        Self {
+            level,
+            message: message.as_ref().to_string(),
+            code: None,
+            spans: Vec::new(),
+        }
+    }
+
+    /// Set the code of the diagnostic, i.e. `E0308` or the name of a lint.
+    pub fn code<S>(mut self, code: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.code = Some(code.as_ref().to_string());
+        self
+    }
+
+    /// Point the diagnostic to `line` and `column` of `file`, both starting at 1.
+    ///
+    /// The first span is the primary one, the others are secondary. Spans are one column wide and
+    /// their byte offsets, which aren't known, are 0.
+    pub fn span<S>(mut self, file: S, line: u32, column: u32) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.spans.push(Span {
+            file: file.as_ref().to_string(),
+            line,
+            column,
+        });
+        self
+    }
+
+    /// The JSON line of the diagnostic, including the newline.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::cargo_json::CargoDiagnostic;
+    /// use carlog::prelude::*;
+    ///
+    /// let line = CargoDiagnostic::new(Level::Error, "mismatched types").to_json();
+    /// assert!(line.starts_with("{\"reason\":\"compiler-message\""));
+    /// ```
+    pub fn to_json(&self) -> String {
+        let record = self.record();
+        self.json(&record.to_status().human_line(&record.line_message()))
+    }
+
+    /// Print the JSON line of the diagnostic to `stream`.
+    ///
+    /// * `stream`: The stream where the diagnostic will be written.
+    pub fn emit(&self, mut stream: CarlogStream<'_>) -> Result<(), Error> {
+        Ok(stream::write_line(&mut stream, &self.to_json())?)
+    }
+
+    /// The record the macros would print for the diagnostic.
+    fn record(&self) -> Record {
+        let (status, color) = match self.level {
+            Level::Trace | Level::Debug | Level::Info => ("note", theme().info),
+            Level::Warning => ("warning", theme().warning),
+            Level::Error => ("error", theme().error),
+        };
+        let mut record = Record::new(status, &self.message)
+            .level(self.level)
+            .color(color);
+        if let Some(code) = &self.code {
+            record = record.id(code);
+        }
+        match self.spans.first() {
+            Some(span) => record.location(&span.file, span.line, span.column),
+            None => record,
+        }
+    }
+
+    /// The JSON line of the diagnostic rendered as `rendered`.
+    fn json(&self, rendered: &str) -> String {
+        let mut line = String::from("{\"reason\":\"compiler-message\",\"message\":{");
+        let start = line.len() - 1;
+        json::push_key(&mut line, start, "$message_type");
+        json::push_string(&mut line, "diagnostic");
+        json::push_key(&mut line, start, "message");
+        json::push_string(&mut line, &self.message);
+        json::push_key(&mut line, start, "code");
+        match &self.code {
+            Some(code) => {
+                line.push_str("{\"code\":");
+                json::push_string(&mut line, code);
+                line.push_str(",\"explanation\":null}");
+            }
+            None => line.push_str("null"),
+        }
+        json::push_key(&mut line, start, "level");
+        json::push_string(
+            &mut line,
+            match self.level {
+                Level::Trace | Level::Debug | Level::Info => "note",
+                Level::Warning => "warning",
+                Level::Error => "error",
+            },
+        );
+        json::push_key(&mut line, start, "spans");
+        line.push('[');
+        for (i, span) in self.spans.iter().enumerate() {
+            if i > 0 {
+                line.push(',');
+            }
+            line.push_str("{\"file_name\":");
+            json::push_string(&mut line, &span.file);
+            line.push_str(&format!(
+                ",\"byte_start\":0,\"byte_end\":0,\"line_start\":{0},\"line_end\":{0},\
+                 \"column_start\":{1},\"column_end\":{2},\"is_primary\":{3},\"text\":[],\
+                 \"label\":null,\"suggested_replacement\":null,\
+                 \"suggestion_applicability\":null,\"expansion\":null}}",
+                span.line,
+                span.column,
+                span.column + 1,
+                i == 0
+            ));
+        }
+        line.push(']');
+        json::push_key(&mut line, start, "children");
+        line.push_str("[]");
+        json::push_key(&mut line, start, "rendered");
+        json::push_string(&mut line, rendered);
+        line.push_str("}}\n");
+        line
+    }
+}
+
+/// The JSON line of `record` when it's a warning or an error printed with
+/// [`Format::CargoJson`], rendered like in the human format.
+pub(crate) fn record_line(record: &Record) -> Option<String> {
+    if crate::format() != Format::CargoJson
+        || !matches!(record.severity(), Level::Warning | Level::Error)
+    {
+        return None;
+    }
+    let mut diagnostic = CargoDiagnostic::new(record.severity(), record.message());
+    if let Some(code) = record.diagnostic_code().or(record.identifier()) {
+        diagnostic = diagnostic.code(code);
+    }
+    if let Some((path, line, column)) = record.source_location() {
+        diagnostic = diagnostic.span(path, line, column);
+    }
+    let rendered = record.to_status().human_line(&record.line_message());
+    Some(diagnostic.json(&rendered))
+}
+
+#[cfg(test)]
+mod test {
+    use super::CargoDiagnostic;
+    use crate::test::{force_colors, global_lock};
+    use crate::{carlog_info, carlog_warning, set_format, CarlogStream, Format, Level};
+
+    #[test]
+    fn test_diagnostic_json() {
+        let _lock = global_lock();
+        force_colors();
+        let diagnostic = CargoDiagnostic::new(Level::Warning, "unused variable: `x`")
+            .code("unused_variables")
+            .span("src\\main.rs", 3, 9)
+            .span("src\\lib.rs", 1, 1);
+        let mut output = Vec::<u8>::new();
+        diagnostic.emit(CarlogStream::Custom(&mut output)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"reason\":\"compiler-message\",\"message\":{\"$message_type\":\"diagnostic\",\
+             \"message\":\"unused variable: `x`\",\"code\":{\"code\":\"unused_variables\",\
+             \"explanation\":null},\"level\":\"warning\",\"spans\":[{\"file_name\":\
+             \"src\\\\main.rs\",\"byte_start\":0,\"byte_end\":0,\"line_start\":3,\"line_end\":3,\
+             \"column_start\":9,\"column_end\":10,\"is_primary\":true,\"text\":[],\"label\":null,\
+             \"suggested_replacement\":null,\"suggestion_applicability\":null,\"expansion\":null},\
+             {\"file_name\":\"src\\\\lib.rs\",\"byte_start\":0,\"byte_end\":0,\"line_start\":1,\
+             \"line_end\":1,\"column_start\":1,\"column_end\":2,\"is_primary\":false,\"text\":[],\
+             \"label\":null,\"suggested_replacement\":null,\"suggestion_applicability\":null,\
+             \"expansion\":null}],\"children\":[],\"rendered\":\"warning: unused variable: `x` \
+             [unused_variables]\\n\"}}\n"
+        );
+        assert_eq!(
+            CargoDiagnostic::new(Level::Error, "mismatched \"types\"").to_json(),
+            "{\"reason\":\"compiler-message\",\"message\":{\"$message_type\":\"diagnostic\",\
+             \"message\":\"mismatched \\\"types\\\"\",\"code\":null,\"level\":\"error\",\
+             \"spans\":[],\"children\":[],\"rendered\":\"error: mismatched \\\"types\\\"\\n\"}}\n"
+        );
+    }
+
+    #[test]
+    fn test_macros_in_cargo_json_format() {
+        let _lock = global_lock();
+        force_colors();
+        set_format(Format::CargoJson);
+        let mut output = Vec::<u8>::new();
+        carlog_info!(
+            "Checking",
+            "carlog v0.1.0",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_warning!("unused import", CarlogStream::Custom(&mut output));
+        let line = line!() - 1;
+        set_format(Format::Human);
+        let human = "    \u{1b}[1;36mChecking\u{1b}[0m carlog v0.1.0\n";
+        let output = String::from_utf8(output).unwrap();
+        let json = output.strip_prefix(human).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                "{{\"reason\":\"compiler-message\",\"message\":{{\"$message_type\":\
+                 \"diagnostic\",\"message\":\"unused import\",\"code\":null,\"level\":\"warning\",\
+                 \"spans\":[{{\"file_name\":\"src/cargo_json.rs\",\"byte_start\":0,\"byte_end\":0,\
+                 \"line_start\":{0},\"line_end\":{0},\"column_start\":9,\"column_end\":10,\
+                 \"is_primary\":true,\"text\":[],\"label\":null,\"suggested_replacement\":null,\
+                 \"suggestion_applicability\":null,\"expansion\":null}}],\"children\":[],\
+                 \"rendered\":\"warning: unused import\\n\"}}}}\n",
+                line
+            )
+        );
+    }
+}
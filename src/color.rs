@@ -0,0 +1,838 @@
+//! The decision of whether the output is colored.
+//!
+//! Every status and decoration printed by the crate asks the decision of the [`Target`] it's
+//! printed to, so stdout can be plain while stderr is colored. The precedence is:
+//! 1. The choice of the program through carlog, see [`set_color_choice`] and
+//!    [`set_color_choice_for`].
+//! 2. The override of the `colored` crate, `colored::control::set_override`, so that carlog agrees
+//!    with the rest of the output of programs already using it, as far as carlog can tell it's set
+//!    (see below).
+//! 3. The environment: `CARGO_TERM_COLOR`, the `--color` flag cargo forwards to its subcommands,
+//!    then `CLICOLOR_FORCE`, `NO_COLOR` and `CLICOLOR`. `CARGO_TERM_COLOR=auto` is ignored.
+//! 4. Whether the stream is a terminal. Custom streams, i.e. files and buffers, are never one, so
+//!    their output is plain unless the status is [forced](crate::Status::force_color).
+//!
+//! The output of steps 2 to 4 to a terminal that can't interpret escape sequences, i.e. a console
+//! of Windows before Windows 10, is plain, so they never show up as `←[1;32m`. The consoles of
+//! Windows 10 are asked to interpret them before the first colored line.
+//!
+//! Steps 2 to 4 extend the decision of `colored` itself, made for stdout. `colored` doesn't tell
+//! whether its override is set, so carlog only notices an override that changes the guess
+//! `colored` makes from the environment and stdout. One that agrees with the guess looks like no
+//! override at all and steps 3 and 4 decide, which can differ from it: with
+//! `CARGO_TERM_COLOR=always`, `colored::control::set_override(false)` on a pipe still leaves the
+//! output of carlog colored. Programs overriding `colored` should make the same choice with
+//! [`set_override`] or [`set_color_choice`] to be sure carlog follows it.
+
+use crate::attributes::Attributes;
+use crate::env::{EnvSource, ProcessEnv};
+use crate::{sys, CargoColor, CarlogStream, Target};
+use colored::control::{ShouldColorize, SHOULD_COLORIZE};
+use colored::{Color, ColoredString, Styles};
+use std::cell::Cell;
+use std::fmt::Write;
+use std::io::{stderr, stdout, IsTerminal};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+static CHOICES: [AtomicU8; 3] = [
+    AtomicU8::new(ColorChoice::Auto as u8),
+    AtomicU8::new(ColorChoice::Auto as u8),
+    AtomicU8::new(ColorChoice::Auto as u8),
+];
+
+/// Whether the terminals are taken for ones that can't interpret escape sequences, to test the
+/// fallback on any platform.
+static NO_VIRTUAL_TERMINAL: AtomicBool = AtomicBool::new(false);
+
+/// The [`ColorDepth`] set by the program, `0` to detect it.
+static DEPTH: AtomicU8 = AtomicU8::new(0);
+
+/// The 16 basic colors, with the RGB values of xterm.
+const BASIC: [(Color, [u8; 3]); 16] = [
+    (Color::Black, [0, 0, 0]),
+    (Color::Red, [205, 0, 0]),
+    (Color::Green, [0, 205, 0]),
+    (Color::Yellow, [205, 205, 0]),
+    (Color::Blue, [0, 0, 238]),
+    (Color::Magenta, [205, 0, 205]),
+    (Color::Cyan, [0, 205, 205]),
+    (Color::White, [229, 229, 229]),
+    (Color::BrightBlack, [127, 127, 127]),
+    (Color::BrightRed, [255, 0, 0]),
+    (Color::BrightGreen, [0, 255, 0]),
+    (Color::BrightYellow, [255, 255, 0]),
+    (Color::BrightBlue, [92, 92, 255]),
+    (Color::BrightMagenta, [255, 0, 255]),
+    (Color::BrightCyan, [0, 255, 255]),
+    (Color::BrightWhite, [255, 255, 255]),
+];
+
+thread_local! {
+    /// The target of the line being rendered by the current thread, and the choice of its status.
+    static CURRENT: Cell<(Target, ColorChoice)> =
+        const { Cell::new((Target::Custom, ColorChoice::Auto)) };
+}
+
+/// The styles in the order `colored` writes their codes.
+const STYLES: [(Styles, &str); 8] = [
+    (Styles::Bold, "1"),
+    (Styles::Dimmed, "2"),
+    (Styles::Italic, "3"),
+    (Styles::Underline, "4"),
+    (Styles::Blink, "5"),
+    (Styles::Reversed, "7"),
+    (Styles::Hidden, "8"),
+    (Styles::Strikethrough, "9"),
+];
+
+/// Whether the output to a target is colored, see [`set_color_choice_for`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorChoice {
+    /// Let the `colored` crate, the environment and the terminal decide (the default).
+    #[default]
+    Auto,
+    /// Always color.
+    Always,
+    /// Never color.
+    Never,
+}
+
+impl ColorChoice {
+    fn from_u8(choice: u8) -> Self {
+        match choice {
+            1 => ColorChoice::Always,
+            2 => ColorChoice::Never,
+            _ => ColorChoice::Auto,
+        }
+    }
+}
+
+fn index(target: Target) -> usize {
+    match target {
+        Target::Stdout => 0,
+        Target::Stderr => 1,
+        Target::Custom => 2,
+    }
+}
+
+/// Choose whether the output of carlog to `target` is colored, whatever the `colored` crate, the
+/// environment or the terminal say unless the choice is [`ColorChoice::Auto`].
+///
+/// ## Example
+/// ```
+/// use carlog::color::{self, ColorChoice};
+/// use carlog::Target;
+///
+/// // The output is piped to another program, the diagnostics stay on the terminal.
+/// carlog::set_color_choice_for(Target::Stdout, ColorChoice::Never);
+/// carlog::set_color_choice_for(Target::Stderr, ColorChoice::Always);
+/// assert!(!color::decision_for(Target::Stdout));
+/// assert!(color::decision_for(Target::Stderr));
+/// # color::unset_override();
+/// ```
+pub fn set_color_choice_for(target: Target, choice: ColorChoice) {
+    CHOICES[index(target)].store(choice as u8, Ordering::Relaxed);
+}
+
+/// The choice made for `target`, see [`set_color_choice_for`].
+pub fn color_choice_for(target: Target) -> ColorChoice {
+    ColorChoice::from_u8(CHOICES[index(target)].load(Ordering::Relaxed))
+}
+
+/// Choose whether the output of carlog to every target is colored, see [`set_color_choice_for`].
+///
+/// With [`ColorChoice::Never`] the statuses are printed plain, justified with the same padding.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::ColorChoice;
+///
+/// carlog::set_color_choice(ColorChoice::Never);
+/// assert!(!carlog::color::decision(&CarlogStream::Stderr));
+/// carlog::set_color_choice(ColorChoice::Auto);
+/// ```
+pub fn set_color_choice(choice: ColorChoice) {
+    for target in [Target::Stdout, Target::Stderr, Target::Custom] {
+        set_color_choice_for(target, choice);
+    }
+}
+
+/// Always (`true`) or never (`false`) color the output of carlog to every target, see
+/// [`set_color_choice`].
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+///
+/// carlog::color::set_override(false);
+/// assert!(!carlog::color::decision(&CarlogStream::Stdout));
+/// # carlog::color::unset_override();
+/// ```
+pub fn set_override(colorize: bool) {
+    set_color_choice(if colorize {
+        ColorChoice::Always
+    } else {
+        ColorChoice::Never
+    });
+}
+
+/// Let the `colored` crate, the environment and the terminal decide again for every target, see
+/// [`set_override`].
+pub fn unset_override() {
+    set_color_choice(ColorChoice::Auto);
+}
+
+/// Whether the output of carlog to `stream` is colored, see the [module](self) for the
+/// precedence.
+pub fn decision(stream: &CarlogStream<'_>) -> bool {
+    decision_for(stream.target())
+}
+
+/// Whether the output of carlog to `target` is colored, see the [module](self) for the
+/// precedence.
+pub fn decision_for(target: Target) -> bool {
+    match color_choice_for(target) {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => auto_decision(target),
+    }
+}
+
+/// Whether the output of carlog to `stream` is colored, so that programs coloring their own output
+/// decide like carlog, see [`decision`].
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use colored::Colorize;
+///
+/// let summary = "2 warnings emitted";
+/// if carlog::color_enabled_for(CarlogStream::Stderr) {
+///     eprintln!("{}", summary.yellow());
+/// } else {
+///     eprintln!("{}", summary);
+/// }
+/// ```
+pub fn color_enabled_for(stream: CarlogStream<'_>) -> bool {
+    decision(&stream)
+}
+
+/// The decision of `colored`, the environment and the terminal for `target`.
+fn auto_decision(target: Target) -> bool {
+    static ENV: OnceLock<(bool, [bool; 3])> = OnceLock::new();
+    let colored = SHOULD_COLORIZE.should_colorize();
+    let &(from_env, decisions) = ENV.get_or_init(|| {
+        // Tests run as if nothing was a terminal, like the environment they see is empty.
+        let terminal = |terminal: bool| terminal && !cfg!(test);
+        (
+            !cfg!(test) && ShouldColorize::from_env().should_colorize(),
+            [
+                env_decision(&ProcessEnv, terminal(stdout().is_terminal())),
+                env_decision(&ProcessEnv, terminal(stderr().is_terminal())),
+                env_decision(&ProcessEnv, false),
+            ],
+        )
+    });
+    let decision = if colored != from_env {
+        colored
+    } else {
+        decisions[index(target)]
+    };
+    decision && !lacks_virtual_terminal(target)
+}
+
+/// Whether `target` is a terminal that can't interpret escape sequences, asking the console to on
+/// Windows the first time.
+fn lacks_virtual_terminal(target: Target) -> bool {
+    static LACKING: OnceLock<[bool; 2]> = OnceLock::new();
+    let lacking = || {
+        LACKING.get_or_init(|| {
+            [
+                stdout().is_terminal() && !sys::enable_virtual_terminal(false),
+                stderr().is_terminal() && !sys::enable_virtual_terminal(true),
+            ]
+        })
+    };
+    match target {
+        Target::Custom => false,
+        _ if NO_VIRTUAL_TERMINAL.load(Ordering::Relaxed) => true,
+        Target::Stdout => lacking()[0],
+        Target::Stderr => lacking()[1],
+    }
+}
+
+/// Take stdout and stderr for terminals that can't interpret escape sequences, or not.
+#[cfg(test)]
+pub(crate) fn set_no_virtual_terminal(lacking: bool) {
+    NO_VIRTUAL_TERMINAL.store(lacking, Ordering::Relaxed);
+}
+
+/// The decision from the environment, for a stream that is a `terminal` or not.
+fn env_decision<E>(env: &E, terminal: bool) -> bool
+where
+    E: EnvSource,
+{
+    match env.var("CARGO_TERM_COLOR").as_deref() {
+        Some("always") => return true,
+        Some("never") => return false,
+        _ => {}
+    }
+    let set = |name| env.var(name).map(|value| value != "0");
+    if set("CLICOLOR_FORCE") == Some(true) {
+        true
+    } else if set("NO_COLOR").is_some() {
+        false
+    } else {
+        set("CLICOLOR").unwrap_or(true) && terminal
+    }
+}
+
+/// The colors the terminal can show, see [`set_color_depth`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorDepth {
+    /// The 16 basic colors: RGB and indexed colors are shown as the nearest of them.
+    Basic = 1,
+    /// The 256 indexed colors: RGB colors are shown as the nearest of them.
+    Ansi256 = 2,
+    /// Any 24-bit RGB color.
+    TrueColor = 3,
+}
+
+/// Set the colors the terminal can show, instead of detecting them from `COLORTERM` and `TERM`.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::ColorDepth;
+///
+/// // The terminal doesn't show 24-bit colors, the brand color becomes yellow.
+/// carlog::set_color_depth(ColorDepth::Basic);
+/// let status = Status::new().color(CargoColor::Rgb(0xe5, 0xa0, 0x0d)).status("Bundling");
+/// ```
+pub fn set_color_depth(depth: ColorDepth) {
+    DEPTH.store(depth as u8, Ordering::Relaxed);
+}
+
+/// The colors the terminal can show, see [`set_color_depth`].
+///
+/// Unless set, 24-bit colors are shown when `COLORTERM` is `truecolor` or `24bit`, like the
+/// `colored` crate decides, and the 256 indexed colors when `TERM` is a `256color` terminal.
+pub fn color_depth() -> ColorDepth {
+    static ENV: OnceLock<ColorDepth> = OnceLock::new();
+    match DEPTH.load(Ordering::Relaxed) {
+        1 => ColorDepth::Basic,
+        2 => ColorDepth::Ansi256,
+        3 => ColorDepth::TrueColor,
+        _ => *ENV.get_or_init(|| env_depth(&ProcessEnv)),
+    }
+}
+
+/// The colors set with [`set_color_depth`], if any, instead of the ones detected.
+pub(crate) fn color_depth_override() -> Option<ColorDepth> {
+    match DEPTH.load(Ordering::Relaxed) {
+        0 => None,
+        _ => Some(color_depth()),
+    }
+}
+
+/// Set the colors the terminal can show, or detect them again with `None`.
+pub(crate) fn set_color_depth_override(depth: Option<ColorDepth>) {
+    DEPTH.store(depth.map_or(0, |depth| depth as u8), Ordering::Relaxed);
+}
+
+/// The colors the terminal can show according to the environment.
+fn env_depth<E>(env: &E) -> ColorDepth
+where
+    E: EnvSource,
+{
+    if matches!(env.var("COLORTERM").as_deref(), Some("truecolor" | "24bit")) {
+        ColorDepth::TrueColor
+    } else if env
+        .var("TERM")
+        .is_some_and(|term| term.contains("256color"))
+    {
+        ColorDepth::Ansi256
+    } else {
+        ColorDepth::Basic
+    }
+}
+
+/// Detect the color depth from the environment again.
+#[cfg(test)]
+pub(crate) fn reset_color_depth() {
+    DEPTH.store(0, Ordering::Relaxed);
+}
+
+/// A foreground color, which `colored` can't represent when it's indexed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Shade {
+    /// A color of `colored`.
+    Named(Color),
+    /// A color of the 256-color palette.
+    Indexed(u8),
+}
+
+/// The color shown for an RGB color with the [`color_depth`].
+pub(crate) fn rgb(r: u8, g: u8, b: u8) -> Shade {
+    match color_depth() {
+        ColorDepth::TrueColor => Shade::Named(Color::TrueColor { r, g, b }),
+        ColorDepth::Ansi256 => Shade::Indexed(nearest_ansi256([r, g, b])),
+        ColorDepth::Basic => Shade::Named(nearest_basic([r, g, b])),
+    }
+}
+
+/// The color shown for the color `index` of the 256-color palette with the [`color_depth`].
+pub(crate) fn ansi256(index: u8) -> Shade {
+    match (color_depth(), BASIC.get(usize::from(index))) {
+        (ColorDepth::Basic, Some(&(color, _))) => Shade::Named(color),
+        (ColorDepth::Basic, None) => Shade::Named(nearest_basic(ansi256_rgb(index))),
+        _ => Shade::Indexed(index),
+    }
+}
+
+/// The RGB value of a color, like xterm shows it.
+pub(crate) fn cargo_rgb(color: CargoColor) -> [u8; 3] {
+    let index = match color {
+        CargoColor::Rgb(r, g, b) => return [r, g, b],
+        CargoColor::Ansi256(index) => index,
+        CargoColor::Black => 0,
+        CargoColor::Red => 1,
+        CargoColor::Green => 2,
+        CargoColor::Yellow => 3,
+        CargoColor::Cyan => 6,
+        CargoColor::White => 7,
+        CargoColor::BrightBlack => 8,
+        CargoColor::BrightRed => 9,
+        CargoColor::BrightGreen => 10,
+        CargoColor::BrightYellow => 11,
+        CargoColor::BrightCyan => 14,
+        CargoColor::BrightWhite => 15,
+    };
+    ansi256_rgb(index)
+}
+
+/// The RGB value of the color `index` of the 256-color palette, like xterm shows it.
+fn ansi256_rgb(index: u8) -> [u8; 3] {
+    let level = |step: u8| if step == 0 { 0 } else { 55 + step * 40 };
+    match index {
+        0..=15 => BASIC[usize::from(index)].1,
+        16..=231 => {
+            let cube = index - 16;
+            [level(cube / 36), level(cube / 6 % 6), level(cube % 6)]
+        }
+        _ => [8 + (index - 232) * 10; 3],
+    }
+}
+
+/// The squared distance between two RGB values.
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(&a, b)| u32::from(a.abs_diff(b)).pow(2))
+        .sum()
+}
+
+/// The basic color the nearest to `rgb`.
+fn nearest_basic(rgb: [u8; 3]) -> Color {
+    let (color, _) = BASIC
+        .iter()
+        .min_by_key(|(_, basic)| distance(rgb, *basic))
+        .unwrap_or(&BASIC[0]);
+    *color
+}
+
+/// The color of the 256-color palette the nearest to `rgb`, out of the cube and the grays, whose
+/// values don't depend on the theme of the terminal like the basic colors do.
+fn nearest_ansi256(rgb: [u8; 3]) -> u8 {
+    (16..=255)
+        .min_by_key(|&index| distance(rgb, ansi256_rgb(index)))
+        .unwrap_or(16)
+}
+
+/// The code of a foreground (`foreground`) or background color. `colored` quantizes 24-bit colors
+/// on its own, `rgb` already did.
+fn color_code(color: Color, foreground: bool) -> String {
+    match (color, foreground) {
+        (Color::TrueColor { r, g, b }, true) => format!("38;2;{};{};{}", r, g, b),
+        (Color::TrueColor { r, g, b }, false) => format!("48;2;{};{};{}", r, g, b),
+        (color, true) => color.to_fg_str().into_owned(),
+        (color, false) => color.to_bg_str().into_owned(),
+    }
+}
+
+/// Render the lines of `render` with the decision of `target`.
+pub(crate) fn with_target<F, R>(target: Target, render: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    with_choice(target, ColorChoice::Auto, render)
+}
+
+/// Render the lines of `render` for `target` with the `choice` of their status, which comes before
+/// the decision of the target unless it's [`ColorChoice::Auto`].
+pub(crate) fn with_choice<F, R>(target: Target, choice: ColorChoice, render: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    /// Restores the previous target, even when unwinding.
+    struct Restore((Target, ColorChoice));
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let _ = CURRENT.try_with(|current| current.set(self.0));
+        }
+    }
+
+    // Thread-locals are gone in their own destructors, where lines render for custom streams.
+    let Ok(previous) = CURRENT.try_with(|current| current.replace((target, choice))) else {
+        return render();
+    };
+    let _restore = Restore(previous);
+    render()
+}
+
+/// The decision of the target of the line being rendered.
+pub(crate) fn enabled() -> bool {
+    let current = CURRENT.try_with(Cell::get);
+    match current.unwrap_or((Target::Custom, ColorChoice::Auto)) {
+        (_, ColorChoice::Always) => true,
+        (_, ColorChoice::Never) => false,
+        (target, ColorChoice::Auto) => decision_for(target),
+    }
+}
+
+/// Render a styled string according to [`decision`], like `colored` would.
+pub(crate) fn paint(styled: ColoredString) -> String {
+    let foreground = styled.fgcolor.map(|color| color_code(color, true));
+    render(styled, foreground)
+}
+
+/// Write `text` in the color `shade` with `attributes` at the end of `line`, like [`paint`]
+/// renders it but without allocating.
+pub(crate) fn write_painted(line: &mut String, text: &str, attributes: Attributes, shade: Shade) {
+    if !enabled() {
+        return line.push_str(text);
+    }
+    line.push_str("\u{1b}[");
+    for code in attributes.codes() {
+        line.push_str(code);
+        line.push(';');
+    }
+    // Writing to a string can't fail.
+    let _ = match shade {
+        Shade::Named(Color::TrueColor { r, g, b }) => write!(line, "38;2;{};{};{}", r, g, b),
+        Shade::Named(color) => line.write_str(&color.to_fg_str()),
+        Shade::Indexed(index) => write!(line, "38;5;{}", index),
+    };
+    line.push('m');
+    line.push_str(text);
+    line.push_str("\u{1b}[0m");
+}
+
+/// Render a styled string with the code of its `foreground` color.
+fn render(styled: ColoredString, foreground: Option<String>) -> String {
+    if !enabled() || (styled.is_plain() && foreground.is_none()) {
+        return styled.input;
+    }
+    let mut codes: Vec<String> = STYLES
+        .iter()
+        .filter(|(style, _)| styled.style.contains(*style))
+        .map(|(_, code)| code.to_string())
+        .collect();
+    if let Some(color) = styled.bgcolor {
+        codes.push(color_code(color, false));
+    }
+    codes.extend(foreground);
+    format!("\u{1b}[{}m{}\u{1b}[0m", codes.join(";"), styled.input)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        decision, decision_for, env_decision, env_depth, nearest_ansi256, nearest_basic, paint,
+        reset_color_depth, set_color_choice, set_color_choice_for, set_color_depth,
+        set_no_virtual_terminal, set_override, unset_override, with_target, ColorChoice,
+        ColorDepth,
+    };
+    use crate::test::{auto_colors, force_colors, global_lock};
+    use crate::{CargoColor, CarlogStream, Status, Target};
+    use colored::{Color, Colorize};
+
+    fn line() -> String {
+        let mut output = Vec::<u8>::new();
+        Status::plain()
+            .bold()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Compiled")
+            .print(&mut output, " carlog v0.1.0")
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_paint() {
+        let _lock = global_lock();
+        force_colors();
+        for styled in [
+            "a".bold().red(),
+            "b".dimmed().italic().on_blue(),
+            "c".into(),
+        ] {
+            assert_eq!(paint(styled.clone()), styled.to_string());
+        }
+    }
+
+    #[test]
+    fn test_decision() {
+        let _lock = global_lock();
+        let colored = "    \u{1b}[1;32mCompiled\u{1b}[0m carlog v0.1.0\n";
+        let plain = "    Compiled carlog v0.1.0\n";
+        auto_colors();
+        assert!(!decision(&CarlogStream::Stdout));
+        assert_eq!(line(), plain);
+        colored::control::set_override(true);
+        assert!(decision(&CarlogStream::Stderr));
+        assert_eq!(line(), colored);
+
+        set_override(false);
+        assert!(!decision(&CarlogStream::Stdout));
+        assert_eq!(line(), plain);
+        colored::control::set_override(false);
+        set_override(true);
+        assert!(decision(&CarlogStream::Custom(&mut Vec::new())));
+        assert_eq!(line(), colored);
+        unset_override();
+        force_colors();
+    }
+
+    #[test]
+    fn test_color_choice() {
+        let _lock = global_lock();
+        force_colors();
+        set_color_choice(ColorChoice::Never);
+        let plain = line();
+        assert!(!decision_for(Target::Stderr));
+        set_color_choice(ColorChoice::Always);
+        colored::control::set_override(false);
+        let colored = line();
+        set_color_choice(ColorChoice::Auto);
+        assert!(!decision_for(Target::Stdout));
+        force_colors();
+        assert_eq!(plain, "    Compiled carlog v0.1.0\n");
+        assert_eq!(colored, "    \u{1b}[1;32mCompiled\u{1b}[0m carlog v0.1.0\n");
+        assert_eq!(crate::fmt::strip_ansi(&colored), plain);
+    }
+
+    #[test]
+    fn test_per_target() {
+        let _lock = global_lock();
+        auto_colors();
+        colored::control::set_override(true);
+        set_color_choice_for(Target::Stdout, ColorChoice::Never);
+        set_color_choice_for(Target::Stderr, ColorChoice::Always);
+        let warning = Status::warning_with_label("warning");
+        let lines: Vec<String> = [Target::Stdout, Target::Stderr, Target::Custom]
+            .into_iter()
+            .map(|target| with_target(target, || warning.line(": unused import")))
+            .collect();
+        assert!(!decision_for(Target::Stdout) && decision_for(Target::Stderr));
+        colored::control::set_override(false);
+        assert!(decision_for(Target::Stderr) && !decision_for(Target::Custom));
+        unset_override();
+        force_colors();
+        assert_eq!(
+            lines,
+            [
+                "warning: unused import\n",
+                "\u{1b}[93mwarning\u{1b}[0m: unused import\n",
+                "\u{1b}[93mwarning\u{1b}[0m: unused import\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_virtual_terminal() {
+        let _lock = global_lock();
+        auto_colors();
+        colored::control::set_override(true);
+        set_no_virtual_terminal(true);
+        let warning = Status::warning_with_label("warning");
+        let lines: Vec<String> = [Target::Stdout, Target::Stderr, Target::Custom]
+            .into_iter()
+            .map(|target| with_target(target, || warning.line(": unused import")))
+            .collect();
+        let decisions = [Target::Stdout, Target::Stderr, Target::Custom].map(decision_for);
+        // The choice of the program still wins.
+        set_color_choice_for(Target::Stderr, ColorChoice::Always);
+        assert!(decision_for(Target::Stderr));
+        unset_override();
+        set_no_virtual_terminal(false);
+        assert!(decision_for(Target::Stdout));
+        assert_eq!(decisions, [false, false, true]);
+        assert_eq!(
+            lines,
+            [
+                "warning: unused import\n",
+                "warning: unused import\n",
+                "\u{1b}[93mwarning\u{1b}[0m: unused import\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_env_decision() {
+        assert!(env_decision(&[], true));
+        assert!(!env_decision(&[], false));
+        assert!(env_decision(
+            &[("CLICOLOR_FORCE", "1"), ("NO_COLOR", "1")],
+            false
+        ));
+        assert!(!env_decision(&[("NO_COLOR", "")], true));
+        assert!(!env_decision(&[("CLICOLOR", "0")], true));
+        assert!(env_decision(&[("CLICOLOR", "1")], true));
+        assert!(env_decision(
+            &[("CLICOLOR_FORCE", "1"), ("CLICOLOR", "0")],
+            false
+        ));
+        assert!(!env_decision(
+            &[("CLICOLOR_FORCE", "0"), ("NO_COLOR", "1")],
+            true
+        ));
+    }
+
+    #[test]
+    fn test_cargo_term_color() {
+        assert!(env_decision(
+            &[("CARGO_TERM_COLOR", "always"), ("NO_COLOR", "1")],
+            false
+        ));
+        assert!(!env_decision(
+            &[("CARGO_TERM_COLOR", "never"), ("CLICOLOR_FORCE", "1")],
+            true
+        ));
+        assert!(env_decision(&[("CARGO_TERM_COLOR", "auto")], true));
+        assert!(!env_decision(&[("CARGO_TERM_COLOR", "auto")], false));
+        assert!(!env_decision(
+            &[("CARGO_TERM_COLOR", "auto"), ("CLICOLOR", "0")],
+            true
+        ));
+    }
+
+    #[test]
+    fn test_rgb() {
+        let _lock = global_lock();
+        force_colors();
+        let status = Status::plain()
+            .bold()
+            .color(CargoColor::Rgb(0xe5, 0xa0, 0x0d))
+            .status("Bundling");
+        set_color_depth(ColorDepth::TrueColor);
+        let truecolor = status.render("");
+        set_color_depth(ColorDepth::Basic);
+        let basic = status.render("");
+        reset_color_depth();
+        assert_eq!(truecolor, "\u{1b}[1;38;2;229;160;13mBundling\u{1b}[0m");
+        assert_eq!(basic, "\u{1b}[1;33mBundling\u{1b}[0m");
+        for (rgb, color) in [
+            ([0xe5, 0xa0, 0x0d], Color::Yellow),
+            ([250, 10, 10], Color::BrightRed),
+            ([10, 10, 10], Color::Black),
+            ([128, 128, 128], Color::BrightBlack),
+            ([0, 0, 240], Color::Blue),
+            ([30, 200, 210], Color::Cyan),
+            ([250, 250, 250], Color::BrightWhite),
+        ] {
+            assert_eq!(nearest_basic(rgb), color, "{:?}", rgb);
+        }
+        assert_eq!(
+            env_depth(&[("COLORTERM", "truecolor")]),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(env_depth(&[("COLORTERM", "24bit")]), ColorDepth::TrueColor);
+        assert_eq!(
+            env_depth(&[("TERM", "xterm-256color")]),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(env_depth(&[]), ColorDepth::Basic);
+        assert_eq!(nearest_ansi256([0xe5, 0xa0, 0x0d]), 178);
+        assert_eq!(nearest_ansi256([240, 240, 240]), 255);
+    }
+
+    #[test]
+    fn test_ansi256() {
+        let _lock = global_lock();
+        force_colors();
+        let render = |index| {
+            let status = Status::plain().color(CargoColor::Ansi256(index));
+            status.status("Testing").render("")
+        };
+        set_color_depth(ColorDepth::Ansi256);
+        let indexed: Vec<String> = [0, 15, 128, 255].into_iter().map(render).collect();
+        let bold = Status::plain()
+            .bold()
+            .color(CargoColor::Ansi256(128))
+            .status("Testing")
+            .render("");
+        let rgb = Status::plain()
+            .color(CargoColor::Rgb(0xe5, 0xa0, 0x0d))
+            .status("Testing")
+            .render("");
+        set_color_depth(ColorDepth::Basic);
+        let basic: Vec<String> = [0, 15, 128, 255].into_iter().map(render).collect();
+        auto_colors();
+        let plain = render(128);
+        reset_color_depth();
+        force_colors();
+        assert_eq!(
+            indexed,
+            [
+                "\u{1b}[38;5;0mTesting\u{1b}[0m",
+                "\u{1b}[38;5;15mTesting\u{1b}[0m",
+                "\u{1b}[38;5;128mTesting\u{1b}[0m",
+                "\u{1b}[38;5;255mTesting\u{1b}[0m",
+            ]
+        );
+        assert_eq!(bold, "\u{1b}[1;38;5;128mTesting\u{1b}[0m");
+        assert_eq!(rgb, "\u{1b}[38;5;178mTesting\u{1b}[0m");
+        assert_eq!(
+            basic,
+            [
+                "\u{1b}[30mTesting\u{1b}[0m",
+                "\u{1b}[97mTesting\u{1b}[0m",
+                "\u{1b}[35mTesting\u{1b}[0m",
+                "\u{1b}[37mTesting\u{1b}[0m",
+            ]
+        );
+        assert_eq!(plain, "Testing");
+    }
+
+    #[test]
+    fn test_force_color() {
+        let _lock = global_lock();
+        let status = || Status::plain().justify().color(CargoColor::Green);
+        let print = |status: Status| {
+            let mut output = Vec::<u8>::new();
+            status.status("Compiled").print(&mut output, " a").unwrap();
+            String::from_utf8(output).unwrap()
+        };
+        auto_colors();
+        let plain = print(status());
+        let forced = print(status().force_color());
+        set_color_choice_for(Target::Custom, ColorChoice::Never);
+        let never = print(status().force_color());
+        set_color_choice(ColorChoice::Always);
+        let no_color = print(status().no_color());
+        unset_override();
+        force_colors();
+        assert_eq!(plain, "    Compiled a\n");
+        assert_eq!(forced, "    \u{1b}[32mCompiled\u{1b}[0m a\n");
+        assert_eq!(never, forced);
+        assert_eq!(no_color, plain);
+        assert_eq!(crate::fmt::strip_ansi(&forced), plain);
+    }
+}
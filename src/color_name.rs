@@ -0,0 +1,247 @@
+//! The names of the colors, i.e. to read them from a configuration file.
+
+use crate::CargoColor;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// The named colors with their names.
+const NAMES: [(CargoColor, &str); 12] = [
+    (CargoColor::Green, "green"),
+    (CargoColor::Cyan, "cyan"),
+    (CargoColor::Yellow, "yellow"),
+    (CargoColor::Red, "red"),
+    (CargoColor::White, "white"),
+    (CargoColor::Black, "black"),
+    (CargoColor::BrightGreen, "bright-green"),
+    (CargoColor::BrightCyan, "bright-cyan"),
+    (CargoColor::BrightYellow, "bright-yellow"),
+    (CargoColor::BrightRed, "bright-red"),
+    (CargoColor::BrightWhite, "bright-white"),
+    (CargoColor::BrightBlack, "bright-black"),
+];
+
+/// A text that isn't a [`CargoColor`], see its [`FromStr`] implementation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ColorParseError {
+    input: String,
+}
+
+impl ColorParseError {
+    /// The text that was parsed.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+}
+
+impl Display for ColorParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid color `{}`, expected one of ", self.input)?;
+        for (_, name) in NAMES {
+            write!(f, "`{}`, ", name)?;
+        }
+        write!(f, "`#rrggbb`, `rgb(r, g, b)` or `ansi256(n)`")
+    }
+}
+
+impl error::Error for ColorParseError {}
+
+/// The names of the colors, i.e. `green` or `bright-red`, `#e5a00d` for RGB colors and
+/// `ansi256(208)` for the colors of the 256-color palette.
+impl Display for CargoColor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            CargoColor::Rgb(r, g, b) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+            CargoColor::Ansi256(index) => write!(f, "ansi256({})", index),
+            color => {
+                let name = NAMES.iter().find(|(named, _)| *named == color);
+                f.write_str(name.map(|(_, name)| *name).unwrap_or_default())
+            }
+        }
+    }
+}
+
+/// Parse the names [displayed](Display) for the colors, ignoring the case and with `_` or `-` in
+/// the names of the bright colors. RGB colors are also parsed from `rgb(229, 160, 13)`.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+///
+/// assert_eq!("Bright_Red".parse(), Ok(CargoColor::BrightRed));
+/// assert_eq!("#E5A00D".parse(), Ok(CargoColor::Rgb(0xe5, 0xa0, 0x0d)));
+/// assert_eq!("rgb(229, 160, 13)".parse(), Ok(CargoColor::Rgb(0xe5, 0xa0, 0x0d)));
+/// assert!("purple".parse::<CargoColor>().is_err());
+/// ```
+impl FromStr for CargoColor {
+    type Err = ColorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let color = s.trim().to_ascii_lowercase().replace('_', "-");
+        let parsed = if let Some(hex) = color.strip_prefix('#') {
+            hex_color(hex)
+        } else if let Some(arguments) = call(&color, "rgb") {
+            rgb_color(arguments)
+        } else if let Some(argument) = call(&color, "ansi256") {
+            argument.trim().parse().ok().map(CargoColor::Ansi256)
+        } else {
+            let named = NAMES.iter().find(|(_, name)| *name == color);
+            named.map(|(color, _)| *color)
+        };
+        parsed.ok_or_else(|| ColorParseError {
+            input: s.to_string(),
+        })
+    }
+}
+
+/// The arguments of `name(arguments)`.
+fn call<'t>(text: &'t str, name: &str) -> Option<&'t str> {
+    let arguments = text.strip_prefix(name)?.trim_start().strip_prefix('(')?;
+    arguments.strip_suffix(')')
+}
+
+/// The color of `rrggbb`.
+fn hex_color(hex: &str) -> Option<CargoColor> {
+    if hex.len() != 6 || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let component = |start: usize| u8::from_str_radix(&hex[start..start + 2], 16).ok();
+    Some(CargoColor::Rgb(component(0)?, component(2)?, component(4)?))
+}
+
+/// The color of `r, g, b`.
+fn rgb_color(arguments: &str) -> Option<CargoColor> {
+    let components: Vec<u8> = arguments
+        .split(',')
+        .map(|component| component.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match components[..] {
+        [r, g, b] => Some(CargoColor::Rgb(r, g, b)),
+        _ => None,
+    }
+}
+
+/// Colors are serialized as the names of their variants, i.e. `"BrightRed"` or
+/// `{"Rgb":[229,160,13]}`, and deserialized from those or from the names of [`FromStr`], i.e.
+/// `"bright-red"` or `"#e5a00d"`, the ones of configuration files.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+///
+/// let colors: Vec<CargoColor> = serde_json::from_str(r#"["BrightRed", "bright-red"]"#).unwrap();
+/// assert_eq!(colors, [CargoColor::BrightRed, CargoColor::BrightRed]);
+/// ```
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CargoColor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Serialized {
+            Name(String),
+            Variant(Variant),
+        }
+
+        #[derive(serde::Deserialize)]
+        enum Variant {
+            Rgb(u8, u8, u8),
+            Ansi256(u8),
+        }
+
+        match Serialized::deserialize(deserializer)? {
+            Serialized::Name(name) => NAMES
+                .iter()
+                .find(|(color, _)| format!("{:?}", color) == name)
+                .map(|(color, _)| Ok(*color))
+                .unwrap_or_else(|| name.parse().map_err(serde::de::Error::custom)),
+            Serialized::Variant(Variant::Rgb(r, g, b)) => Ok(CargoColor::Rgb(r, g, b)),
+            Serialized::Variant(Variant::Ansi256(index)) => Ok(CargoColor::Ansi256(index)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::NAMES;
+    use crate::CargoColor;
+
+    #[test]
+    fn test_round_trip() {
+        let others = [
+            CargoColor::Rgb(0xe5, 0xa0, 0x0d),
+            CargoColor::Rgb(0, 0, 0),
+            CargoColor::Rgb(255, 255, 255),
+            CargoColor::Ansi256(0),
+            CargoColor::Ansi256(208),
+        ];
+        for color in NAMES.iter().map(|(color, _)| *color).chain(others) {
+            assert_eq!(color.to_string().parse(), Ok(color), "{}", color);
+        }
+        assert_eq!(CargoColor::BrightRed.to_string(), "bright-red");
+        assert_eq!(CargoColor::Rgb(0xe5, 0xa0, 0x0d).to_string(), "#e5a00d");
+        assert_eq!(CargoColor::Ansi256(208).to_string(), "ansi256(208)");
+    }
+
+    #[test]
+    fn test_parse() {
+        let parsed = [
+            ("GREEN", CargoColor::Green),
+            (" bright_red ", CargoColor::BrightRed),
+            ("Bright-Black", CargoColor::BrightBlack),
+            ("#E5A00D", CargoColor::Rgb(0xe5, 0xa0, 0x0d)),
+            ("rgb(229,160,13)", CargoColor::Rgb(229, 160, 13)),
+            ("RGB( 1, 2 , 3 )", CargoColor::Rgb(1, 2, 3)),
+            ("ansi256(15)", CargoColor::Ansi256(15)),
+        ];
+        for (text, color) in parsed {
+            assert_eq!(text.parse(), Ok(color), "{}", text);
+        }
+        for text in [
+            "purple",
+            "",
+            "brightred",
+            "#e5a00",
+            "#e5a00g",
+            "#\u{e9}\u{e9}aa",
+            "rgb(256, 0, 0)",
+            "rgb(1, 2)",
+            "rgb(1, 2, 3",
+            "ansi256(-1)",
+        ] {
+            let error = text.parse::<CargoColor>().unwrap_err();
+            assert_eq!(error.input(), text);
+        }
+        let error = "purple".parse::<CargoColor>().unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("invalid color `purple`, expected one of `green`, `cyan`, "));
+        assert!(error
+            .to_string()
+            .ends_with("`bright-black`, `#rrggbb`, `rgb(r, g, b)` or `ansi256(n)`"));
+    }
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde() {
+        let others = [CargoColor::Rgb(0xe5, 0xa0, 0x0d), CargoColor::Ansi256(208)];
+        for color in NAMES.iter().map(|(color, _)| *color).chain(others) {
+            let json = serde_json::to_string(&color).unwrap();
+            assert_eq!(serde_json::from_str::<CargoColor>(&json).unwrap(), color);
+            let name = serde_json::to_string(&color.to_string()).unwrap();
+            assert_eq!(serde_json::from_str::<CargoColor>(&name).unwrap(), color);
+        }
+        assert_eq!(
+            serde_json::to_string(&CargoColor::BrightRed).unwrap(),
+            "\"BrightRed\""
+        );
+        assert_eq!(
+            serde_json::to_string(&CargoColor::Rgb(1, 2, 3)).unwrap(),
+            "{\"Rgb\":[1,2,3]}"
+        );
+        let error = serde_json::from_str::<CargoColor>("\"purple\"").unwrap_err();
+        assert!(error.to_string().starts_with("invalid color `purple`"));
+        assert!(serde_json::from_str::<CargoColor>("{\"Hsl\":[1,2,3]}").is_err());
+    }
+}
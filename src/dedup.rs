@@ -0,0 +1,225 @@
+//! Collapsing of the bursts of identical messages into a `... repeated 57 times` line, like the
+//! `last message repeated` lines of syslog.
+//!
+//! Off by default, see [`set_dedup_window`]. A message of the preset macros identical to the one
+//! printed just before it, status and message alike, is held back while it comes within the
+//! window of the first one. The count of the held back messages is printed, dimmed, to the stream
+//! of the first one when a different message comes, when an identical one comes after the window
+//! or on [`flush`](crate::flush) and [`shutdown`](crate::shutdown).
+//!
+//! The messages to a [custom](CarlogStream::Custom), [file](CarlogStream::File) or
+//! [boxed](CarlogStream::Boxed) stream, which can't be written to after the call, are never held
+//! back.
+//!
+//! ## Example
+//! ```
+//! #[macro_use] extern crate carlog;
+//!
+//! use std::time::Duration;
+//!
+//! carlog::set_dedup_window(Duration::from_secs(1));
+//! for _ in 0..58 {
+//!     carlog_info!("Waiting", "for the file lock on the build directory");
+//! }
+//! carlog::flush().unwrap(); // "         ... repeated 57 times"
+//! # carlog::set_dedup_window(Duration::ZERO);
+//! ```
+
+use crate::stream::Stored;
+use crate::{color, fmt, time, CarlogStream, Error, Record, Status};
+use colored::Colorize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STATE: Mutex<State> = Mutex::new(State {
+    window: Duration::ZERO,
+    last: None,
+});
+
+struct State {
+    window: Duration,
+    last: Option<Last>,
+}
+
+/// The last message printed and the identical ones held back since.
+struct Last {
+    status: String,
+    message: String,
+    stream: Stored,
+    first: Instant,
+    repeats: usize,
+}
+
+/// Hold back the messages identical to the one before them within `window` of its first print,
+/// `Duration::ZERO` (the default) to print every message.
+///
+/// The count held back so far is printed first when the window changes.
+pub fn set_dedup_window(window: Duration) {
+    let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = release(&mut state);
+    state.window = window;
+    ENABLED.store(!window.is_zero(), Ordering::Relaxed);
+}
+
+/// The window of the messages held back, see [`set_dedup_window`].
+pub fn dedup_window() -> Duration {
+    STATE.lock().unwrap_or_else(|e| e.into_inner()).window
+}
+
+/// Whether `record`, about to be printed to `stream`, repeats the last message, which then counts
+/// it instead.
+pub(crate) fn hold(record: &Record, stream: &CarlogStream<'_>) -> Result<bool, Error> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return Ok(false);
+    }
+    let (status, message) = (record.status(), record.line_message());
+    let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let now = time::monotonic_now();
+    let window = state.window;
+    if let Some(last) = &mut state.last {
+        if last.status == status
+            && last.message == message
+            && now.saturating_duration_since(last.first) < window
+        {
+            last.repeats += 1;
+            return Ok(true);
+        }
+    }
+    release(&mut state)?;
+    state.last = Stored::retain(stream).map(|stream| Last {
+        status: status.to_string(),
+        message,
+        stream,
+        first: now,
+        repeats: 0,
+    });
+    Ok(false)
+}
+
+/// Print the count of the messages held back, if any.
+pub(crate) fn flush() -> Result<(), Error> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    release(&mut STATE.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+fn release(state: &mut State) -> Result<(), Error> {
+    match state.last.take() {
+        Some(last) if last.repeats > 0 => {
+            let repeated = format!(" repeated {}", fmt::count(last.repeats, "time"));
+            Status::plain()
+                .justify()
+                .dimmed()
+                .status("...")
+                .print_stream(&mut last.stream.stream(), color::paint(repeated.dimmed()))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::set_dedup_window;
+    use crate::test::{auto_colors, force_colors, global_lock};
+    use crate::{carlog_info, carlog_warning, set_monotonic_clock, CarlogStream};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex, OnceLock};
+    use std::time::{Duration, Instant};
+
+    static ELAPSED_MS: AtomicU64 = AtomicU64::new(0);
+
+    fn clock() -> Instant {
+        static START: OnceLock<Instant> = OnceLock::new();
+        *START.get_or_init(Instant::now) + Duration::from_millis(ELAPSED_MS.load(Ordering::Relaxed))
+    }
+
+    fn with_window<F>(f: F) -> String
+    where
+        F: FnOnce(&dyn Fn() -> CarlogStream<'static>),
+    {
+        let _lock = global_lock();
+        auto_colors();
+        ELAPSED_MS.store(0, Ordering::Relaxed);
+        set_monotonic_clock(clock);
+        set_dedup_window(Duration::from_secs(1));
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        f(&|| CarlogStream::from(Arc::clone(&output)));
+        set_dedup_window(Duration::ZERO);
+        set_monotonic_clock(Instant::now);
+        force_colors();
+        let output = output.lock().unwrap().clone();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_burst() {
+        let output = with_window(|stream| {
+            for _ in 0..58 {
+                carlog_info!("Waiting", "for the file lock", stream());
+                ELAPSED_MS.fetch_add(10, Ordering::Relaxed);
+            }
+            // After the window of the first one.
+            ELAPSED_MS.fetch_add(1000, Ordering::Relaxed);
+            carlog_info!("Waiting", "for the file lock", stream());
+            carlog_info!("Waiting", "for the file lock", stream());
+            crate::flush().unwrap();
+        });
+        assert_eq!(
+            output,
+            "     Waiting for the file lock\n         ... repeated 57 times\n     \
+             Waiting for the file lock\n         ... repeated 1 time\n"
+        );
+    }
+
+    #[test]
+    fn test_interleaved() {
+        let output = with_window(|stream| {
+            carlog_info!("Waiting", "for the file lock", stream());
+            carlog_info!("Waiting", "for the file lock", stream());
+            carlog_warning!("the lock is stale", stream());
+            carlog_info!("Waiting", "for the file lock", stream());
+            carlog_info!("Waiting", "for a job slot", stream());
+            crate::flush().unwrap();
+        });
+        assert_eq!(
+            output,
+            "     Waiting for the file lock\n         ... repeated 1 time\nwarning: the lock is \
+             stale\n     Waiting for the file lock\n     Waiting for a job slot\n"
+        );
+    }
+
+    #[test]
+    fn test_flush_at_exit() {
+        let mut custom = Vec::<u8>::new();
+        let output = with_window(|stream| {
+            for _ in 0..3 {
+                carlog_info!("Waiting", "for the file lock", stream());
+            }
+            // Written to by the count of the message before it.
+            for _ in 0..3 {
+                carlog_info!(
+                    "Blocking",
+                    "on a custom stream",
+                    CarlogStream::Custom(&mut custom)
+                );
+            }
+            for _ in 0..4 {
+                carlog_info!("Waiting", "for the file lock", stream());
+            }
+            crate::flush().unwrap();
+            crate::flush().unwrap();
+        });
+        assert_eq!(
+            output,
+            "     Waiting for the file lock\n         ... repeated 2 times\n     \
+             Waiting for the file lock\n         ... repeated 3 times\n"
+        );
+        assert_eq!(
+            String::from_utf8(custom).unwrap(),
+            "    Blocking on a custom stream\n".repeat(3)
+        );
+    }
+}
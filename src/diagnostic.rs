@@ -0,0 +1,452 @@
+//! Diagnostics about the files of the users, rendered like the ones of rustc.
+//!
+//! A [`Diagnostic`] is an `error:` or `warning:` line, the `-->` line of its location, the lines
+//! of source it points to with the span underlined, then its notes and help:
+//! ```text
+//! error[E0308]: mismatched types
+//!  --> src/main.rs:2:18
+//!   |
+//! 2 |     let x: u32 = "7";
+//!   |                  ^^^
+//!   |
+//!   = help: change the type of `x` to `&str`
+//! ```
+//! Tabs in the source are expanded to 4 columns, and the underline follows them and the wide
+//! characters. A span longer than the rest of its line goes on in the next lines of the source,
+//! each line underlined for its part of the span.
+//!
+//! ## Example
+//! ```
+//! use carlog::diagnostic::Diagnostic;
+//! use carlog::prelude::*;
+//!
+//! Diagnostic::new(Level::Error, "mismatched types")
+//!     .code("E0308")
+//!     .span("src/main.rs", 2, 18, 3)
+//!     .source("    let x: u32 = \"7\";")
+//!     .help("change the type of `x` to `&str`")
+//!     .emit(CarlogStream::Stderr)
+//!     .unwrap();
+//! ```
+
+use crate::attributes::Attributes;
+use crate::{fmt, summary, theme, CargoColor, CarlogStream, Error, Format, Level, Status};
+
+/// The width of the tab stops of the source lines.
+const TAB_WIDTH: usize = 4;
+
+/// A problem in a file of the user, see the [module](self).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    level: Level,
+    message: String,
+    code: Option<String>,
+    span: Option<Span>,
+    source: Option<String>,
+    children: Vec<(Child, String)>,
+}
+
+/// The part of a file a diagnostic points to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Span {
+    pub(crate) path: String,
+    pub(crate) line: u32,
+    pub(crate) column: u32,
+    /// The length in characters, see [`Diagnostic::span`].
+    pub(crate) len: usize,
+}
+
+/// The kind of a line after the snippet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Child {
+    Note,
+    Help,
+}
+
+/// A line of the snippet: its number, its text with the tabs expanded, and the columns of its
+/// underline.
+struct SnippetLine {
+    number: u32,
+    text: String,
+    underline: Option<(usize, usize)>,
+}
+
+impl Diagnostic {
+    /// Creates a diagnostic of `level`, a `note` for the levels below [`Level::Warning`].
+    pub fn new<M>(level: Level, message: M) -> Self
+    where
+        M: AsRef<str>,
+    {
+        Self {
+            level,
+            message: message.as_ref().to_string(),
+            code: None,
+            span: None,
+            source: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Set the code of the diagnostic, shown next to its status, i.e. `error[E0308]`.
+    pub fn code<S>(mut self, code: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.code = Some(code.as_ref().to_string());
+        self
+    }
+
+    /// Point the diagnostic to `len` characters of `path` from `line` and `column`, both starting
+    /// at 1.
+    ///
+    /// The newlines of a span over several lines count as one character.
+    pub fn span<P>(mut self, path: P, line: u32, column: u32, len: usize) -> Self
+    where
+        P: AsRef<str>,
+    {
+        self.span = Some(Span {
+            path: path.as_ref().to_string(),
+            line,
+            column,
+            len,
+        });
+        self
+    }
+
+    /// Set the source the span points to, from the start of the line of the span. The source
+    /// holds as many lines as the span goes over.
+    ///
+    /// Without it, only the location of the span is shown.
+    pub fn source<S>(mut self, source: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.source = Some(source.as_ref().to_string());
+        self
+    }
+
+    /// Add a `note:` line after the snippet.
+    pub fn note<S>(mut self, note: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.children.push((Child::Note, note.as_ref().to_string()));
+        self
+    }
+
+    /// Add a `help:` line after the snippet.
+    pub fn help<S>(mut self, help: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.children.push((Child::Help, help.as_ref().to_string()));
+        self
+    }
+
+    /// Print the diagnostic to `stream`, unless its level is filtered out.
+    ///
+    /// Warnings and errors are counted in the [summary](crate::print_summary). With
+    /// [`Format::Short`] only the first line is printed, located at the span, i.e.
+    /// `src/main.rs:2:18: error[E0308]: mismatched types`.
+    ///
+    /// * `stream`: The stream where the diagnostic will be written.
+    pub fn emit(&self, mut stream: CarlogStream<'_>) -> Result<(), Error> {
+        if !crate::__enabled(self.level) {
+            return Ok(());
+        }
+        summary::count(self.level, self.code.as_deref());
+        let status = self.status();
+        if crate::format() == Format::Short {
+            let status = match &self.span {
+                Some(span) => status.location(&span.path, span.line, span.column),
+                None => status,
+            };
+            return status.print_stream(&mut stream, format!(": {}", self.message));
+        }
+        status.print_stream(&mut stream, self.body())
+    }
+
+    /// The status of the first line, i.e. `error[E0308]`.
+    fn status(&self) -> Status {
+        let (label, color) = match self.level {
+            Level::Trace | Level::Debug | Level::Info => ("note", theme().info),
+            Level::Warning => ("warning", theme().warning),
+            Level::Error => ("error", theme().error),
+        };
+        let status = Status::plain().color(color).status(label).level(self.level);
+        match &self.code {
+            Some(code) => status.code(code),
+            None => status,
+        }
+    }
+
+    /// Everything after the status.
+    fn body(&self) -> String {
+        let mut body = format!(": {}", self.message);
+        let Some(span) = &self.span else {
+            for (child, text) in &self.children {
+                body.push('\n');
+                body.push_str(&child_line(*child, text));
+            }
+            return body;
+        };
+        let (gutter, shown) = write_span(
+            &mut body,
+            "",
+            span,
+            self.source.as_deref(),
+            None,
+            self.status().color,
+        );
+        let bar = gutter_str("|");
+        if !self.children.is_empty() && shown {
+            body.push_str(&format!("\n{} {}", gutter, bar));
+        }
+        for (child, text) in &self.children {
+            body.push_str(&format!(
+                "\n{} {} {}",
+                gutter,
+                gutter_str("="),
+                child_line(*child, text)
+            ));
+        }
+        body
+    }
+}
+
+/// Append the `-->` line of `span` and the lines of `source` it goes over, if any, at the end of
+/// `body`, with the span underlined in `color` and followed by `label`. Every line starts with
+/// `indent`.
+///
+/// Returns the gutter of the snippet and whether it has any source line.
+pub(crate) fn write_span(
+    body: &mut String,
+    indent: &str,
+    span: &Span,
+    source: Option<&str>,
+    label: Option<&str>,
+    color: CargoColor,
+) -> (String, bool) {
+    let lines = source.map_or_else(Vec::new, |source| snippet(span, source));
+    let last = lines.last().map_or(span.line, |line| line.number);
+    let gutter = " ".repeat(last.to_string().len());
+    let bar = gutter_str("|");
+    body.push_str(&format!(
+        "\n{}{}{} {}:{}:{}",
+        indent,
+        gutter,
+        gutter_str("-->"),
+        span.path,
+        span.line,
+        span.column
+    ));
+    if !lines.is_empty() {
+        body.push_str(&format!("\n{}{} {}", indent, gutter, bar));
+    }
+    let underlined = lines.iter().rposition(|line| line.underline.is_some());
+    for (i, line) in lines.iter().enumerate() {
+        let number = format!("{:>1$}", line.number, gutter.len());
+        body.push_str(&format!("\n{}{} {}", indent, gutter_str(&number), bar));
+        if !line.text.is_empty() {
+            body.push(' ');
+            body.push_str(&line.text);
+        }
+        if let Some((start, width)) = line.underline {
+            let mut carets = "^".repeat(width);
+            // The label follows the end of the span.
+            if let Some(label) = label.filter(|_| Some(i) == underlined) {
+                carets = format!("{} {}", carets, label);
+            }
+            body.push_str(&format!(
+                "\n{}{} {} {}{}",
+                indent,
+                gutter,
+                bar,
+                " ".repeat(start),
+                Status::color_str(color, Attributes::BOLD, carets)
+            ));
+        }
+    }
+    (format!("{}{}", indent, gutter), !lines.is_empty())
+}
+
+/// The lines of `source` that `span` goes over, see [`Diagnostic::source`].
+fn snippet(span: &Span, source: &str) -> Vec<SnippetLine> {
+    let mut lines = Vec::new();
+    let mut remaining = span.len.max(1);
+    let mut column = span.column.saturating_sub(1) as usize;
+    for (number, text) in (span.line..).zip(source.lines()) {
+        let chars: Vec<char> = text.chars().collect();
+        let start = column.min(chars.len());
+        let covered = remaining.min(chars.len() - start);
+        // The newline at the end of the line is part of the span too.
+        remaining = remaining.saturating_sub(covered + 1);
+        let columns = |end: usize| {
+            let prefix: String = chars[..end].iter().collect();
+            fmt::display_width(&fmt::expand_tabs(&prefix, TAB_WIDTH))
+        };
+        let (from, to) = (columns(start), columns(start + covered));
+        let underline = match lines.is_empty() {
+            // The first line always points somewhere, even past its end.
+            true => Some((from, (to - from).max(1))),
+            false => (to > from).then_some((from, to - from)),
+        };
+        lines.push(SnippetLine {
+            number,
+            text: fmt::expand_tabs(text, TAB_WIDTH),
+            underline,
+        });
+        if remaining == 0 {
+            break;
+        }
+        column = 0;
+    }
+    lines
+}
+
+/// A piece of the gutter, bold in the color of `carlog_info!`.
+fn gutter_str(piece: &str) -> String {
+    Status::color_str(theme().info, Attributes::BOLD, piece)
+}
+
+/// A `note: ` or `help: ` line, like `carlog_note!` and `carlog_help!` print them.
+fn child_line(child: Child, text: &str) -> String {
+    let (label, color) = match child {
+        Child::Note => ("note", CargoColor::BrightWhite),
+        Child::Help => ("help", CargoColor::BrightCyan),
+    };
+    format!(
+        "{}: {}",
+        Status::color_str(color, Attributes::BOLD, label),
+        text
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::Diagnostic;
+    use crate::test::{auto_colors, force_colors, global_lock};
+    use crate::{set_format, set_max_level, CarlogStream, Format, Level};
+
+    fn emitted(diagnostic: &Diagnostic) -> String {
+        let mut output = Vec::<u8>::new();
+        diagnostic.emit(CarlogStream::Custom(&mut output)).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn plain(diagnostic: &Diagnostic) -> String {
+        auto_colors();
+        let output = emitted(diagnostic);
+        force_colors();
+        output
+    }
+
+    #[test]
+    fn test_snippet() {
+        let _lock = global_lock();
+        force_colors();
+        let diagnostic = Diagnostic::new(Level::Error, "mismatched types")
+            .code("E0308")
+            .span("src/main.rs", 2, 18, 3)
+            .source("    let x: u32 = \"7\";")
+            .note("expected `u32`, found `&str`")
+            .help("change the type of `x` to `&str`");
+        assert_eq!(
+            emitted(&diagnostic),
+            "\u{1b}[91merror[E0308]\u{1b}[0m: mismatched types\n \u{1b}[1;36m-->\u{1b}[0m \
+             src/main.rs:2:18\n  \u{1b}[1;36m|\u{1b}[0m\n\u{1b}[1;36m2\u{1b}[0m \u{1b}[1;36m|\
+             \u{1b}[0m     let x: u32 = \"7\";\n  \u{1b}[1;36m|\u{1b}[0m                  \
+             \u{1b}[1;91m^^^\u{1b}[0m\n  \u{1b}[1;36m|\u{1b}[0m\n  \u{1b}[1;36m=\u{1b}[0m \
+             \u{1b}[1;97mnote\u{1b}[0m: expected `u32`, found `&str`\n  \u{1b}[1;36m=\u{1b}[0m \
+             \u{1b}[1;96mhelp\u{1b}[0m: change the type of `x` to `&str`\n"
+        );
+        assert_eq!(
+            plain(&diagnostic),
+            "error[E0308]: mismatched types\n \
+             --> src/main.rs:2:18\n  \
+             |\n\
+             2 |     let x: u32 = \"7\";\n  \
+             |                  ^^^\n  \
+             |\n  \
+             = note: expected `u32`, found `&str`\n  \
+             = help: change the type of `x` to `&str`\n"
+        );
+    }
+
+    #[test]
+    fn test_multi_line_span() {
+        let _lock = global_lock();
+        // From `(` to `)`, over the newlines and the empty line.
+        let diagnostic = Diagnostic::new(Level::Warning, "unbalanced call")
+            .span("src/lib.rs", 9, 16, 19)
+            .source("    let x = foo(\n        1,\n\n    );\n    bar();");
+        assert_eq!(
+            plain(&diagnostic),
+            "warning: unbalanced call\n  \
+             --> src/lib.rs:9:16\n   \
+             |\n 9 |     let x = foo(\n   \
+             |                ^\n\
+             10 |         1,\n   \
+             | ^^^^^^^^^^\n\
+             11 |\n\
+             12 |     );\n   \
+             | ^^^^^\n"
+        );
+    }
+
+    #[test]
+    fn test_tabs_and_wide_characters() {
+        let _lock = global_lock();
+        let diagnostic = Diagnostic::new(Level::Error, "undefined name")
+            .span("config.toml", 3, 7, 1)
+            .source("\tk\t= \u{4f60}x");
+        // The tabs stop at 4 and 8 columns, `你` takes two.
+        assert_eq!(
+            plain(&diagnostic),
+            "error: undefined name\n \
+             --> config.toml:3:7\n  \
+             |\n\
+             3 |     k   = \u{4f60}x\n  \
+             |             ^\n"
+        );
+        let past_the_end = Diagnostic::new(Level::Error, "expected `]`")
+            .span("config.toml", 1, 8, 0)
+            .source("[table")
+            .note("the table starts here");
+        assert_eq!(
+            plain(&past_the_end),
+            "error: expected `]`\n \
+             --> config.toml:1:8\n  \
+             |\n\
+             1 | [table\n  \
+             |       ^\n  \
+             |\n  \
+             = note: the table starts here\n"
+        );
+    }
+
+    #[test]
+    fn test_without_snippet() {
+        let _lock = global_lock();
+        let located = Diagnostic::new(Level::Warning, "unused manifest key")
+            .span("Cargo.toml", 7, 1, 9)
+            .note("the key is ignored");
+        assert_eq!(
+            plain(&located),
+            "warning: unused manifest key\n --> Cargo.toml:7:1\n  = note: the key is ignored\n"
+        );
+        let bare = Diagnostic::new(Level::Info, "the lock file is up to date")
+            .help("run `cargo update` to refresh it");
+        assert_eq!(
+            plain(&bare),
+            "note: the lock file is up to date\nhelp: run `cargo update` to refresh it\n"
+        );
+        set_format(Format::Short);
+        let short = plain(&located);
+        set_max_level(Level::Error);
+        let filtered = plain(&located);
+        crate::settings::reset();
+        assert_eq!(short, "Cargo.toml:7:1: warning: unused manifest key\n");
+        assert_eq!(filtered, "");
+    }
+}
@@ -0,0 +1,37 @@
+//! The environment variables the output is configured from.
+
+#[cfg(not(test))]
+use std::env;
+
+/// Where environment variables are read from, so that detection can be tested.
+pub(crate) trait EnvSource {
+    /// The value of the variable `name`, if it's set.
+    fn var(&self, name: &str) -> Option<String>;
+}
+
+/// The environment of the process.
+///
+/// Tests see an empty environment instead, so they pass wherever they run, i.e. with
+/// `CARGO_TERM_COLOR=always` in CI.
+pub(crate) struct ProcessEnv;
+
+impl EnvSource for ProcessEnv {
+    #[cfg(not(test))]
+    fn var(&self, name: &str) -> Option<String> {
+        env::var_os(name).map(|value| value.to_string_lossy().into_owned())
+    }
+
+    #[cfg(test)]
+    fn var(&self, _: &str) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+impl<const N: usize> EnvSource for [(&str, &str); N] {
+    fn var(&self, name: &str) -> Option<String> {
+        self.iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+    }
+}
@@ -0,0 +1,368 @@
+//! The error of a failed print, with what was being printed and where.
+
+use crate::{fmt, Target};
+use std::error;
+use std::fmt::{self as std_fmt, Display, Formatter};
+use std::io;
+use std::sync::Mutex;
+
+static ERROR_HANDLER: Mutex<fn(Error)> = Mutex::new(panic_handler);
+
+/// The number of characters of the message kept in the preview of an [`Error`].
+const PREVIEW_LEN: usize = 40;
+
+/// A failure to print a status, i.e. because the disk of a log file is full.
+///
+/// Besides the I/O error it tells the destination (`stdout`, `stderr`, the path of a
+/// [`FileSink`](crate::sinks::FileSink) or the name of a [`Named`](crate::sinks::Named) stream),
+/// the status text and the start of the message that was lost.
+///
+/// It converts from and into [`io::Error`], so the `?` operator works in functions returning
+/// either. An `io::Error` converted from an `Error` keeps its kind and its message.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::sinks::Named;
+///
+/// let mut buffer = [0u8; 8];
+/// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
+/// let error = status
+///     .print(Named::new("the buffer", &mut buffer[..]), " carlog v0.1.0")
+///     .unwrap_err();
+/// assert_eq!(error.destination(), Some("the buffer"));
+/// assert_eq!(error.status(), Some("Compiled"));
+/// ```
+#[derive(Debug)]
+pub struct Error {
+    source: io::Error,
+    context: Option<Context>,
+}
+
+#[derive(Debug)]
+struct Context {
+    destination: String,
+    status: String,
+    preview: String,
+}
+
+/// An I/O error of a stream with a description of the stream, see [`destined`].
+#[derive(Debug)]
+struct Destined {
+    destination: String,
+    source: io::Error,
+}
+
+impl Display for Destined {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std_fmt::Result {
+        write!(f, "{} ({})", self.source, self.destination)
+    }
+}
+
+impl error::Error for Destined {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Attach the description of the stream that failed to an I/O error, so that a status printed to
+/// the stream reports it as its destination.
+pub(crate) fn destined<D>(error: io::Error, destination: D) -> io::Error
+where
+    D: Display,
+{
+    if error.get_ref().is_some_and(|inner| inner.is::<Destined>()) {
+        return error;
+    }
+    io::Error::new(
+        error.kind(),
+        Destined {
+            destination: destination.to_string(),
+            source: error,
+        },
+    )
+}
+
+impl Error {
+    /// The error of printing `status` and `msg` to `target`.
+    pub(crate) fn printing(source: io::Error, target: Target, status: &str, msg: &str) -> Self {
+        let (destination, source) = if source.get_ref().is_some_and(|e| e.is::<Destined>()) {
+            let destined = source.into_inner().unwrap().downcast::<Destined>().unwrap();
+            (destined.destination, destined.source)
+        } else {
+            (default_destination(target).to_string(), source)
+        };
+        let msg = fmt::strip_ansi(msg);
+        let msg = msg.trim_start_matches(':').trim();
+        let mut preview: String = msg.chars().take(PREVIEW_LEN).collect();
+        if msg.chars().count() > PREVIEW_LEN {
+            preview.push('…');
+        }
+        Self {
+            source,
+            context: Some(Context {
+                destination,
+                status: fmt::strip_ansi(status),
+                preview,
+            }),
+        }
+    }
+
+    /// The kind of the I/O error.
+    pub fn kind(&self) -> io::ErrorKind {
+        self.source.kind()
+    }
+
+    /// The I/O error.
+    pub fn io_error(&self) -> &io::Error {
+        &self.source
+    }
+
+    /// Where the status was printed, i.e. `stderr` or the path of a log file, unless the error was
+    /// converted from an [`io::Error`].
+    pub fn destination(&self) -> Option<&str> {
+        self.context.as_ref().map(|c| c.destination.as_str())
+    }
+
+    /// The text of the status printed, without its style.
+    pub fn status(&self) -> Option<&str> {
+        self.context.as_ref().map(|c| c.status.as_str())
+    }
+
+    /// The start of the message printed, without its style.
+    pub fn preview(&self) -> Option<&str> {
+        self.context.as_ref().map(|c| c.preview.as_str())
+    }
+}
+
+/// The destination of the streams that don't describe themselves.
+fn default_destination(target: Target) -> &'static str {
+    match target {
+        Target::Stdout => "stdout",
+        Target::Stderr => "stderr",
+        Target::Custom => "a custom stream",
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std_fmt::Result {
+        match &self.context {
+            Some(context) => write!(
+                f,
+                "failed to print `{}` with message `{}` to {}: {}",
+                context.status, context.preview, context.destination, self.source
+            ),
+            None => write!(f, "{}", self.source),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        match error.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+            true => *error.into_inner().unwrap().downcast::<Error>().unwrap(),
+            false => Self {
+                source: error,
+                context: None,
+            },
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        match error.context {
+            Some(_) => io::Error::new(error.kind(), error),
+            None => error.source,
+        }
+    }
+}
+
+/// Choose what the macros do with a failure to print, i.e. because a log file is on a full disk.
+///
+/// The default handler panics. Broken pipes, i.e. when the output is piped to `head` and `head`
+/// exits, don't reach the handler: the macros ignore them, like the failures after
+/// [`shutdown`](crate::shutdown). The `try_` variants of the macros, i.e. `try_carlog_info!`,
+/// return the failures instead.
+///
+/// ## Example
+/// ```
+/// fn log_failure(error: carlog::Error) {
+///     eprintln!("lost a message: {}", error);
+/// }
+///
+/// carlog::set_error_handler(log_failure);
+/// ```
+pub fn set_error_handler(handler: fn(Error)) {
+    *ERROR_HANDLER.lock().unwrap_or_else(|e| e.into_inner()) = handler;
+}
+
+/// The default error handler, see [`set_error_handler`].
+fn panic_handler(error: Error) {
+    panic!("Failed to print to the carlog stream!: {}", error);
+}
+
+/// Handle the result of a print from a macro, see [`set_error_handler`].
+#[doc(hidden)]
+pub fn __printed(result: Result<(), Error>) {
+    let Err(error) = result else {
+        return;
+    };
+    if error.kind() == io::ErrorKind::BrokenPipe || crate::is_shut_down() {
+        return;
+    }
+    let handler = *ERROR_HANDLER.lock().unwrap_or_else(|e| e.into_inner());
+    handler(error);
+}
+
+/// Restore the default error handler.
+#[cfg(test)]
+pub(crate) fn reset_error_handler() {
+    set_error_handler(panic_handler);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{destined, reset_error_handler, set_error_handler, Error};
+    use crate::sinks::{FileSink, Named};
+    use crate::test::{force_colors, global_lock};
+    use crate::{CargoColor, CarlogStream, Status};
+    use std::io::{self, Write};
+    use std::panic;
+    use std::sync::Mutex;
+
+    /// A stream that always fails with the same error.
+    struct Failing(io::ErrorKind);
+
+    impl Write for Failing {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(self.0.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(self.0.into())
+        }
+    }
+
+    #[test]
+    fn test_context() {
+        let _lock = global_lock();
+        force_colors();
+        let mut buffer = [0u8; 8];
+        let status = Status::new()
+            .bold()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Compiling");
+        let error = status
+            .print(
+                Named::new("`build.log`", &mut buffer[..]),
+                " carlog v0.1.0 (/home/user/projects/carlog) with a long message",
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+        assert_eq!(
+            error.to_string(),
+            "failed to print `Compiling` with message \
+             `carlog v0.1.0 (/home/user/projects/carlo…` to `build.log`: \
+             failed to write whole buffer"
+        );
+
+        let io_error = io::Error::from(error);
+        assert_eq!(io_error.kind(), io::ErrorKind::WriteZero);
+        assert!(io_error.to_string().contains("to `build.log`"));
+        let error = Error::from(io_error);
+        assert_eq!(error.status(), Some("Compiling"));
+
+        let plain = Error::from(io::Error::from(io::ErrorKind::BrokenPipe));
+        assert_eq!(plain.destination(), None);
+        assert_eq!(plain.to_string(), "broken pipe");
+        let named = destined(io::Error::from(io::ErrorKind::BrokenPipe), "a pipe");
+        assert_eq!(
+            destined(named, "another").to_string(),
+            "broken pipe (a pipe)"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_sink() {
+        let _lock = global_lock();
+        let Ok(mut sink) = FileSink::builder("/dev/full").header(false).open() else {
+            return;
+        };
+        let error = Status::plain()
+            .status("warning")
+            .print(&mut sink, ": unused import")
+            .unwrap_err();
+        assert_eq!(error.destination(), Some("`/dev/full`"));
+        assert_eq!(error.preview(), Some("unused import"));
+    }
+
+    #[test]
+    fn test_macro_failures() {
+        static HANDLED: Mutex<Vec<(io::ErrorKind, Option<String>)>> = Mutex::new(Vec::new());
+
+        fn handler(error: Error) {
+            let status = error.status().map(str::to_string);
+            HANDLED.lock().unwrap().push((error.kind(), status));
+        }
+
+        let _lock = global_lock();
+        let mut broken = Failing(io::ErrorKind::BrokenPipe);
+        let mut denied = Failing(io::ErrorKind::PermissionDenied);
+        // Broken pipes never panic nor reach the handler.
+        crate::carlog_info!("Compiling", "carlog", CarlogStream::Custom(&mut broken));
+        crate::carlog_error!("failed", CarlogStream::Custom(&mut broken));
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            crate::carlog_ok!("Finished", "dev", CarlogStream::Custom(&mut denied));
+        }));
+        assert!(result.is_err());
+
+        set_error_handler(handler);
+        crate::carlog_warning!("unused import", CarlogStream::Custom(&mut broken));
+        crate::carlog_warning!(label: "deprecated", "old API", CarlogStream::Custom(&mut denied));
+        crate::carlog!(
+            "Packaged",
+            "carlog",
+            true,
+            true,
+            CargoColor::Green,
+            CarlogStream::Custom(&mut denied)
+        );
+        reset_error_handler();
+        assert_eq!(
+            *HANDLED.lock().unwrap(),
+            [
+                (
+                    io::ErrorKind::PermissionDenied,
+                    Some("deprecated".to_string())
+                ),
+                (
+                    io::ErrorKind::PermissionDenied,
+                    Some("Packaged".to_string())
+                ),
+            ]
+        );
+
+        let error =
+            crate::try_carlog_info!("Compiling", "carlog", CarlogStream::Custom(&mut broken))
+                .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::BrokenPipe);
+        let error =
+            crate::try_carlog_error!(label: "security", "vulnerable", CarlogStream::Custom(&mut denied))
+                .unwrap_err();
+        assert_eq!(error.status(), Some("security"));
+        let mut output = Vec::<u8>::new();
+        crate::try_carlog_ok!(target: "build", "Finished", "dev", CarlogStream::Custom(&mut output))
+            .unwrap();
+        let output = crate::fmt::strip_ansi(&String::from_utf8(output).unwrap());
+        assert_eq!(output, "    Finished dev\n");
+    }
+}
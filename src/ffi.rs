@@ -0,0 +1,181 @@
+//! C interface, so that C and C++ code in the same program prints the same messages.
+//!
+//! Enabled with the `ffi` feature. The declarations are in `include/carlog.h`.
+//!
+//! Every function accepts null pointers, which are printed as empty strings, and invalid UTF-8,
+//! which is shown with the replacement character. Panics never cross the boundary: the functions
+//! return `-1` instead, and `0` on success.
+//!
+//! The messages go through the same path as the macros, so they follow the max level, the
+//! default streams, the warning and error counts and the structured formats set from Rust.
+
+use crate::record::{self, Record};
+use crate::{color, stream, CarlogStream, Error, Level};
+use std::borrow::Cow;
+use std::ffi::{c_char, c_int, CStr};
+use std::panic::{self, AssertUnwindSafe};
+
+/// Let colors be decided from the terminal, see [`carlog_set_color_choice`].
+pub const CARLOG_COLOR_AUTO: c_int = 0;
+/// Always print colors, see [`carlog_set_color_choice`].
+pub const CARLOG_COLOR_ALWAYS: c_int = 1;
+/// Never print colors, see [`carlog_set_color_choice`].
+pub const CARLOG_COLOR_NEVER: c_int = 2;
+
+/// Print an info like cargo message to the default stream, i.e. `   Compiling carlog v0.1.0`.
+///
+/// # Safety
+/// `status` and `msg` must each be null or point to a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn carlog_info(status: *const c_char, msg: *const c_char) -> c_int {
+    // SAFETY: guaranteed by the caller.
+    let (status, msg) = unsafe { (text(status), text(msg)) };
+    guard(|| info(stream::default_stream(), &status, &msg))
+}
+
+/// Print a warning like cargo message to the default error stream, i.e. `warning: unused variable`.
+///
+/// # Safety
+/// `msg` must be null or point to a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn carlog_warning(msg: *const c_char) -> c_int {
+    // SAFETY: guaranteed by the caller.
+    let msg = unsafe { text(msg) };
+    guard(|| warning(stream::default_error_stream(), &msg))
+}
+
+/// Print an error like cargo message to the default error stream, i.e. `error: could not compile`.
+///
+/// # Safety
+/// `msg` must be null or point to a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn carlog_error(msg: *const c_char) -> c_int {
+    // SAFETY: guaranteed by the caller.
+    let msg = unsafe { text(msg) };
+    guard(|| error(stream::default_error_stream(), &msg))
+}
+
+/// Choose whether colors are printed: one of [`CARLOG_COLOR_AUTO`], [`CARLOG_COLOR_ALWAYS`] or
+/// [`CARLOG_COLOR_NEVER`]. Returns `-1` for any other value.
+#[no_mangle]
+pub extern "C" fn carlog_set_color_choice(choice: c_int) -> c_int {
+    match choice {
+        CARLOG_COLOR_AUTO => color::unset_override(),
+        CARLOG_COLOR_ALWAYS => color::set_override(true),
+        CARLOG_COLOR_NEVER => color::set_override(false),
+        _ => return -1,
+    }
+    0
+}
+
+/// The text of a C string, empty for a null pointer.
+///
+/// # Safety
+/// `ptr` must be null or point to a nul terminated string.
+unsafe fn text<'a>(ptr: *const c_char) -> Cow<'a, str> {
+    if ptr.is_null() {
+        return Cow::Borrowed("");
+    }
+    // SAFETY: guaranteed by the caller.
+    unsafe { CStr::from_ptr(ptr) }.to_string_lossy()
+}
+
+/// Run `print`, turning errors and panics into `-1`.
+fn guard<F>(print: F) -> c_int
+where
+    F: FnOnce() -> Result<(), Error>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(print)) {
+        Ok(Ok(())) => 0,
+        _ => -1,
+    }
+}
+
+fn info(stream: CarlogStream<'_>, status: &str, msg: &str) -> Result<(), Error> {
+    print(Record::new(status, msg), stream)
+}
+
+fn warning(stream: CarlogStream<'_>, msg: &str) -> Result<(), Error> {
+    print(Record::new("warning", msg).level(Level::Warning), stream)
+}
+
+fn error(stream: CarlogStream<'_>, msg: &str) -> Result<(), Error> {
+    print(Record::new("error", msg).level(Level::Error), stream)
+}
+
+/// Print a record like the macros do, unless it's less severe than the max level.
+fn print(record: Record, stream: CarlogStream<'_>) -> Result<(), Error> {
+    if !crate::__enabled(record.severity()) {
+        return Ok(());
+    }
+    record::__print_record(record::kind(record.severity()), &record, stream)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        carlog_error, carlog_info, carlog_set_color_choice, carlog_warning, guard, info, text,
+        warning, CARLOG_COLOR_ALWAYS, CARLOG_COLOR_AUTO,
+    };
+    use crate::test::{force_colors, global_lock};
+    use crate::{settings, summary, CarlogStream, Level};
+    use std::ptr;
+
+    #[test]
+    fn test_text() {
+        let valid = c"carlog v0.1.0";
+        let invalid = c"caf\xe9";
+        unsafe {
+            assert_eq!(text(valid.as_ptr()), "carlog v0.1.0");
+            assert_eq!(text(invalid.as_ptr()), "caf\u{fffd}");
+            assert_eq!(text(ptr::null()), "");
+        }
+    }
+
+    #[test]
+    fn test_messages() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        let msg = unsafe { text(c"caf\xe9".as_ptr()) };
+        info(CarlogStream::Custom(&mut output), "Compiling", &msg).unwrap();
+        warning(CarlogStream::Custom(&mut output), "").unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "   \u{1b}[1;36mCompiling\u{1b}[0m caf\u{fffd}\n\u{1b}[93mwarning\u{1b}[0m: \n"
+        );
+    }
+
+    #[test]
+    fn test_follows_settings() {
+        let _lock = global_lock();
+        force_colors();
+        summary::reset_counts();
+        settings::set_max_level(Level::Error);
+        let mut output = Vec::<u8>::new();
+        warning(CarlogStream::Custom(&mut output), "filtered").unwrap();
+        assert!(output.is_empty());
+        assert_eq!(summary::warning_count(), 0);
+        settings::reset();
+        warning(CarlogStream::Custom(&mut output), "counted").unwrap();
+        assert_eq!(summary::warning_count(), 1);
+        summary::reset_counts();
+    }
+
+    #[test]
+    fn test_extern_functions() {
+        let _lock = global_lock();
+        let status = c"Testing".as_ptr();
+        let invalid = c"\xff\xfe".as_ptr();
+        unsafe {
+            assert_eq!(carlog_info(status, invalid), 0);
+            assert_eq!(carlog_info(ptr::null(), ptr::null()), 0);
+            assert_eq!(carlog_warning(ptr::null()), 0);
+            assert_eq!(carlog_error(invalid), 0);
+        }
+        assert_eq!(carlog_set_color_choice(42), -1);
+        assert_eq!(carlog_set_color_choice(CARLOG_COLOR_ALWAYS), 0);
+        assert_eq!(carlog_set_color_choice(CARLOG_COLOR_AUTO), 0);
+        assert_eq!(guard(|| panic!("boom")), -1);
+    }
+}
@@ -0,0 +1,713 @@
+//! Formatting helpers for the values shown in messages.
+//!
+//! ## Example
+//! ```
+//! use carlog::fmt;
+//!
+//! assert_eq!(fmt::count(1, "warning"), "1 warning");
+//! assert_eq!(fmt::count(3, "warning"), "3 warnings");
+//! assert_eq!(fmt::count_with(2, "dependency", "dependencies"), "2 dependencies");
+//! ```
+
+use crate::ansi::{self, Token};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Count of a noun, pluralized by appending an `s` unless `n` is one, i.e. `3 warnings`.
+///
+/// * `n`: The count.
+/// * `noun`: The singular form of the noun.
+pub fn count(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("{} {}", n, noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
+
+/// Count of a noun with an irregular plural, i.e. `2 dependencies`.
+///
+/// * `n`: The count.
+/// * `singular`: The form used when `n` is one.
+/// * `plural`: The form used otherwise, including zero.
+pub fn count_with(n: usize, singular: &str, plural: &str) -> String {
+    format!("{} {}", n, if n == 1 { singular } else { plural })
+}
+
+/// Options for [`path_with`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PathOptions {
+    /// Abbreviate paths inside this directory to `~`.
+    pub home: Option<PathBuf>,
+
+    /// Show paths inside this directory relative to it.
+    pub cwd: Option<PathBuf>,
+}
+
+impl PathOptions {
+    /// Abbreviate the paths inside the home directory and the current directory of the process,
+    /// like cargo does.
+    pub fn detect() -> Self {
+        let home = env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" })
+            .filter(|home| !home.is_empty())
+            .map(PathBuf::from);
+        Self {
+            home,
+            cwd: env::current_dir().ok(),
+        }
+    }
+}
+
+/// Display a path the way cargo would print it.
+///
+/// On Windows the verbatim `\\?\` prefix added by `canonicalize` is removed when the path is
+/// still valid without it, and forward slashes are shown as backslashes. Components that aren't
+/// valid UTF-8 are shown with the replacement character.
+///
+/// ## Example
+/// ```
+/// use carlog::fmt;
+/// use std::path::Path;
+///
+/// assert_eq!(fmt::path(Path::new("Cargo.toml")), "Cargo.toml");
+/// ```
+pub fn path<P>(path: P) -> String
+where
+    P: AsRef<Path>,
+{
+    path_with(path, &PathOptions::default())
+}
+
+/// Display a path like [`path`], abbreviating the home and current directories.
+///
+/// * `path`: The path to display.
+/// * `options`: The directories to abbreviate, see [`PathOptions::detect`].
+pub fn path_with<P>(path: P, options: &PathOptions) -> String
+where
+    P: AsRef<Path>,
+{
+    let dir = |dir: &Option<PathBuf>| {
+        dir.as_ref()
+            .map(|dir| display_path(&dir.to_string_lossy(), cfg!(windows)))
+    };
+    let path = display_path(&path.as_ref().to_string_lossy(), cfg!(windows));
+    abbreviate(path, dir(&options.home), dir(&options.cwd), cfg!(windows))
+}
+
+fn display_path(path: &str, windows: bool) -> String {
+    if !windows {
+        return path.to_string();
+    }
+    let path = path.replace('/', "\\");
+    if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        if verbatim_is_safe(unc) {
+            return format!(r"\\{}", unc);
+        }
+    } else if let Some(disk) = path.strip_prefix(r"\\?\") {
+        let bytes = disk.as_bytes();
+        let is_disk = bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+        if is_disk && verbatim_is_safe(disk) {
+            return disk.to_string();
+        }
+    }
+    path
+}
+
+/// Whether a verbatim path still means the same without its prefix.
+fn verbatim_is_safe(path: &str) -> bool {
+    const MAX_PATH: usize = 260;
+    path.len() < MAX_PATH
+        && path.split('\\').all(|component| {
+            component != "."
+                && component != ".."
+                && !component.ends_with('.')
+                && !component.ends_with(' ')
+        })
+}
+
+fn abbreviate(path: String, home: Option<String>, cwd: Option<String>, windows: bool) -> String {
+    let separator = if windows { '\\' } else { '/' };
+    let inside = |dir: &str| -> Option<String> {
+        let dir = dir.trim_end_matches(separator);
+        let rest = path.strip_prefix(dir)?;
+        if dir.is_empty() {
+            None
+        } else if rest.is_empty() {
+            Some(String::new())
+        } else {
+            rest.strip_prefix(separator).map(|rest| rest.to_string())
+        }
+    };
+    if let Some(rest) = cwd.as_deref().and_then(inside) {
+        return if rest.is_empty() {
+            ".".to_string()
+        } else {
+            rest
+        };
+    }
+    if let Some(rest) = home.as_deref().and_then(inside) {
+        return if rest.is_empty() {
+            "~".to_string()
+        } else {
+            format!("~{}{}", separator, rest)
+        };
+    }
+    path
+}
+
+/// Expand the tabs of `text` to spaces, with tab stops every `width` columns.
+///
+/// Every line starts at column zero, ANSI escape sequences don't take any column and the other
+/// characters take their [width](char_width).
+pub(crate) fn expand_tabs(text: &str, width: usize) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut column = 0;
+    for token in ansi::tokens(text) {
+        let text = match token {
+            Token::Escape(escape) => {
+                expanded.push_str(escape);
+                continue;
+            }
+            Token::Text(text) => text,
+        };
+        for c in text.chars() {
+            match c {
+                '\t' => {
+                    let spaces = if width == 0 {
+                        0
+                    } else {
+                        width - column % width
+                    };
+                    expanded.push_str(&" ".repeat(spaces));
+                    column += spaces;
+                }
+                '\n' => {
+                    expanded.push(c);
+                    column = 0;
+                }
+                _ => {
+                    expanded.push(c);
+                    column += char_width(c);
+                }
+            }
+        }
+    }
+    expanded
+}
+
+/// The text without its ANSI escape sequences, i.e. colors, see [`ansi::strip`].
+/// Run `f` with an empty buffer of the thread, i.e. to render a line, reused from one call to the
+/// next so that printing doesn't allocate.
+pub(crate) fn with_buffer<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut String) -> R,
+{
+    /// The capacity kept for the next call, so one long line doesn't hold memory forever.
+    const KEPT: usize = 16 * 1024;
+    thread_local! {
+        static BUFFER: Cell<String> = const { Cell::new(String::new()) };
+    }
+    // A nested call, or one from a thread exiting, gets a new buffer.
+    let mut buffer = BUFFER.try_with(Cell::take).unwrap_or_default();
+    buffer.clear();
+    let result = f(&mut buffer);
+    if buffer.capacity() <= KEPT {
+        let _ = BUFFER.try_with(|kept| kept.set(buffer));
+    }
+    result
+}
+
+pub(crate) fn strip_ansi(text: &str) -> String {
+    ansi::strip(text).into_owned()
+}
+
+/// The ranges of characters shown two columns wide by terminals: the East Asian wide and fullwidth
+/// characters, and the emoji shown as pictures by default.
+#[cfg(not(feature = "unicode-width"))]
+const WIDE: [(char, char); 55] = [
+    ('\u{1100}', '\u{115f}'),
+    ('\u{231a}', '\u{231b}'),
+    ('\u{23e9}', '\u{23ec}'),
+    ('\u{23f0}', '\u{23f0}'),
+    ('\u{23f3}', '\u{23f3}'),
+    ('\u{25fd}', '\u{25fe}'),
+    ('\u{2614}', '\u{2615}'),
+    ('\u{2648}', '\u{2653}'),
+    ('\u{267f}', '\u{267f}'),
+    ('\u{2693}', '\u{2693}'),
+    ('\u{26a1}', '\u{26a1}'),
+    ('\u{26aa}', '\u{26ab}'),
+    ('\u{26bd}', '\u{26be}'),
+    ('\u{26c4}', '\u{26c5}'),
+    ('\u{26ce}', '\u{26ce}'),
+    ('\u{26d4}', '\u{26d4}'),
+    ('\u{26ea}', '\u{26ea}'),
+    ('\u{26f2}', '\u{26f3}'),
+    ('\u{26f5}', '\u{26f5}'),
+    ('\u{26fa}', '\u{26fa}'),
+    ('\u{26fd}', '\u{26fd}'),
+    ('\u{2705}', '\u{2705}'),
+    ('\u{270a}', '\u{270b}'),
+    ('\u{2728}', '\u{2728}'),
+    ('\u{274c}', '\u{274c}'),
+    ('\u{274e}', '\u{274e}'),
+    ('\u{2753}', '\u{2755}'),
+    ('\u{2757}', '\u{2757}'),
+    ('\u{2795}', '\u{2797}'),
+    ('\u{27b0}', '\u{27b0}'),
+    ('\u{27bf}', '\u{27bf}'),
+    ('\u{2b1b}', '\u{2b1c}'),
+    ('\u{2b50}', '\u{2b50}'),
+    ('\u{2b55}', '\u{2b55}'),
+    ('\u{2e80}', '\u{303e}'),
+    ('\u{3041}', '\u{33ff}'),
+    ('\u{3400}', '\u{4dbf}'),
+    ('\u{4e00}', '\u{9fff}'),
+    ('\u{a000}', '\u{a4cf}'),
+    ('\u{ac00}', '\u{d7a3}'),
+    ('\u{f900}', '\u{faff}'),
+    ('\u{fe30}', '\u{fe4f}'),
+    ('\u{ff00}', '\u{ff60}'),
+    ('\u{ffe0}', '\u{ffe6}'),
+    ('\u{1f004}', '\u{1f004}'),
+    ('\u{1f0cf}', '\u{1f0cf}'),
+    ('\u{1f18e}', '\u{1f18e}'),
+    ('\u{1f191}', '\u{1f19a}'),
+    ('\u{1f200}', '\u{1f251}'),
+    ('\u{1f300}', '\u{1f64f}'),
+    ('\u{1f680}', '\u{1f6ff}'),
+    ('\u{1f7e0}', '\u{1f7eb}'),
+    ('\u{1f900}', '\u{1f9ff}'),
+    ('\u{1fa70}', '\u{1faff}'),
+    ('\u{20000}', '\u{3fffd}'),
+];
+
+/// The number of columns a character takes in a terminal, from the tables of `unicode-width`.
+#[cfg(feature = "unicode-width")]
+pub(crate) fn char_width(c: char) -> usize {
+    unicode_width::UnicodeWidthChar::width(c).unwrap_or(0)
+}
+
+/// The number of columns a character takes in a terminal, from [`WIDE`] without the
+/// `unicode-width` feature.
+#[cfg(not(feature = "unicode-width"))]
+pub(crate) fn char_width(c: char) -> usize {
+    let combining = matches!(c, '\u{300}'..='\u{36f}' | '\u{200b}'..='\u{200f}' | '\u{fe0f}');
+    if c.is_control() || combining {
+        0
+    } else if WIDE.iter().any(|&(start, end)| (start..=end).contains(&c)) {
+        2
+    } else {
+        1
+    }
+}
+
+/// The number of columns a text takes in a terminal, without its ANSI escape sequences.
+pub(crate) fn display_width(text: &str) -> usize {
+    ansi::tokens(text)
+        .map(|token| match token {
+            Token::Text(text) => text.chars().map(char_width).sum(),
+            Token::Escape(_) => 0,
+        })
+        .sum()
+}
+
+/// The pieces of a text: its ANSI escape sequences, of no width, and its characters.
+pub(crate) fn pieces(text: &str) -> Vec<(&str, usize)> {
+    let mut pieces = Vec::new();
+    for token in ansi::tokens(text) {
+        match token {
+            Token::Escape(escape) => pieces.push((escape, 0)),
+            Token::Text(text) => pieces.extend(
+                text.char_indices()
+                    .map(|(i, c)| (&text[i..i + c.len_utf8()], char_width(c))),
+            ),
+        }
+    }
+    pieces
+}
+
+/// Shorten a text wider than `max_width` columns by replacing its middle with `…`, keeping the
+/// start and the end, i.e. the root and the file name of a path.
+///
+/// The width is the one shown by a terminal: wide characters take two columns and ANSI escape
+/// sequences none. The cuts never split a character nor an escape sequence, and styles open before
+/// the ellipsis are reset before it.
+///
+/// ## Example
+/// ```
+/// use carlog::fmt;
+///
+/// let path = "/home/user/projects/carlog/target/debug/build/out.rs";
+/// assert_eq!(fmt::ellipsize_middle(path, 24), "/home/user/p…uild/out.rs");
+/// assert_eq!(fmt::ellipsize_middle("src/lib.rs", 24), "src/lib.rs");
+/// ```
+pub fn ellipsize_middle(text: &str, max_width: usize) -> Cow<'_, str> {
+    if display_width(text) <= max_width {
+        return Cow::Borrowed(text);
+    }
+    let Some(budget) = max_width.checked_sub(1) else {
+        return Cow::Borrowed("");
+    };
+    let pieces = pieces(text);
+    let (mut head, mut head_width) = (0, 0);
+    while let Some(&(_, width)) = pieces.get(head) {
+        if head_width + width > budget - budget / 2 {
+            break;
+        }
+        head_width += width;
+        head += 1;
+    }
+    // A wide character that doesn't fit in the head leaves its column to the tail.
+    let (mut tail, mut tail_width) = (pieces.len(), 0);
+    while tail > head {
+        let width = pieces[tail - 1].1;
+        if tail_width + width > budget - head_width {
+            break;
+        }
+        tail_width += width;
+        tail -= 1;
+    }
+    let mut shortened: String = pieces[..head].iter().map(|(piece, _)| *piece).collect();
+    if ansi::contains_escape(shortened.as_bytes()) {
+        shortened.push_str("\u{1b}[0m");
+    }
+    shortened.push('\u{2026}');
+    shortened.extend(pieces[tail..].iter().map(|(piece, _)| *piece));
+    Cow::Owned(shortened)
+}
+
+/// Whether a word of a message looks like a path or a URL.
+fn is_path(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    let disk = bytes.len() > 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':';
+    word.contains("://")
+        || word.starts_with(['/', '~', '.'])
+        || disk
+        || word.trim_matches(['/', '\\']).contains(['/', '\\'])
+}
+
+/// Shorten the paths and URLs of a message wider than `max_width`, if the line of the message,
+/// after a prefix `prefix_width` columns wide, is wider than the terminal.
+///
+/// The punctuation around a path, i.e. the parentheses of `(/home/user/projects/carlog)`, is kept.
+pub(crate) fn ellipsize_paths(
+    msg: &str,
+    max_width: usize,
+    prefix_width: usize,
+    columns: Option<usize>,
+) -> Cow<'_, str> {
+    let fits = |columns| prefix_width + display_width(msg) <= columns;
+    if columns.is_none_or(fits) {
+        return Cow::Borrowed(msg);
+    }
+    let mut shortened = String::with_capacity(msg.len());
+    for token in msg.split_inclusive(char::is_whitespace) {
+        let word = token.trim_end_matches(char::is_whitespace);
+        let inner = word.trim_matches(['(', ')', '[', ']', '<', '>', '`', '\'', '"', ',', ';']);
+        if inner.is_empty() || !is_path(inner) || display_width(inner) <= max_width {
+            shortened.push_str(token);
+            continue;
+        }
+        let start = word.find(inner).unwrap_or(0);
+        shortened.push_str(&token[..start]);
+        shortened.push_str(&ellipsize_middle(inner, max_width));
+        shortened.push_str(&token[start + inner.len()..]);
+    }
+    Cow::Owned(shortened)
+}
+
+/// Keep the head and the tail of a message longer than `max` bytes, replacing the middle with a
+/// marker like `… [1.9 MB truncated] …`.
+///
+/// The cuts never split a UTF-8 character nor an ANSI escape sequence, and styles open in the
+/// head are reset before the marker.
+pub(crate) fn truncate_middle(msg: &[u8], max: usize) -> Cow<'_, [u8]> {
+    if msg.len() <= max {
+        return Cow::Borrowed(msg);
+    }
+    let is_boundary = |i: usize| i >= msg.len() || msg[i] & 0xc0 != 0x80;
+    let mut head = max - max / 2;
+    while !is_boundary(head) {
+        head -= 1;
+    }
+    if let Some((start, _)) = ansi::sequence_around(msg, head) {
+        head = start;
+    }
+    let mut tail = msg.len() - max / 2;
+    while !is_boundary(tail) {
+        tail += 1;
+    }
+    if let Some((_, end)) = ansi::sequence_around(msg, tail) {
+        tail = end;
+    }
+    let mut truncated = msg[..head].to_vec();
+    if ansi::contains_escape(&truncated) {
+        truncated.extend_from_slice(b"\x1b[0m");
+    }
+    let marker = format!(
+        "\u{2026} [{} truncated] \u{2026}",
+        binary_bytes((tail - head) as u64)
+    );
+    truncated.extend_from_slice(marker.as_bytes());
+    truncated.extend_from_slice(&msg[tail..]);
+    Cow::Owned(truncated)
+}
+
+/// Human readable byte size using decimal units, i.e. `3.4 MB`.
+pub(crate) fn bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KB", "MB", "GB", "TB", "PB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while value >= 999.95 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Human readable byte size using binary units, i.e. `1.9 MiB`, to match limits given in
+/// powers of two like the 64 KiB of [`max_message_len`](crate::max_message_len).
+pub(crate) fn binary_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1023.95 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Elapsed time in seconds with two decimals, i.e. `1.02s`.
+pub(crate) fn elapsed(duration: Duration) -> String {
+    format!("{:.2}s", duration.as_secs_f64())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        abbreviate, binary_bytes, bytes, count, count_with, display_path, display_width, elapsed,
+        ellipsize_middle, ellipsize_paths, expand_tabs, strip_ansi, truncate_middle,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn test_verbatim_paths() {
+        assert_eq!(
+            display_path(r"\\?\C:\Users\me\proj\target", true),
+            r"C:\Users\me\proj\target"
+        );
+        assert_eq!(
+            display_path(r"\\?\UNC\server\share\dir", true),
+            r"\\server\share\dir"
+        );
+        // Trailing dots are only valid in verbatim paths, so the prefix must stay.
+        assert_eq!(display_path(r"\\?\C:\dir.", true), r"\\?\C:\dir.");
+        assert_eq!(
+            display_path(r"\\?\Volume{1234}\dir", true),
+            r"\\?\Volume{1234}\dir"
+        );
+        assert_eq!(display_path("C:/Users/me", true), r"C:\Users\me");
+        assert_eq!(display_path(r"src/a\b.rs", false), r"src/a\b.rs");
+    }
+
+    #[test]
+    fn test_abbreviate() {
+        let home = Some("/home/me".to_string());
+        let cwd = Some("/home/me/proj/".to_string());
+        let unix = |path: &str| abbreviate(path.to_string(), home.clone(), cwd.clone(), false);
+        assert_eq!(unix("/home/me/proj/target/debug"), "target/debug");
+        assert_eq!(unix("/home/me/proj"), ".");
+        assert_eq!(unix("/home/me/projects"), "~/projects");
+        assert_eq!(unix("/home/me"), "~");
+        assert_eq!(unix("/home/meow"), "/home/meow");
+        assert_eq!(unix("relative/path"), "relative/path");
+        let windows = abbreviate(
+            r"C:\Users\me\proj\target".to_string(),
+            Some(r"C:\Users\me".to_string()),
+            None,
+            true,
+        );
+        assert_eq!(windows, r"~\proj\target");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_path() {
+        use super::{path_with, PathOptions};
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        use std::path::Path;
+
+        let path = Path::new(OsStr::from_bytes(b"/tmp/caf\xe9/lib.rs"));
+        let options = PathOptions {
+            home: None,
+            cwd: Some("/tmp".into()),
+        };
+        assert_eq!(path_with(path, &options), "caf\u{fffd}/lib.rs");
+    }
+
+    #[test]
+    fn test_count() {
+        assert_eq!(count(0, "warning"), "0 warnings");
+        assert_eq!(count(1, "warning"), "1 warning");
+        assert_eq!(count(42, "warning"), "42 warnings");
+        assert_eq!(
+            count_with(0, "dependency", "dependencies"),
+            "0 dependencies"
+        );
+        assert_eq!(count_with(1, "dependency", "dependencies"), "1 dependency");
+        assert_eq!(
+            count_with(7, "dependency", "dependencies"),
+            "7 dependencies"
+        );
+    }
+
+    #[test]
+    fn test_expand_tabs() {
+        assert_eq!(expand_tabs("\ta", 4), "    a");
+        assert_eq!(expand_tabs("ab\tc", 4), "ab  c");
+        assert_eq!(expand_tabs("abcd\te", 4), "abcd    e");
+        assert_eq!(expand_tabs("a\tb\n\tc", 4), "a   b\n    c");
+        assert_eq!(expand_tabs("a\tb", 0), "ab");
+        assert_eq!(
+            expand_tabs("\u{1b}[1;31mab\tc\u{1b}[0m\td", 4),
+            "\u{1b}[1;31mab  c\u{1b}[0m   d"
+        );
+        // Wide characters take two columns, combining ones none.
+        assert_eq!(
+            expand_tabs("\u{6784}\t\u{5efa}\u{5efa}\ta", 4),
+            "\u{6784}  \u{5efa}\u{5efa}    a"
+        );
+        assert_eq!(expand_tabs("e\u{301}\tb", 4), "e\u{301}   b");
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(bytes(0), "0 B");
+        assert_eq!(bytes(999), "999 B");
+        assert_eq!(bytes(1000), "1.0 KB");
+        assert_eq!(bytes(3_400_000), "3.4 MB");
+        assert_eq!(bytes(999_990), "1.0 MB");
+        assert_eq!(bytes(u64::MAX), "18446.7 PB");
+    }
+
+    #[test]
+    fn test_binary_bytes() {
+        assert_eq!(binary_bytes(0), "0 B");
+        assert_eq!(binary_bytes(1023), "1023 B");
+        assert_eq!(binary_bytes(1024), "1.0 KiB");
+        assert_eq!(binary_bytes(2_000_000), "1.9 MiB");
+        assert_eq!(binary_bytes(1_048_575), "1.0 MiB");
+        assert_eq!(binary_bytes(u64::MAX), "16384.0 PiB");
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(strip_ansi("\u{1b}[1;31merror\u{1b}[0m: x"), "error: x");
+        assert_eq!(strip_ansi("plain \u{1b}"), "plain ");
+    }
+
+    #[test]
+    fn test_truncate_middle() {
+        assert_eq!(truncate_middle(b"short", 5), &b"short"[..]);
+        assert_eq!(
+            &*truncate_middle(b"0123456789", 4),
+            "01\u{2026} [6 B truncated] \u{2026}89".as_bytes()
+        );
+        // The head would split the two bytes of `é`, the tail the three of `€`.
+        assert_eq!(
+            &*truncate_middle("aé-----€b".as_bytes(), 4),
+            "a\u{2026} [10 B truncated] \u{2026}b".as_bytes()
+        );
+        assert_eq!(
+            &*truncate_middle(b"ab\x1b[1;31mc------d\x1b[0mef", 8),
+            "ab\u{2026} [19 B truncated] \u{2026}ef".as_bytes()
+        );
+        assert_eq!(
+            &*truncate_middle(b"\x1b[1mab--------cd", 12),
+            "\u{1b}[1mab\u{1b}[0m\u{2026} [4 B truncated] \u{2026}----cd".as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_elapsed() {
+        assert_eq!(elapsed(Duration::from_millis(1020)), "1.02s");
+        assert_eq!(elapsed(Duration::ZERO), "0.00s");
+    }
+
+    #[test]
+    fn test_display_width() {
+        for emoji in [
+            "\u{1f680}",
+            "\u{2705}",
+            "\u{274c}",
+            "\u{2b50}",
+            "\u{1fa80}",
+            "\u{1f4e6}",
+        ] {
+            assert_eq!(display_width(emoji), 2, "{:?}", emoji);
+        }
+        assert_eq!(display_width("\u{6784}\u{5efa}"), 4);
+        assert_eq!(display_width("Pre\u{301}pare\u{301}"), 7);
+        assert_eq!(
+            display_width("\u{1b}[1;32m\u{1f680} Deploying\u{1b}[0m"),
+            12
+        );
+    }
+
+    #[test]
+    fn test_ellipsize_middle() {
+        let path = "/home/user/projects/carlog/src/lib.rs";
+        assert_eq!(ellipsize_middle("src/lib.rs", 10), "src/lib.rs");
+        assert_eq!(ellipsize_middle(path, path.len()), path);
+        assert_eq!(
+            ellipsize_middle(path, path.len() - 1),
+            "/home/user/project\u{2026}carlog/src/lib.rs"
+        );
+        assert_eq!(ellipsize_middle(path, 11), "/home\u{2026}ib.rs");
+        assert_eq!(ellipsize_middle(path, 1), "\u{2026}");
+        assert_eq!(ellipsize_middle(path, 0), "");
+
+        // The head has one column left for a wide character, given to the tail instead.
+        let wide = "ab\u{4f60}\u{597d}cd";
+        assert_eq!(display_width(wide), 8);
+        assert_eq!(ellipsize_middle(wide, 7), "ab\u{2026}\u{597d}cd");
+        // No character fits in the column left.
+        assert_eq!(ellipsize_middle(wide, 6), "ab\u{2026}cd");
+
+        let styled = "\u{1b}[1m/home/user\u{1b}[0m/projects/carlog";
+        assert_eq!(
+            ellipsize_middle(styled, 15),
+            "\u{1b}[1m/home/u\u{1b}[0m\u{2026}/carlog"
+        );
+        assert_eq!(
+            ellipsize_middle(styled, 10),
+            "\u{1b}[1m/home\u{1b}[0m\u{2026}rlog"
+        );
+    }
+
+    #[test]
+    fn test_ellipsize_paths() {
+        let msg =
+            "carlog v0.1.0 (/home/user/projects/carlog) from https://example.com/a/b/c/carlog";
+        assert_eq!(ellipsize_paths(msg, 16, 13, None), msg);
+        assert_eq!(ellipsize_paths(msg, 16, 13, Some(120)), msg);
+        assert_eq!(
+            ellipsize_paths(msg, 16, 13, Some(80)),
+            "carlog v0.1.0 (/home/us\u{2026}/carlog) from https://\u{2026}/carlog"
+        );
+        let words = "a long message with v0.1.0 and carlog::fmt but no path at all, only words";
+        assert_eq!(ellipsize_paths(words, 4, 13, Some(40)), words);
+    }
+}
@@ -0,0 +1,178 @@
+//! Warnings and errors as the annotations of GitHub Actions, shown on the lines of pull requests.
+//!
+//! With [`Format::GithubActions`](crate::Format::GithubActions), which
+//! [`init_default`](crate::init_default) picks when `GITHUB_ACTIONS` is `true`, `carlog_warning!`
+//! and `carlog_error!` print workflow commands like
+//! `::warning file=Cargo.toml,line=7,col=1::unused manifest key` instead of their colored lines.
+//! The other messages are printed like in the human format. The file, line and column are the
+//! [input](crate::Record::input) of the message, i.e. the `file:`, `line:` and `col:` arguments of
+//! the macros, and are left out when there's none.
+//!
+//! ## Example
+//! ```
+//! use carlog::github;
+//!
+//! assert_eq!(github::escape_data("50%\ndone"), "50%25%0Adone");
+//! assert_eq!(github::escape_property("a:b,c"), "a%3Ab%2Cc");
+//! ```
+
+use crate::{ansi, Level, Record};
+use std::borrow::Cow;
+
+/// Escape the message of a workflow command: `%`, `\r` and `\n`.
+pub fn escape_data(text: &str) -> Cow<'_, str> {
+    escape(text, false)
+}
+
+/// Escape the value of a property of a workflow command, i.e. a file name: `%`, `\r`, `\n`, `:`
+/// and `,`.
+pub fn escape_property(text: &str) -> Cow<'_, str> {
+    escape(text, true)
+}
+
+fn escape(text: &str, property: bool) -> Cow<'_, str> {
+    let escaped = |c: char| matches!(c, '%' | '\r' | '\n') || property && matches!(c, ':' | ',');
+    if !text.contains(escaped) {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '%' => out.push_str("%25"),
+            '\r' => out.push_str("%0D"),
+            '\n' => out.push_str("%0A"),
+            ':' if property => out.push_str("%3A"),
+            ',' if property => out.push_str("%2C"),
+            c => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// The workflow command of a warning or an error `message` about `input`, including the newline.
+///
+/// The levels below [`Level::Warning`] are `notice` annotations.
+///
+/// ## Example
+/// ```
+/// use carlog::github;
+/// use carlog::prelude::*;
+///
+/// assert_eq!(
+///     github::annotation(Level::Warning, "unused key", Some(("Cargo.toml", 7, 1))),
+///     "::warning file=Cargo.toml,line=7,col=1::unused key\n"
+/// );
+/// assert_eq!(github::annotation(Level::Error, "failed", None), "::error::failed\n");
+/// ```
+pub fn annotation(level: Level, message: &str, input: Option<(&str, u32, u32)>) -> String {
+    let command = match level {
+        Level::Trace | Level::Debug | Level::Info => "notice",
+        Level::Warning => "warning",
+        Level::Error => "error",
+    };
+    let mut line = format!("::{}", command);
+    if let Some((file, line_number, column)) = input {
+        line.push_str(&format!(
+            " file={},line={},col={}",
+            escape_property(file),
+            line_number,
+            column
+        ));
+    }
+    line.push_str("::");
+    line.push_str(&escape_data(&ansi::strip(message)));
+    line.push('\n');
+    line
+}
+
+/// The annotation of `record` when it's a warning or an error printed with
+/// [`Format::GithubActions`](crate::Format::GithubActions).
+pub(crate) fn record_line(record: &Record) -> Option<String> {
+    if crate::format() != crate::Format::GithubActions
+        || !matches!(record.severity(), Level::Warning | Level::Error)
+    {
+        return None;
+    }
+    // Messages start with their separator from the status, i.e. `: `.
+    let message = record.line_message();
+    let message = message.trim_start_matches(':').trim_start();
+    Some(annotation(
+        record.severity(),
+        message,
+        record.input_location(),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{annotation, escape_data, escape_property};
+    use crate::test::{force_colors, global_lock};
+    use crate::{carlog_error, carlog_ok, carlog_warning, set_format, CarlogStream, Format, Level};
+
+    #[test]
+    fn test_escape_data() {
+        assert_eq!(escape_data(""), "");
+        assert_eq!(escape_data("plain: text, as is"), "plain: text, as is");
+        assert_eq!(escape_data("%"), "%25");
+        assert_eq!(escape_data("\r"), "%0D");
+        assert_eq!(escape_data("\n"), "%0A");
+        assert_eq!(escape_data("\r\n"), "%0D%0A");
+        assert_eq!(escape_data("%0A"), "%250A");
+        assert_eq!(escape_data("100%\r\nnext"), "100%25%0D%0Anext");
+        assert_eq!(escape_data("\u{4f60}%\u{597d}"), "\u{4f60}%25\u{597d}");
+    }
+
+    #[test]
+    fn test_escape_property() {
+        assert_eq!(escape_property("src/main.rs"), "src/main.rs");
+        assert_eq!(escape_property(":"), "%3A");
+        assert_eq!(escape_property(","), "%2C");
+        assert_eq!(escape_property("%"), "%25");
+        assert_eq!(escape_property("\r"), "%0D");
+        assert_eq!(escape_property("\n"), "%0A");
+        assert_eq!(
+            escape_property("C:\\src\\a,b%\n.rs"),
+            "C%3A\\src\\a%2Cb%25%0A.rs"
+        );
+    }
+
+    #[test]
+    fn test_annotation() {
+        assert_eq!(
+            annotation(Level::Warning, "a: b, c", Some(("C:\\x,y.rs", 10, 5))),
+            "::warning file=C%3A\\x%2Cy.rs,line=10,col=5::a: b, c\n"
+        );
+        assert_eq!(
+            annotation(Level::Error, "\u{1b}[1mline 1\u{1b}[0m\nline 2", None),
+            "::error::line 1%0Aline 2\n"
+        );
+        assert_eq!(annotation(Level::Info, "50%", None), "::notice::50%25\n");
+    }
+
+    #[test]
+    fn test_macros_in_github_actions_format() {
+        let _lock = global_lock();
+        force_colors();
+        set_format(Format::GithubActions);
+        let mut output = Vec::<u8>::new();
+        carlog_ok!("Finished", "dev profile", CarlogStream::Custom(&mut output));
+        carlog_warning!(
+            file: "Cargo.toml",
+            line: 7,
+            col: 1,
+            "unused manifest key: `package.edition2`",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_error!(
+            "could not compile\n`carlog`",
+            CarlogStream::Custom(&mut output)
+        );
+        set_format(Format::Human);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "    \u{1b}[1;32mFinished\u{1b}[0m dev profile\n\
+             ::warning file=Cargo.toml,line=7,col=1::unused manifest key: `package.edition2`\n\
+             ::error::could not compile%0A`carlog`\n"
+        );
+    }
+}
@@ -0,0 +1,398 @@
+//! HTML renderings of the lines, i.e. for build reports.
+//!
+//! [`Status::render_html`] renders a line as `<span>` markup colored like the terminal, with the
+//! palette of xterm, and a [`Document`] puts successive lines together in a page with a dark
+//! background. The statuses are styled with inline styles, or with the `carlog-` classes of the
+//! stylesheet of the document with [`Styling::Classes`].
+//!
+//! ## Example
+//! ```
+//! use carlog::html::Document;
+//! use carlog::prelude::*;
+//! use carlog::Record;
+//!
+//! let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
+//! assert_eq!(
+//!     status.render_html(" carlog <v0.1.0>"),
+//!     "    <span style=\"color: #00cd00; font-weight: bold\">Compiled</span> carlog \
+//!      &lt;v0.1.0&gt;"
+//! );
+//!
+//! let mut document = Document::new().title("Build report");
+//! document.push(&Record::new("Compiling", "carlog v0.1.0"));
+//! document.write_to(std::io::sink()).unwrap();
+//! ```
+
+use crate::attributes::Attributes;
+use crate::{color, CargoColor, Loggable, Record, Status};
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+/// How the statuses are styled, see the [module](self).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Styling {
+    /// With `style` attributes, i.e. `style="color: #00cd00; font-weight: bold"`.
+    #[default]
+    Inline,
+    /// With classes, i.e. `class="carlog-green carlog-bold"`, defined by the stylesheet of
+    /// [`Document`]. RGB colors and the colors of the 256-color palette, which have no class, are
+    /// still inline styles.
+    Classes,
+}
+
+/// The named colors with their classes.
+const CLASSES: [(CargoColor, &str); 12] = [
+    (CargoColor::Green, "carlog-green"),
+    (CargoColor::Cyan, "carlog-cyan"),
+    (CargoColor::Yellow, "carlog-yellow"),
+    (CargoColor::Red, "carlog-red"),
+    (CargoColor::White, "carlog-white"),
+    (CargoColor::Black, "carlog-black"),
+    (CargoColor::BrightGreen, "carlog-bright-green"),
+    (CargoColor::BrightCyan, "carlog-bright-cyan"),
+    (CargoColor::BrightYellow, "carlog-bright-yellow"),
+    (CargoColor::BrightRed, "carlog-bright-red"),
+    (CargoColor::BrightWhite, "carlog-bright-white"),
+    (CargoColor::BrightBlack, "carlog-bright-black"),
+];
+
+/// The text attributes with their classes and declarations.
+const ATTRIBUTES: [(Attributes, &str, &str); 5] = [
+    (Attributes::BOLD, "carlog-bold", "font-weight: bold"),
+    (Attributes::DIMMED, "carlog-dimmed", "opacity: 0.5"),
+    (Attributes::ITALIC, "carlog-italic", "font-style: italic"),
+    (
+        Attributes::UNDERLINE,
+        "carlog-underline",
+        "text-decoration: underline",
+    ),
+    (
+        Attributes::STRIKETHROUGH,
+        "carlog-strikethrough",
+        "text-decoration: line-through",
+    ),
+];
+
+/// Escape `text` for the content or an attribute value of an element: `&`, `<`, `>`, `"` and
+/// `'`.
+///
+/// ## Example
+/// ```
+/// assert_eq!(carlog::html::escape("<a href=\"x\">"), "&lt;a href=&quot;x&quot;&gt;");
+/// ```
+pub fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len() + 16);
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// The class of a named color.
+fn color_class(color: CargoColor) -> Option<&'static str> {
+    CLASSES
+        .iter()
+        .find(|(named, _)| *named == color)
+        .map(|(_, class)| *class)
+}
+
+/// The CSS value of a color, like xterm shows it.
+fn color_value(color: CargoColor) -> String {
+    let [r, g, b] = color::cargo_rgb(color);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// The declarations of `attributes`, the text decorations put together.
+fn attribute_declarations(attributes: Attributes) -> Vec<String> {
+    let mut declarations = Vec::new();
+    let mut decorations = Vec::new();
+    for (attribute, _, declaration) in ATTRIBUTES {
+        if !attributes.contains(attribute) {
+            continue;
+        }
+        match declaration.strip_prefix("text-decoration: ") {
+            Some(decoration) => decorations.push(decoration),
+            None => declarations.push(declaration.to_string()),
+        }
+    }
+    if !decorations.is_empty() {
+        declarations.push(format!("text-decoration: {}", decorations.join(" ")));
+    }
+    declarations
+}
+
+/// Write `text` as a span in `color` with `attributes` at the end of `out`.
+pub(crate) fn write_span(
+    out: &mut String,
+    text: &str,
+    color: CargoColor,
+    attributes: Attributes,
+    styling: Styling,
+) {
+    let class = color_class(color).filter(|_| styling == Styling::Classes);
+    let mut styles = Vec::new();
+    if class.is_none() {
+        styles.push(format!("color: {}", color_value(color)));
+    }
+    out.push_str("<span");
+    match styling {
+        Styling::Inline => styles.extend(attribute_declarations(attributes)),
+        Styling::Classes => {
+            let classes: Vec<&str> = class
+                .into_iter()
+                .chain(
+                    ATTRIBUTES
+                        .iter()
+                        .filter(|(attribute, _, _)| attributes.contains(*attribute))
+                        .map(|(_, class, _)| *class),
+                )
+                .collect();
+            if !classes.is_empty() {
+                out.push_str(&format!(" class=\"{}\"", classes.join(" ")));
+            }
+        }
+    }
+    if !styles.is_empty() {
+        out.push_str(&format!(" style=\"{}\"", styles.join("; ")));
+    }
+    out.push('>');
+    out.push_str(&escape(text));
+    out.push_str("</span>");
+}
+
+/// A page of rendered lines, see the [module](self).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Document {
+    styling: Styling,
+    title: String,
+    lines: Vec<String>,
+}
+
+impl Document {
+    /// Creates an empty document with inline styles.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how the statuses of the lines pushed next are styled.
+    pub fn styling(mut self, styling: Styling) -> Self {
+        self.styling = styling;
+        self
+    }
+
+    /// Set the title of the page, `carlog` by default.
+    pub fn title<S>(mut self, title: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.title = title.as_ref().to_string();
+        self
+    }
+
+    /// Add the line of `record`, like [`print_record`](crate::print_record) prints it.
+    pub fn push(&mut self, record: &Record) {
+        let line = record
+            .to_status()
+            .render_html_with(record.line_message(), self.styling);
+        self.lines.push(line);
+    }
+
+    /// Add the line of `status` for `msg`.
+    pub fn push_status<S>(&mut self, status: &Status, msg: S)
+    where
+        S: Loggable,
+    {
+        self.lines.push(status.render_html_with(msg, self.styling));
+    }
+
+    /// The number of lines of the document.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Whether the document has no lines.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// The complete page of the lines.
+    pub fn to_html(&self) -> String {
+        let title = match self.title.is_empty() {
+            true => "carlog",
+            false => &self.title,
+        };
+        let mut page = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+             <style>\nbody {{ margin: 0; background-color: black; color: {}; }}\n\
+             pre {{ margin: 0; padding: 1%; padding-left: 50px; }}\n",
+            escape(title),
+            color_value(CargoColor::White)
+        );
+        if self.styling == Styling::Classes {
+            for (color, class) in CLASSES {
+                page.push_str(&format!(
+                    ".{} {{ color: {}; }}\n",
+                    class,
+                    color_value(color)
+                ));
+            }
+            for (_, class, declaration) in ATTRIBUTES {
+                page.push_str(&format!(".{} {{ {}; }}\n", class, declaration));
+            }
+        }
+        page.push_str("</style>\n</head>\n<body>\n<pre>\n");
+        for line in &self.lines {
+            page.push_str(line);
+            page.push('\n');
+        }
+        page.push_str("</pre>\n</body>\n</html>\n");
+        page
+    }
+
+    /// Write the page to `out`.
+    pub fn write_to<W>(&self, mut out: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        out.write_all(self.to_html().as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{color_class, color_value, escape, Document, Styling};
+    use crate::{CargoColor, Record, Status};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("plain text"), "plain text");
+        assert_eq!(escape("<"), "&lt;");
+        assert_eq!(escape(">"), "&gt;");
+        assert_eq!(escape("&"), "&amp;");
+        assert_eq!(escape("\""), "&quot;");
+        assert_eq!(escape("'"), "&#39;");
+        assert_eq!(escape("&lt;"), "&amp;lt;");
+        assert_eq!(
+            escape("Vec<&'a str> = \"\u{4f60}\""),
+            "Vec&lt;&amp;&#39;a str&gt; = &quot;\u{4f60}&quot;"
+        );
+        let status = Status::plain().status("<b>&\"'");
+        assert_eq!(
+            status.render_html(" a < b && c > \"d\""),
+            "<span style=\"color: #e5e5e5\">&lt;b&gt;&amp;&quot;&#39;</span> a &lt; b &amp;&amp; c \
+             &gt; &quot;d&quot;"
+        );
+    }
+
+    #[test]
+    fn test_colors() {
+        let colors = [
+            (CargoColor::Green, Some("carlog-green"), "#00cd00"),
+            (CargoColor::Cyan, Some("carlog-cyan"), "#00cdcd"),
+            (CargoColor::Yellow, Some("carlog-yellow"), "#cdcd00"),
+            (CargoColor::Red, Some("carlog-red"), "#cd0000"),
+            (CargoColor::White, Some("carlog-white"), "#e5e5e5"),
+            (CargoColor::Black, Some("carlog-black"), "#000000"),
+            (
+                CargoColor::BrightGreen,
+                Some("carlog-bright-green"),
+                "#00ff00",
+            ),
+            (
+                CargoColor::BrightCyan,
+                Some("carlog-bright-cyan"),
+                "#00ffff",
+            ),
+            (
+                CargoColor::BrightYellow,
+                Some("carlog-bright-yellow"),
+                "#ffff00",
+            ),
+            (CargoColor::BrightRed, Some("carlog-bright-red"), "#ff0000"),
+            (
+                CargoColor::BrightWhite,
+                Some("carlog-bright-white"),
+                "#ffffff",
+            ),
+            (
+                CargoColor::BrightBlack,
+                Some("carlog-bright-black"),
+                "#7f7f7f",
+            ),
+            (CargoColor::Rgb(0xe5, 0xa0, 0x0d), None, "#e5a00d"),
+            (CargoColor::Ansi256(208), None, "#ff8700"),
+            (CargoColor::Ansi256(244), None, "#808080"),
+        ];
+        for (color, class, value) in colors {
+            assert_eq!(color_class(color), class, "{}", color);
+            assert_eq!(color_value(color), value, "{}", color);
+        }
+    }
+
+    #[test]
+    fn test_styling() {
+        let status = Status::plain()
+            .bold()
+            .underline()
+            .strikethrough()
+            .justify()
+            .color(CargoColor::BrightRed)
+            .status("error");
+        assert_eq!(
+            status.render_html(": failed"),
+            "       <span style=\"color: #ff0000; font-weight: bold; text-decoration: underline \
+             line-through\">error</span>: failed"
+        );
+        assert_eq!(
+            status.render_html_with(": failed", Styling::Classes),
+            "       <span class=\"carlog-bright-red carlog-bold carlog-underline \
+             carlog-strikethrough\">error</span>: failed"
+        );
+        let rgb = Status::plain()
+            .italic()
+            .color(CargoColor::Rgb(1, 2, 3))
+            .status("\u{1b}[1mNote\u{1b}[0m");
+        assert_eq!(
+            rgb.render_html_with(" \u{1b}[32mcolored\u{1b}[0m", Styling::Classes),
+            "<span class=\"carlog-italic\" style=\"color: #010203\">Note</span> colored"
+        );
+    }
+
+    #[test]
+    fn test_document() {
+        let mut document = Document::new().styling(Styling::Classes).title("<Report>");
+        assert!(document.is_empty());
+        document.push(&Record::new("Compiling", "carlog v0.1.0").color(CargoColor::Green));
+        document.push_status(&Status::plain().dimmed().status("note"), ": done");
+        assert_eq!(document.len(), 2);
+        let page = document.to_html();
+        assert!(page.starts_with(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>&lt;Report&gt;</title>\n<style>\n\
+             body { margin: 0; background-color: black; color: #e5e5e5; }\n"
+        ));
+        assert!(page.contains("\n.carlog-bright-red { color: #ff0000; }\n"));
+        assert!(page.contains("\n.carlog-dimmed { opacity: 0.5; }\n"));
+        assert!(page.ends_with(
+            "<pre>\n   <span class=\"carlog-green carlog-bold\">Compiling</span> carlog v0.1.0\n\
+             <span class=\"carlog-white carlog-dimmed\">note</span>: done\n</pre>\n</body>\n\
+             </html>\n"
+        ));
+        let mut output = Vec::new();
+        document.write_to(&mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), page);
+        let inline = Document::new().to_html();
+        assert!(inline.contains("<title>carlog</title>"));
+        assert!(!inline.contains(".carlog-"));
+    }
+}
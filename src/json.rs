@@ -0,0 +1,57 @@
+//! The lines of the [JSON format](crate::Format::Json), written without serde.
+
+/// Write `text` as a JSON string, quotes included, at the end of `out`.
+pub(crate) fn push_string(out: &mut String, text: &str) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\u{c}' => out.push_str("\\f"),
+            c if u32::from(c) < 0x20 || c == '\u{7f}' => {
+                out.push_str(&format!("\\u{:04x}", u32::from(c)))
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Write the key of a field, preceded by a comma unless it's the first of the object that starts
+/// at `start`.
+pub(crate) fn push_key(out: &mut String, start: usize, key: &str) {
+    if out.len() > start + 1 {
+        out.push(',');
+    }
+    push_string(out, key);
+    out.push(':');
+}
+
+#[cfg(test)]
+mod test {
+    use super::{push_key, push_string};
+
+    #[test]
+    fn test_escaping() {
+        let mut out = String::new();
+        push_string(&mut out, "say \"hi\"\\\n\tnow\u{1b}[1m\u{7f} \u{4f60}");
+        assert_eq!(
+            out,
+            "\"say \\\"hi\\\"\\\\\\n\\tnow\\u001b[1m\\u007f \u{4f60}\""
+        );
+    }
+
+    #[test]
+    fn test_keys() {
+        let mut out = String::from("[{");
+        push_key(&mut out, 1, "a");
+        out.push('1');
+        push_key(&mut out, 1, "b");
+        out.push_str("true}");
+        assert_eq!(out, "[{\"a\":1,\"b\":true}");
+    }
+}
@@ -4,12 +4,14 @@
 //! * Cargo info: `carlog_info!`
 //! * Cargo warning: `carlog_warning!`
 //! * Cargo error: `carlog_error!`
+//! * Cargo fresh: `carlog_fresh!`
+//! * Cargo dirty: `carlog_dirty!`
 //!
 //! The crate provides support for logging to both stdout and stderr and to any stream that implements
 //! the `Write` trait.
 //!
 //! ## Example
-//! ```ignore
+//! ```
 //! #[macro_use] extern crate carlog;
 //!
 //! use carlog::prelude::*;
@@ -24,9 +26,156 @@
 //!     <span style="color: #16C60C;"><b>Compiled</b></span><span> carlog v0.1.0</span>
 //! </div>
 
+#![cfg_attr(
+    all(feature = "proc-macro", carlog_nightly),
+    feature(proc_macro_diagnostic)
+)]
+
+use attributes::Attributes;
+use color::Shade;
 use colored::*;
-use std::io;
-use std::io::{stderr, stdout, Write};
+use std::borrow::Cow;
+use std::fs::{File, OpenOptions};
+use std::io::{self, stderr, stdout, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+mod aggregate;
+pub mod ansi;
+#[cfg(feature = "async")]
+mod async_io;
+mod attributes;
+mod banner;
+mod block;
+pub mod cache;
+pub mod cargo_json;
+pub mod color;
+mod color_name;
+mod dedup;
+pub mod diagnostic;
+mod env;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fmt;
+pub mod github;
+pub mod html;
+mod json;
+mod library;
+mod link;
+pub mod lint;
+mod loggable;
+#[cfg(feature = "log")]
+mod logger;
+pub mod merge;
+mod metadata;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "miette")]
+mod miette_report;
+mod mux;
+mod output;
+#[cfg(feature = "proc-macro")]
+pub mod proc_macros;
+pub mod prompt;
+mod record;
+pub mod render;
+mod report;
+mod route;
+mod scale;
+mod settings;
+mod shutdown;
+#[cfg(unix)]
+mod signal;
+pub mod sinks;
+mod snapshot;
+mod stream;
+mod summary;
+mod sys;
+#[cfg(feature = "syslog")]
+pub mod syslog;
+mod theme;
+pub mod thread;
+mod time;
+mod timing;
+mod transcript;
+pub mod wire;
+
+pub use aggregate::Aggregator;
+pub use ansi::{strip_ansi, StripAnsi};
+#[cfg(feature = "async")]
+pub use async_io::{AsyncDrain, AsyncSink};
+pub use banner::{banner, banner_to};
+pub use block::{emit_block, emit_block_to, set_block_max_lines, BlockStyle};
+pub use cache::{dirty_count, emit_summary, emit_summary_to, fresh_count, reset_cache_counts};
+#[cfg(feature = "macros")]
+pub use carlog_macros::timed;
+pub use color::{
+    color_depth, color_enabled_for, set_color_choice, set_color_choice_for, set_color_depth,
+    ColorChoice, ColorDepth,
+};
+pub use color_name::ColorParseError;
+pub use dedup::{dedup_window, set_dedup_window};
+#[doc(hidden)]
+pub use error::__printed;
+pub use error::{set_error_handler, Error};
+pub use library::{
+    disable_library_output, enable_all_library_output, enable_library_output, library_handle,
+    LibraryLogger,
+};
+pub use link::{hyperlinks, link, set_hyperlinks, Link};
+#[doc(hidden)]
+pub use loggable::__private;
+pub use loggable::Loggable;
+#[cfg(feature = "log")]
+pub use logger::{init, init_with_level, CarlogLogger};
+pub use metadata::Metadata;
+#[cfg(feature = "miette")]
+pub use miette_report::report_diagnostic;
+pub use mux::{JobHandle, Multiplexer};
+pub use output::{atomic, atomic_to, BlockWriter, Session};
+#[doc(hidden)]
+pub use record::{__discarded, __print_note, __print_record};
+pub use record::{print_record, print_record_to, Record};
+pub use report::{print_env_report, print_env_report_to};
+pub use route::{
+    clear_routes, route_target, route_target_with, target_scope, unroute_target, RouteMode,
+    TargetGuard,
+};
+pub use scale::ColorScale;
+pub use settings::{
+    init_default, locations, max_level, quiet, quiet_scope, set_locations, set_max_level,
+    set_quiet, set_verbose, setting_sources, verbose, QuietGuard, SettingSource, SettingSources,
+};
+pub use shutdown::{is_shut_down, shutdown};
+#[cfg(unix)]
+pub use signal::{install_reopen_on, Signal};
+pub use snapshot::{apply_snapshot, config_snapshot, ConfigSnapshot, CONFIG_SNAPSHOT_VERSION};
+pub use stream::{
+    default_error_stream, default_stream, flush, flush_policy, set_default_error_stream,
+    set_default_stream, set_flush_policy, with_default_stream, FlushPolicy,
+};
+pub use summary::__duplicate_warning;
+pub use summary::{
+    code_counts, error_count, print_summary, print_summary_by_code, reset_counts, warning_count,
+    CodeCount,
+};
+pub use theme::{set_theme, style_scope, theme, StyleGuard, Theme};
+pub use thread::{set_thread_tag_width, show_thread_names, thread_names, thread_tag_width};
+pub use time::{
+    enable_timestamps, init_timer, set_clock, set_monotonic_clock, timestamps, TimestampKind,
+};
+#[doc(hidden)]
+pub use timing::__Timed;
+pub use timing::{
+    clear_timings, report_timing, set_slow_threshold, set_timings_capacity, set_warn_threshold,
+    timings, Stopwatch, Timing,
+};
+pub use transcript::{
+    dump_transcript, record_transcript, set_transcript_capacity, transcript, TranscriptEntry,
+};
+pub use wire::FORMAT_VERSION;
 
 /// Module to import required structs and enums to use this crate.
 ///
@@ -37,24 +186,276 @@ use std::io::{stderr, stdout, Write};
 pub mod prelude {
     pub use crate::CargoColor;
     pub use crate::CarlogStream;
+    pub use crate::ColorScale;
+    pub use crate::Format;
+    pub use crate::Level;
+    pub use crate::Loggable;
     pub use crate::Status;
 }
 
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+/// Set the verbosity of the output, following cargo's convention.
+///
+/// * `0`: Normal output (the default).
+/// * `1`: Verbose output, as with `-v`.
+/// * `2`: Very verbose output, as with `-vv`.
+///
+/// ## Example
+/// ```
+/// carlog::set_verbosity(1);
+/// assert_eq!(carlog::verbosity(), 1);
+/// # carlog::set_verbosity(0);
+/// ```
+pub fn set_verbosity(verbosity: u8) {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+}
+
+/// The current output verbosity, see [`set_verbosity`].
+pub fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
+
+static FORMAT: AtomicU8 = AtomicU8::new(Format::Human as u8);
+
+/// How messages are rendered.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Format {
+    /// Cargo like messages with justified, styled statuses (the default).
+    #[default]
+    Human,
+    /// One terse line per message, like `rustc --error-format=short`:
+    /// `src/main.rs:3:5: warning: unused variable`.
+    ///
+    /// The location is the call site of the macros or the [location](Status::location) of the
+    /// status and is left out when there's none. Only the status is colored.
+    Short,
+    /// One JSON object per line, without any ANSI code, i.e. for `jq` or a log collector:
+    /// `{"format_version":1,"status":"Compiling","message":"carlog v0.1.0","level":"Info",...}`.
+    ///
+    /// The lines are the ones of the [`wire`] format, which `wire::parse` reads back, with the
+    /// `color`, `timestamp` and `bold` fields always present. The `level` is the one of the macros
+    /// or of [`Status::level`], `Info` otherwise, and the `location` the one shown in the short
+    /// format, when known. The records of the macros keep their fields, identifier and sequence
+    /// number apart from the message.
+    Json,
+    /// The warnings and errors of the macros as the diagnostics of
+    /// `cargo --message-format=json`, see [`cargo_json`], and the other messages like
+    /// [`Human`](Self::Human).
+    CargoJson,
+    /// The warnings and errors of the macros as GitHub Actions annotations, see [`github`], and
+    /// the other messages like [`Human`](Self::Human). Picked by [`init_default`] when
+    /// `GITHUB_ACTIONS` is `true`.
+    GithubActions,
+}
+
+/// Set how messages are rendered.
+///
+/// ## Example
+/// ```
+/// use carlog::Format;
+///
+/// carlog::set_format(Format::Short);
+/// assert_eq!(carlog::format(), Format::Short);
+/// # carlog::set_format(Format::Human);
+/// ```
+pub fn set_format(format: Format) {
+    store_format(format);
+    settings::store_format_source(SettingSource::Programmatic);
+}
+
+pub(crate) fn store_format(format: Format) {
+    FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+/// How messages are rendered, see [`set_format`].
+pub fn format() -> Format {
+    match FORMAT.load(Ordering::Relaxed) {
+        1 => Format::Short,
+        2 => Format::Json,
+        3 => Format::CargoJson,
+        4 => Format::GithubActions,
+        _ => Format::Human,
+    }
+}
+
+static MAX_MESSAGE_LEN: AtomicUsize = AtomicUsize::new(64 * 1024);
+
+/// Truncate the messages longer than `bytes` in the middle, keeping their head and tail around a
+/// marker like `… [1.9 MiB truncated] …`.
+///
+/// The default is 64 KiB, `usize::MAX` never truncates. The limit applies to every printed
+/// message, so also to what the [`sinks`] write.
+///
+/// ## Example
+/// ```
+/// carlog::max_message_len(1024);
+/// assert_eq!(carlog::message_len_limit(), 1024);
+/// # carlog::max_message_len(64 * 1024);
+/// ```
+pub fn max_message_len(bytes: usize) {
+    MAX_MESSAGE_LEN.store(bytes, Ordering::Relaxed);
+}
+
+/// The length above which messages are truncated, see [`max_message_len`].
+pub fn message_len_limit() -> usize {
+    MAX_MESSAGE_LEN.load(Ordering::Relaxed)
+}
+
+/// The width of the longest status justified with [`Status::justify_auto`] so far.
+static AUTO_WIDTH: AtomicUsize = AtomicUsize::new(0);
+
+/// The width of the statuses justified with [`Status::justify_auto`] so far.
+pub(crate) fn auto_width() -> usize {
+    AUTO_WIDTH.load(Ordering::Relaxed)
+}
+
+/// Set the width of the statuses justified with [`Status::justify_auto`] so far.
+pub(crate) fn set_auto_width(width: usize) {
+    AUTO_WIDTH.store(width, Ordering::Relaxed);
+}
+
+/// A message [truncated](max_message_len) to the limit.
+fn capped(msg: &str) -> Cow<'_, str> {
+    match fmt::truncate_middle(msg.as_bytes(), message_len_limit()) {
+        Cow::Borrowed(_) => Cow::Borrowed(msg),
+        // The cuts are on character boundaries.
+        Cow::Owned(bytes) => Cow::Owned(String::from_utf8_lossy(&bytes).into_owned()),
+    }
+}
+
+/// The message without the separator from its status it starts with, `: ` or ` `.
+fn unseparated(msg: &str) -> &str {
+    msg.strip_prefix(": ")
+        .or_else(|| msg.strip_prefix(' '))
+        .unwrap_or(msg)
+}
+
+/// The severity of a message.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Level {
+    /// The finest details, i.e. every step of an algorithm.
+    Trace,
+    /// Details only useful when debugging.
+    Debug,
+    /// Progress messages, i.e. `carlog_info!` and `carlog_ok!`.
+    Info,
+    /// Warnings, i.e. `carlog_warning!`.
+    Warning,
+    /// Errors, i.e. `carlog_error!`.
+    Error,
+}
+
+impl Level {
+    /// The name of the level, i.e. `warning`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        }
+    }
+}
+
+static STATUS_DEFAULTS: Mutex<StatusDefaults> = Mutex::new(StatusDefaults::PLAIN);
+
+/// The style every [`Status::new`] starts with, see [`set_status_defaults`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatusDefaults {
+    /// If statuses are bold.
+    pub bold: bool,
+
+    /// If statuses are justified.
+    pub justify: bool,
+
+    /// The width statuses are justified to.
+    pub width: usize,
+
+    /// The color of statuses, white if `None`.
+    pub color: Option<CargoColor>,
+}
+
+impl StatusDefaults {
+    const PLAIN: Self = Self {
+        bold: false,
+        justify: false,
+        width: 12,
+        color: None,
+    };
+}
+
+impl Default for StatusDefaults {
+    fn default() -> Self {
+        Self::PLAIN
+    }
+}
+
+/// Set the style every [`Status::new`] starts with, i.e. the house style of a program.
+///
+/// The builder methods still apply on top of it, and [`Status::plain`] ignores it. The presets of
+/// the macros are unaffected.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::StatusDefaults;
+///
+/// carlog::set_status_defaults(StatusDefaults {
+///     bold: true,
+///     justify: true,
+///     width: 12,
+///     color: Some(CargoColor::Green),
+/// });
+/// Status::new().status("Compiled").print_stdout(" carlog v0.1.0").unwrap();
+/// # carlog::set_status_defaults(StatusDefaults::default());
+/// ```
+pub fn set_status_defaults(defaults: StatusDefaults) {
+    *STATUS_DEFAULTS.lock().unwrap_or_else(|e| e.into_inner()) = defaults;
+}
+
+/// The style every [`Status::new`] starts with, see [`set_status_defaults`].
+pub fn status_defaults() -> StatusDefaults {
+    *STATUS_DEFAULTS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 /// Cargo terminal colors.
-#[derive(Copy, Clone)]
+///
+/// The named colors are the 16 basic colors of terminals. Since 0.2 `Yellow` and `Red` are the
+/// normal colors like the others, the warnings and errors of the [`theme`] use their bright
+/// variants.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum CargoColor {
     Green,
     Cyan,
     Yellow,
     Red,
+    #[default]
     White,
     Black,
-}
-
-impl Default for CargoColor {
-    fn default() -> Self {
-        Self::White
-    }
+    /// The bright variant of [`Green`](Self::Green).
+    BrightGreen,
+    /// The bright variant of [`Cyan`](Self::Cyan).
+    BrightCyan,
+    /// The bright variant of [`Yellow`](Self::Yellow), the color of warnings.
+    BrightYellow,
+    /// The bright variant of [`Red`](Self::Red), the color of errors.
+    BrightRed,
+    /// The bright variant of [`White`](Self::White).
+    BrightWhite,
+    /// The bright variant of [`Black`](Self::Black), a gray.
+    BrightBlack,
+    /// A 24-bit color, i.e. `CargoColor::Rgb(0xe5, 0xa0, 0x0d)`, shown as the nearest basic color
+    /// when the terminal can't show it, see [`set_color_depth`].
+    Rgb(u8, u8, u8),
+    /// A color of the 256-color palette, i.e. `CargoColor::Ansi256(208)`, shown as the nearest
+    /// basic color when the terminal can't show it, see [`set_color_depth`].
+    Ansi256(u8),
 }
 
 /// Carlog library streams.
@@ -62,9 +463,12 @@ impl Default for CargoColor {
 /// This enum contains the two output standard streams:
 /// * stdout
 /// * stderr
-/// It also has a custom variant which holds a mutable reference to a writeable stream (socket, vec, slice...).
 ///
-/// The default variant is the stdout.
+/// It also has a custom variant which holds a mutable reference to a writeable stream (socket, vec, slice...)
+/// and a null variant which discards the messages. Files can be owned by the stream, see
+/// [`file_stream`].
+///
+/// The default stream is the stdout, unless it's [replaced](set_default_stream).
 ///
 /// ## Example
 /// ```
@@ -74,49 +478,345 @@ impl Default for CargoColor {
 /// let stderr = CarlogStream::Stderr;
 /// let mut output = Vec::<u8>::new();
 /// let custom = CarlogStream::Custom(&mut output);
+/// let null = CarlogStream::Null;
+/// let boxed = CarlogStream::boxed(std::io::sink());
+/// let shared = CarlogStream::from(std::sync::Arc::new(std::sync::Mutex::new(Vec::<u8>::new())));
+/// let tee = carlog::tee([CarlogStream::Stdout, CarlogStream::Null]);
 /// ```
 pub enum CarlogStream<'a> {
     Stdout,
     Stderr,
     Custom(&'a mut dyn Write),
+    /// Writes to an owned file, plain unless the status [forces the color](Status::force_color).
+    File(File),
+    /// Writes to an owned writer, which doesn't borrow anything so the stream can be stored, see
+    /// [`boxed`](Self::boxed).
+    Boxed(Box<dyn Write + Send>),
+    /// Writes to a writer shared between threads, locked while a whole line is written so the
+    /// lines of the threads never interleave.
+    Shared(Arc<Mutex<dyn Write + Send>>),
+    /// Writes every message to each of the streams, see [`tee`].
+    Tee(Vec<CarlogStream<'a>>),
+    /// Discards the messages, i.e. in a `--quiet` mode, without formatting them unless they are
+    /// [routed](route_target) somewhere else.
+    Null,
+}
+
+/// The kind of stream a message is printed to, which has its own [color decision](color).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Target {
+    /// The standard output.
+    Stdout,
+    /// The standard error.
+    Stderr,
+    /// Any other stream, i.e. a file or a [`CarlogStream::Custom`].
+    Custom,
+}
+
+impl Target {
+    /// The name of the target, i.e. `stderr`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Target::Stdout => "stdout",
+            Target::Stderr => "stderr",
+            Target::Custom => "custom",
+        }
+    }
+}
+
+impl CarlogStream<'_> {
+    /// The kind of the stream, [`Target::Custom`] for a [`Tee`](Self::Tee) whose streams have
+    /// their own.
+    pub fn target(&self) -> Target {
+        match self {
+            CarlogStream::Stdout => Target::Stdout,
+            CarlogStream::Stderr => Target::Stderr,
+            CarlogStream::Custom(_)
+            | CarlogStream::File(_)
+            | CarlogStream::Boxed(_)
+            | CarlogStream::Shared(_)
+            | CarlogStream::Tee(_)
+            | CarlogStream::Null => Target::Custom,
+        }
+    }
+
+    /// Create a stream owning `writer`.
+    ///
+    /// The stream isn't [`Send`] itself, since a [`Custom`](Self::Custom) stream may borrow a
+    /// writer that isn't, so a writer moved to another thread is wrapped there.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// struct Build {
+    ///     log: CarlogStream<'static>,
+    /// }
+    ///
+    /// let mut build = Build { log: CarlogStream::boxed(Vec::<u8>::new()) };
+    /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiling");
+    /// status.print_stream(&mut build.log, " carlog v0.1.0").unwrap();
+    /// ```
+    pub fn boxed<W>(writer: W) -> Self
+    where
+        W: Write + Send + 'static,
+    {
+        CarlogStream::Boxed(Box::new(writer))
+    }
+
+    /// Whether the stream discards the messages.
+    pub fn is_null(&self) -> bool {
+        matches!(self, CarlogStream::Null)
+    }
+}
+
+/// A [`CarlogStream::Shared`] stream, one per thread writing to the same writer.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+/// use std::sync::{Arc, Mutex};
+///
+/// let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+/// let worker = Arc::clone(&output);
+/// std::thread::spawn(move || carlog_info!("Compiling", "carlog v0.1.0", CarlogStream::from(worker)))
+///     .join()
+///     .unwrap();
+/// assert!(!output.lock().unwrap().is_empty());
+/// ```
+impl<W> From<Arc<Mutex<W>>> for CarlogStream<'_>
+where
+    W: Write + Send + 'static,
+{
+    fn from(writer: Arc<Mutex<W>>) -> Self {
+        CarlogStream::Shared(writer)
+    }
+}
+
+/// A [`CarlogStream::Tee`] writing every message to each of `streams`.
+///
+/// The message is formatted once and each stream decides its colors, i.e. the terminal gets
+/// colors and a file gets plain text. A failure to write to a stream doesn't stop the others,
+/// the first one is returned once every stream was written.
+///
+/// ## Example
+/// ```no_run
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// let log = carlog::file_stream("build.log", true)?;
+/// carlog_info!("Compiling", "carlog v0.1.0", carlog::tee([CarlogStream::Stderr, log]));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn tee<'a, I>(streams: I) -> CarlogStream<'a>
+where
+    I: IntoIterator<Item = CarlogStream<'a>>,
+{
+    CarlogStream::Tee(streams.into_iter().collect())
+}
+
+/// Open a file as a [`CarlogStream`], creating it if it doesn't exist.
+///
+/// * `path`: The path of the file, i.e. `build.log`.
+/// * `append`: Whether the messages are appended to the contents of the file instead of replacing
+///   them.
+///
+/// ## Example
+/// ```no_run
+/// use carlog::prelude::*;
+///
+/// let mut log = carlog::file_stream("build.log", true)?;
+/// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiling");
+/// status.print_stream(&mut log, " carlog v0.1.0")?;
+/// # Ok::<(), carlog::Error>(())
+/// ```
+pub fn file_stream<'a, P>(path: P, append: bool) -> io::Result<CarlogStream<'a>>
+where
+    P: AsRef<Path>,
+{
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)?;
+    Ok(CarlogStream::File(file))
 }
 
 impl Default for CarlogStream<'_> {
     fn default() -> Self {
-        Self::Stdout
+        default_stream()
+    }
+}
+
+impl std::fmt::Debug for CarlogStream<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CarlogStream::Stdout => f.write_str("Stdout"),
+            CarlogStream::Stderr => f.write_str("Stderr"),
+            CarlogStream::Custom(_) => f.write_str("Custom(..)"),
+            CarlogStream::File(file) => f.debug_tuple("File").field(file).finish(),
+            CarlogStream::Boxed(_) => f.write_str("Boxed(..)"),
+            CarlogStream::Shared(_) => f.write_str("Shared(..)"),
+            CarlogStream::Tee(streams) => f.debug_tuple("Tee").field(streams).finish(),
+            CarlogStream::Null => f.write_str("Null"),
+        }
     }
 }
 
 /// Simple cargo status log.
 ///
-/// This is the part displayed before the actual message to be logged i.e. 'Compiled'.
+/// This is the part displayed before the actual message to be logged i.e. 'Compiled'. Printing
+/// borrows the status, so one status can print any number of messages.
 ///
 /// ## Example
 /// ```
 /// use carlog::prelude::*;
 ///
 /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
-/// status.print_stdout("carlog v0.1.0");
+/// status.print_stdout(" carlog-macros v0.1.0");
+/// status.print_stdout(" carlog v0.1.0");
 /// ```
-#[derive(Default)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Status {
-    /// If the status must be padded to 12 characters to the right using spaces.
+    /// If the status must be padded to `width` characters to the right using spaces.
     justify: bool,
 
-    /// If the status must be bold.
-    bold: bool,
+    /// The width the status is justified to.
+    width: usize,
+
+    /// If the width grows to the longest status justified with [`justify_auto`](Self::justify_auto).
+    auto_width: bool,
+
+    /// The text attributes of the status, i.e. bold.
+    attributes: Attributes,
+
+    /// If control characters in byte messages must be written verbatim.
+    raw_bytes: bool,
+
+    /// If a pre-styled status must lose its own style for the one of the status.
+    restyle: bool,
+
+    /// Whether the status is colored whatever the stream, the environment or the terminal.
+    color_choice: ColorChoice,
+
+    /// The width of the tab stops in the message, if tabs must be expanded to spaces.
+    tab_width: Option<usize>,
+
+    /// The width paths and URLs are shortened to when the line is wider than the terminal.
+    ellipsize_paths: Option<usize>,
 
     /// The color of the status.
     color: CargoColor,
 
     /// The string of the status.
     status: String,
+
+    /// The code of the message, written in brackets after the status, i.e. `E0308`.
+    code: Option<String>,
+
+    /// The causes of the message, written on `caused by:` lines after it.
+    causes: Vec<String>,
+
+    /// Where the message comes from, as path, line and column.
+    location: Option<(String, u32, u32)>,
+
+    /// If a timestamp is printed before the status even when [`enable_timestamps`] is off.
+    timestamp: bool,
+
+    /// If the elapsed time is printed before the status even when [`enable_timestamps`] is off.
+    elapsed: bool,
+
+    /// If the thread is tagged before the status even when [`show_thread_names`] is off.
+    thread_tag: bool,
+
+    /// The level of the message, written in the JSON format.
+    level: Option<Level>,
+}
+
+impl Default for Status {
+    fn default() -> Self {
+        let defaults = status_defaults();
+        Self {
+            justify: defaults.justify,
+            attributes: match defaults.bold {
+                true => Attributes::BOLD,
+                false => Attributes::NONE,
+            },
+            width: defaults.width,
+            color: defaults.color.unwrap_or_default(),
+            ..Self::plain()
+        }
+    }
+}
+
+/// The serialized form of a [`Status`].
+#[cfg(feature = "serde")]
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SerializedStatus {
+    justify: bool,
+    bold: bool,
+    color: CargoColor,
+    status: String,
+}
+
+/// Statuses are serialized as their `justify`, `bold`, `color` and `status` fields, the other
+/// settings are left to the defaults of [`Status::plain`] when deserializing, as are the missing
+/// fields. Unknown fields are rejected, so a typo in a configuration file isn't silently ignored.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+///
+/// let status: Status = serde_json::from_str(r#"{"bold":true,"color":"green"}"#).unwrap();
+/// assert_eq!(status, Status::plain().bold().color(CargoColor::Green));
+/// assert!(serde_json::from_str::<Status>(r#"{"colour":"green"}"#).is_err());
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for Status {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        SerializedStatus {
+            justify: self.justify,
+            bold: self.attributes.contains(Attributes::BOLD),
+            color: self.color,
+            status: self.status.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Status {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let serialized = SerializedStatus::deserialize(deserializer)?;
+        let mut status = Status::plain()
+            .color(serialized.color)
+            .status(serialized.status);
+        if serialized.justify {
+            status = status.justify();
+        }
+        if serialized.bold {
+            status = status.bold();
+        }
+        Ok(status)
+    }
 }
 
 impl Status {
     /// Creates a new empty status.
     ///
-    /// The status has the default values:
+    /// The status has the default values, unless changed with [`set_status_defaults`]:
     /// * <b>NO</b> justification.
     /// * <b>NO</b> boldness.
     /// * White color.
@@ -131,401 +831,4136 @@ impl Status {
         Self::default()
     }
 
-    /// Justify the status.
+    /// Creates a new empty status with the default values of [`new`](Self::new), ignoring
+    /// [`set_status_defaults`].
     ///
-    /// Sets the status to be padded to 12 characters to the right using spaces.
     /// ## Example
     /// ```
     /// use carlog::prelude::*;
     ///
-    /// let status = Status::new().justify();
+    /// let status = Status::plain().color(CargoColor::Red).status("error");
     /// ```
-    pub fn justify(mut self) -> Self {
-        self.justify = true;
-        self
+    pub fn plain() -> Self {
+        Self {
+            justify: false,
+            width: StatusDefaults::PLAIN.width,
+            auto_width: false,
+            attributes: Attributes::NONE,
+            raw_bytes: false,
+            restyle: false,
+            color_choice: ColorChoice::Auto,
+            tab_width: None,
+            ellipsize_paths: None,
+            color: CargoColor::default(),
+            status: String::new(),
+            code: None,
+            causes: Vec::new(),
+            location: None,
+            timestamp: false,
+            elapsed: false,
+            thread_tag: false,
+            level: None,
+        }
     }
 
-    /// Set the status to be bold.
+    /// The status of `carlog_warning!` with another label than `warning`, i.e. `deprecated`.
+    ///
+    /// The label takes the warning color of the [`theme`].
     ///
     /// ## Example
     /// ```
     /// use carlog::prelude::*;
     ///
-    /// let status = Status::new().bold();
+    /// let status = Status::warning_with_label("deprecated");
+    /// status.print_stdout(": use of old API");
     /// ```
-    pub fn bold(mut self) -> Self {
-        self.bold = true;
-        self
+    pub fn warning_with_label<S>(label: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        Self::plain()
+            .color(theme().warning)
+            .status(label)
+            .level(Level::Warning)
     }
 
-    /// Set the color of the status.
+    /// The status of `carlog_error!` with another label than `error`, i.e. `security`.
     ///
-    /// * `color`: The cargo color of the status.
+    /// The label takes the error color of the [`theme`].
+    pub fn error_with_label<S>(label: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        Self::plain()
+            .color(theme().error)
+            .status(label)
+            .level(Level::Error)
+    }
+
+    /// Justify the status.
     ///
+    /// Sets the status to be padded to 12 columns, or the width of the
+    /// [defaults](set_status_defaults), to the right using spaces. Wide characters, i.e. CJK, take
+    /// two columns and combining marks none, like in a terminal.
     /// ## Example
     /// ```
     /// use carlog::prelude::*;
     ///
-    /// let status = Status::new().color(CargoColor::Cyan);
+    /// let status = Status::new().justify();
     /// ```
-    pub fn color(mut self, color: CargoColor) -> Self {
-        self.color = color;
+    pub fn justify(mut self) -> Self {
+        self.justify = true;
         self
     }
 
-    /// Set the string status.
+    /// Justify the status to `width` columns instead of 12.
     ///
-    /// * `str`: The status text from a type that can be converted to a string reference.
+    /// A status longer than the width is printed in full, without padding.
     ///
     /// ## Example
     /// ```
     /// use carlog::prelude::*;
     ///
-    /// let status = Status::new().status("Text");
+    /// let status = Status::new().justify_to(16).status("Cross-compiling");
     /// ```
-    pub fn status<S>(mut self, str: S) -> Self
-    where
-        S: AsRef<str>,
-    {
-        self.status = str.as_ref().to_string();
+    pub fn justify_to(mut self, width: usize) -> Self {
+        self.justify = true;
+        self.width = width;
         self
     }
 
-    /// Print the status to stdout.
+    /// Justify the status to the longest status justified this way so far in the process, and at
+    /// least to the width of [`justify`](Self::justify).
     ///
-    /// `msg`: The message to be printed alongside the status.
+    /// The statuses that aren't justified this way keep their own width. A column only lines up
+    /// once its longest status has been printed, so long statuses can be rendered beforehand, see
+    /// [`render`](Self::render).
     ///
     /// ## Example
     /// ```
     /// use carlog::prelude::*;
     ///
-    /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
-    /// status.print_stdout("carlog v0.1.0");
+    /// carlog::color::set_override(false);
+    /// let first = Status::new().justify_auto().status("Regenerating");
+    /// let second = Status::new().justify_auto().status("Cross-compiling");
+    /// assert_eq!(first.render(" a"), "Regenerating a");
+    /// assert_eq!(second.render(" b"), "Cross-compiling b");
+    /// assert_eq!(first.render(" c"), "   Regenerating c");
+    /// # carlog::color::unset_override();
     /// ```
-    pub fn print_stdout<S>(self, msg: S) -> io::Result<()>
-    where
-        S: AsRef<str>,
-    {
-        self.print(stdout().lock(), msg)
+    pub fn justify_auto(mut self) -> Self {
+        self.justify = true;
+        self.auto_width = true;
+        self
     }
 
-    /// Print the status to stderr.
-    ///
-    /// `msg`: The message to be printed alongside the status.
+    /// Set the status to be bold.
     ///
     /// ## Example
     /// ```
     /// use carlog::prelude::*;
     ///
-    /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
-    /// status.print_stderr("carlog v0.1.0");
+    /// let status = Status::new().bold();
     /// ```
-    pub fn print_stderr<S>(self, msg: S) -> io::Result<()>
-    where
-        S: AsRef<str>,
-    {
-        self.print(stderr().lock(), msg)
+    pub fn bold(mut self) -> Self {
+        self.attributes |= Attributes::BOLD;
+        self
     }
 
-    /// Print the status to the specified stream.
-    ///
-    /// `stream`: The stream where the status and message will be written.
-    /// `msg`: The message to be printed alongside the status.
+    /// Set the status to be dimmed, like the `Fresh` status of cargo.
     ///
     /// ## Example
     /// ```
     /// use carlog::prelude::*;
     ///
-    /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
+    /// let status = Status::new().justify().dimmed().status("Fresh");
+    /// ```
+    pub fn dimmed(mut self) -> Self {
+        self.attributes |= Attributes::DIMMED;
+        self
+    }
+
+    /// Set the status to be italic.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().italic();
+    /// ```
+    pub fn italic(mut self) -> Self {
+        self.attributes |= Attributes::ITALIC;
+        self
+    }
+
+    /// Set the status to be underlined.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().bold().underline().status("Publishing");
+    /// ```
+    pub fn underline(mut self) -> Self {
+        self.attributes |= Attributes::UNDERLINE;
+        self
+    }
+
+    /// Set the status to be struck through.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().strikethrough().status("Skipped");
+    /// ```
+    pub fn strikethrough(mut self) -> Self {
+        self.attributes |= Attributes::STRIKETHROUGH;
+        self
+    }
+
+    /// Expand the tabs of the message to spaces, with tab stops every `width` columns (default
+    /// off).
+    ///
+    /// The columns are counted from the start of the message, so columns aligned with tabs stay
+    /// aligned whatever the length of the status. Escape sequences don't take any column.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().status("note").expand_tabs(Some(4));
+    /// ```
+    pub fn expand_tabs(mut self, width: Option<usize>) -> Self {
+        self.tab_width = width;
+        self
+    }
+
+    /// Shorten the paths and URLs of the message wider than `max` columns with
+    /// [`fmt::ellipsize_middle`], when the line would be wider than the terminal.
+    ///
+    /// Paths and URLs are the words of the message with a `/`, a `\` or a `://`, or that start
+    /// like a path, i.e. `~/`. Lines printed while no terminal is attached are left whole.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().status("Compiling").ellipsize_paths(40);
+    /// ```
+    pub fn ellipsize_paths(mut self, max: usize) -> Self {
+        self.ellipsize_paths = Some(max);
+        self
+    }
+
+    /// Print a timestamp before the status, in RFC 3339 unless [`enable_timestamps`] chose another
+    /// kind.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().justify().status("Finished").timestamp();
+    /// ```
+    pub fn timestamp(mut self) -> Self {
+        self.timestamp = true;
+        self
+    }
+
+    /// Print the time elapsed since [`init_timer`] before the status, unless [`enable_timestamps`]
+    /// chose another kind of timestamp.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().justify().status("Finished").elapsed();
+    /// ```
+    pub fn elapsed(mut self) -> Self {
+        self.elapsed = true;
+        self
+    }
+
+    /// Tag the lines with the name of the thread printing them, see [`show_thread_names`].
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().justify().status("Compiling").thread_tag();
+    /// ```
+    pub fn thread_tag(mut self) -> Self {
+        self.thread_tag = true;
+        self
+    }
+
+    /// Set the level of the message, written in the [JSON format](Format::Json) and told to the
+    /// [`RingBuffer`](sinks::RingBuffer) and syslog sinks writing its line.
+    ///
+    /// The macros set it to theirs.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().status("warning").level(Level::Warning);
+    /// ```
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Set the code of the message, written in brackets after the status in its style, i.e.
+    /// `error[E0308]`, and as the `code` field in the [JSON format](Format::Json).
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::plain().color(CargoColor::BrightRed).status("error").code("E0308");
+    /// status.print_stderr(": mismatched types");
+    /// ```
+    pub fn code<S>(mut self, code: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.code = Some(code.as_ref().to_string());
+        self
+    }
+
+    /// Add a cause of the message, i.e. the [`source`](std::error::Error::source) of an error,
+    /// written after the message on a dimmed `caused by:` line indented by two spaces.
+    ///
+    /// In the [short format](Format::Short) the causes follow the message on its line, separated
+    /// by `: `, and in the [JSON format](Format::Json) they're the `causes` array.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::plain().color(CargoColor::BrightRed).status("error");
+    /// status.cause("permission denied").print_stderr(": failed to read `Cargo.toml`");
+    /// ```
+    pub fn cause<S>(mut self, cause: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.causes.push(cause.as_ref().to_string());
+        self
+    }
+
+    /// Set where the message comes from, shown in the [short format](Format::Short).
+    ///
+    /// The macros set it to their call site.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().status("warning").location("src/main.rs", 3, 5);
+    /// ```
+    pub fn location<S>(mut self, path: S, line: u32, column: u32) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.location = Some((path.as_ref().to_string(), line, column));
+        self
+    }
+
+    /// Write byte messages verbatim, including the control characters that
+    /// [`print_bytes`](Self::print_bytes) escapes by default.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().status("stdout").raw_bytes();
+    /// ```
+    pub fn raw_bytes(mut self) -> Self {
+        self.raw_bytes = true;
+        self
+    }
+
+    /// Whether a status text that is already styled, i.e. by another crate, has its escape
+    /// sequences replaced by the style of the status (`true`) or is printed as it is (`false`, the
+    /// default).
+    ///
+    /// Either way a pre-styled status is justified by its visible width, and printed without
+    /// escape sequences when colors are [disabled](color::decision).
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().color(CargoColor::Green).status("\u{1b}[35mNotice\u{1b}[0m");
+    /// let status = status.restyle(true);
+    /// ```
+    pub fn restyle(mut self, restyle: bool) -> Self {
+        self.restyle = restyle;
+        self
+    }
+
+    /// Color the status whatever the [decision](color::decision) for its stream, i.e. for a custom
+    /// stream known to be a terminal, which are printed plain by default.
+    ///
+    /// The justification is the same either way, so colored and plain lines stay aligned.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().color(CargoColor::Green).status("Compiled").force_color();
+    /// let mut output = Vec::<u8>::new();
+    /// status.print(&mut output, " carlog v0.1.0").unwrap();
+    /// assert_eq!(output, b"\x1b[32mCompiled\x1b[0m carlog v0.1.0\n");
+    /// ```
+    pub fn force_color(mut self) -> Self {
+        self.color_choice = ColorChoice::Always;
+        self
+    }
+
+    /// Never color the status, whatever the [decision](color::decision) for its stream, i.e. for a
+    /// socket given as a custom stream while the colors are [forced](set_color_choice).
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// carlog::set_color_choice(carlog::ColorChoice::Always);
+    /// let status = Status::new().color(CargoColor::Green).status("Compiled").no_color();
+    /// let mut output = Vec::<u8>::new();
+    /// status.print(&mut output, " carlog v0.1.0").unwrap();
+    /// assert_eq!(output, b"Compiled carlog v0.1.0\n");
+    /// # carlog::set_color_choice(carlog::ColorChoice::Auto);
+    /// ```
+    pub fn no_color(mut self) -> Self {
+        self.color_choice = ColorChoice::Never;
+        self
+    }
+
+    /// Set the color of the status.
+    ///
+    /// * `color`: The cargo color of the status.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().color(CargoColor::Cyan);
+    /// ```
+    pub fn color(mut self, color: CargoColor) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Set the color of the status from a value and a color scale.
+    ///
+    /// * `scale`: The scale mapping the value to a color.
+    /// * `value`: The value shown by the status.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().status("Coverage").color_by(&ColorScale::default(), 0.73);
+    /// ```
+    pub fn color_by(self, scale: &ColorScale, value: f64) -> Self {
+        self.color(scale.color(value))
+    }
+
+    /// Set the string status.
+    ///
+    /// * `str`: The status text from a type that can be converted to a string reference.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().status("Text");
+    /// ```
+    pub fn status<S>(mut self, str: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.status = str.as_ref().to_string();
+        self
+    }
+
+    /// Print the status to stdout.
+    ///
+    /// `msg`: The message to be printed alongside the status.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
+    /// status.print_stdout("carlog v0.1.0");
+    /// ```
+    pub fn print_stdout<S>(&self, msg: S) -> Result<(), Error>
+    where
+        S: Loggable,
+    {
+        let _output = output::lock();
+        self.print_to(stdout().lock(), Target::Stdout, msg)
+    }
+
+    /// Print the status to stderr.
+    ///
+    /// `msg`: The message to be printed alongside the status.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
+    /// status.print_stderr("carlog v0.1.0");
+    /// ```
+    pub fn print_stderr<S>(&self, msg: S) -> Result<(), Error>
+    where
+        S: Loggable,
+    {
+        let _output = output::lock();
+        self.print_to(stderr().lock(), Target::Stderr, msg)
+    }
+
+    /// Print the status to the specified stream.
+    ///
+    /// `stream`: The stream where the status and message will be written.
+    /// `msg`: The message to be printed alongside the status, see [`Loggable`].
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
     /// let mut output = Vec::<u8>::new();
     /// status.print(output, "carlog v0.1.0");
     /// ```
-    pub fn print<W, S>(self, mut stream: W, msg: S) -> io::Result<()>
+    pub fn print<W, S>(&self, stream: W, msg: S) -> Result<(), Error>
     where
         W: Write,
-        S: AsRef<str>,
+        S: Loggable,
     {
-        let status = Self::color_str(self.color, self.bold, &self.status);
-        if self.justify {
-            let padding = " ".repeat(usize::saturating_sub(12, self.status.len()));
-            write!(stream, "{}{}", padding, status)?;
-        } else {
-            write!(stream, "{}", status)?;
+        self.print_to(stream, Target::Custom, msg)
+    }
+
+    /// The line [`print`](Self::print) would write for `msg`, without the trailing newline, i.e.
+    /// to hand it to another logging framework.
+    ///
+    /// `msg`: The message to be rendered alongside the status, see [`Loggable`].
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// carlog::color::set_override(false);
+    /// let status = Status::new().justify().color(CargoColor::Green).status("Compiled");
+    /// assert_eq!(status.render(" carlog v0.1.0"), "    Compiled carlog v0.1.0");
+    /// # carlog::color::unset_override();
+    /// ```
+    pub fn render<S>(&self, msg: S) -> String
+    where
+        S: Loggable,
+    {
+        let mut line = self.render_line(msg);
+        line.pop();
+        line
+    }
+
+    /// The line [`print`](Self::print) would write for `msg`, including the trailing newline.
+    ///
+    /// `msg`: The message to be rendered alongside the status, see [`Loggable`].
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
+    /// let line = status.render_line(" carlog v0.1.0");
+    /// assert!(line.ends_with(" carlog v0.1.0\n"));
+    /// ```
+    pub fn render_line<S>(&self, msg: S) -> String
+    where
+        S: Loggable,
+    {
+        self.rendered(Target::Custom, &msg.to_log_string())
+    }
+
+    /// The line printed for `msg` to `target`, with the color decision of the target.
+    fn rendered(&self, target: Target, msg: &str) -> String {
+        let mut line = String::new();
+        self.render_into(&mut line, target, msg);
+        line
+    }
+
+    /// Render the line printed for `msg` to `target` at the end of `line`.
+    fn render_into(&self, line: &mut String, target: Target, msg: &str) {
+        color::with_choice(target, self.color_choice(), || self.write_line(line, msg));
+    }
+
+    fn print_to<W, S>(&self, mut stream: W, target: Target, msg: S) -> Result<(), Error>
+    where
+        W: Write,
+        S: Loggable,
+    {
+        let msg = msg.to_log_string();
+        fmt::with_buffer(|line| {
+            self.render_into(line, target, &msg);
+            let _output = output::lock();
+            transcript::append(target, line);
+            // A single write keeps the line whole on streams shared between threads.
+            sinks::with_level(self.level, || {
+                stream
+                    .write_all(line.as_bytes())
+                    .and_then(|_| stream::flush_line(&mut stream))
+            })
+            .map_err(|e| Error::printing(e, target, &self.status, &msg))
+        })
+    }
+
+    /// Print the status to a [`CarlogStream`], i.e. one stored in the configuration of the program.
+    ///
+    /// The bytes written are the same as the ones of [`print_stdout`](Self::print_stdout),
+    /// [`print_stderr`](Self::print_stderr) or [`print`](Self::print) for the stream.
+    ///
+    /// `stream`: The stream where the status and message will be written.
+    /// `msg`: The message to be printed alongside the status, see [`Loggable`].
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
+    /// let mut output = Vec::<u8>::new();
+    /// status.print_stream(&mut CarlogStream::Custom(&mut output), "carlog v0.1.0");
+    /// ```
+    pub fn print_stream<S>(&self, stream: &mut CarlogStream<'_>, msg: S) -> Result<(), Error>
+    where
+        S: Loggable,
+    {
+        match stream {
+            CarlogStream::Stdout if shutdown::is_shut_down() => self.print_stderr(msg),
+            CarlogStream::Stdout => self.print_stdout(msg),
+            CarlogStream::Stderr => self.print_stderr(msg),
+            CarlogStream::Custom(stream) => self.print(stream, msg),
+            CarlogStream::File(file) => self.print(file, msg),
+            CarlogStream::Boxed(writer) => self.print(writer, msg),
+            CarlogStream::Shared(writer) => {
+                // The output lock is taken before the writer, like everywhere else.
+                let _output = output::lock();
+                let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+                self.print(&mut *writer, msg)
+            }
+            CarlogStream::Tee(streams) => {
+                let msg = msg.to_log_string();
+                let mut result = Ok(());
+                for stream in streams {
+                    let printed = self.print_stream(stream, &*msg);
+                    result = result.and(printed);
+                }
+                result
+            }
+            CarlogStream::Null => Ok(()),
+        }
+    }
+
+    /// Print the status followed by a raw byte message to the specified stream.
+    ///
+    /// The bytes are written as they are, even when they aren't valid UTF-8, so that the output of
+    /// other processes can be relayed without corrupting it. A newline is appended unless the
+    /// message already ends with one. Control characters other than tabs and newlines, which could
+    /// change the state of the terminal, are escaped in caret notation (i.e. `^[` for `ESC`) unless
+    /// the status is [`raw_bytes`](Self::raw_bytes).
+    ///
+    /// The short and JSON [formats](Format) need text: the bytes are converted to UTF-8 with their
+    /// invalid sequences replaced by `U+FFFD`, and the JSON line of such a message has a
+    /// `"lossy":true` field. JSON escapes the control characters itself.
+    ///
+    /// `stream`: The stream where the status and message will be written.
+    /// `msg`: The bytes to be printed alongside the status.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().color(CargoColor::Cyan).status("stdout");
+    /// let mut output = Vec::<u8>::new();
+    /// status.print_bytes(&mut output, b": caf\xe9\n");
+    /// ```
+    pub fn print_bytes<W>(&self, mut stream: W, msg: &[u8]) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        let msg = msg.strip_suffix(b"\n").unwrap_or(msg);
+        let msg = &*fmt::truncate_middle(msg, message_len_limit());
+        let line = match format() {
+            Format::Short | Format::Json => {
+                // JSON escapes the control characters itself.
+                let text = match format() {
+                    Format::Json => String::from_utf8_lossy(msg),
+                    _ => Cow::Owned(String::from_utf8_lossy(&self.escaped_bytes(msg)).into_owned()),
+                };
+                let lossy = std::str::from_utf8(msg).is_err();
+                let mut line = String::new();
+                color::with_choice(Target::Custom, self.color_choice(), || {
+                    self.write_formatted_line(&mut line, &text, lossy)
+                });
+                line.into_bytes()
+            }
+            Format::Human | Format::CargoJson | Format::GithubActions => {
+                let mut line =
+                    color::with_choice(Target::Custom, self.color_choice(), || self.prefix())
+                        .into_bytes();
+                line.extend_from_slice(&self.escaped_bytes(msg));
+                line.push(b'\n');
+                line
+            }
+        };
+        let _output = output::lock();
+        transcript::append(Target::Custom, &String::from_utf8_lossy(&line));
+        // A single write keeps the line whole on streams shared between threads.
+        sinks::with_level(self.level, || {
+            stream
+                .write_all(&line)
+                .and_then(|_| stream::flush_line(&mut stream))
+        })
+        .map_err(|e| {
+            Error::printing(
+                e,
+                Target::Custom,
+                &self.status,
+                &String::from_utf8_lossy(msg),
+            )
+        })
+    }
+
+    /// The bytes of a byte message with its control characters escaped, unless the status is
+    /// [`raw_bytes`](Self::raw_bytes).
+    fn escaped_bytes<'m>(&self, msg: &'m [u8]) -> Cow<'m, [u8]> {
+        if self.raw_bytes {
+            return Cow::Borrowed(msg);
+        }
+        let mut escaped = Vec::with_capacity(msg.len());
+        for &byte in msg {
+            match byte {
+                b'\t' | b'\n' => escaped.push(byte),
+                0x00..=0x1f => escaped.extend_from_slice(&[b'^', byte + 0x40]),
+                0x7f => escaped.extend_from_slice(b"^?"),
+                _ => escaped.push(byte),
+            }
+        }
+        Cow::Owned(escaped)
+    }
+
+    /// Print the status followed by a path displayed with [`fmt::path`] to the specified stream.
+    ///
+    /// `stream`: The stream where the status and path will be written.
+    /// `prefix`: The message printed between the status and the path.
+    /// `path`: The path printed as the rest of the message.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    /// use std::path::Path;
+    ///
+    /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Packaged");
+    /// let mut output = Vec::<u8>::new();
+    /// status.print_path(&mut output, " ", Path::new("target/package/carlog-0.1.0.crate"));
+    /// ```
+    pub fn print_path<W, S, P>(&self, stream: W, prefix: S, path: P) -> Result<(), Error>
+    where
+        W: Write,
+        S: AsRef<str>,
+        P: AsRef<std::path::Path>,
+    {
+        let msg = format!("{}{}", prefix.as_ref(), fmt::path(path));
+        self.print(stream, msg)
+    }
+
+    /// Print the status to a stream with the whole message linked to `url`, see [`link`].
+    ///
+    /// The spaces the message starts with aren't linked.
+    ///
+    /// `stream`: The stream where the status and message will be written.
+    /// `msg`: The message to be printed alongside the status, see [`Loggable`].
+    /// `url`: The URL the message links to.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::new().bold().justify().color(CargoColor::Green).status("Documenting");
+    /// let mut output = Vec::<u8>::new();
+    /// status.print_linked(&mut output, " carlog v0.1.0", "https://docs.rs/carlog");
+    /// ```
+    pub fn print_linked<W, S, U>(&self, stream: W, msg: S, url: U) -> Result<(), Error>
+    where
+        W: Write,
+        S: Loggable,
+        U: std::fmt::Display,
+    {
+        let msg = msg.to_log_string();
+        let text = msg.trim_start();
+        let spaces = &msg[..msg.len() - text.len()];
+        self.print(stream, format!("{}{}", spaces, link(text, url)))
+    }
+
+    /// The whole line printed for `msg`, including the newline.
+    fn line(&self, msg: &str) -> String {
+        let mut line = String::new();
+        self.write_line(&mut line, msg);
+        line
+    }
+
+    /// Write the whole line printed for `msg`, including the newline, at the end of `line`.
+    fn write_line(&self, line: &mut String, msg: &str) {
+        self.write_formatted_line(line, msg, false)
+    }
+
+    /// Write the whole line printed for `msg` like [`write_line`](Self::write_line), `lossy` if
+    /// the message had invalid UTF-8 replaced.
+    fn write_formatted_line(&self, line: &mut String, msg: &str, lossy: bool) {
+        let capped = capped(msg);
+        let truncated = matches!(capped, Cow::Owned(_));
+        let msg = &*link::for_line(&capped);
+        match format() {
+            Format::Human | Format::CargoJson | Format::GithubActions => {
+                self.write_human_line(line, msg)
+            }
+            Format::Short => self.write_short_line(line, msg),
+            Format::Json => self.write_json_line(line, msg, truncated, lossy),
+        }
+    }
+
+    /// The uncolored line printed for `msg` in the human format, whatever the current one.
+    pub(crate) fn human_line(&self, msg: &str) -> String {
+        let mut line = String::new();
+        color::with_choice(Target::Custom, ColorChoice::Never, || {
+            self.write_human_line(&mut line, &capped(msg))
+        });
+        ansi::strip(&line).into_owned()
+    }
+
+    /// Render the line of `msg` as HTML, the status as a `<span>` colored with an inline style, see
+    /// [`html`].
+    ///
+    /// The line is the one of the human format without its timestamp nor its thread tag, and
+    /// without the newline. The status and the message are escaped and their escape sequences are
+    /// removed.
+    ///
+    /// `msg`: The message to be rendered alongside the status, see [`Loggable`].
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// let status = Status::plain().color(CargoColor::BrightYellow).status("warning");
+    /// assert_eq!(
+    ///     status.render_html(": unused `Vec<u8>`"),
+    ///     "<span style=\"color: #ffff00\">warning</span>: unused `Vec&lt;u8&gt;`"
+    /// );
+    /// ```
+    pub fn render_html<S>(&self, msg: S) -> String
+    where
+        S: Loggable,
+    {
+        self.render_html_with(msg, html::Styling::Inline)
+    }
+
+    /// Render the line of `msg` as HTML like [`render_html`](Self::render_html), with the status
+    /// styled with `styling`.
+    pub fn render_html_with<S>(&self, msg: S, styling: html::Styling) -> String
+    where
+        S: Loggable,
+    {
+        let msg = msg.to_log_string();
+        let msg = ansi::strip(&capped(&msg)).into_owned();
+        let mut line = " ".repeat(self.padding());
+        let mut status = ansi::strip(&self.status).into_owned();
+        if let Some(code) = &self.code {
+            status.push_str(&format!("[{}]", code));
+        }
+        html::write_span(&mut line, &status, self.color, self.attributes, styling);
+        match self.tab_width {
+            Some(width) => line.push_str(&html::escape(&fmt::expand_tabs(&msg, width))),
+            None => line.push_str(&html::escape(&msg)),
+        }
+        line
+    }
+
+    /// Write the line printed for `msg` in the human format at the end of `line`.
+    fn write_human_line(&self, line: &mut String, msg: &str) {
+        let start = line.len();
+        self.write_timestamp(line);
+        if self.thread_tag || thread::thread_names() {
+            thread::write_thread_tag(line);
+        }
+        self.write_prefix(line);
+        let msg = &*self.ellipsized(msg, fmt::display_width(&line[start..]));
+        match self.tab_width {
+            Some(width) => line.push_str(&fmt::expand_tabs(msg, width)),
+            None => line.push_str(msg),
+        }
+        for cause in &self.causes {
+            line.push('\n');
+            let start = line.len();
+            line.push_str("  caused by: ");
+            line.push_str(cause);
+            if color::enabled() {
+                line.insert_str(start, "\u{1b}[2m");
+                line.push_str("\u{1b}[0m");
+            }
+        }
+        line.push('\n');
+    }
+
+    /// Write the line printed for `msg` in the short format at the end of `line`.
+    fn write_short_line(&self, line: &mut String, msg: &str) {
+        let start = line.len();
+        self.write_timestamp(line);
+        if self.thread_tag || thread::thread_names() {
+            thread::write_thread_tag(line);
+        }
+        if let Some((path, row, column)) = self.location.as_ref().filter(|_| locations()) {
+            line.push_str(&format!("{}:{}:{}: ", path, row, column));
+        }
+        self.write_styled_status(line, Attributes::NONE);
+        let msg = unseparated(msg);
+        if !msg.is_empty() {
+            line.push_str(": ");
+            let width = fmt::display_width(&line[start..]);
+            line.push_str(&self.ellipsized(msg, width));
+        }
+        for cause in &self.causes {
+            line.push_str(": ");
+            line.push_str(cause);
+        }
+        line.push('\n');
+    }
+
+    /// Write the line printed for `msg` in the JSON format at the end of `line`, a line of the
+    /// [`wire`] format.
+    fn write_json_line(&self, line: &mut String, msg: &str, truncated: bool, lossy: bool) {
+        let status = ansi::strip(&self.status);
+        let message = ansi::strip(unseparated(msg));
+        let causes: Vec<Cow<'_, str>> = self.causes.iter().map(|c| ansi::strip(c)).collect();
+        wire::Fields {
+            status: &status,
+            message: &message,
+            level: self.level,
+            color: Some(self.color),
+            location: self
+                .location
+                .as_ref()
+                .filter(|_| locations())
+                .map(|(path, row, column)| (path.as_str(), *row, *column)),
+            code: self.code.as_deref(),
+            causes: causes.iter().map(|cause| &**cause).collect(),
+            timestamp: Some(time::now()),
+            bold: Some(self.attributes.contains(Attributes::BOLD)),
+            truncated,
+            lossy,
+            ..wire::Fields::default()
+        }
+        .write(line);
+        line.push('\n');
+    }
+
+    /// The message with its paths shortened, see [`ellipsize_paths`](Self::ellipsize_paths).
+    fn ellipsized<'m>(&self, msg: &'m str, prefix_width: usize) -> Cow<'m, str> {
+        match self.ellipsize_paths {
+            Some(max) => {
+                let columns = sys::terminal_size().map(|(columns, _)| usize::from(columns));
+                fmt::ellipsize_paths(msg, max, prefix_width, columns)
+            }
+            None => Cow::Borrowed(msg),
+        }
+    }
+
+    /// The choice of the status over the decision of its stream, see [`force_color`](Self::force_color)
+    /// and [`no_color`](Self::no_color).
+    fn color_choice(&self) -> ColorChoice {
+        self.color_choice
+    }
+
+    /// Write the dimmed timestamp of the line and its separator at the end of `line`, if any.
+    fn write_timestamp(&self, line: &mut String) {
+        // The first line starts the timer of the elapsed timestamps if the program didn't.
+        time::timer();
+        let kind = match time::timestamps() {
+            TimestampKind::None if self.elapsed => TimestampKind::Elapsed,
+            TimestampKind::None if self.timestamp => TimestampKind::Rfc3339,
+            kind => kind,
+        };
+        if kind == TimestampKind::None {
+            return;
+        }
+        let start = line.len();
+        time::write_timestamp(line, kind);
+        // The timestamp is dimmed in place, without another buffer.
+        if color::enabled() {
+            line.insert_str(start, "\u{1b}[2m");
+            line.push_str("\u{1b}[0m");
+        }
+        line.push(' ');
+    }
+
+    /// The styled status, justified if requested.
+    fn prefix(&self) -> String {
+        let mut prefix = String::new();
+        self.write_prefix(&mut prefix);
+        prefix
+    }
+
+    /// Write the styled status, justified if requested, at the end of `prefix`.
+    fn write_prefix(&self, prefix: &mut String) {
+        prefix.extend(std::iter::repeat_n(' ', self.padding()));
+        self.write_styled_status(prefix, self.attributes);
+    }
+
+    /// The spaces before the status to justify it.
+    fn padding(&self) -> usize {
+        if !self.justify {
+            return 0;
+        }
+        // The columns of the status, so wide and combining characters still line up.
+        let mut columns = fmt::display_width(&self.status);
+        if let Some(code) = &self.code {
+            columns += fmt::display_width(code) + 2;
+        }
+        let mut width = self.width;
+        if self.auto_width {
+            let longest = AUTO_WIDTH.fetch_max(columns, Ordering::Relaxed);
+            width = width.max(longest).max(columns);
+        }
+        width.saturating_sub(columns)
+    }
+
+    /// Write the status text with its style and its code at the end of `line`, see
+    /// [`restyle`](Self::restyle) for pre-styled statuses.
+    fn write_styled_status(&self, line: &mut String, attributes: Attributes) {
+        let code = self.code.as_ref().map(|code| format!("[{}]", code));
+        if !ansi::contains_escape(self.status.as_bytes()) {
+            let status = match code {
+                Some(code) => Cow::Owned(format!("{}{}", self.status, code)),
+                None => Cow::Borrowed(self.status.as_str()),
+            };
+            return Self::write_colored(line, self.color, attributes, &status);
+        }
+        let plain = ansi::strip(&self.status);
+        if self.restyle {
+            Self::write_colored(line, self.color, attributes, &plain)
+        } else if color::enabled() {
+            line.push_str(&self.status)
+        } else {
+            line.push_str(&plain)
+        }
+        if let Some(code) = code {
+            Self::write_colored(line, self.color, attributes, &code);
+        }
+    }
+
+    fn color_str<S>(color: CargoColor, attributes: Attributes, str: S) -> String
+    where
+        S: AsRef<str>,
+    {
+        let mut colored = String::new();
+        Self::write_colored(&mut colored, color, attributes, str.as_ref());
+        colored
+    }
+
+    /// Write `str` in `color` with `attributes` at the end of `line`.
+    fn write_colored(line: &mut String, color: CargoColor, attributes: Attributes, str: &str) {
+        let shade = match color {
+            CargoColor::Green => Shade::Named(Color::Green),
+            CargoColor::Cyan => Shade::Named(Color::Cyan),
+            CargoColor::Yellow => Shade::Named(Color::Yellow),
+            CargoColor::Red => Shade::Named(Color::Red),
+            CargoColor::White => Shade::Named(Color::White),
+            CargoColor::Black => Shade::Named(Color::Black),
+            CargoColor::BrightGreen => Shade::Named(Color::BrightGreen),
+            CargoColor::BrightCyan => Shade::Named(Color::BrightCyan),
+            CargoColor::BrightYellow => Shade::Named(Color::BrightYellow),
+            CargoColor::BrightRed => Shade::Named(Color::BrightRed),
+            CargoColor::BrightWhite => Shade::Named(Color::BrightWhite),
+            CargoColor::BrightBlack => Shade::Named(Color::BrightBlack),
+            CargoColor::Rgb(r, g, b) => color::rgb(r, g, b),
+            CargoColor::Ansi256(index) => color::ansi256(index),
+        };
+        color::write_painted(line, str, attributes, shade)
+    }
+}
+
+/// Emit a message of the macros from a procedural macro, returning whether it was handled.
+#[doc(hidden)]
+pub fn __proc_macro_emit(status: &str, msg: &str) -> bool {
+    #[cfg(feature = "proc-macro")]
+    {
+        proc_macros::emit(status, msg)
+    }
+    #[cfg(not(feature = "proc-macro"))]
+    {
+        let _ = (status, msg);
+        false
+    }
+}
+
+/// Whether the messages of the macros are emitted by [`__proc_macro_emit`], so they must be
+/// formatted before printing them.
+#[doc(hidden)]
+pub fn __proc_macro_active() -> bool {
+    #[cfg(feature = "proc-macro")]
+    {
+        proc_macros::active()
+    }
+    #[cfg(not(feature = "proc-macro"))]
+    {
+        false
+    }
+}
+
+/// Count a message of the macros, of `level` and `target`, in the [`metrics`].
+#[doc(hidden)]
+pub fn __record_message(kind: &str, level: Level, target: Option<&str>) {
+    #[cfg(feature = "metrics")]
+    metrics::record(kind, level, target);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (kind, level, target);
+}
+
+/// The least severe level the macros are compiled with, `None` when they are all compiled out.
+///
+/// Release builds can compile out the macros below a level, which then evaluate nothing and don't
+/// even check the [max level](set_max_level), with the `release_max_level_debug`,
+/// `release_max_level_info`, `release_max_level_warn`, `release_max_level_error` and
+/// `release_max_level_off` features. The most restrictive one wins, and the arguments of the macros
+/// are still type checked. Debug builds compile every level.
+pub const STATIC_MAX_LEVEL: Option<Level> = if cfg!(debug_assertions) {
+    Some(Level::Trace)
+} else if cfg!(feature = "release_max_level_off") {
+    None
+} else if cfg!(feature = "release_max_level_error") {
+    Some(Level::Error)
+} else if cfg!(feature = "release_max_level_warn") {
+    Some(Level::Warning)
+} else if cfg!(feature = "release_max_level_info") {
+    Some(Level::Info)
+} else if cfg!(feature = "release_max_level_debug") {
+    Some(Level::Debug)
+} else {
+    Some(Level::Trace)
+};
+
+/// Whether the macros of `level` are compiled in, see [`STATIC_MAX_LEVEL`].
+#[doc(hidden)]
+#[inline(always)]
+pub const fn __compiled(level: Level) -> bool {
+    match STATIC_MAX_LEVEL {
+        Some(max) => level as u8 >= max as u8,
+        None => false,
+    }
+}
+
+/// Whether the macros print messages of `level`, see [`set_max_level`], [`set_quiet`] and
+/// [`set_verbose`]. Counts the suppressed messages in the [`metrics`].
+#[doc(hidden)]
+pub fn __enabled(level: Level) -> bool {
+    let enabled = __compiled(level) && level >= settings::threshold();
+    if !enabled {
+        record_suppressed();
+    }
+    enabled
+}
+
+/// The message of `carlog_trace!`, after its call site when `located`, i.e. in debug builds.
+#[doc(hidden)]
+pub fn __traced<'m>(file: &str, line: u32, located: bool, message: &'m str) -> Cow<'m, str> {
+    match located {
+        true => Cow::Owned(format!("{}:{}: {}", file, line, message)),
+        false => Cow::Borrowed(message),
+    }
+}
+
+/// Whether `carlog!` prints a message of `level`, checked like the ones of the preset macros, or
+/// without a level, only filtered out when [quiet](set_quiet). Counts the suppressed messages in
+/// the [`metrics`].
+#[doc(hidden)]
+#[inline]
+pub fn __carlog_enabled(level: Option<Level>) -> bool {
+    if let Some(level) = level {
+        return __compiled(level) && __enabled(level);
+    }
+    // The messages without a level are compiled out with the info ones.
+    let loud = __compiled(Level::Info) && !quiet();
+    if !loud {
+        record_suppressed();
+    }
+    loud
+}
+
+/// Count a message that wasn't printed, i.e. below the verbosity, in the [`metrics`].
+pub(crate) fn record_suppressed() {
+    #[cfg(feature = "metrics")]
+    metrics::record_suppressed();
+}
+
+/// Print a cargo like message.
+///
+/// A failure to print goes to the [error handler](set_error_handler), except for broken pipes,
+/// i.e. when the output is piped to `head`, which are ignored. See [`try_carlog!`] to handle the
+/// failures instead, like every macro has a `try_` variant.
+///
+/// The message is printed whatever the [max level](set_max_level) but not when [quiet](set_quiet),
+/// unless a leading `level:` argument gives its level. Like with the preset macros, the other arguments aren't evaluated when
+/// the level is filtered out.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog!("Compiling", "carlog v0.1.0"); // Not justified, not bold, stdout, white.
+/// carlog!("Compiling", "carlog v0.1.0", CargoColor::Cyan); // Not justified, not bold, stdout.
+///
+/// let mut output = Vec::<u8>::new();
+/// carlog!(
+///     "Compiling",                      // Status text
+///     "carlog v0.1.0",                  // Message
+///     true,                             // Bold
+///     false,                            // Justified
+///     CargoColor::Cyan,                 // Color
+///     CarlogStream::Custom(&mut output) // Stream
+/// );
+/// println!("{}", String::from_utf8(output).unwrap());
+///
+/// // Only printed when the max level is `Debug` or `Trace`, see `set_max_level`.
+/// carlog!(level: Level::Debug, "Fingerprint", "carlog v0.1.0 is fresh");
+/// ```
+#[macro_export]
+macro_rules! carlog {
+    ($($arg:tt)+) => {
+        $crate::__printed($crate::try_carlog!($($arg)+))
+    };
+}
+
+/// Print a cargo like message like [`carlog!`], evaluating to the [`Result`] of the print instead
+/// of handling its failure.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// fn build() -> std::io::Result<()> {
+///     try_carlog!("Compiling", "carlog v0.1.0", CargoColor::Cyan)?;
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! try_carlog {
+    (
+        @print $level:expr,
+        $status:expr,
+        $message:expr,
+        $bold:expr,
+        $justify:expr,
+        $color:expr,
+        $stream:expr
+    ) => {
+        // Nothing is evaluated when filtered out.
+        if $crate::__carlog_enabled($level) {
+            let mut status = $crate::Status::plain()
+                .color($color)
+                .status($status)
+                .location(file!(), line!(), column!());
+            if $bold {
+                status = status.bold();
+            }
+            if $justify {
+                status = status.justify();
+            }
+            if let ::std::option::Option::Some(level) = $level {
+                status = status.level(level);
+            }
+            status.print_stream(&mut $stream, $message)
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    };
+    (level: $level:expr, $status:expr, $message:expr) => {
+        $crate::try_carlog!(level: $level, $status, $message, $crate::CargoColor::default())
+    };
+    (level: $level:expr, $status:expr, $message:expr, $color:expr) => {
+        $crate::try_carlog!(
+            level: $level,
+            $status,
+            $message,
+            false,
+            false,
+            $color,
+            $crate::CarlogStream::default()
+        )
+    };
+    (
+        level: $level:expr,
+        $status:expr,
+        $message:expr,
+        $bold:expr,
+        $justify:expr,
+        $color:expr,
+        $stream:expr
+    ) => {
+        $crate::try_carlog!(
+            @print ::std::option::Option::Some($level),
+            $status,
+            $message,
+            $bold,
+            $justify,
+            $color,
+            $stream
+        )
+    };
+    ($status:expr, $message:expr) => {
+        $crate::try_carlog!($status, $message, $crate::CargoColor::default())
+    };
+    ($status:expr, $message:expr, $color:expr) => {
+        $crate::try_carlog!(
+            $status,
+            $message,
+            false,
+            false,
+            $color,
+            $crate::CarlogStream::default()
+        )
+    };
+    ($status:expr, $message:expr, $bold:expr, $justify:expr, $color:expr, $stream:expr) => {
+        $crate::try_carlog!(
+            @print ::std::option::Option::None,
+            $status,
+            $message,
+            $bold,
+            $justify,
+            $color,
+            $stream
+        )
+    };
+}
+
+/// Print a cargo like message like [`carlog!`], only the first time the call site is reached.
+///
+/// Every later call from the same place is skipped without evaluating its arguments, even when its
+/// message differs. The arguments are the ones of [`carlog!`], see [`carlog_warn_once!`] for
+/// warnings and [`try_carlog_once!`] to handle the failures to print.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// for _ in 0..3 {
+///     carlog_once!("Updating", "crates.io index");
+/// }
+/// ```
+#[macro_export]
+macro_rules! carlog_once {
+    ($($arg:tt)+) => {
+        $crate::__printed($crate::try_carlog_once!($($arg)+))
+    };
+}
+
+/// Print a cargo like message like [`carlog_once!`], evaluating to the [`Result`] of the print,
+/// which is `Ok` for the skipped calls.
+#[macro_export]
+macro_rules! try_carlog_once {
+    ($($arg:tt)+) => {{
+        static REACHED: ::std::sync::atomic::AtomicBool = ::std::sync::atomic::AtomicBool::new(false);
+        if !REACHED.swap(true, ::std::sync::atomic::Ordering::Relaxed) {
+            $crate::try_carlog!($($arg)+)
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+}
+
+/// Print an info-like cargo message.
+///
+/// The status is justified, bold and in cyan, or the info color of the [`theme`]. A leading
+/// `color:` argument overrides the color for one call. A `target:` argument before any other
+/// gives the message a target for the [routes](route_target), like every preset macro.
+/// Failures to print are handled like in [`carlog!`], see [`try_carlog_info!`] otherwise.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog_info!("Compiling", "carlog v0.1.0");
+/// carlog_info!(color: CargoColor::Yellow, "Notice", "maintenance window at 22:00");
+/// let mut output = Vec::<u8>::new();
+/// carlog_info!("Compiling", "carlog v0.1.0", CarlogStream::Custom(&mut output));
+/// println!("{}", String::from_utf8(output).unwrap());
+/// ```
+#[macro_export]
+macro_rules! carlog_info {
+    ($($arg:tt)+) => {
+        $crate::__printed($crate::try_carlog_info!($($arg)+))
+    };
+}
+
+/// Print an info-like cargo message like [`carlog_info!`], evaluating to the [`Result`] of the
+/// print.
+#[macro_export]
+macro_rules! try_carlog_info {
+    (target: $target:expr, $($rest:tt)+) => {{
+        let _target = $crate::target_scope($target);
+        $crate::try_carlog_info!($($rest)+)
+    }};
+    (color: $color:expr, $status:expr, $message:expr) => {
+        // The message isn't evaluated when filtered out, nor formatted before it's read unless
+        // a procedural macro emits it.
+        if $crate::__compiled($crate::Level::Info)
+            && $crate::__enabled($crate::Level::Info)
+            && $crate::__proc_macro_active()
+        {
+            let status = $status;
+            let message = $crate::__carlog_message!($message).into_owned();
+            if $crate::__proc_macro_emit(::std::convert::AsRef::<str>::as_ref(&status), &message) {
+                ::std::result::Result::Ok(())
+            } else {
+                $crate::try_carlog_info!(color: $color, status, message, $crate::CarlogStream::default())
+            }
+        } else {
+            $crate::try_carlog_info!(color: $color, $status, $message, $crate::CarlogStream::default())
+        }
+    };
+    (color: $color:expr, $status:expr, $message:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Info)
+            && $crate::__enabled($crate::Level::Info)
+            && !$crate::__discarded(&stream)
+        {
+            match &$message {
+                message => $crate::Record::__lazy(
+                    $status,
+                    || $crate::__carlog_message!(*message).into_owned(),
+                    |record| {
+                        let record = record
+                            .color($color)
+                            .location(file!(), line!(), column!());
+                        $crate::__print_record("info", &record, stream)
+                    },
+                ),
+            }
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    ($status:expr, $message:expr) => {
+        $crate::try_carlog_info!(color: $crate::theme().info, $status, $message)
+    };
+    ($status:expr, $message:expr, $stream:expr) => {
+        $crate::try_carlog_info!(color: $crate::theme().info, $status, $message, $stream)
+    };
+}
+
+/// Print an ok-like cargo message.
+///
+/// The status is justified, bold and in green, or the ok color of the [`theme`]. A leading
+/// `color:` argument overrides the color for one call. Failures to print are handled like in
+/// [`carlog!`], see [`try_carlog_ok!`] otherwise.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog_ok!("Compiled", "carlog v0.1.0");
+/// carlog_ok!(color: CargoColor::Cyan, "Cached", "carlog v0.1.0");
+/// let mut output = Vec::<u8>::new();
+/// carlog_ok!("Compiled", "carlog v0.1.0", CarlogStream::Custom(&mut output));
+/// println!("{}", String::from_utf8(output).unwrap());
+/// ```
+#[macro_export]
+macro_rules! carlog_ok {
+    ($($arg:tt)+) => {
+        $crate::__printed($crate::try_carlog_ok!($($arg)+))
+    };
+}
+
+/// Print an ok-like cargo message like [`carlog_ok!`], evaluating to the [`Result`] of the print.
+#[macro_export]
+macro_rules! try_carlog_ok {
+    (target: $target:expr, $($rest:tt)+) => {{
+        let _target = $crate::target_scope($target);
+        $crate::try_carlog_ok!($($rest)+)
+    }};
+    (color: $color:expr, $status:expr, $message:expr) => {
+        // The message isn't evaluated when filtered out, nor formatted before it's read unless
+        // a procedural macro emits it.
+        if $crate::__compiled($crate::Level::Info)
+            && $crate::__enabled($crate::Level::Info)
+            && $crate::__proc_macro_active()
+        {
+            let status = $status;
+            let message = $crate::__carlog_message!($message).into_owned();
+            if $crate::__proc_macro_emit(::std::convert::AsRef::<str>::as_ref(&status), &message) {
+                ::std::result::Result::Ok(())
+            } else {
+                $crate::try_carlog_ok!(color: $color, status, message, $crate::CarlogStream::default())
+            }
+        } else {
+            $crate::try_carlog_ok!(color: $color, $status, $message, $crate::CarlogStream::default())
+        }
+    };
+    (color: $color:expr, $status:expr, $message:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Info)
+            && $crate::__enabled($crate::Level::Info)
+            && !$crate::__discarded(&stream)
+        {
+            match &$message {
+                message => $crate::Record::__lazy(
+                    $status,
+                    || $crate::__carlog_message!(*message).into_owned(),
+                    |record| {
+                        let record = record
+                            .color($color)
+                            .location(file!(), line!(), column!());
+                        $crate::__print_record("ok", &record, stream)
+                    },
+                ),
+            }
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    ($status:expr, $message:expr) => {
+        $crate::try_carlog_ok!(color: $crate::theme().ok, $status, $message)
+    };
+    ($status:expr, $message:expr, $stream:expr) => {
+        $crate::try_carlog_ok!(color: $crate::theme().ok, $status, $message, $stream)
+    };
+}
+
+/// Print a debug-like cargo message, only when the [max level](set_max_level) is `Debug` or
+/// `Trace`, i.e. for a `-vv` mode.
+///
+/// The status is justified, dimmed and in cyan, or the info color of the [`theme`]. The arguments
+/// are the ones of [`carlog_info!`], and none is evaluated when the level is filtered out. Failures
+/// to print are handled like in [`carlog!`], see [`try_carlog_debug!`] otherwise.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog::set_max_level(Level::Debug);
+/// carlog_debug!("Resolving", "dependency graph");
+/// let mut output = Vec::<u8>::new();
+/// carlog_debug!("Resolving", "dependency graph", CarlogStream::Custom(&mut output));
+/// println!("{}", String::from_utf8(output).unwrap());
+/// # carlog::set_max_level(Level::Info);
+/// ```
+#[macro_export]
+macro_rules! carlog_debug {
+    ($($arg:tt)+) => {
+        $crate::__printed($crate::try_carlog_debug!($($arg)+))
+    };
+}
+
+/// Print a debug-like cargo message like [`carlog_debug!`], evaluating to the [`Result`] of the
+/// print.
+#[macro_export]
+macro_rules! try_carlog_debug {
+    (target: $target:expr, $($rest:tt)+) => {{
+        let _target = $crate::target_scope($target);
+        $crate::try_carlog_debug!($($rest)+)
+    }};
+    (color: $color:expr, $status:expr, $message:expr) => {
+        // The message isn't evaluated when filtered out, nor formatted before it's read unless
+        // a procedural macro emits it.
+        if $crate::__compiled($crate::Level::Debug)
+            && $crate::__enabled($crate::Level::Debug)
+            && $crate::__proc_macro_active()
+        {
+            let status = $status;
+            let message = $crate::__carlog_message!($message).into_owned();
+            if $crate::__proc_macro_emit(::std::convert::AsRef::<str>::as_ref(&status), &message) {
+                ::std::result::Result::Ok(())
+            } else {
+                $crate::try_carlog_debug!(color: $color, status, message, $crate::CarlogStream::default())
+            }
+        } else {
+            $crate::try_carlog_debug!(color: $color, $status, $message, $crate::CarlogStream::default())
+        }
+    };
+    (color: $color:expr, $status:expr, $message:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Debug)
+            && $crate::__enabled($crate::Level::Debug)
+            && !$crate::__discarded(&stream)
+        {
+            match &$message {
+                message => $crate::Record::__lazy(
+                    $status,
+                    || $crate::__carlog_message!(*message).into_owned(),
+                    |record| {
+                        let record = record
+                            .level($crate::Level::Debug)
+                            .color($color)
+                            .location(file!(), line!(), column!());
+                        $crate::__print_record("info", &record, stream)
+                    },
+                ),
+            }
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    ($status:expr, $message:expr) => {
+        $crate::try_carlog_debug!(color: $crate::theme().info, $status, $message)
+    };
+    ($status:expr, $message:expr, $stream:expr) => {
+        $crate::try_carlog_debug!(color: $crate::theme().info, $status, $message, $stream)
+    };
+}
+
+/// Print a trace-like cargo message, only when the [max level](set_max_level) is `Trace`.
+///
+/// The status is justified, dimmed and bright black. In debug builds the message starts with the
+/// call site, i.e. `src/main.rs:42: `. The arguments are the ones of [`carlog_info!`], and none is
+/// evaluated when the level is filtered out. Failures to print are handled like in [`carlog!`],
+/// see [`try_carlog_trace!`] otherwise.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog::set_max_level(Level::Trace);
+/// carlog_trace!("Visiting", "carlog v0.1.0");
+/// # carlog::set_max_level(Level::Info);
+/// ```
+#[macro_export]
+macro_rules! carlog_trace {
+    ($($arg:tt)+) => {
+        $crate::__printed($crate::try_carlog_trace!($($arg)+))
+    };
+}
+
+/// Print a trace-like cargo message like [`carlog_trace!`], evaluating to the [`Result`] of the
+/// print.
+#[macro_export]
+macro_rules! try_carlog_trace {
+    (target: $target:expr, $($rest:tt)+) => {{
+        let _target = $crate::target_scope($target);
+        $crate::try_carlog_trace!($($rest)+)
+    }};
+    (color: $color:expr, $status:expr, $message:expr) => {
+        // The message isn't evaluated when filtered out, nor formatted before it's read unless
+        // a procedural macro emits it.
+        if $crate::__compiled($crate::Level::Trace)
+            && $crate::__enabled($crate::Level::Trace)
+            && $crate::__proc_macro_active()
+        {
+            let status = $status;
+            let message = $crate::__carlog_message!($message).into_owned();
+            if $crate::__proc_macro_emit(::std::convert::AsRef::<str>::as_ref(&status), &message) {
+                ::std::result::Result::Ok(())
+            } else {
+                $crate::try_carlog_trace!(color: $color, status, message, $crate::CarlogStream::default())
+            }
+        } else {
+            $crate::try_carlog_trace!(color: $color, $status, $message, $crate::CarlogStream::default())
+        }
+    };
+    (color: $color:expr, $status:expr, $message:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Trace)
+            && $crate::__enabled($crate::Level::Trace)
+            && !$crate::__discarded(&stream)
+        {
+            match &$message {
+                message => $crate::Record::__lazy(
+                    $status,
+                    || {
+                        let message = $crate::__carlog_message!(*message).into_owned();
+                        $crate::__traced(file!(), line!(), cfg!(debug_assertions), &message)
+                            .into_owned()
+                    },
+                    |record| {
+                        let record = record
+                            .level($crate::Level::Trace)
+                            .color($color)
+                            .location(file!(), line!(), column!());
+                        $crate::__print_record("info", &record, stream)
+                    },
+                ),
+            }
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    ($status:expr, $message:expr) => {
+        $crate::try_carlog_trace!(color: $crate::CargoColor::BrightBlack, $status, $message)
+    };
+    ($status:expr, $message:expr, $stream:expr) => {
+        $crate::try_carlog_trace!(color: $crate::CargoColor::BrightBlack, $status, $message, $stream)
+    };
+}
+
+/// Print an warning like cargo message.
+///
+/// The status is not justified, not bold and light yellow, or the warning color of the
+/// [`theme`], with the status text 'warning'. A leading `label:` argument replaces the status
+/// text, i.e. `deprecated`, and a leading `color:` argument, after the label if any, overrides
+/// the color for one call. The message is still a warning for the max level and the metrics.
+/// A leading `id =` argument makes the warning a [lint](crate::lint), printed according to the
+/// level users chose for it, with the identifier dimmed after the message. A leading `code:`
+/// argument tags the warning with a code users can look up, printed in brackets after the status
+/// in its style, i.e. `warning[W0611]`, and counted in the [summary by code](print_summary_by_code).
+/// Leading `file:`, `line:` and `col:` arguments give the place of the input the warning is about,
+/// which the [GitHub Actions annotations](github) point to. Failures to print are handled like in
+/// [`carlog!`], see [`try_carlog_warning!`] otherwise.
+///
+/// Warnings go to stderr unless a stream is given, like the ones of cargo, so that they don't mix
+/// with the data a program prints to stdout. Before carlog 0.2 they went to stdout. See
+/// [`set_default_error_stream`] to send them somewhere else.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog_warning!("carlog (v0.1.0) generated a warning!");
+/// carlog_warning!(color: CargoColor::Red, "carlog (v0.1.0) is deprecated");
+/// carlog_warning!(label: "deprecated", "use of old API `Status::print`");
+/// carlog_warning!(id = "unused-manifest-key", "unused manifest key: `package.edition2`");
+/// carlog_warning!(code: "W0611", "unused import: `std::io`");
+/// carlog_warning!(file: "Cargo.toml", line: 7, col: 1, "unused manifest key: `package.edition2`");
+/// let mut output = Vec::<u8>::new();
+/// carlog_warning!("carlog (v0.1.0) generated a warning!", CarlogStream::Custom(&mut output));
+/// println!("{}", String::from_utf8(output).unwrap());
+/// ```
+#[macro_export]
+macro_rules! carlog_warning {
+    ($($arg:tt)+) => {
+        $crate::__printed($crate::try_carlog_warning!($($arg)+))
+    };
+}
+
+/// Print an warning like cargo message like [`carlog_warning!`], evaluating to the [`Result`] of
+/// the print.
+#[macro_export]
+macro_rules! try_carlog_warning {
+    (target: $target:expr, $($rest:tt)+) => {{
+        let _target = $crate::target_scope($target);
+        $crate::try_carlog_warning!($($rest)+)
+    }};
+    (file: $file:expr, line: $line:expr, col: $column:expr, $message:expr) => {
+        $crate::try_carlog_warning!(file: $file, line: $line, col: $column, $message, $crate::default_error_stream())
+    };
+    (file: $file:expr, line: $line:expr, col: $column:expr, $message:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Warning)
+            && $crate::__enabled($crate::Level::Warning)
+            && !$crate::__discarded(&stream)
+        {
+            match &$message {
+                message => $crate::Record::__lazy(
+                    "warning",
+                    || $crate::__carlog_message!(*message).into_owned(),
+                    |record| {
+                        let record = record
+                            .level($crate::Level::Warning)
+                            .color($crate::theme().warning)
+                            .location(file!(), line!(), column!())
+                            .input($file, $line, $column);
+                        $crate::__print_record("warning", &record, stream)
+                    },
+                ),
+            }
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    (id = $id:expr, $message:expr) => {
+        $crate::try_carlog_warning!(id = $id, $message, $crate::default_error_stream())
+    };
+    (id = $id:expr, $message:expr, $stream:expr) => {
+        if $crate::__compiled($crate::Level::Warning) {
+            $crate::lint::__emit(
+                ::std::convert::AsRef::<str>::as_ref(&$id),
+                &$crate::__carlog_message!($message),
+                (file!(), line!(), column!()),
+                $stream,
+            )
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    };
+    (code: $code:expr, $message:expr) => {
+        $crate::try_carlog_warning!(code: $code, $message, $crate::default_error_stream())
+    };
+    (code: $code:expr, $message:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Warning)
+            && $crate::__enabled($crate::Level::Warning)
+            && !$crate::__discarded(&stream)
+        {
+            match &$message {
+                message => $crate::Record::__lazy(
+                    "warning",
+                    || $crate::__carlog_message!(*message).into_owned(),
+                    |record| {
+                        let record = record
+                            .level($crate::Level::Warning)
+                            .color($crate::theme().warning)
+                            .location(file!(), line!(), column!())
+                            .code($code);
+                        $crate::__print_record("warning", &record, stream)
+                    },
+                ),
+            }
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    (label: $label:expr, color: $color:expr, $message:expr) => {
+        // The message isn't evaluated when filtered out, nor formatted before it's read unless
+        // a procedural macro emits it.
+        if $crate::__compiled($crate::Level::Warning)
+            && $crate::__enabled($crate::Level::Warning)
+            && $crate::__proc_macro_active()
+        {
+            let label = $label;
+            let message = $crate::__carlog_message!($message).into_owned();
+            if $crate::__proc_macro_emit(::std::convert::AsRef::<str>::as_ref(&label), &message) {
+                ::std::result::Result::Ok(())
+            } else {
+                $crate::try_carlog_warning!(label: label, color: $color, message, $crate::default_error_stream())
+            }
+        } else {
+            $crate::try_carlog_warning!(label: $label, color: $color, $message, $crate::default_error_stream())
+        }
+    };
+    (label: $label:expr, color: $color:expr, $message:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Warning)
+            && $crate::__enabled($crate::Level::Warning)
+            && !$crate::__discarded(&stream)
+        {
+            match &$message {
+                message => $crate::Record::__lazy(
+                    $label,
+                    || $crate::__carlog_message!(*message).into_owned(),
+                    |record| {
+                        let record = record
+                            .level($crate::Level::Warning)
+                            .color($color)
+                            .location(file!(), line!(), column!());
+                        $crate::__print_record("warning", &record, stream)
+                    },
+                ),
+            }
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    (label: $label:expr, $message:expr) => {
+        $crate::try_carlog_warning!(label: $label, color: $crate::theme().warning, $message)
+    };
+    (label: $label:expr, $message:expr, $stream:expr) => {
+        $crate::try_carlog_warning!(label: $label, color: $crate::theme().warning, $message, $stream)
+    };
+    (color: $color:expr, $message:expr) => {
+        $crate::try_carlog_warning!(label: "warning", color: $color, $message)
+    };
+    (color: $color:expr, $message:expr, $stream:expr) => {
+        $crate::try_carlog_warning!(label: "warning", color: $color, $message, $stream)
+    };
+    ($message:expr) => {
+        $crate::try_carlog_warning!(label: "warning", color: $crate::theme().warning, $message)
+    };
+    ($message:expr, $stream:expr) => {
+        $crate::try_carlog_warning!(label: "warning", color: $crate::theme().warning, $message, $stream)
+    };
+}
+
+/// Print a warning like [`carlog_warning!`], only the first time the call site is reached.
+///
+/// Every later call from the same place is skipped without evaluating its arguments, even when its
+/// message differs, and counted as a duplicate in the [summary](print_summary). The arguments are
+/// the ones of [`carlog_warning!`]. A first call filtered out by the max level still counts as
+/// the one of the call site. Failures to print are handled like in [`carlog!`], see
+/// [`try_carlog_warn_once!`] otherwise.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// for path in ["a.toml", "b.toml", "c.toml"] {
+///     carlog_warn_once!(format!("`{}`: the `[project]` table is deprecated", path));
+/// }
+/// ```
+#[macro_export]
+macro_rules! carlog_warn_once {
+    ($($arg:tt)+) => {
+        $crate::__printed($crate::try_carlog_warn_once!($($arg)+))
+    };
+}
+
+/// Print a warning like [`carlog_warn_once!`], evaluating to the [`Result`] of the print, which is
+/// `Ok` for the skipped calls.
+#[macro_export]
+macro_rules! try_carlog_warn_once {
+    ($($arg:tt)+) => {{
+        static REACHED: ::std::sync::atomic::AtomicBool = ::std::sync::atomic::AtomicBool::new(false);
+        if !REACHED.swap(true, ::std::sync::atomic::Ordering::Relaxed) {
+            $crate::try_carlog_warning!($($arg)+)
+        } else {
+            $crate::__duplicate_warning();
+            ::std::result::Result::Ok(())
+        }
+    }};
+}
+
+/// Print a `note:` line like the ones rustc prints after its warnings and errors, i.e.
+/// `note: `#[warn(unused_variables)]` on by default`.
+///
+/// The status is not justified, bold and bright white. A leading `indent:` argument moves the status
+/// that many columns to the right, so the line nests under the diagnostic before it, and a
+/// `color:` argument, after the indent if any, overrides the color for one call. The line goes to
+/// stderr unless a stream is given, like [`carlog_warning!`], and is filtered out with the
+/// warnings by the [max level](set_max_level). Failures to print are handled like in
+/// [`carlog!`], see [`try_carlog_note!`] otherwise.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog_warning!("unused variable: `x`");
+/// carlog_note!(indent: 2, "`#[warn(unused_variables)]` on by default");
+/// let mut output = Vec::<u8>::new();
+/// carlog_note!("`#[warn(unused_variables)]` on by default", CarlogStream::Custom(&mut output));
+/// println!("{}", String::from_utf8(output).unwrap());
+/// ```
+#[macro_export]
+macro_rules! carlog_note {
+    ($($arg:tt)+) => {
+        $crate::__printed($crate::try_carlog_note!($($arg)+))
+    };
+}
+
+/// Print a `note:` line like [`carlog_note!`], evaluating to the [`Result`] of the print.
+#[macro_export]
+macro_rules! try_carlog_note {
+    (target: $target:expr, $($rest:tt)+) => {{
+        let _target = $crate::target_scope($target);
+        $crate::try_carlog_note!($($rest)+)
+    }};
+    (indent: $indent:expr, color: $color:expr, $message:expr) => {
+        // The message isn't evaluated when filtered out, nor formatted before it's read unless
+        // a procedural macro emits it.
+        if $crate::__compiled($crate::Level::Warning)
+            && $crate::__enabled($crate::Level::Warning)
+            && $crate::__proc_macro_active()
+        {
+            let message = $crate::__carlog_message!($message).into_owned();
+            if $crate::__proc_macro_emit("note", &message) {
+                ::std::result::Result::Ok(())
+            } else {
+                $crate::try_carlog_note!(indent: $indent, color: $color, message, $crate::default_error_stream())
+            }
+        } else {
+            $crate::try_carlog_note!(indent: $indent, color: $color, $message, $crate::default_error_stream())
+        }
+    };
+    (indent: $indent:expr, color: $color:expr, $message:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Warning)
+            && $crate::__enabled($crate::Level::Warning)
+            && !$crate::__discarded(&stream)
+        {
+            $crate::__print_note(
+                "note",
+                $color,
+                $indent,
+                &$crate::__carlog_message!($message),
+                (file!(), line!(), column!()),
+                stream,
+            )
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    (indent: $indent:expr, $message:expr) => {
+        $crate::try_carlog_note!(indent: $indent, color: $crate::CargoColor::BrightWhite, $message)
+    };
+    (indent: $indent:expr, $message:expr, $stream:expr) => {
+        $crate::try_carlog_note!(indent: $indent, color: $crate::CargoColor::BrightWhite, $message, $stream)
+    };
+    (color: $color:expr, $message:expr) => {
+        $crate::try_carlog_note!(indent: 0, color: $color, $message)
+    };
+    (color: $color:expr, $message:expr, $stream:expr) => {
+        $crate::try_carlog_note!(indent: 0, color: $color, $message, $stream)
+    };
+    ($message:expr) => {
+        $crate::try_carlog_note!(indent: 0, color: $crate::CargoColor::BrightWhite, $message)
+    };
+    ($message:expr, $stream:expr) => {
+        $crate::try_carlog_note!(indent: 0, color: $crate::CargoColor::BrightWhite, $message, $stream)
+    };
+}
+
+/// Print a `help:` line like the ones rustc prints after its warnings and errors, i.e.
+/// `help: if this is intentional, prefix it with an underscore: `_x``.
+///
+/// The status is not justified, bold and bright cyan. A leading `indent:` argument moves the status
+/// that many columns to the right, so the line nests under the diagnostic before it, and a
+/// `color:` argument, after the indent if any, overrides the color for one call. The line goes to
+/// stderr unless a stream is given, like [`carlog_warning!`], and is filtered out with the
+/// warnings by the [max level](set_max_level). Failures to print are handled like in
+/// [`carlog!`], see [`try_carlog_help!`] otherwise.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog_warning!("unused variable: `x`");
+/// carlog_help!(indent: 2, "if this is intentional, prefix it with an underscore: `_x`");
+/// let mut output = Vec::<u8>::new();
+/// carlog_help!("if this is intentional, prefix it with an underscore: `_x`", CarlogStream::Custom(&mut output));
+/// println!("{}", String::from_utf8(output).unwrap());
+/// ```
+#[macro_export]
+macro_rules! carlog_help {
+    ($($arg:tt)+) => {
+        $crate::__printed($crate::try_carlog_help!($($arg)+))
+    };
+}
+
+/// Print a `help:` line like [`carlog_help!`], evaluating to the [`Result`] of the print.
+#[macro_export]
+macro_rules! try_carlog_help {
+    (target: $target:expr, $($rest:tt)+) => {{
+        let _target = $crate::target_scope($target);
+        $crate::try_carlog_help!($($rest)+)
+    }};
+    (indent: $indent:expr, color: $color:expr, $message:expr) => {
+        // The message isn't evaluated when filtered out, nor formatted before it's read unless
+        // a procedural macro emits it.
+        if $crate::__compiled($crate::Level::Warning)
+            && $crate::__enabled($crate::Level::Warning)
+            && $crate::__proc_macro_active()
+        {
+            let message = $crate::__carlog_message!($message).into_owned();
+            if $crate::__proc_macro_emit("help", &message) {
+                ::std::result::Result::Ok(())
+            } else {
+                $crate::try_carlog_help!(indent: $indent, color: $color, message, $crate::default_error_stream())
+            }
+        } else {
+            $crate::try_carlog_help!(indent: $indent, color: $color, $message, $crate::default_error_stream())
+        }
+    };
+    (indent: $indent:expr, color: $color:expr, $message:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Warning)
+            && $crate::__enabled($crate::Level::Warning)
+            && !$crate::__discarded(&stream)
+        {
+            $crate::__print_note(
+                "help",
+                $color,
+                $indent,
+                &$crate::__carlog_message!($message),
+                (file!(), line!(), column!()),
+                stream,
+            )
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    (indent: $indent:expr, $message:expr) => {
+        $crate::try_carlog_help!(indent: $indent, color: $crate::CargoColor::BrightCyan, $message)
+    };
+    (indent: $indent:expr, $message:expr, $stream:expr) => {
+        $crate::try_carlog_help!(indent: $indent, color: $crate::CargoColor::BrightCyan, $message, $stream)
+    };
+    (color: $color:expr, $message:expr) => {
+        $crate::try_carlog_help!(indent: 0, color: $color, $message)
+    };
+    (color: $color:expr, $message:expr, $stream:expr) => {
+        $crate::try_carlog_help!(indent: 0, color: $color, $message, $stream)
+    };
+    ($message:expr) => {
+        $crate::try_carlog_help!(indent: 0, color: $crate::CargoColor::BrightCyan, $message)
+    };
+    ($message:expr, $stream:expr) => {
+        $crate::try_carlog_help!(indent: 0, color: $crate::CargoColor::BrightCyan, $message, $stream)
+    };
+}
+
+/// Print an error like cargo message.
+///
+/// The status is not justified, not bold and light red, or the error color of the [`theme`],
+/// with the status text 'error'. A leading `label:` argument replaces the status text, i.e.
+/// `security`, and a leading `color:` argument, after the label if any, overrides the color for
+/// one call. The message is still an error for the max level and the metrics. A leading `code:`
+/// argument prints a code after the status, i.e. `error[E0308]`, and leading `file:`, `line:` and
+/// `col:` arguments give the place of the input the error is about, like in [`carlog_warning!`].
+/// An `err:` argument, a `&dyn std::error::Error`, is printed with its chain of sources, one
+/// dimmed `caused by:` line each, see [`Record::from_error`]. Failures to print are handled like
+/// in [`carlog!`], see [`try_carlog_error!`] otherwise.
+///
+/// Errors go to stderr unless a stream is given, like [`carlog_warning!`]. Before carlog 0.2 they
+/// went to stdout.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog_error!("carlog (v0.1.0) generated an error!");
+/// carlog_error!(color: CargoColor::Yellow, "carlog (v0.1.0) failed to build");
+/// carlog_error!(label: "security", "carlog (v0.1.0) has a known vulnerability");
+/// carlog_error!(code: "E0308", "mismatched types");
+/// carlog_error!(err: &std::io::Error::other("failed to read `Cargo.toml`"));
+/// carlog_error!(file: "src/main.rs", line: 3, col: 5, "mismatched types");
+/// let mut output = Vec::<u8>::new();
+/// carlog_error!("carlog (v0.1.0) generated an error!", CarlogStream::Custom(&mut output));
+/// println!("{}", String::from_utf8(output).unwrap());
+/// ```
+#[macro_export]
+macro_rules! carlog_error {
+    ($($arg:tt)+) => {
+        $crate::__printed($crate::try_carlog_error!($($arg)+))
+    };
+}
+
+/// Print an error like cargo message like [`carlog_error!`], evaluating to the [`Result`] of the
+/// print.
+#[macro_export]
+macro_rules! try_carlog_error {
+    (target: $target:expr, $($rest:tt)+) => {{
+        let _target = $crate::target_scope($target);
+        $crate::try_carlog_error!($($rest)+)
+    }};
+    (file: $file:expr, line: $line:expr, col: $column:expr, $message:expr) => {
+        $crate::try_carlog_error!(file: $file, line: $line, col: $column, $message, $crate::default_error_stream())
+    };
+    (file: $file:expr, line: $line:expr, col: $column:expr, $message:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Error)
+            && $crate::__enabled($crate::Level::Error)
+            && !$crate::__discarded(&stream)
+        {
+            match &$message {
+                message => $crate::Record::__lazy(
+                    "error",
+                    || $crate::__carlog_message!(*message).into_owned(),
+                    |record| {
+                        let record = record
+                            .level($crate::Level::Error)
+                            .color($crate::theme().error)
+                            .location(file!(), line!(), column!())
+                            .input($file, $line, $column);
+                        $crate::__print_record("error", &record, stream)
+                    },
+                ),
+            }
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    (err: $error:expr) => {
+        $crate::try_carlog_error!(err: $error, $crate::default_error_stream())
+    };
+    (err: $error:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Error)
+            && $crate::__enabled($crate::Level::Error)
+            && !$crate::__discarded(&stream)
+        {
+            let error: &dyn ::std::error::Error = $error;
+            let record = $crate::Record::from_error(error)
+                .color($crate::theme().error)
+                .location(file!(), line!(), column!());
+            $crate::__print_record("error", &record, stream)
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    (code: $code:expr, $message:expr) => {
+        $crate::try_carlog_error!(code: $code, $message, $crate::default_error_stream())
+    };
+    (code: $code:expr, $message:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Error)
+            && $crate::__enabled($crate::Level::Error)
+            && !$crate::__discarded(&stream)
+        {
+            match &$message {
+                message => $crate::Record::__lazy(
+                    "error",
+                    || $crate::__carlog_message!(*message).into_owned(),
+                    |record| {
+                        let record = record
+                            .level($crate::Level::Error)
+                            .color($crate::theme().error)
+                            .location(file!(), line!(), column!())
+                            .code($code);
+                        $crate::__print_record("error", &record, stream)
+                    },
+                ),
+            }
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    (label: $label:expr, color: $color:expr, $message:expr) => {
+        // The message isn't evaluated when filtered out, nor formatted before it's read unless
+        // a procedural macro emits it.
+        if $crate::__compiled($crate::Level::Error)
+            && $crate::__enabled($crate::Level::Error)
+            && $crate::__proc_macro_active()
+        {
+            let label = $label;
+            let message = $crate::__carlog_message!($message).into_owned();
+            if $crate::__proc_macro_emit(::std::convert::AsRef::<str>::as_ref(&label), &message) {
+                ::std::result::Result::Ok(())
+            } else {
+                $crate::try_carlog_error!(label: label, color: $color, message, $crate::default_error_stream())
+            }
+        } else {
+            $crate::try_carlog_error!(label: $label, color: $color, $message, $crate::default_error_stream())
+        }
+    };
+    (label: $label:expr, color: $color:expr, $message:expr, $stream:expr) => {{
+        let stream = $stream;
+        if $crate::__compiled($crate::Level::Error)
+            && $crate::__enabled($crate::Level::Error)
+            && !$crate::__discarded(&stream)
+        {
+            match &$message {
+                message => $crate::Record::__lazy(
+                    $label,
+                    || $crate::__carlog_message!(*message).into_owned(),
+                    |record| {
+                        let record = record
+                            .level($crate::Level::Error)
+                            .color($color)
+                            .location(file!(), line!(), column!());
+                        $crate::__print_record("error", &record, stream)
+                    },
+                ),
+            }
+        } else {
+            ::std::result::Result::Ok(())
+        }
+    }};
+    (label: $label:expr, $message:expr) => {
+        $crate::try_carlog_error!(label: $label, color: $crate::theme().error, $message)
+    };
+    (label: $label:expr, $message:expr, $stream:expr) => {
+        $crate::try_carlog_error!(label: $label, color: $crate::theme().error, $message, $stream)
+    };
+    (color: $color:expr, $message:expr) => {
+        $crate::try_carlog_error!(label: "error", color: $color, $message)
+    };
+    (color: $color:expr, $message:expr, $stream:expr) => {
+        $crate::try_carlog_error!(label: "error", color: $color, $message, $stream)
+    };
+    ($message:expr) => {
+        $crate::try_carlog_error!(label: "error", color: $crate::theme().error, $message)
+    };
+    ($message:expr, $stream:expr) => {
+        $crate::try_carlog_error!(label: "error", color: $crate::theme().error, $message, $stream)
+    };
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use crate::{
+        fmt, max_message_len, print_record, set_format, set_max_level, set_status_defaults,
+        style_scope, CargoColor, CarlogStream, Format, Level, Record, Status, StatusDefaults,
+        Target, Theme,
+    };
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::sync::{Arc, Mutex, MutexGuard};
+
+    /// Serialize the tests that change the global configuration.
+    pub(crate) fn global_lock() -> MutexGuard<'static, ()> {
+        static LOCK: Mutex<()> = Mutex::new(());
+        LOCK.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The expected outputs are colored even when the tests don't run in a terminal.
+    pub(crate) fn force_colors() {
+        crate::set_color_choice(crate::ColorChoice::Always);
+        colored::control::set_override(true);
+    }
+
+    /// A writer the test keeps a handle to, to read what a stream owning it was given.
+    #[derive(Clone, Default)]
+    pub(crate) struct Shared(pub(crate) Arc<Mutex<Vec<u8>>>);
+
+    impl Shared {
+        /// What was written so far.
+        pub(crate) fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    impl std::io::Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// The expected outputs are decided like in a program that didn't choose, which is plain
+    /// unless a status forces its color since the tests see no terminal nor color variable.
+    pub(crate) fn auto_colors() {
+        crate::set_color_choice(crate::ColorChoice::Auto);
+        colored::control::set_override(false);
+    }
+
+    #[test]
+    fn test_carlog_info() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        carlog_info!(
+            "Compiling",
+            "carlog v0.1.0",
+            CarlogStream::Custom(&mut output)
+        );
+        let output = String::from_utf8(output);
+        assert!(output.is_ok());
+        assert_eq!(
+            output.unwrap(),
+            "   \u{1b}[1;36mCompiling\u{1b}[0m carlog v0.1.0\n"
+        );
+    }
+
+    #[test]
+    fn test_attributes() {
+        let _lock = global_lock();
+        let status = Status::plain()
+            .bold()
+            .dimmed()
+            .underline()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Fresh");
+        let mut output = Vec::<u8>::new();
+        force_colors();
+        status.print(&mut output, " carlog v0.1.0").unwrap();
+        Status::plain()
+            .italic()
+            .strikethrough()
+            .status("skipped")
+            .print(&mut output, ": carlog-macros")
+            .unwrap();
+        auto_colors();
+        status.print(&mut output, " carlog v0.1.0").unwrap();
+        force_colors();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "       \u{1b}[1;2;4;32mFresh\u{1b}[0m carlog v0.1.0\n\
+             \u{1b}[3;9;37mskipped\u{1b}[0m: carlog-macros\n       \
+             Fresh carlog v0.1.0\n"
+        );
+    }
+
+    #[test]
+    fn test_named_colors() {
+        let _lock = global_lock();
+        force_colors();
+        let colors = [
+            (CargoColor::Green, 32),
+            (CargoColor::Cyan, 36),
+            (CargoColor::Yellow, 33),
+            (CargoColor::Red, 31),
+            (CargoColor::White, 37),
+            (CargoColor::Black, 30),
+            (CargoColor::BrightGreen, 92),
+            (CargoColor::BrightCyan, 96),
+            (CargoColor::BrightYellow, 93),
+            (CargoColor::BrightRed, 91),
+            (CargoColor::BrightWhite, 97),
+            (CargoColor::BrightBlack, 90),
+        ];
+        for (color, code) in colors {
+            let status = Status::plain().color(color).status("s");
+            assert_eq!(status.render(""), format!("\u{1b}[{}ms\u{1b}[0m", code));
+        }
+    }
+
+    #[test]
+    fn test_carlog_ok() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        carlog_ok!(
+            "Compiled",
+            "carlog v0.1.0",
+            CarlogStream::Custom(&mut output)
+        );
+        let output = String::from_utf8(output);
+        assert!(output.is_ok());
+        assert_eq!(
+            output.unwrap(),
+            "    \u{1b}[1;32mCompiled\u{1b}[0m carlog v0.1.0\n"
+        );
+    }
+
+    #[test]
+    fn test_carlog_warning() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        carlog_warning!(
+            "carlog (v0.1.0) generated a warning!",
+            CarlogStream::Custom(&mut output)
+        );
+        let output = String::from_utf8(output);
+        assert!(output.is_ok());
+        assert_eq!(
+            output.unwrap(),
+            "\u{1b}[93mwarning\u{1b}[0m: carlog (v0.1.0) generated a warning!\n"
+        );
+    }
+
+    #[test]
+    fn test_carlog_error() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        carlog_error!(
+            "carlog (v0.1.0) generated an error!",
+            CarlogStream::Custom(&mut output)
+        );
+        let output = String::from_utf8(output);
+        assert!(output.is_ok());
+        assert_eq!(
+            output.unwrap(),
+            "\u{1b}[91merror\u{1b}[0m: carlog (v0.1.0) generated an error!\n"
+        );
+    }
+
+    #[test]
+    fn test_once_macros() {
+        let _lock = global_lock();
+        auto_colors();
+        crate::reset_counts();
+        let mut output = Vec::<u8>::new();
+        let mut evaluated = 0;
+        for i in 0..100 {
+            carlog_warn_once!(
+                {
+                    evaluated += 1;
+                    format!("file {}: `[project]` is deprecated", i)
+                },
+                CarlogStream::Custom(&mut output)
+            );
+            carlog_once!(
+                "Updating",
+                " crates.io index",
+                false,
+                false,
+                CargoColor::Green,
+                CarlogStream::Custom(&mut output)
+            );
+        }
+        carlog_warn_once!("another site", CarlogStream::Custom(&mut output));
+        let mut summary = Vec::<u8>::new();
+        crate::print_summary(CarlogStream::Custom(&mut summary)).unwrap();
+        crate::reset_counts();
+        force_colors();
+        assert_eq!(evaluated, 1);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "warning: file 0: `[project]` is deprecated\nUpdating crates.io index\n\
+             warning: another site\n"
+        );
+        assert_eq!(
+            String::from_utf8(summary).unwrap(),
+            "warning: 2 warnings emitted; 99 duplicate warnings suppressed\n"
+        );
+    }
+
+    #[test]
+    fn test_carlog_note_and_help() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        carlog_warning!("unused variable: `x`", CarlogStream::Custom(&mut output));
+        carlog_note!(
+            indent: 2,
+            "`#[warn(unused_variables)]` on by default",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_help!(
+            indent: 2,
+            "prefix it with an underscore: `_x`",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_note!("see the docs", CarlogStream::Custom(&mut output));
+        carlog_help!(
+            color: CargoColor::Green,
+            "run `cargo fix`",
+            CarlogStream::Custom(&mut output)
+        );
+        set_max_level(Level::Error);
+        carlog_note!("hidden", CarlogStream::Custom(&mut output));
+        carlog_help!(indent: 2, "hidden", CarlogStream::Custom(&mut output));
+        crate::settings::reset();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\u{1b}[93mwarning\u{1b}[0m: unused variable: `x`\n  \
+             \u{1b}[1;97mnote\u{1b}[0m: `#[warn(unused_variables)]` on by default\n  \
+             \u{1b}[1;96mhelp\u{1b}[0m: prefix it with an underscore: `_x`\n\
+             \u{1b}[1;97mnote\u{1b}[0m: see the docs\n\
+             \u{1b}[1;32mhelp\u{1b}[0m: run `cargo fix`\n"
+        );
+    }
+
+    #[test]
+    fn test_color_override() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        carlog_info!(
+            color: CargoColor::BrightYellow,
+            "Notice",
+            "maintenance window at 22:00",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_ok!(
+            color: CargoColor::Cyan,
+            "Cached",
+            "carlog v0.1.0",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_warning!(
+            color: CargoColor::BrightRed,
+            "carlog (v0.1.0) is deprecated",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_error!(
+            color: CargoColor::BrightYellow,
+            "carlog (v0.1.0) failed to build",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_info!("Notice", "default", CarlogStream::Custom(&mut output));
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "      \u{1b}[1;93mNotice\u{1b}[0m maintenance window at 22:00\n      \
+             \u{1b}[1;36mCached\u{1b}[0m carlog v0.1.0\n\
+             \u{1b}[91mwarning\u{1b}[0m: carlog (v0.1.0) is deprecated\n\
+             \u{1b}[93merror\u{1b}[0m: carlog (v0.1.0) failed to build\n      \
+             \u{1b}[1;36mNotice\u{1b}[0m default\n"
+        );
+    }
+
+    #[test]
+    fn test_custom_labels() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        carlog_warning!(
+            label: "deprecated",
+            "use of old API",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_error!(
+            label: String::from("security"),
+            color: CargoColor::BrightYellow,
+            "known vulnerability",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_warning!("default label", CarlogStream::Custom(&mut output));
+        set_max_level(Level::Error);
+        carlog_warning!(label: "deprecated", "hidden", CarlogStream::Custom(&mut output));
+        crate::settings::reset();
+        let mut expected = Vec::<u8>::new();
+        Status::warning_with_label("deprecated")
+            .print(&mut expected, ": use of old API")
+            .unwrap();
+        Status::error_with_label("security")
+            .color(CargoColor::BrightYellow)
+            .print(&mut expected, ": known vulnerability")
+            .unwrap();
+        assert!(output.starts_with(&expected));
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\u{1b}[93mdeprecated\u{1b}[0m: use of old API\n\
+             \u{1b}[93msecurity\u{1b}[0m: known vulnerability\n\
+             \u{1b}[93mwarning\u{1b}[0m: default label\n"
+        );
+    }
+
+    #[test]
+    fn test_lints() {
+        let _lock = global_lock();
+        force_colors();
+        #[cfg(feature = "metrics")]
+        crate::metrics::reset();
+        crate::lint::declare("unused-key");
+        crate::lint::declare("missing-docs");
+        crate::lint::declare("old-api");
+        crate::lint::allow("unused-key");
+        crate::lint::deny("missing-docs");
+        let mut output = Vec::<u8>::new();
+        carlog_warning!(
+            id = "unused-key",
+            "unused key `foo`",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_warning!(
+            id = "missing-docs",
+            "missing docs for `bar`",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_warning!(
+            id = String::from("old-api"),
+            "use of `baz`",
+            CarlogStream::Custom(&mut output)
+        );
+        crate::lint::reset();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\u{1b}[91merror\u{1b}[0m: missing docs for `bar` \u{1b}[2m[missing-docs]\u{1b}[0m\n\
+             \u{1b}[93mwarning\u{1b}[0m: use of `baz` \u{1b}[2m[old-api]\u{1b}[0m\n"
+        );
+        #[cfg(feature = "metrics")]
+        {
+            let metrics = crate::metrics::snapshot();
+            crate::metrics::reset();
+            assert_eq!((metrics.warning, metrics.error), (1, 1));
+        }
+    }
+
+    #[test]
+    fn test_target_macros() {
+        let _lock = global_lock();
+        force_colors();
+        let net = Shared::default();
+        crate::route_target("net", net.clone());
+        let mut output = Vec::<u8>::new();
+        carlog_info!(
+            target: "net::http",
+            "Fetching",
+            "index",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_warning!(target: "net", "slow mirror", CarlogStream::Custom(&mut output));
+        carlog_error!(target: "db", "locked", CarlogStream::Custom(&mut output));
+        crate::clear_routes();
+        assert_eq!(
+            net.text(),
+            "    \u{1b}[1;36mFetching\u{1b}[0m index\n\u{1b}[93mwarning\u{1b}[0m: slow mirror\n"
+        );
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\u{1b}[91merror\u{1b}[0m: locked\n"
+        );
+    }
+
+    #[test]
+    fn test_pre_styled_status() {
+        let _lock = global_lock();
+        force_colors();
+        let status = || {
+            Status::new()
+                .bold()
+                .justify()
+                .color(CargoColor::Green)
+                .status("\u{1b}[35mNotice\u{1b}[0m")
+        };
+        let mut output = Vec::<u8>::new();
+        status().print(&mut output, " kept").unwrap();
+        status()
+            .restyle(true)
+            .print(&mut output, " restyled")
+            .unwrap();
+        auto_colors();
+        status().print(&mut output, " plain").unwrap();
+        force_colors();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "      \u{1b}[35mNotice\u{1b}[0m kept\n      \u{1b}[1;32mNotice\u{1b}[0m restyled\n      \
+             Notice plain\n"
+        );
+    }
+
+    #[test]
+    fn test_print_record() {
+        let _lock = global_lock();
+        force_colors();
+        let mut expected = Vec::<u8>::new();
+        carlog_info!("Compiling", "carlog", CarlogStream::Custom(&mut expected));
+        carlog_warning!(label: "deprecated", "old", CarlogStream::Custom(&mut expected));
+        carlog_error!("failed", CarlogStream::Custom(&mut expected));
+        let records = [
+            Record::new("Compiling", "carlog"),
+            Record::new("deprecated", "old").level(Level::Warning),
+            Record::new("error", "failed").level(Level::Error),
+        ];
+        let mut output = Vec::<u8>::new();
+        for record in &records {
+            crate::print_record_to(record, CarlogStream::Custom(&mut output)).unwrap();
+        }
+        assert_eq!(output, expected);
+
+        let mut output = Vec::<u8>::new();
+        let record = Record::new("Checking", "carlog")
+            .color(CargoColor::BrightYellow)
+            .field("jobs", 4)
+            .field("profile", "dev");
+        crate::print_record_to(&record, CarlogStream::Custom(&mut output)).unwrap();
+        set_max_level(Level::Error);
+        let lazy = Record::lazy("error", || unreachable!("the record is filtered out"));
+        crate::print_record_to(
+            &lazy.level(Level::Warning),
+            CarlogStream::Custom(&mut output),
+        )
+        .unwrap();
+        crate::settings::reset();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "    \u{1b}[1;93mChecking\u{1b}[0m carlog jobs=4 profile=dev\n"
+        );
+    }
+
+    #[test]
+    fn test_print_stream() {
+        let _lock = global_lock();
+        force_colors();
+        let status = || {
+            Status::new()
+                .bold()
+                .justify()
+                .color(CargoColor::Green)
+                .status("Compiled")
+        };
+        let mut expected = Vec::<u8>::new();
+        status().print(&mut expected, " carlog v0.1.0").unwrap();
+        let mut output = Vec::<u8>::new();
+        status()
+            .print_stream(&mut CarlogStream::Custom(&mut output), " carlog v0.1.0")
+            .unwrap();
+        assert_eq!(output, expected);
+        for mut stream in [CarlogStream::Stdout, CarlogStream::Stderr] {
+            status()
+                .print_stream(&mut stream, " carlog v0.1.0")
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let _lock = global_lock();
+        let statuses: [fn() -> Status; 5] = [
+            || Status::plain().status("plain"),
+            || {
+                Status::plain()
+                    .justify()
+                    .color(CargoColor::Green)
+                    .status("Compiled")
+            },
+            || {
+                Status::plain()
+                    .bold()
+                    .color(CargoColor::BrightRed)
+                    .status("error")
+            },
+            || {
+                Status::plain()
+                    .dimmed()
+                    .justify()
+                    .status("Fresh")
+                    .force_color()
+            },
+            || {
+                Status::plain()
+                    .justify()
+                    .expand_tabs(Some(4))
+                    .status("Running")
+            },
+        ];
+        for colors in [true, false] {
+            if colors {
+                force_colors();
+            } else {
+                auto_colors();
+            }
+            for status in statuses {
+                let line = status().render_line(" a\tb");
+                let mut output = Vec::<u8>::new();
+                status().print(&mut output, " a\tb").unwrap();
+                assert_eq!(line.as_bytes(), output);
+                assert_eq!(status().render(" a\tb") + "\n", line);
+            }
+        }
+        force_colors();
+    }
+
+    #[test]
+    fn test_reuse_status() {
+        let _lock = global_lock();
+        force_colors();
+        let status = Status::plain()
+            .bold()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Compiling");
+        let mut output = Vec::<u8>::new();
+        for name in ["a", "b", "c"] {
+            status.print(&mut output, format!(" {}", name)).unwrap();
+        }
+        let output = String::from_utf8(output).unwrap();
+        let prefixes: Vec<&str> = output.lines().map(|line| &line[..line.len() - 2]).collect();
+        assert_eq!(prefixes, ["   \u{1b}[1;32mCompiling\u{1b}[0m"; 3]);
+        assert_eq!(status.render(" d"), "   \u{1b}[1;32mCompiling\u{1b}[0m d");
+    }
+
+    #[test]
+    fn test_null_stream() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Counts how many times it's formatted.
+        struct Counted<'a>(&'a AtomicUsize);
+
+        impl std::fmt::Display for Counted<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                write!(f, "carlog v0.1.0")
+            }
+        }
+
+        let _lock = global_lock();
+        force_colors();
+        let count = AtomicUsize::new(0);
+        crate::record_transcript(true);
+        carlog_info!("Compiling", Counted(&count), CarlogStream::Null);
+        carlog_warning!(Counted(&count), CarlogStream::Null);
+        carlog!(
+            "Compiling",
+            format_args!(" {}", Counted(&count)),
+            true,
+            true,
+            CargoColor::Green,
+            CarlogStream::Null
+        );
+        Status::new()
+            .status("Compiling")
+            .print_stream(
+                &mut CarlogStream::Null,
+                format_args!(" {}", Counted(&count)),
+            )
+            .unwrap();
+        crate::record_transcript(false);
+        assert!(crate::transcript().is_empty());
+        assert!(CarlogStream::Null.is_null());
+        assert_eq!(CarlogStream::Null.target(), Target::Custom);
+        assert_eq!(format!("{:?}", CarlogStream::Null), "Null");
+
+        let routed = Shared::default();
+        crate::route_target("quiet", routed.clone());
+        carlog_info!(target: "quiet", "Compiling", Counted(&count), CarlogStream::Null);
+        crate::clear_routes();
+        assert_eq!(
+            routed.text(),
+            "   \u{1b}[1;36mCompiling\u{1b}[0m carlog v0.1.0\n"
+        );
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_file_stream() {
+        let _lock = global_lock();
+        auto_colors();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.log");
+        let status = Status::new()
+            .bold()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Compiling");
+        let mut log = crate::file_stream(&path, true).unwrap();
+        status.print_stream(&mut log, " a").unwrap();
+        carlog_warning!("unused import", crate::file_stream(&path, true).unwrap());
+        status.print_stream(&mut log, " b").unwrap();
+        drop(log);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "   Compiling a\nwarning: unused import\n   Compiling b\n"
+        );
+
+        let mut log = crate::file_stream(&path, false).unwrap();
+        status.force_color().print_stream(&mut log, " c").unwrap();
+        drop(log);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "   \u{1b}[1;32mCompiling\u{1b}[0m c\n"
+        );
+        assert!(crate::file_stream(dir.path().join("missing/build.log"), true).is_err());
+        force_colors();
+    }
+
+    #[test]
+    fn test_boxed_stream() {
+        let _lock = global_lock();
+        force_colors();
+        let output = Shared::default();
+        let writer: Box<dyn std::io::Write + Send> = Box::new(output.clone());
+        let warnings = output.clone();
+        std::thread::spawn(move || {
+            let mut stream = CarlogStream::Boxed(writer);
+            let status = Status::new().color(CargoColor::Green).status("worker");
+            status.print_stream(&mut stream, ": started").unwrap();
+            carlog_warning!("unused import", CarlogStream::boxed(warnings));
+            status.print_stream(&mut stream, ": done").unwrap();
+        })
+        .join()
+        .unwrap();
+        assert_eq!(
+            output.text(),
+            "\u{1b}[32mworker\u{1b}[0m: started\n\
+             \u{1b}[93mwarning\u{1b}[0m: unused import\n\
+             \u{1b}[32mworker\u{1b}[0m: done\n"
+        );
+        assert_eq!(CarlogStream::boxed(Vec::new()).target(), Target::Custom);
+        assert_eq!(
+            format!("{:?}", CarlogStream::boxed(Vec::new())),
+            "Boxed(..)"
+        );
+    }
+
+    #[test]
+    fn test_shared_stream() {
+        let _lock = global_lock();
+        force_colors();
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let workers: Vec<_> = (0..8)
+            .map(|worker| {
+                let output = Arc::clone(&output);
+                std::thread::spawn(move || {
+                    for job in 0..100 {
+                        let stream = CarlogStream::from(Arc::clone(&output));
+                        carlog_info!("Compiling", format!("job-{}-{}", worker, job), stream);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        let mut jobs: Vec<&str> = output
+            .lines()
+            .map(|line| {
+                line.strip_prefix("   \u{1b}[1;36mCompiling\u{1b}[0m job-")
+                    .unwrap()
+            })
+            .collect();
+        jobs.sort_unstable();
+        let mut expected: Vec<String> = (0..8)
+            .flat_map(|worker| (0..100).map(move |job| format!("{}-{}", worker, job)))
+            .collect();
+        expected.sort_unstable();
+        assert_eq!(jobs, expected);
+        let stream = CarlogStream::from(Arc::new(Mutex::new(Vec::<u8>::new())));
+        assert_eq!(format!("{:?}", stream), "Shared(..)");
+    }
+
+    #[test]
+    fn test_tee_stream() {
+        /// Fails every write.
+        struct Broken;
+
+        impl std::io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let _lock = global_lock();
+        force_colors();
+        let status = Status::new().color(CargoColor::Green).status("tee");
+        let (mut first, mut second) = (Vec::<u8>::new(), Vec::<u8>::new());
+        let mut stream = crate::tee([
+            CarlogStream::Custom(&mut first),
+            CarlogStream::Custom(&mut second),
+        ]);
+        status.print_stream(&mut stream, ": a").unwrap();
+        drop(stream);
+        let line = "\u{1b}[32mtee\u{1b}[0m: a\n";
+        assert_eq!(String::from_utf8(first).unwrap(), line);
+        assert_eq!(String::from_utf8(second).unwrap(), line);
+
+        let (mut first, mut second) = (Vec::<u8>::new(), Vec::<u8>::new());
+        let mut stream = crate::tee([
+            CarlogStream::Custom(&mut first),
+            CarlogStream::boxed(Broken),
+            CarlogStream::Custom(&mut second),
+        ]);
+        let error = status.print_stream(&mut stream, ": b").unwrap_err();
+        assert_eq!(error.io_error().kind(), std::io::ErrorKind::BrokenPipe);
+        drop(stream);
+        assert_eq!(first, second);
+        assert!(!first.is_empty());
+
+        let mut plain = Vec::<u8>::new();
+        let colored = Status::new()
+            .color(CargoColor::Green)
+            .status("tee")
+            .force_color();
+        auto_colors();
+        let mut stream = crate::tee([CarlogStream::Custom(&mut plain), CarlogStream::Null]);
+        status.print_stream(&mut stream, ": c").unwrap();
+        colored.print_stream(&mut stream, ": d").unwrap();
+        force_colors();
+        drop(stream);
+        assert_eq!(
+            String::from_utf8(plain).unwrap(),
+            "tee: c\n\u{1b}[32mtee\u{1b}[0m: d\n"
+        );
+    }
+
+    #[test]
+    fn test_status_traits() {
+        let status = Status::plain()
+            .bold()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Compiled");
+        let clone = status.clone().color(CargoColor::Red);
+        assert_ne!(clone, status);
+        assert_eq!(status, status.clone());
+        let debug = format!("{:?}", status);
+        for field in [
+            "justify: true",
+            "attributes: Attributes(BOLD)",
+            "color: Green",
+            "status: \"Compiled\"",
+        ] {
+            assert!(debug.contains(field), "{}", debug);
+        }
+        assert!(format!("{:?}", clone).contains("color: Red"));
+        let mut output = Vec::<u8>::new();
+        assert_eq!(
+            format!(
+                "{:?}",
+                [
+                    CarlogStream::Stdout,
+                    CarlogStream::Stderr,
+                    CarlogStream::Custom(&mut output)
+                ]
+            ),
+            "[Stdout, Stderr, Custom(..)]"
+        );
+    }
+
+    #[test]
+    fn test_justify_width() {
+        let _lock = global_lock();
+        auto_colors();
+        let status = Status::plain().status("Regenerating");
+        let lines = [
+            status.clone().justify_to(8).render(" a"),
+            status.clone().justify_to(12).render(" a"),
+            status.clone().justify_to(16).render(" a"),
+            status.clone().justify().render(" a"),
+            Status::plain().justify().status("Compiling").render(" a"),
+        ];
+        let auto = |status| Status::plain().justify_auto().status(status).render(" a");
+        let auto_lines = [
+            auto("Compiling"),
+            auto("Regenerating"),
+            auto("Cross-compiling"),
+            auto("Compiling"),
+        ];
+        let fixed = Status::plain().justify().status("Compiling").render(" a");
+        force_colors();
+        assert_eq!(
+            lines,
+            [
+                "Regenerating a",
+                "Regenerating a",
+                "    Regenerating a",
+                "Regenerating a",
+                "   Compiling a",
+            ]
+        );
+        assert_eq!(
+            auto_lines,
+            [
+                "   Compiling a",
+                "Regenerating a",
+                "Cross-compiling a",
+                "      Compiling a",
+            ]
+        );
+        assert_eq!(fixed, "   Compiling a");
+    }
+
+    #[test]
+    fn test_justify_display_width() {
+        let _lock = global_lock();
+        force_colors();
+        let statuses = [
+            "Compiling",
+            "Pr\u{e9}par\u{e9}",
+            "Pre\u{301}pare\u{301}",
+            "\u{6784}\u{5efa}",
+            "\u{1f4e6} Packed",
+            "\u{1f680} Deploying",
+        ];
+        for status in statuses {
+            let line = Status::plain()
+                .bold()
+                .justify()
+                .color(CargoColor::Green)
+                .status(status)
+                .render(" carlog");
+            let prefix = line.strip_suffix(" carlog").unwrap();
+            assert_eq!(fmt::display_width(prefix), 12, "{:?}", line);
+            assert!(prefix.ends_with(&format!("{}\u{1b}[0m", status)));
+        }
+        let line = Status::plain()
+            .justify()
+            .status("\u{6784}\u{5efa}")
+            .render("");
+        assert_eq!(line, "        \u{1b}[37m\u{6784}\u{5efa}\u{1b}[0m");
+    }
+
+    #[test]
+    fn test_print_bytes() {
+        let _lock = global_lock();
+        force_colors();
+        let status = || Status::new().color(CargoColor::Cyan).status("out");
+        let mut output = Vec::<u8>::new();
+        status()
+            .print_bytes(&mut output, b": caf\xe9\xff\t|")
+            .unwrap();
+        status().print_bytes(&mut output, b": a\nb\n").unwrap();
+        status()
+            .print_bytes(&mut output, b": \x1b[2J\r\x7f")
+            .unwrap();
+        status()
+            .raw_bytes()
+            .print_bytes(&mut output, b": \x1b[2J\xe9")
+            .unwrap();
+        let expected: &[u8] = b"\x1b[36mout\x1b[0m: caf\xe9\xff\t|\n\x1b[36mout\x1b[0m: a\nb\n\
+              \x1b[36mout\x1b[0m: ^[[2J^M^?\n\x1b[36mout\x1b[0m: \x1b[2J\xe9\n";
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_expand_tabs() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        for (status, msg) in [("Compiling", " a\tb"), ("Ok", " abc\td\n\te")] {
+            Status::new()
+                .justify()
+                .status(status)
+                .expand_tabs(Some(4))
+                .print(&mut output, msg)
+                .unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "   \u{1b}[37mCompiling\u{1b}[0m a  b\n          \u{1b}[37mOk\u{1b}[0m abc    \
+             d\n    e\n"
+        );
+    }
+
+    #[test]
+    fn test_short_format() {
+        let _lock = global_lock();
+        force_colors();
+        set_format(Format::Short);
+        let mut output = Vec::<u8>::new();
+        Status::new()
+            .color(CargoColor::BrightYellow)
+            .status("warning")
+            .location("src/main.rs", 3, 5)
+            .print(&mut output, ": unused variable")
+            .unwrap();
+        Status::new()
+            .bold()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Finished")
+            .print(&mut output, " dev profile")
+            .unwrap();
+        carlog_error!("failed", CarlogStream::Custom(&mut output));
+        let line = line!() - 1;
+        set_format(Format::Human);
+        carlog_ok!("Finished", "dev profile", CarlogStream::Custom(&mut output));
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "src/main.rs:3:5: \u{1b}[93mwarning\u{1b}[0m: unused variable\n\
+                 \u{1b}[32mFinished\u{1b}[0m: dev profile\n\
+                 src/lib.rs:{}:9: \u{1b}[91merror\u{1b}[0m: failed\n    \
+                 \u{1b}[1;32mFinished\u{1b}[0m dev profile\n",
+                line
+            )
+        );
+    }
+
+    #[test]
+    fn test_short_format_without_colors() {
+        let _lock = global_lock();
+        crate::color::set_override(false);
+        set_format(Format::Short);
+        let mut output = Vec::<u8>::new();
+        Status::new()
+            .bold()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Compiling")
+            .print(&mut output, " carlog v0.1.0")
+            .unwrap();
+        carlog_warning!(label: "deprecated", "old API", CarlogStream::Custom(&mut output));
+        let line = line!() - 1;
+        carlog_info!("Checking", "  indented", CarlogStream::Custom(&mut output));
+        set_format(Format::Human);
+        crate::color::unset_override();
+        force_colors();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "Compiling: carlog v0.1.0\n\
+                 src/lib.rs:{}:9: deprecated: old API\n\
+                 src/lib.rs:{}:9: Checking:   indented\n",
+                line,
+                line + 2
+            )
+        );
+    }
+
+    #[test]
+    fn test_json_format() {
+        use std::time::{Duration, SystemTime};
+
+        fn clock() -> SystemTime {
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        }
+
+        let _lock = global_lock();
+        force_colors();
+        crate::set_clock(clock);
+        set_format(Format::Json);
+        let mut output = Vec::<u8>::new();
+        carlog_info!(
+            "Compiling",
+            "carlog v0.1.0",
+            CarlogStream::Custom(&mut output)
+        );
+        let info = line!() - 5;
+        carlog_warning!(
+            "unused \"x\"\n  in `main`",
+            CarlogStream::Custom(&mut output)
+        );
+        let warning = line!() - 4;
+        Status::new()
+            .status("\u{1b}[1mPlain\u{1b}[0m")
+            .print(&mut output, " \u{1b}[2mno\tlevel\u{1b}[0m")
+            .unwrap();
+        // Only the separator from the status is removed, not the colons nor the indentation.
+        let plain = Status::plain().status("Using");
+        plain.print(&mut output, " ::std::io").unwrap();
+        plain.print(&mut output, ":   indented").unwrap();
+        set_format(Format::Human);
+        crate::set_clock(SystemTime::now);
+        let output = String::from_utf8(output).unwrap();
+        assert!(!output.contains('\u{1b}'));
+        let lines = output
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        let timestamp =
+            serde_json::json!({"secs_since_epoch": 1_700_000_000, "nanos_since_epoch": 0});
+        assert_eq!(
+            lines,
+            [
+                serde_json::json!({
+                    "format_version": 1,
+                    "status": "Compiling",
+                    "message": "carlog v0.1.0",
+                    "level": "Info",
+                    "color": "Cyan",
+                    "location": {"path": "src/lib.rs", "line": info, "column": 9},
+                    "timestamp": timestamp,
+                    "bold": true,
+                }),
+                serde_json::json!({
+                    "format_version": 1,
+                    "status": "warning",
+                    "message": "unused \"x\"\n  in `main`",
+                    "level": "Warning",
+                    "color": "BrightYellow",
+                    "location": {"path": "src/lib.rs", "line": warning, "column": 9},
+                    "timestamp": timestamp,
+                    "bold": false,
+                }),
+                serde_json::json!({
+                    "format_version": 1,
+                    "status": "Plain",
+                    "message": "no\tlevel",
+                    "level": "Info",
+                    "color": "White",
+                    "timestamp": timestamp,
+                    "bold": false,
+                }),
+                serde_json::json!({
+                    "format_version": 1,
+                    "status": "Using",
+                    "message": "::std::io",
+                    "level": "Info",
+                    "color": "White",
+                    "timestamp": timestamp,
+                    "bold": false,
+                }),
+                serde_json::json!({
+                    "format_version": 1,
+                    "status": "Using",
+                    "message": "  indented",
+                    "level": "Info",
+                    "color": "White",
+                    "timestamp": timestamp,
+                    "bold": false,
+                }),
+            ]
+        );
+        // The lines are records of the wire format.
+        #[cfg(feature = "serde")]
+        for line in output.lines() {
+            crate::wire::parse(line).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_json_format_bytes_and_truncation() {
+        use std::time::{Duration, SystemTime};
+
+        fn clock() -> SystemTime {
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        }
+
+        let _lock = global_lock();
+        force_colors();
+        crate::set_clock(clock);
+        set_format(Format::Json);
+        let status = Status::plain()
+            .color(CargoColor::Cyan)
+            .status("stdout")
+            .level(Level::Info);
+        let mut output = Vec::<u8>::new();
+        status
+            .print_bytes(&mut output, b": caf\xc3\xa9\t\x1b[1m\n")
+            .unwrap();
+        status.print_bytes(&mut output, b": caf\xe9\n").unwrap();
+        crate::max_message_len(16);
+        carlog_error!("x".repeat(64), CarlogStream::Custom(&mut output));
+        crate::max_message_len(64 * 1024);
+        set_format(Format::Human);
+        crate::set_clock(SystemTime::now);
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[..2],
+            [
+                "{\"format_version\":1,\"status\":\"stdout\",\"message\":\"caf\u{e9}\\t\",\
+                 \"level\":\"Info\",\"color\":\"Cyan\",\"timestamp\":{\"secs_since_epoch\":\
+                 1700000000,\"nanos_since_epoch\":0},\"bold\":false}",
+                "{\"format_version\":1,\"status\":\"stdout\",\"message\":\"caf\u{fffd}\",\
+                 \"level\":\"Info\",\"color\":\"Cyan\",\"timestamp\":{\"secs_since_epoch\":\
+                 1700000000,\"nanos_since_epoch\":0},\"bold\":false,\"lossy\":true}",
+            ]
+        );
+        let error = serde_json::from_str::<serde_json::Value>(lines[2]).unwrap();
+        assert_eq!(error["truncated"], true);
+        assert!(error["message"].as_str().unwrap().contains("truncated"));
+    }
+
+    #[test]
+    fn test_codes() {
+        use std::time::{Duration, SystemTime};
+
+        fn clock() -> SystemTime {
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        }
+
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        carlog_error!(
+            code: "E1042",
+            "mismatched types",
+            CarlogStream::Custom(&mut output)
+        );
+        carlog_warning!(
+            code: String::from("W0611"),
+            "unused import",
+            CarlogStream::Custom(&mut output)
+        );
+        Status::plain()
+            .bold()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Checked")
+            .code("C1")
+            .print(&mut output, " carlog")
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\u{1b}[91merror[E1042]\u{1b}[0m: mismatched types\n\
+             \u{1b}[93mwarning[W0611]\u{1b}[0m: unused import\n \
+             \u{1b}[1;32mChecked[C1]\u{1b}[0m carlog\n"
+        );
+        crate::set_clock(clock);
+        set_format(Format::Json);
+        let mut json = Vec::<u8>::new();
+        let record = crate::Record::new("error", "mismatched types")
+            .level(Level::Error)
+            .code("E1042");
+        crate::print_record_to(&record, CarlogStream::Custom(&mut json)).unwrap();
+        set_format(Format::Human);
+        crate::set_clock(SystemTime::now);
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            "{\"format_version\":1,\"status\":\"error\",\"message\":\"mismatched types\",\
+             \"level\":\"Error\",\"color\":\"BrightRed\",\"code\":\"E1042\",\"timestamp\":\
+             {\"secs_since_epoch\":1700000000,\"nanos_since_epoch\":0},\"bold\":false}\n"
+        );
+    }
+
+    /// An error of `message` caused by `source`.
+    #[derive(Debug)]
+    struct Chained {
+        message: &'static str,
+        source: Option<Box<dyn std::error::Error + 'static>>,
+    }
+
+    impl std::fmt::Display for Chained {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(self.message)
+        }
+    }
+
+    impl std::error::Error for Chained {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            self.source.as_deref()
+        }
+    }
+
+    /// An error that is its own source.
+    #[derive(Debug)]
+    struct Looping;
+
+    static LOOPING: Looping = Looping;
+
+    impl std::fmt::Display for Looping {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("loop")
+        }
+    }
+
+    impl std::error::Error for Looping {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            Some(&LOOPING)
+        }
+    }
+
+    #[test]
+    fn test_error_chain() {
+        use std::time::{Duration, SystemTime};
+
+        fn clock() -> SystemTime {
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        }
+
+        let _lock = global_lock();
+        force_colors();
+        let error = Chained {
+            message: "failed to load the manifest",
+            source: Some(Box::new(Chained {
+                message: "failed to read `Cargo.toml`",
+                source: Some(Box::new(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    "permission denied",
+                ))),
+            })),
+        };
+        let mut output = Vec::<u8>::new();
+        carlog_error!(err: &error, CarlogStream::Custom(&mut output));
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\u{1b}[91merror\u{1b}[0m: failed to load the manifest\n\
+             \u{1b}[2m  caused by: failed to read `Cargo.toml`\u{1b}[0m\n\
+             \u{1b}[2m  caused by: permission denied\u{1b}[0m\n"
+        );
+        auto_colors();
+        let mut plain = Vec::<u8>::new();
+        carlog_error!(err: &error, CarlogStream::Custom(&mut plain));
+        crate::set_locations(false);
+        set_format(Format::Short);
+        carlog_error!(err: &error, CarlogStream::Custom(&mut plain));
+        crate::set_clock(clock);
+        set_format(Format::Json);
+        carlog_error!(err: &error, CarlogStream::Custom(&mut plain));
+        crate::set_clock(SystemTime::now);
+        crate::settings::reset();
+        force_colors();
+        assert_eq!(
+            String::from_utf8(plain).unwrap(),
+            "error: failed to load the manifest\n  \
+             caused by: failed to read `Cargo.toml`\n  \
+             caused by: permission denied\n\
+             error: failed to load the manifest: failed to read `Cargo.toml`: permission denied\n\
+             {\"format_version\":1,\"status\":\"error\",\"message\":\"failed to load the manifest\",\
+             \"level\":\"Error\",\"color\":\"BrightRed\",\"causes\":[\"failed to read `Cargo.toml`\",\
+             \"permission denied\"],\"timestamp\":{\"secs_since_epoch\":1700000000,\
+             \"nanos_since_epoch\":0},\"bold\":false}\n"
+        );
+        let looping = crate::Record::from_error(&LOOPING);
+        assert_eq!(looping.causes().count(), 32);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_status_serde() {
+        let status = Status::plain()
+            .bold()
+            .justify()
+            .color(CargoColor::Rgb(0xe5, 0xa0, 0x0d))
+            .status("Compiling");
+        let json = serde_json::to_string(&status).unwrap();
+        assert_eq!(
+            json,
+            r#"{"justify":true,"bold":true,"color":{"Rgb":[229,160,13]},"status":"Compiling"}"#
+        );
+        assert_eq!(serde_json::from_str::<Status>(&json).unwrap(), status);
+        // The other settings aren't serialized.
+        let wide = status.clone().justify_to(20).italic();
+        let json = serde_json::to_string(&wide).unwrap();
+        assert_eq!(serde_json::from_str::<Status>(&json).unwrap(), status);
+        // The shape of a configuration file, with the names of the colors and missing fields.
+        let config = r#"{ "justify": true, "color": "bright-yellow", "status": "Checking" }"#;
+        assert_eq!(
+            serde_json::from_str::<Status>(config).unwrap(),
+            Status::plain()
+                .justify()
+                .color(CargoColor::BrightYellow)
+                .status("Checking")
+        );
+        assert_eq!(
+            serde_json::from_str::<Status>("{}").unwrap(),
+            Status::plain()
+        );
+        let config = "justify = true\ncolor = \"bright-yellow\"\nstatus = \"Checking\"\n";
+        assert_eq!(
+            toml::from_str::<Status>(config).unwrap(),
+            Status::plain()
+                .justify()
+                .color(CargoColor::BrightYellow)
+                .status("Checking")
+        );
+        let config = toml::to_string(&status).unwrap();
+        assert_eq!(
+            config,
+            "justify = true\nbold = true\nstatus = \"Compiling\"\n\n[color]\nRgb = [229, 160, 13]\n"
+        );
+        assert_eq!(toml::from_str::<Status>(&config).unwrap(), status);
+        assert!(toml::from_str::<Status>("width = 20\n").is_err());
+        for invalid in [
+            r#"{"status":"Checking","width":20}"#,
+            r#"{"color":"yellowish"}"#,
+            r#"{"bold":"yes"}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<Status>(invalid).is_err(),
+                "{}",
+                invalid
+            );
         }
-        writeln!(stream, "{}", msg.as_ref())?;
-        stream.flush()?;
-        Ok(())
     }
 
-    fn color_str<S>(color: CargoColor, bold: bool, str: S) -> String
-    where
-        S: AsRef<str>,
-    {
-        let mut colored = match color {
-            CargoColor::Green => str.as_ref().green(),
-            CargoColor::Cyan => str.as_ref().cyan(),
-            CargoColor::Yellow => str.as_ref().bright_yellow(),
-            CargoColor::Red => str.as_ref().bright_red(),
-            CargoColor::White => str.as_ref().white(),
-            CargoColor::Black => str.as_ref().black(),
-        };
-        if bold {
-            colored = colored.bold()
-        }
-        colored.to_string()
+    #[test]
+    fn test_status_defaults() {
+        let _lock = global_lock();
+        force_colors();
+        set_status_defaults(StatusDefaults {
+            bold: true,
+            justify: true,
+            width: 10,
+            color: Some(CargoColor::Green),
+        });
+        let mut output = Vec::<u8>::new();
+        Status::new()
+            .status("X")
+            .print(&mut output, " new")
+            .unwrap();
+        Status::new()
+            .color(CargoColor::BrightRed)
+            .status("X")
+            .print(&mut output, " override")
+            .unwrap();
+        Status::plain()
+            .status("X")
+            .print(&mut output, " plain")
+            .unwrap();
+        carlog_warning!("preset", CarlogStream::Custom(&mut output));
+        set_status_defaults(StatusDefaults::default());
+        Status::new()
+            .status("X")
+            .print(&mut output, " reset")
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "         \u{1b}[1;32mX\u{1b}[0m new\n         \u{1b}[1;91mX\u{1b}[0m override\n\
+             \u{1b}[37mX\u{1b}[0m plain\n\u{1b}[93mwarning\u{1b}[0m: preset\n\u{1b}[37mX\u{1b}[0m reset\n"
+        );
     }
-}
 
-/// Print a cargo like message.
-///
-/// ## Example
-/// ```ignore
-/// #[macro_use] extern crate carlog;
-///
-/// use carlog::prelude::*;
-///
-/// carlog!("Compiling", "carlog v0.1.0"); // Not justified, not bold, stdout, white.
-/// carlog!("Compiling", "carlog v0.1.0", CargoColor::Cyan); // Not justified, not bold, stdout.
-///
-/// let mut output = Vec::<u8>::new();
-/// carlog!(
-///     "Compiling",                      // Status text
-///     "carlog v0.1.0",                  // Message
-///     true,                             // Bold
-///     false,                            // Justified
-///     CargoColor::Cyan,                 // Color
-///     CarlogStream::Custom(&mut output) // Stream
-/// );
-/// println!("{}", String::from_utf8(output).unwrap());
-/// ```
-#[macro_export]
-macro_rules! carlog {
-    ($status:expr, $message:expr) => {
-        carlog!($status, $message, crate::CargoColor::default());
-    };
-    ($status:expr, $message:expr, $color:expr) => {
-        carlog!(
-            $status,
-            $message,
-            false,
-            false,
-            $color,
-            crate::CarlogStream::default()
-        )
-    };
-    ($status:expr, $message:expr, $bold:expr, $justify:expr, $color:expr, $stream:expr) => {
-        let mut status = crate::Status::new().color($color).status($status);
-        if $bold {
-            status = status.bold();
-        }
-        if $justify {
-            status = status.justify();
+    #[test]
+    fn test_style_scope() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        {
+            let _danger = style_scope(Theme::warning_tint());
+            carlog_info!("Removing", "target", CarlogStream::Custom(&mut output));
+            carlog_error!("permission denied", CarlogStream::Custom(&mut output));
+            Status::new()
+                .color(CargoColor::Green)
+                .status("Kept")
+                .print(&mut output, " explicit")
+                .unwrap();
         }
-        match $stream {
-            crate::CarlogStream::Stdout => status
-                .print_stdout($message)
-                .expect("Failed to print to stdout!"),
-            crate::CarlogStream::Stderr => status
-                .print_stderr($message)
-                .expect("Failed to print to stderr!"),
-            crate::CarlogStream::Custom(stream) => status
-                .print(stream, $message)
-                .expect("Failed to print to custom stream!"),
+        carlog_info!("Removed", "target", CarlogStream::Custom(&mut output));
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "    \u{1b}[1;93mRemoving\u{1b}[0m target\n\u{1b}[91merror\u{1b}[0m: permission denied\n\
+             \u{1b}[32mKept\u{1b}[0m explicit\n     \u{1b}[1;36mRemoved\u{1b}[0m target\n"
+        );
+    }
+
+    #[test]
+    fn test_max_message_len() {
+        let _lock = global_lock();
+        force_colors();
+        let msg = format!(" head {} tail", "\u{e9}".repeat(1_500_000));
+        let status = || Status::plain().status("Body");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.log");
+        let sink = crate::sinks::FileSink::builder(&path)
+            .header(false)
+            .open()
+            .unwrap();
+        status().print(&sink, &msg).unwrap();
+        let mut output = Vec::<u8>::new();
+        status().print(&mut output, &msg).unwrap();
+        status().print_bytes(&mut output, msg.as_bytes()).unwrap();
+        max_message_len(usize::MAX);
+        status().print(&mut output, &msg).unwrap();
+        max_message_len(64 * 1024);
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], lines[1]);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            lines[0].to_string() + "\n"
+        );
+        for line in &lines[..2] {
+            assert!(line.starts_with("\u{1b}[37mBody\u{1b}[0m head \u{e9}"));
+            assert!(line.ends_with("\u{e9} tail"));
+            assert!(line.contains("\u{e9}\u{2026} [2.8 MiB truncated] \u{2026}\u{e9}"));
+            assert!(line.len() < 64 * 1024 + 64);
         }
-    };
-}
+        assert_eq!(lines[2].len(), msg.len() + 13);
+    }
 
-/// Print an info-like cargo message.
-///
-/// The status is justified, bold and in cyan.
-///
-/// ## Example
-/// ```ignore
-/// #[macro_use] extern crate carlog;
-///
-/// use carlog::prelude::*;
-///
-/// carlog_info!("Compiling", "carlog v0.1.0");
-/// let mut output = Vec::<u8>::new();
-/// carlog_info!("Compiling", "carlog v0.1.0", CarlogStream::Custom(&mut output));
-/// println!("{}", String::from_utf8(output).unwrap());
-/// ```
-#[macro_export]
-macro_rules! carlog_info {
-    ($status:expr, $message:expr) => {
-        carlog_info!($status, $message, crate::CarlogStream::default());
-    };
-    ($status:expr, $message:expr, $stream:expr) => {
-        carlog!(
-            $status,
-            format!(" {}", $message),
-            true,
-            true,
-            crate::CargoColor::Cyan,
-            $stream
+    #[test]
+    fn test_max_level() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        set_max_level(Level::Warning);
+        carlog_info!("Compiling", "hidden", CarlogStream::Custom(&mut output));
+        carlog_warning!("shown", CarlogStream::Custom(&mut output));
+        set_max_level(Level::Error);
+        carlog_warning!("hidden", CarlogStream::Custom(&mut output));
+        carlog_error!("shown", CarlogStream::Custom(&mut output));
+        crate::settings::reset();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\u{1b}[93mwarning\u{1b}[0m: shown\n\u{1b}[91merror\u{1b}[0m: shown\n"
         );
-    };
-}
+    }
 
-/// Print an ok-like cargo message.
-///
-/// The status is justified, bold and in green.
-///
-/// ## Example
-/// ```ignore
-/// #[macro_use] extern crate carlog;
-///
-/// use carlog::prelude::*;
-///
-/// carlog_ok!("Compiled", "carlog v0.1.0");
-/// let mut output = Vec::<u8>::new();
-/// carlog_ok!("Compiled", "carlog v0.1.0", CarlogStream::Custom(&mut output));
-/// println!("{}", String::from_utf8(output).unwrap());
-/// ```
-#[macro_export]
-macro_rules! carlog_ok {
-    ($status:expr, $message:expr) => {
-        carlog_ok!($status, $message, crate::CarlogStream::default());
-    };
-    ($status:expr, $message:expr, $stream:expr) => {
-        carlog!(
-            $status,
-            format!(" {}", $message),
-            true,
-            true,
-            crate::CargoColor::Green,
-            $stream
+    #[test]
+    fn test_filtered_not_evaluated() {
+        let _lock = global_lock();
+        auto_colors();
+        let evaluated = Cell::new(0);
+        let message = || {
+            evaluated.set(evaluated.get() + 1);
+            " carlog v0.1.0"
+        };
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        set_max_level(Level::Error);
+        crate::with_default_stream(CarlogStream::from(Arc::clone(&output)), || {
+            carlog_info!("Compiling", message());
+            carlog_ok!(color: CargoColor::Cyan, "Compiled", message());
+            carlog_warning!(message());
+            carlog_warning!(label: "deprecated", message());
+            carlog!(level: Level::Warning, "Compiling", message());
+            carlog!(level: Level::Info, "Compiling", message(), CargoColor::Green);
+            carlog_info!(
+                "Compiling",
+                message(),
+                CarlogStream::from(Arc::clone(&output))
+            );
+            carlog_warning!(message(), CarlogStream::from(Arc::clone(&output)));
+        });
+        assert_eq!(evaluated.get(), 0);
+        set_max_level(Level::Trace);
+        crate::with_default_stream(CarlogStream::from(Arc::clone(&output)), || {
+            carlog_info!("Compiling", message());
+            carlog!(level: Level::Trace, "Tracing", message());
+        });
+        crate::settings::reset();
+        force_colors();
+        assert_eq!(evaluated.get(), 2);
+        assert_eq!(
+            String::from_utf8(output.lock().unwrap().clone()).unwrap(),
+            "   Compiling  carlog v0.1.0\nTracing carlog v0.1.0\n"
         );
-    };
-}
+    }
 
-/// Print an warning like cargo message.
-///
-/// The status is not justified, not bold and light yellow with the status text 'warning'.
-///
-/// ## Example
-/// ```ignore
-/// #[macro_use] extern crate carlog;
-///
-/// use carlog::prelude::*;
-///
-/// carlog_warning!("carlog (v0.1.0) generated a warning!");
-/// let mut output = Vec::<u8>::new();
-/// carlog_warning!("carlog (v0.1.0) generated a warning!", CarlogStream::Custom(&mut output));
-/// println!("{}", String::from_utf8(output).unwrap());
-/// ```
-#[macro_export]
-macro_rules! carlog_warning {
-    ($message:expr) => {
-        carlog_warning!($message, crate::CarlogStream::default());
-    };
-    ($message:expr, $stream:expr) => {
-        carlog!(
-            "warning",
-            format!(": {}", $message),
-            false,
-            false,
-            crate::CargoColor::Yellow,
-            $stream
+    #[test]
+    fn test_debug_and_trace_macros() {
+        let _lock = global_lock();
+        force_colors();
+        let evaluated = Cell::new(0);
+        let message = || {
+            evaluated.set(evaluated.get() + 1);
+            format!("dependency graph of {} crates", 12)
+        };
+        let mut output = Vec::<u8>::new();
+        carlog_debug!("Resolving", message(), CarlogStream::Custom(&mut output));
+        carlog_trace!("Visiting", message(), CarlogStream::Custom(&mut output));
+        carlog_debug!("Resolving", message());
+        carlog_trace!("Visiting", message());
+        assert_eq!(evaluated.get(), 0);
+        set_max_level(Level::Debug);
+        carlog_debug!("Resolving", message(), CarlogStream::Custom(&mut output));
+        carlog_trace!("Visiting", message(), CarlogStream::Custom(&mut output));
+        assert_eq!(evaluated.get(), 1);
+        set_max_level(Level::Trace);
+        let line = line!() + 1;
+        carlog_trace!("Visiting", message(), CarlogStream::Custom(&mut output));
+        carlog_debug!(
+            color: CargoColor::Yellow,
+            "Resolving",
+            "features",
+            CarlogStream::Custom(&mut output)
         );
-    };
-}
+        crate::settings::reset();
+        assert_eq!(evaluated.get(), 2);
+        // The call site is only printed in debug builds.
+        let site = match cfg!(debug_assertions) {
+            true => format!("{}:{}: ", file!(), line),
+            false => String::new(),
+        };
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!(
+                "   \u{1b}[2;36mResolving\u{1b}[0m dependency graph of 12 crates\n    \
+                 \u{1b}[2;90mVisiting\u{1b}[0m {}dependency graph of 12 crates\n   \
+                 \u{1b}[2;33mResolving\u{1b}[0m features\n",
+                site
+            )
+        );
+    }
 
-/// Print an error like cargo message.
-///
-/// The status is not justified, not bold and light red with the status text 'error'.
-///
-/// ## Example
-/// ```ignore
-/// #[macro_use] extern crate carlog;
-///
-/// use carlog::prelude::*;
-///
-/// carlog_error!("carlog (v0.1.0) generated an error!");
-/// let mut output = Vec::<u8>::new();
-/// carlog_error!("carlog (v0.1.0) generated an error!", CarlogStream::Custom(&mut output));
-/// println!("{}", String::from_utf8(output).unwrap());
-/// ```
-#[macro_export]
-macro_rules! carlog_error {
-    ($message:expr) => {
-        carlog_error!($message, crate::CarlogStream::default());
-    };
-    ($message:expr, $stream:expr) => {
-        carlog!(
-            "error",
-            format!(": {}", $message),
-            false,
-            false,
-            crate::CargoColor::Red,
-            $stream
+    #[test]
+    fn test_quiet_and_verbose() {
+        use crate::{quiet, quiet_scope, set_quiet, set_verbose};
+
+        let _lock = global_lock();
+        auto_colors();
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let print = |step: &str| {
+            let stream = || CarlogStream::from(Arc::clone(&output));
+            carlog_debug!("Debug", step, stream());
+            carlog_info!("Info", step, stream());
+            carlog_ok!("Ok", step, stream());
+            carlog!(
+                "Plain",
+                format!(" {}", step),
+                false,
+                false,
+                CargoColor::White,
+                stream()
+            );
+            carlog!(
+                level: Level::Error,
+                "Failed",
+                format!(" {}", step),
+                false,
+                false,
+                CargoColor::Red,
+                stream()
+            );
+            carlog_warning!(step, stream());
+        };
+        set_quiet(true);
+        print("quiet");
+        set_verbose(true);
+        print("quiet and verbose");
+        set_quiet(false);
+        print("verbose");
+        set_verbose(false);
+        set_max_level(Level::Error);
+        set_verbose(true);
+        print("verbose over error");
+        crate::settings::reset();
+        {
+            let _outer = quiet_scope();
+            let _inner = quiet_scope();
+            drop(_inner);
+            assert!(quiet());
+            std::thread::spawn(|| assert!(!quiet())).join().unwrap();
+            print("scope");
+        }
+        assert!(!quiet());
+        force_colors();
+        assert_eq!(
+            String::from_utf8(output.lock().unwrap().clone()).unwrap(),
+            "Failed quiet\n\
+             warning: quiet\n\
+             Failed quiet and verbose\n\
+             warning: quiet and verbose\n       \
+             Debug verbose\n        \
+             Info verbose\n          \
+             Ok verbose\n\
+             Plain verbose\n\
+             Failed verbose\n\
+             warning: verbose\n       \
+             Debug verbose over error\n        \
+             Info verbose over error\n          \
+             Ok verbose over error\n\
+             Plain verbose over error\n\
+             Failed verbose over error\n\
+             warning: verbose over error\n\
+             Failed scope\n\
+             warning: scope\n"
         );
-    };
-}
+    }
 
-#[cfg(test)]
-mod test {
-    use crate::CarlogStream;
+    #[test]
+    fn test_default_stream_macros() {
+        let _lock = global_lock();
+        crate::record_transcript(true);
+        carlog_info!("Testing", "info on stdout");
+        carlog_ok!("Tested", String::from("ok on stdout"));
+        carlog_warning!(std::path::Path::new("warning/on/stderr"));
+        carlog_warning!(label: "deprecated", "on stderr");
+        carlog_warning!(id = "default-stream", "on stderr");
+        carlog_error!(42);
+        carlog_info!(color: CargoColor::BrightYellow, "Notice", "info on stdout");
+        carlog_error!(color: CargoColor::BrightYellow, "error on stderr");
+        print_record(&Record::new("warning", "record on stderr").level(Level::Warning)).unwrap();
+        crate::record_transcript(false);
+        let targets: Vec<(Target, String)> = crate::transcript()
+            .into_iter()
+            .map(|entry| (entry.target, entry.line))
+            .collect();
+        let line = |target, line: &str| (target, line.to_string());
+        assert_eq!(
+            targets,
+            [
+                line(Target::Stdout, "     Testing info on stdout"),
+                line(Target::Stdout, "      Tested ok on stdout"),
+                line(Target::Stderr, "warning: warning/on/stderr"),
+                line(Target::Stderr, "deprecated: on stderr"),
+                line(Target::Stderr, "warning: on stderr [default-stream]"),
+                line(Target::Stderr, "error: 42"),
+                line(Target::Stdout, "      Notice info on stdout"),
+                line(Target::Stderr, "error: error on stderr"),
+                line(Target::Stderr, "warning: record on stderr"),
+            ]
+        );
+    }
 
     #[test]
-    fn test_carlog_info() {
+    fn test_macro_messages() {
+        let _lock = global_lock();
+        force_colors();
         let mut output = Vec::<u8>::new();
         carlog_info!(
-            "Compiling",
-            "carlog v0.1.0",
+            "Checking",
+            std::path::Path::new("src/lib.rs"),
             CarlogStream::Custom(&mut output)
         );
-        let output = String::from_utf8(output);
-        assert!(output.is_ok());
+        carlog_ok!("Finished", 42, CarlogStream::Custom(&mut output));
         assert_eq!(
-            output.unwrap(),
-            "   \u{1b}[1;36mCompiling\u{1b}[0m carlog v0.1.0\n"
+            String::from_utf8(output).unwrap(),
+            "    \u{1b}[1;36mChecking\u{1b}[0m src/lib.rs\n    \u{1b}[1;32mFinished\u{1b}[0m 42\n"
         );
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_carlog_ok() {
+    fn test_macro_non_utf8() {
+        let _lock = global_lock();
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        force_colors();
         let mut output = Vec::<u8>::new();
-        carlog_ok!(
-            "Compiled",
-            "carlog v0.1.0",
-            CarlogStream::Custom(&mut output)
-        );
-        let output = String::from_utf8(output);
-        assert!(output.is_ok());
+        let name = OsStr::from_bytes(b"caf\xe9.rs");
+        carlog_warning!(name, CarlogStream::Custom(&mut output));
         assert_eq!(
-            output.unwrap(),
-            "    \u{1b}[1;32mCompiled\u{1b}[0m carlog v0.1.0\n"
+            String::from_utf8(output).unwrap(),
+            "\u{1b}[93mwarning\u{1b}[0m: caf\u{fffd}.rs\n"
         );
     }
 
+    #[cfg(feature = "metrics")]
     #[test]
-    fn test_carlog_warning() {
+    fn test_metrics() {
+        let _lock = global_lock();
+        crate::metrics::reset();
+        crate::set_verbosity(0);
         let mut output = Vec::<u8>::new();
-        carlog_warning!(
-            "carlog (v0.1.0) generated a warning!",
-            CarlogStream::Custom(&mut output)
+        for _ in 0..3 {
+            carlog_info!("Compiling", "unit", CarlogStream::Custom(&mut output));
+        }
+        carlog_info!("Checking", "unit", CarlogStream::Custom(&mut output));
+        carlog_ok!("Finished", "build", CarlogStream::Custom(&mut output));
+        carlog_warning!("unused", CarlogStream::Custom(&mut output));
+        carlog_warning!(label: "deprecated", "old", CarlogStream::Custom(&mut output));
+        carlog_error!("failed", CarlogStream::Custom(&mut output));
+        crate::carlog_fresh!("cached", CarlogStream::Custom(&mut output));
+        crate::carlog_dirty!("changed", "reason", CarlogStream::Custom(&mut output));
+        let metrics = crate::metrics::snapshot();
+        crate::reset_cache_counts();
+        crate::metrics::reset();
+        assert_eq!(
+            (metrics.info, metrics.ok, metrics.warning, metrics.error),
+            (4, 1, 2, 1)
         );
-        let output = String::from_utf8(output);
-        assert!(output.is_ok());
+        assert_eq!(metrics.suppressed, 2);
+        let levels: Vec<u64> = metrics.levels.values().copied().collect();
+        assert_eq!(levels, [0, 0, 5, 2, 1]);
+        assert!(metrics.targets.is_empty());
+    }
+
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn test_metrics_targets() {
+        let _lock = global_lock();
+        crate::metrics::reset();
+        crate::metrics::count_targets(true);
+        crate::set_max_level(Level::Debug);
+        let mut output = Vec::<u8>::new();
+        carlog_info!(target: "net::http", "Fetching", "index", CarlogStream::Custom(&mut output));
+        carlog_debug!(target: "net::http", "Resolved", "crates.io", CarlogStream::Custom(&mut output));
+        {
+            let _db = crate::target_scope("db");
+            carlog_warning!("slow query", CarlogStream::Custom(&mut output));
+        }
+        carlog_error!("failed", CarlogStream::Custom(&mut output));
+        let metrics = crate::metrics::snapshot();
+        crate::metrics::count_targets(false);
+        crate::metrics::reset();
+        crate::settings::reset();
+        let targets: Vec<(&str, u64)> = metrics
+            .targets
+            .iter()
+            .map(|(target, count)| (target.as_str(), *count))
+            .collect();
+        assert_eq!(targets, [("db", 1), ("net::http", 2)]);
+        assert_eq!(metrics.levels[&Level::Debug], 1);
+        assert_eq!(metrics.levels[&Level::Error], 1);
+    }
+
+    #[test]
+    fn test_timestamps() {
+        use crate::{enable_timestamps, set_clock, TimestampKind};
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        fn clock() -> SystemTime {
+            UNIX_EPOCH + Duration::from_secs(1_715_522_602)
+        }
+
+        let _lock = global_lock();
+        force_colors();
+        set_clock(clock);
+        let status = Status::plain()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Compiled");
+        let mut output = Vec::<u8>::new();
+        status.print(&mut output, " carlog v0.1.0").unwrap();
+        status
+            .clone()
+            .timestamp()
+            .print(&mut output, " carlog v0.1.0")
+            .unwrap();
+        enable_timestamps(TimestampKind::Rfc3339);
+        status.print(&mut output, " carlog v0.1.0").unwrap();
+        auto_colors();
+        status.print(&mut output, " carlog v0.1.0").unwrap();
+        set_format(Format::Short);
+        status.print(&mut output, " carlog v0.1.0").unwrap();
+        set_format(Format::Human);
+        enable_timestamps(TimestampKind::LocalHms);
+        let local = status.render(" carlog v0.1.0");
+        enable_timestamps(TimestampKind::None);
+        set_clock(SystemTime::now);
+        force_colors();
         assert_eq!(
-            output.unwrap(),
-            "\u{1b}[93mwarning\u{1b}[0m: carlog (v0.1.0) generated a warning!\n"
+            String::from_utf8(output).unwrap(),
+            "    \u{1b}[32mCompiled\u{1b}[0m carlog v0.1.0\n\
+             \u{1b}[2m2024-05-12T14:03:22Z\u{1b}[0m     \u{1b}[32mCompiled\u{1b}[0m carlog v0.1.0\n\
+             \u{1b}[2m2024-05-12T14:03:22Z\u{1b}[0m     \u{1b}[32mCompiled\u{1b}[0m carlog v0.1.0\n\
+             2024-05-12T14:03:22Z     Compiled carlog v0.1.0\n\
+             2024-05-12T14:03:22Z Compiled: carlog v0.1.0\n"
         );
+        // The time of the day is fixed-width whatever the time zone.
+        let (time, line) = local.split_at(8);
+        assert!(time.bytes().enumerate().all(|(i, b)| match i {
+            2 | 5 => b == b':',
+            _ => b.is_ascii_digit(),
+        }));
+        assert_eq!(line, "     Compiled carlog v0.1.0");
+        assert_eq!(&time[6..], "22");
     }
 
     #[test]
-    fn test_carlog_error() {
+    fn test_elapsed_timestamps() {
+        use crate::{enable_timestamps, init_timer, set_monotonic_clock, TimestampKind};
+        use std::time::{Duration, Instant};
+
+        fn clock() -> Instant {
+            crate::time::timer() + Duration::from_millis(12_345)
+        }
+
+        fn later() -> Instant {
+            crate::time::timer() + Duration::from_micros(1_234_567_999)
+        }
+
+        let _lock = global_lock();
+        auto_colors();
+        // The clock reads the timer, which must have started before.
+        init_timer();
+        set_monotonic_clock(clock);
+        let status = Status::plain().justify().status("Compiled");
         let mut output = Vec::<u8>::new();
-        carlog_error!(
-            "carlog (v0.1.0) generated an error!",
-            CarlogStream::Custom(&mut output)
+        status
+            .clone()
+            .elapsed()
+            .print(&mut output, " carlog v0.1.0")
+            .unwrap();
+        enable_timestamps(TimestampKind::Elapsed);
+        status
+            .clone()
+            .timestamp()
+            .print(&mut output, " carlog v0.1.0")
+            .unwrap();
+        set_monotonic_clock(later);
+        status.print(&mut output, " carlog v0.1.0").unwrap();
+        enable_timestamps(TimestampKind::None);
+        set_monotonic_clock(Instant::now);
+        force_colors();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[ 12.345s]     Compiled carlog v0.1.0\n\
+             [ 12.345s]     Compiled carlog v0.1.0\n\
+             [1234.567s]     Compiled carlog v0.1.0\n"
         );
-        let output = String::from_utf8(output);
-        assert!(output.is_ok());
+    }
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Counts the allocations of each thread.
+    struct Counting;
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: Counting = Counting;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(Cell::get)
+    }
+
+    #[test]
+    fn test_print_allocations() {
+        let _lock = crate::test::global_lock();
+        crate::test::force_colors();
+        let status = Status::plain()
+            .bold()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Compiled");
+        let mut output = Vec::<u8>::with_capacity(4096);
+        // The first line allocates the buffer of the thread.
+        status.print(&mut output, " carlog v0.1.0").unwrap();
+        let before = allocations();
+        for _ in 0..100 {
+            output.clear();
+            status.print(&mut output, " carlog v0.1.0").unwrap();
+        }
+        assert_eq!(allocations() - before, 0);
         assert_eq!(
-            output.unwrap(),
-            "\u{1b}[91merror\u{1b}[0m: carlog (v0.1.0) generated an error!\n"
+            String::from_utf8(output).unwrap(),
+            "    \u{1b}[1;32mCompiled\u{1b}[0m carlog v0.1.0\n"
         );
     }
 }
@@ -0,0 +1,207 @@
+//! Output of the libraries that use carlog, silent unless the application enables it.
+//!
+//! A library shouldn't print on its own: the application decides. A library gets a
+//! [`LibraryLogger`] with [`library_handle`], which prints nothing until the application calls
+//! [`enable_library_output`] for it. Once enabled, its messages go through the same pipeline as
+//! the ones of the application: the [theme](crate::set_theme), the
+//! [max level](crate::set_max_level) and the [routes](crate::route_target), with the name of the
+//! library as their target.
+
+use crate::{print_record, Error, Level, Record};
+use std::sync::Mutex;
+
+static ENABLED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Let the libraries matching `pattern` print their messages.
+///
+/// The pattern is the name of a library, i.e. `tls-probe`, or a glob where `*` matches any
+/// characters, i.e. `tls-*`. `*` alone enables every library, see [`enable_all_library_output`].
+///
+/// ## Example
+/// ```
+/// carlog::enable_library_output("tls-*");
+/// assert!(carlog::library_handle("tls-probe").enabled());
+/// assert!(!carlog::library_handle("zip").enabled());
+/// # carlog::disable_library_output();
+/// ```
+pub fn enable_library_output<S>(pattern: S)
+where
+    S: AsRef<str>,
+{
+    let mut enabled = ENABLED.lock().unwrap_or_else(|e| e.into_inner());
+    enabled.push(pattern.as_ref().to_string());
+}
+
+/// Let every library print its messages, see [`enable_library_output`].
+pub fn enable_all_library_output() {
+    enable_library_output("*");
+}
+
+/// Silence every library again, the default.
+pub fn disable_library_output() {
+    ENABLED.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// The patterns enabled, in the order they were, see [`enable_library_output`].
+pub(crate) fn enabled_patterns() -> Vec<String> {
+    ENABLED.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Replace the patterns enabled, see [`enable_library_output`].
+pub(crate) fn set_enabled_patterns(patterns: Vec<String>) {
+    *ENABLED.lock().unwrap_or_else(|e| e.into_inner()) = patterns;
+}
+
+/// The logger of a library, see the [module](self).
+///
+/// * `name`: The name of the library, usually `env!("CARGO_PKG_NAME")`.
+///
+/// ## Example
+/// ```
+/// let log = carlog::library_handle(env!("CARGO_PKG_NAME"));
+/// log.warning("the cache is corrupt, rebuilding it").unwrap(); // Silent by default.
+/// ```
+pub fn library_handle<S>(name: S) -> LibraryLogger
+where
+    S: AsRef<str>,
+{
+    LibraryLogger {
+        name: name.as_ref().to_string(),
+    }
+}
+
+/// Prints the messages of a library once the application enables them, see [`library_handle`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LibraryLogger {
+    name: String,
+}
+
+impl LibraryLogger {
+    /// The name of the library.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the application enabled the output of the library.
+    pub fn enabled(&self) -> bool {
+        let enabled = ENABLED.lock().unwrap_or_else(|e| e.into_inner());
+        enabled.iter().any(|pattern| glob(pattern, &self.name))
+    }
+
+    /// Print an info message, like `carlog_info!`.
+    pub fn info<S, M>(&self, status: S, message: M) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        M: AsRef<str>,
+    {
+        self.print(Record::new(status, message))
+    }
+
+    /// Print a warning, like `carlog_warning!`.
+    pub fn warning<M>(&self, message: M) -> Result<(), Error>
+    where
+        M: AsRef<str>,
+    {
+        self.print(Record::new("warning", message).level(Level::Warning))
+    }
+
+    /// Print an error, like `carlog_error!`.
+    pub fn error<M>(&self, message: M) -> Result<(), Error>
+    where
+        M: AsRef<str>,
+    {
+        self.print(Record::new("error", message).level(Level::Error))
+    }
+
+    /// Print a record, with the name of the library as its target unless it has one.
+    pub fn print(&self, record: Record) -> Result<(), Error> {
+        if !self.enabled() {
+            return Ok(());
+        }
+        let record = match record.log_target() {
+            Some(_) => record,
+            None => record.target(&self.name),
+        };
+        print_record(&record)
+    }
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any characters.
+fn glob(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(start) => rest = &rest[start + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{disable_library_output, enable_library_output, glob, library_handle};
+    use crate::test::{force_colors, global_lock, Shared};
+    use crate::{clear_routes, route_target, set_max_level, set_theme, CargoColor, Level, Theme};
+
+    #[test]
+    fn test_glob() {
+        assert!(glob("tls-probe", "tls-probe"));
+        assert!(!glob("tls-probe", "tls-probe2"));
+        assert!(glob("tls-*", "tls-probe"));
+        assert!(glob("*", "zip"));
+        assert!(glob("*-sys", "openssl-sys"));
+        assert!(glob("a*b*c", "a-b-c"));
+        assert!(!glob("a*b*c", "a-c"));
+        assert!(!glob("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_library_output() {
+        let _lock = global_lock();
+        force_colors();
+        let (probe, zip) = (Shared::default(), Shared::default());
+        route_target("tls-probe", probe.clone());
+        route_target("zip", zip.clone());
+        let log = library_handle("tls-probe");
+        let other = library_handle("zip");
+
+        // Silent by default.
+        log.warning("silent").unwrap();
+        assert!(!log.enabled());
+
+        enable_library_output("tls-*");
+        set_theme(Theme {
+            warning: CargoColor::BrightRed,
+            ..Theme::default()
+        });
+        log.warning("weak cipher").unwrap();
+        log.info("Probing", "example.com").unwrap();
+        other.error("not enabled").unwrap();
+        set_max_level(Level::Error);
+        log.warning("quiet").unwrap();
+        crate::settings::reset();
+
+        enable_library_output("zip");
+        other.error("corrupt archive").unwrap();
+        set_theme(Theme::default());
+        disable_library_output();
+        other.error("disabled again").unwrap();
+        clear_routes();
+
+        assert_eq!(
+            probe.text(),
+            "\u{1b}[91mwarning\u{1b}[0m: weak cipher\n     \
+             \u{1b}[1;36mProbing\u{1b}[0m example.com\n"
+        );
+        assert_eq!(zip.text(), "\u{1b}[91merror\u{1b}[0m: corrupt archive\n");
+    }
+}
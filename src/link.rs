@@ -0,0 +1,183 @@
+//! Hyperlinks of the terminals, the OSC 8 escape sequences around the text they link.
+//!
+//! [`link`] writes a link, i.e. to make the `src/lib.rs:12` of a warning open the file, and the
+//! rendering of the lines removes the links the target doesn't show.
+//!
+//! ## Example
+//! ```
+//! #[macro_use] extern crate carlog;
+//!
+//! let location = carlog::link("src/lib.rs:12", "file:///home/me/carlog/src/lib.rs");
+//! carlog_warning!(format!("unused import at {}", location));
+//! ```
+
+use crate::ansi::{self, Token};
+use crate::color;
+use crate::env::{EnvSource, ProcessEnv};
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Write links (`true`, the default) or just their text (`false`) from now on, see [`link`].
+pub fn set_hyperlinks(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether links are written, see [`set_hyperlinks`].
+pub fn hyperlinks() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// A text linked to a URL, see [`link`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Link<T, U> {
+    text: T,
+    url: U,
+}
+
+impl<T, U> Display for Link<T, U>
+where
+    T: Display,
+    U: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match hyperlinks() {
+            true => write!(
+                f,
+                "\u{1b}]8;;{}\u{1b}\\{}\u{1b}]8;;\u{1b}\\",
+                self.url, self.text
+            ),
+            false => write!(f, "{}", self.text),
+        }
+    }
+}
+
+/// Link `text` to `url`, displayed as the OSC 8 hyperlink
+/// `ESC ] 8 ; ; url ESC \ text ESC ] 8 ; ; ESC \`.
+///
+/// Links are kept in the lines to the targets that are colored, see the [`color`](crate::color)
+/// decision, when the terminal shows them: not under `TERM=dumb` nor in the console of Linux.
+/// Elsewhere the lines get just the text of the links. They take no columns, so they change
+/// neither the justification of the statuses nor the width of the lines.
+///
+/// ## Example
+/// ```
+/// let link = carlog::link("carlog", "https://crates.io/crates/carlog");
+/// assert_eq!(
+///     link.to_string(),
+///     "\u{1b}]8;;https://crates.io/crates/carlog\u{1b}\\carlog\u{1b}]8;;\u{1b}\\"
+/// );
+/// ```
+pub fn link<T, U>(text: T, url: U) -> Link<T, U>
+where
+    T: Display,
+    U: Display,
+{
+    Link { text, url }
+}
+
+/// Whether the terminal shows links according to the environment.
+fn env_support<E>(env: &E) -> bool
+where
+    E: EnvSource,
+{
+    !matches!(env.var("TERM").as_deref(), Some("dumb" | "linux"))
+}
+
+/// Whether the links are kept in the line being rendered.
+fn enabled() -> bool {
+    static ENV: OnceLock<bool> = OnceLock::new();
+    hyperlinks() && color::enabled() && *ENV.get_or_init(|| env_support(&ProcessEnv))
+}
+
+/// The message of the line being rendered, without its links if they aren't kept.
+pub(crate) fn for_line(msg: &str) -> Cow<'_, str> {
+    let is_link = |escape: &str| escape.starts_with("\u{1b}]8;") || escape.starts_with("\u{9d}8;");
+    if !msg.contains("]8;") && !msg.contains("\u{9d}8;") || enabled() {
+        return Cow::Borrowed(msg);
+    }
+    let mut plain = String::with_capacity(msg.len());
+    for token in ansi::tokens(msg) {
+        match token {
+            Token::Escape(escape) if is_link(escape) => {}
+            Token::Escape(text) | Token::Text(text) => plain.push_str(text),
+        }
+    }
+    Cow::Owned(plain)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{env_support, link, set_hyperlinks};
+    use crate::test::{auto_colors, force_colors, global_lock};
+    use crate::Status;
+
+    const URL: &str = "file:///carlog/src/lib.rs";
+
+    #[test]
+    fn test_link() {
+        let _lock = global_lock();
+        assert_eq!(
+            link("src/lib.rs:12", URL).to_string(),
+            "\u{1b}]8;;file:///carlog/src/lib.rs\u{1b}\\src/lib.rs:12\u{1b}]8;;\u{1b}\\"
+        );
+        set_hyperlinks(false);
+        let plain = link("src/lib.rs:12", URL).to_string();
+        set_hyperlinks(true);
+        assert_eq!(plain, "src/lib.rs:12");
+    }
+
+    #[test]
+    fn test_lines() {
+        let _lock = global_lock();
+        force_colors();
+        let message = format!(": unused import at {}", link("src/lib.rs:12", URL));
+        // A linked status is pre-styled, so it's printed as is.
+        let status = Status::plain()
+            .justify()
+            .status(link("warning", URL).to_string());
+        let mut linked = Vec::<u8>::new();
+        status.print(&mut linked, &message).unwrap();
+        auto_colors();
+        let mut plain = Vec::<u8>::new();
+        status.print(&mut plain, &message).unwrap();
+        force_colors();
+        // The links take no columns, the status is justified to 12 like any other.
+        assert_eq!(
+            String::from_utf8(linked).unwrap(),
+            "     \u{1b}]8;;file:///carlog/src/lib.rs\u{1b}\\warning\u{1b}]8;;\u{1b}\\: unused import at \u{1b}]8;;file:///carlog/src/lib.rs\u{1b}\\src/lib.rs:12\
+             \u{1b}]8;;\u{1b}\\\n"
+        );
+        assert_eq!(
+            String::from_utf8(plain).unwrap(),
+            "     warning: unused import at src/lib.rs:12\n"
+        );
+    }
+
+    #[test]
+    fn test_print_linked() {
+        let _lock = global_lock();
+        force_colors();
+        let status = Status::plain().justify().status("Documenting");
+        let mut output = Vec::<u8>::new();
+        status
+            .print_linked(&mut output, " carlog v0.1.0", "https://docs.rs/carlog")
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            " \u{1b}[37mDocumenting\u{1b}[0m \u{1b}]8;;https://docs.rs/carlog\u{1b}\\carlog v0.1.0\
+             \u{1b}]8;;\u{1b}\\\n"
+        );
+    }
+
+    #[test]
+    fn test_env_support() {
+        assert!(env_support(&[]));
+        assert!(env_support(&[("TERM", "xterm-256color")]));
+        assert!(!env_support(&[("TERM", "dumb")]));
+        assert!(!env_support(&[("TERM", "linux")]));
+    }
+}
@@ -0,0 +1,277 @@
+//! Warnings with an identifier that users can allow or deny, like the lints of rustc.
+//!
+//! A program declares its lints with [`declare`] and emits them with
+//! `carlog_warning!(id = "unused-manifest-key", "...")`. Users choose the level of every lint,
+//! i.e. from the command line with [`apply_args`]:
+//! * [`allow`]: the warnings aren't printed nor counted.
+//! * [`warn`]: the warnings are printed as such (the default).
+//! * [`deny`]: the warnings are printed and counted as errors.
+//! * [`forbid`]: like [`deny`], but the level can't be changed anymore.
+//!
+//! The identifier is printed dimmed after the message, i.e.
+//! `warning: unused key `foo` [unused-manifest-key]`, and kept in the [`Record`].
+//!
+//! ## Example
+//! ```
+//! carlog::lint::declare("unused-manifest-key");
+//! let args = carlog::lint::apply_args(["build", "--deny", "unused-manifest-key"]);
+//! assert_eq!(args, ["build"]);
+//! assert_eq!(carlog::lint::level("unused-manifest-key"), carlog::lint::LintLevel::Deny);
+//! ```
+
+use crate::record::{self, Record};
+use crate::{theme, CarlogStream, Error, Level};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+
+static LINTS: Mutex<Lints> = Mutex::new(Lints {
+    declared: BTreeSet::new(),
+    levels: BTreeMap::new(),
+});
+
+struct Lints {
+    declared: BTreeSet<String>,
+    levels: BTreeMap<String, LintLevel>,
+}
+
+/// What happens to the warnings of a lint.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LintLevel {
+    /// The warnings aren't printed nor counted.
+    Allow,
+    /// The warnings are printed as warnings (the default).
+    #[default]
+    Warn,
+    /// The warnings are printed and counted as errors.
+    Deny,
+    /// Like `Deny`, and the level can't be changed anymore.
+    Forbid,
+}
+
+impl LintLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warn",
+            LintLevel::Deny => "deny",
+            LintLevel::Forbid => "forbid",
+        }
+    }
+}
+
+/// Declare a lint of the program, so that choosing its level doesn't warn about an unknown lint.
+pub fn declare<S>(id: S)
+where
+    S: AsRef<str>,
+{
+    let mut lints = LINTS.lock().unwrap_or_else(|e| e.into_inner());
+    lints.declared.insert(id.as_ref().to_string());
+}
+
+/// Don't print the warnings of a lint, see [`set_level`].
+pub fn allow<S>(id: S)
+where
+    S: AsRef<str>,
+{
+    set_level(id, LintLevel::Allow);
+}
+
+/// Print the warnings of a lint as warnings, see [`set_level`].
+pub fn warn<S>(id: S)
+where
+    S: AsRef<str>,
+{
+    set_level(id, LintLevel::Warn);
+}
+
+/// Print the warnings of a lint as errors, see [`set_level`].
+pub fn deny<S>(id: S)
+where
+    S: AsRef<str>,
+{
+    set_level(id, LintLevel::Deny);
+}
+
+/// Print the warnings of a lint as errors for good, see [`set_level`].
+pub fn forbid<S>(id: S)
+where
+    S: AsRef<str>,
+{
+    set_level(id, LintLevel::Forbid);
+}
+
+/// Choose what happens to the warnings of a lint.
+///
+/// Prints a warning to stderr if the lint wasn't [declared](declare), or if it's forbidden and
+/// `level` isn't `Forbid`, in which case the level is kept.
+pub fn set_level<S>(id: S, level: LintLevel)
+where
+    S: AsRef<str>,
+{
+    let id = id.as_ref();
+    let diagnostic = {
+        let mut lints = LINTS.lock().unwrap_or_else(|e| e.into_inner());
+        if lints.levels.get(id) == Some(&LintLevel::Forbid) && level != LintLevel::Forbid {
+            Some(format!(
+                "`{}({})` incompatible with previous `forbid`",
+                level.as_str(),
+                id
+            ))
+        } else {
+            lints.levels.insert(id.to_string(), level);
+            (!lints.declared.contains(id)).then(|| format!("unknown lint: `{}`", id))
+        }
+    };
+    if let Some(diagnostic) = diagnostic {
+        let record = Record::new("warning", diagnostic).level(Level::Warning);
+        // The level is set anyway, failing to report it isn't worth failing.
+        let _ = record::print_record_to(&record, crate::default_error_stream());
+    }
+}
+
+/// The level of a lint, see [`set_level`].
+pub fn level<S>(id: S) -> LintLevel
+where
+    S: AsRef<str>,
+{
+    let lints = LINTS.lock().unwrap_or_else(|e| e.into_inner());
+    lints.levels.get(id.as_ref()).copied().unwrap_or_default()
+}
+
+/// The level chosen for every lint, see [`set_level`].
+pub(crate) fn levels() -> BTreeMap<String, LintLevel> {
+    LINTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .levels
+        .clone()
+}
+
+/// Replace the levels of every lint, including the forbidden ones.
+pub(crate) fn set_levels(levels: BTreeMap<String, LintLevel>) {
+    LINTS.lock().unwrap_or_else(|e| e.into_inner()).levels = levels;
+}
+
+/// Apply the lint options of a command line and return the other arguments.
+///
+/// The options are like the ones of rustc: `--allow ID`, `--warn ID`, `--deny ID` and
+/// `--forbid ID`, also written `--allow=ID` or `-A ID`, `-W ID`, `-D ID` and `-F ID`.
+pub fn apply_args<I, S>(args: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let options = [
+        ("--allow", "-A", LintLevel::Allow),
+        ("--warn", "-W", LintLevel::Warn),
+        ("--deny", "-D", LintLevel::Deny),
+        ("--forbid", "-F", LintLevel::Forbid),
+    ];
+    let mut rest = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let arg = arg.as_ref();
+        let option = options.iter().find_map(|&(long, short, level)| {
+            if arg == long || arg == short {
+                Some((level, None))
+            } else {
+                let value = arg.strip_prefix(long)?.strip_prefix('=')?;
+                Some((level, Some(value.to_string())))
+            }
+        });
+        match option {
+            Some((level, Some(id))) => set_level(id, level),
+            Some((level, None)) => match args.next() {
+                Some(id) => set_level(id, level),
+                None => rest.push(arg.to_string()),
+            },
+            None => rest.push(arg.to_string()),
+        }
+    }
+    rest
+}
+
+/// Print the warning of a lint from `carlog_warning!`, according to its level.
+#[doc(hidden)]
+pub fn __emit(
+    id: &str,
+    message: &str,
+    location: (&str, u32, u32),
+    stream: CarlogStream<'_>,
+) -> Result<(), Error> {
+    let (label, level, color) = match level(id) {
+        LintLevel::Allow => return Ok(()),
+        LintLevel::Warn => ("warning", Level::Warning, theme().warning),
+        LintLevel::Deny | LintLevel::Forbid => ("error", Level::Error, theme().error),
+    };
+    if !crate::__enabled(level) {
+        return Ok(());
+    }
+    let (path, line, column) = location;
+    let record = Record::new(label, message)
+        .level(level)
+        .color(color)
+        .id(id)
+        .location(path, line, column);
+    record::__print_record(record::kind(level), &record, stream)
+}
+
+/// Forget every declared lint and level.
+#[cfg(test)]
+pub(crate) fn reset() {
+    let mut lints = LINTS.lock().unwrap_or_else(|e| e.into_inner());
+    lints.declared.clear();
+    lints.levels.clear();
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply_args, declare, forbid, level, reset, set_level, LintLevel};
+    use crate::test::global_lock;
+    use crate::{record_transcript, transcript, Target};
+
+    #[test]
+    fn test_levels() {
+        let _lock = global_lock();
+        declare("unused-manifest-key");
+        declare("missing-docs");
+        record_transcript(true);
+        let rest = apply_args([
+            "build",
+            "-A",
+            "unused-manifest-key",
+            "--deny=missing-docs",
+            "--warn",
+            "typo-lint",
+            "--forbid",
+        ]);
+        forbid("missing-docs");
+        set_level("missing-docs", LintLevel::Allow);
+        record_transcript(false);
+        assert_eq!(rest, ["build", "--forbid"]);
+        assert_eq!(level("unused-manifest-key"), LintLevel::Allow);
+        assert_eq!(level("missing-docs"), LintLevel::Forbid);
+        assert_eq!(level("typo-lint"), LintLevel::Warn);
+        assert_eq!(level("never-set"), LintLevel::Warn);
+        let diagnostics: Vec<(Target, String)> = transcript()
+            .into_iter()
+            .map(|entry| (entry.target, entry.line))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            [
+                (
+                    Target::Stderr,
+                    "warning: unknown lint: `typo-lint`".to_string()
+                ),
+                (
+                    Target::Stderr,
+                    "warning: `allow(missing-docs)` incompatible with previous `forbid`"
+                        .to_string()
+                ),
+            ]
+        );
+        reset();
+    }
+}
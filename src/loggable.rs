@@ -0,0 +1,207 @@
+//! Values that can be printed as messages.
+
+use crate::fmt;
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A value that can be printed as the message of a status.
+///
+/// Implemented for the string types, and for paths and OS strings so that they can be printed
+/// without converting them first. Invalid UTF-8 is shown with the replacement character `U+FFFD`
+/// and paths are displayed with [`fmt::path`].
+///
+/// The macros accept any `Loggable` or [`Display`](std::fmt::Display) message.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use std::path::Path;
+///
+/// let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiling");
+/// let mut output = Vec::<u8>::new();
+/// status.print(&mut output, Path::new("src/lib.rs"));
+/// ```
+pub trait Loggable {
+    /// The text of the message.
+    fn to_log_string(&self) -> Cow<'_, str>;
+}
+
+impl<T> Loggable for &T
+where
+    T: Loggable + ?Sized,
+{
+    fn to_log_string(&self) -> Cow<'_, str> {
+        (**self).to_log_string()
+    }
+}
+
+impl<T> Loggable for &mut T
+where
+    T: Loggable + ?Sized,
+{
+    fn to_log_string(&self) -> Cow<'_, str> {
+        (**self).to_log_string()
+    }
+}
+
+impl Loggable for str {
+    fn to_log_string(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl Loggable for String {
+    fn to_log_string(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl Loggable for Cow<'_, str> {
+    fn to_log_string(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl Loggable for Box<str> {
+    fn to_log_string(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl Loggable for Rc<str> {
+    fn to_log_string(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl Loggable for Arc<str> {
+    fn to_log_string(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl Loggable for std::fmt::Arguments<'_> {
+    fn to_log_string(&self) -> Cow<'_, str> {
+        match self.as_str() {
+            Some(str) => Cow::Borrowed(str),
+            None => Cow::Owned(self.to_string()),
+        }
+    }
+}
+
+impl Loggable for OsStr {
+    fn to_log_string(&self) -> Cow<'_, str> {
+        self.to_string_lossy()
+    }
+}
+
+impl Loggable for OsString {
+    fn to_log_string(&self) -> Cow<'_, str> {
+        self.to_string_lossy()
+    }
+}
+
+impl Loggable for Path {
+    fn to_log_string(&self) -> Cow<'_, str> {
+        Cow::Owned(fmt::path(self))
+    }
+}
+
+impl Loggable for PathBuf {
+    fn to_log_string(&self) -> Cow<'_, str> {
+        Cow::Owned(fmt::path(self))
+    }
+}
+
+/// Macro support: picks the [`Loggable`] conversion of a message when there is one and falls back
+/// to [`Display`](std::fmt::Display) otherwise.
+#[doc(hidden)]
+pub mod __private {
+    use super::Loggable;
+    use std::borrow::Cow;
+    use std::fmt::Display;
+
+    pub struct Message<'a, T: ?Sized>(pub &'a T);
+
+    pub trait ViaLoggable {
+        fn __log_string(&self) -> Cow<'_, str>;
+    }
+
+    impl<T> ViaLoggable for &Message<'_, T>
+    where
+        T: Loggable + ?Sized,
+    {
+        fn __log_string(&self) -> Cow<'_, str> {
+            self.0.to_log_string()
+        }
+    }
+
+    pub trait ViaDisplay {
+        fn __log_string(&self) -> Cow<'_, str>;
+    }
+
+    impl<T> ViaDisplay for Message<'_, T>
+    where
+        T: Display + ?Sized,
+    {
+        fn __log_string(&self) -> Cow<'_, str> {
+            Cow::Owned(self.0.to_string())
+        }
+    }
+}
+
+/// The text of a macro message, see [`Loggable`].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __carlog_message {
+    ($message:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::__private::{ViaDisplay as _, ViaLoggable as _};
+        (&&$crate::__private::Message(&$message)).__log_string()
+    }};
+}
+
+#[cfg(test)]
+mod test {
+    use super::Loggable;
+    use crate::test::{force_colors, global_lock};
+    use crate::Status;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_messages() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        let status = || Status::new().status(">");
+        status().print(&mut output, " str").unwrap();
+        status()
+            .print(&mut output, String::from(" string"))
+            .unwrap();
+        status()
+            .print(&mut output, Path::new("src/lib.rs"))
+            .unwrap();
+        status()
+            .print(&mut output, PathBuf::from("Cargo.toml"))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "\u{1b}[37m>\u{1b}[0m str\n\u{1b}[37m>\u{1b}[0m string\n\u{1b}[37m>\u{1b}[0msrc/lib.rs\n\
+             \u{1b}[37m>\u{1b}[0mCargo.toml\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = OsStr::from_bytes(b"caf\xe9.rs");
+        assert_eq!(name.to_log_string(), "caf\u{fffd}.rs");
+        assert_eq!(Path::new(name).to_log_string(), "caf\u{fffd}.rs");
+    }
+}
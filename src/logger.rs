@@ -0,0 +1,161 @@
+//! Backend of the `log` crate printing its records in the style of the crate.
+//!
+//! Enabled with the `log` feature.
+
+use crate::record::{self, Record};
+use crate::{CarlogStream, Level};
+use log::{LevelFilter, Log, Metadata, SetLoggerError};
+use std::io::{stderr, stdout, Write};
+
+/// Print the records of the `log` crate like cargo, see [`init_with_level`].
+///
+/// * `info!`: The first word of the message is the status, justified, bold and in the info color
+///   of the [`theme`](crate::theme), i.e. `info!("Compiling carlog v0.1.0")` prints
+///   `   Compiling carlog v0.1.0`.
+/// * `warn!` and `error!`: A `warning:` or `error:` status, like `carlog_warning!` and
+///   `carlog_error!`.
+/// * `debug!` and `trace!`: A dimmed `debug` or `trace` status.
+///
+/// Warnings and errors go to stderr and the other records to stdout. The target of a record, its
+/// module path by default, is the target of the message for the [routes](crate::route_target).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CarlogLogger {
+    level: LevelFilter,
+}
+
+impl CarlogLogger {
+    /// Create a logger printing the records up to `level`.
+    pub fn new(level: LevelFilter) -> Self {
+        Self { level }
+    }
+}
+
+/// The record of the crate printed for a record of `log`.
+fn to_record(record: &log::Record<'_>) -> Record {
+    let message = record.args().to_string();
+    let printed = match record.level() {
+        log::Level::Error => Record::new("error", message).level(Level::Error),
+        log::Level::Warn => Record::new("warning", message).level(Level::Warning),
+        log::Level::Info => {
+            let (status, message) = message.split_once(' ').unwrap_or((&message, ""));
+            Record::new(status, message)
+        }
+        log::Level::Debug => Record::new("debug", message).level(Level::Debug),
+        log::Level::Trace => Record::new("trace", message).level(Level::Trace),
+    };
+    printed.target(record.target())
+}
+
+impl Log for CarlogLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let printed = to_record(record);
+        let stream = match record.level() {
+            log::Level::Error | log::Level::Warn => CarlogStream::Stderr,
+            _ => CarlogStream::Stdout,
+        };
+        crate::__printed(record::__print_record(
+            record::kind(printed.severity()),
+            &printed,
+            stream,
+        ));
+    }
+
+    fn flush(&self) {
+        let _output = crate::output::lock();
+        let _ = stdout().flush();
+        let _ = stderr().flush();
+    }
+}
+
+/// Install a [`CarlogLogger`] printing the info, warning and error records of the `log` crate.
+///
+/// See [`init_with_level`].
+pub fn init() -> Result<(), SetLoggerError> {
+    init_with_level(LevelFilter::Info)
+}
+
+/// Install a [`CarlogLogger`] as the logger of the `log` crate, printing the records up to
+/// `level`.
+///
+/// Fails if a logger is already installed. The level of the `log` crate replaces the
+/// [max level](crate::set_max_level) of the crate for its records.
+///
+/// ## Example
+/// ```
+/// carlog::init_with_level(log::LevelFilter::Debug).unwrap();
+/// log::info!("Compiling carlog v0.1.0");
+/// log::warn!("unused import: `std::io`");
+/// ```
+pub fn init_with_level(level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_logger(Box::leak(Box::new(CarlogLogger::new(level))))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{init_with_level, CarlogLogger};
+    use crate::test::{force_colors, global_lock, Shared};
+    use crate::{
+        clear_routes, record_transcript, route_target_with, transcript, RouteMode, Target,
+    };
+    use log::{LevelFilter, Log, Metadata};
+    use std::sync::Once;
+
+    /// The logger of `log` can only be installed once per process.
+    fn install() {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| init_with_level(LevelFilter::Debug).unwrap());
+    }
+
+    #[test]
+    fn test_logger() {
+        let _lock = global_lock();
+        force_colors();
+        install();
+        let sink = Shared::default();
+        route_target_with(module_path!(), sink.clone(), RouteMode::Also);
+        record_transcript(true);
+        log::info!("Compiling carlog v0.1.0");
+        log::warn!("unused import: `std::io`");
+        log::error!("could not compile `carlog`");
+        log::debug!("fingerprint of carlog is fresh");
+        log::trace!("not printed");
+        log::logger().flush();
+        record_transcript(false);
+        clear_routes();
+
+        assert_eq!(
+            sink.text(),
+            "   \u{1b}[1;36mCompiling\u{1b}[0m carlog v0.1.0\n\
+             \u{1b}[93mwarning\u{1b}[0m: unused import: `std::io`\n\
+             \u{1b}[91merror\u{1b}[0m: could not compile `carlog`\n       \
+             \u{1b}[2;36mdebug\u{1b}[0m fingerprint of carlog is fresh\n"
+        );
+        let targets: Vec<Target> = transcript()
+            .into_iter()
+            .map(|entry| entry.target)
+            .filter(|target| *target != Target::Custom)
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                Target::Stdout,
+                Target::Stderr,
+                Target::Stderr,
+                Target::Stdout
+            ]
+        );
+        let info = Metadata::builder().level(log::Level::Info).build();
+        assert!(!CarlogLogger::new(LevelFilter::Warn).enabled(&info));
+        assert!(log::logger().enabled(&info));
+        assert!(!log::log_enabled!(log::Level::Trace));
+    }
+}
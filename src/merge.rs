@@ -0,0 +1,238 @@
+//! Chronological replay of the records of several sources, i.e. per-job buffers.
+
+use crate::{Multiplexer, Record, Status};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::io;
+use std::time::SystemTime;
+
+/// Merge records of several sources, each already in order, by their timestamp.
+///
+/// The merge is stable, ties are ordered by:
+/// 1. The timestamp. A record without timestamp takes the one of the previous record of its
+///    source, so it stays after it, or sorts first if there is none.
+/// 2. The sequence number, records without one first.
+/// 3. The position of the source in `sources`.
+///
+/// The records of a source are never reordered.
+///
+/// ## Example
+/// ```
+/// use carlog::Record;
+/// use std::time::{Duration, SystemTime};
+///
+/// let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+/// let frontend = vec![Record::new("Compiling", "app").timestamp(at(1))];
+/// let db = vec![Record::new("Running", "migrations").timestamp(at(0))];
+/// let sources = vec![frontend.into_iter(), db.into_iter()];
+/// let merged: Vec<_> = carlog::merge::by_timestamp(sources).collect();
+/// assert_eq!(merged[0].status(), "Running");
+/// ```
+pub fn by_timestamp<I>(sources: Vec<I>) -> ByTimestamp<I>
+where
+    I: Iterator<Item = Record>,
+{
+    let mut merge = ByTimestamp {
+        heads: BinaryHeap::new(),
+        last: vec![None; sources.len()],
+        sources,
+    };
+    for source in 0..merge.sources.len() {
+        merge.advance(source);
+    }
+    merge
+}
+
+/// Print the merged records of labelled sources through a multiplexer, see [`by_timestamp`].
+///
+/// * `mux`: The multiplexer where the records are printed, each source as a job.
+/// * `sources`: The label and the records of every source.
+/// * `theme`: The status every record is printed with, the message is printed after a space.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::{Multiplexer, Record};
+///
+/// let mux = Multiplexer::new();
+/// let sources = vec![("db", vec![Record::new("Running", "migrations")].into_iter())];
+/// carlog::merge::print_merged(&mux, sources, |record| {
+///     Status::new().bold().justify().color(CargoColor::Cyan).status(record.status())
+/// })
+/// .unwrap();
+/// ```
+pub fn print_merged<S, I, F>(
+    mux: &Multiplexer,
+    sources: Vec<(S, I)>,
+    mut theme: F,
+) -> io::Result<()>
+where
+    S: AsRef<str>,
+    I: Iterator<Item = Record>,
+    F: FnMut(&Record) -> Status,
+{
+    let (jobs, sources): (Vec<_>, Vec<_>) = sources
+        .into_iter()
+        .map(|(label, records)| (mux.job(label), records))
+        .unzip();
+    let mut merge = by_timestamp(sources);
+    while let Some((source, record)) = merge.next_with_source() {
+        jobs[source].print(theme(&record), format!(" {}", record.message()))?;
+    }
+    Ok(())
+}
+
+/// The iterator returned by [`by_timestamp`].
+pub struct ByTimestamp<I> {
+    heads: BinaryHeap<Reverse<Head>>,
+    /// The timestamp of the last record of every source.
+    last: Vec<Option<SystemTime>>,
+    sources: Vec<I>,
+}
+
+impl<I> ByTimestamp<I>
+where
+    I: Iterator<Item = Record>,
+{
+    /// The next record and the position of its source.
+    pub fn next_with_source(&mut self) -> Option<(usize, Record)> {
+        let Reverse(head) = self.heads.pop()?;
+        self.advance(head.source);
+        Some((head.source, head.record))
+    }
+
+    /// Queue the next record of a source.
+    fn advance(&mut self, source: usize) {
+        if let Some(record) = self.sources[source].next() {
+            let time = record.time().or(self.last[source]);
+            self.last[source] = time;
+            self.heads.push(Reverse(Head {
+                time,
+                source,
+                record,
+            }));
+        }
+    }
+}
+
+impl<I> Iterator for ByTimestamp<I>
+where
+    I: Iterator<Item = Record>,
+{
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Record> {
+        self.next_with_source().map(|(_, record)| record)
+    }
+}
+
+/// The next record of a source, ordered by its [merge key](by_timestamp).
+struct Head {
+    time: Option<SystemTime>,
+    source: usize,
+    record: Record,
+}
+
+impl Head {
+    fn key(&self) -> (Option<SystemTime>, Option<u64>, usize) {
+        (self.time, self.record.sequence_number(), self.source)
+    }
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Head {}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{by_timestamp, print_merged};
+    use crate::test::{force_colors, global_lock, Shared};
+    use crate::{CargoColor, Multiplexer, Record, Status};
+    use std::time::{Duration, SystemTime};
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn sources() -> Vec<std::vec::IntoIter<Record>> {
+        vec![
+            vec![
+                Record::new("a", "1").timestamp(at(1)),
+                Record::new("a", "untimed").sequence(7),
+                Record::new("a", "3").timestamp(at(3)).sequence(2),
+            ],
+            vec![
+                Record::new("b", "untimed"),
+                Record::new("b", "1").timestamp(at(1)),
+                Record::new("b", "3").timestamp(at(3)).sequence(1),
+            ],
+            vec![
+                Record::new("c", "0").timestamp(at(0)),
+                Record::new("c", "1").timestamp(at(1)).sequence(3),
+                Record::new("c", "not sorted").timestamp(at(0)),
+            ],
+        ]
+        .into_iter()
+        .map(Vec::into_iter)
+        .collect()
+    }
+
+    #[test]
+    fn test_by_timestamp() {
+        let merged: Vec<String> = by_timestamp(sources())
+            .map(|record| format!("{} {}", record.status(), record.message()))
+            .collect();
+        assert_eq!(
+            merged,
+            [
+                "b untimed",
+                "c 0",
+                "a 1",
+                "b 1",
+                "c 1",
+                "c not sorted",
+                "a untimed",
+                "b 3",
+                "a 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_print_merged() {
+        let _lock = global_lock();
+        force_colors();
+        let output = Shared::default();
+        let mux = Multiplexer::with_stream(output.clone());
+        let sources: Vec<_> = ["api", "db", "web"].into_iter().zip(sources()).collect();
+        print_merged(&mux, sources, |record| {
+            Status::new()
+                .color(CargoColor::White)
+                .status(record.status())
+        })
+        .unwrap();
+        let output = output.text();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert_eq!(
+            lines[0],
+            "\u{1b}[32mdb\u{1b}[0m  \u{1b}[2m|\u{1b}[0m \u{1b}[37mb\u{1b}[0m untimed"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Information about the application using carlog.
+
+use crate::Record;
+use std::env;
+
+/// Name, version and build information of the application that is logging.
+///
+/// [`metadata_from_cargo!`](crate::metadata_from_cargo) captures it at compile time and
+/// [`banner`](crate::banner) prints it.
+///
+/// ## Example
+/// ```
+/// use carlog::Metadata;
+///
+/// let metadata = Metadata::new("mytool", "1.2.0").git_hash("3f2a91c").extra("target", "x86_64");
+/// let from_cargo = Metadata::from_env();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// The name of the application.
+    pub name: String,
+
+    /// The version of the application.
+    pub version: String,
+
+    /// The commit the application was built from.
+    pub git_hash: Option<String>,
+
+    /// When the application was built.
+    pub build_date: Option<String>,
+
+    /// Other build information, i.e. the target or the enabled features, as key-value pairs.
+    pub extra: Vec<(String, String)>,
+}
+
+impl Metadata {
+    /// Create metadata for the application `name` at `version`.
+    pub fn new<N, V>(name: N, version: V) -> Self
+    where
+        N: AsRef<str>,
+        V: AsRef<str>,
+    {
+        Self {
+            name: name.as_ref().to_string(),
+            version: version.as_ref().to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Set the commit the application was built from.
+    pub fn git_hash<S>(mut self, git_hash: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.git_hash = Some(git_hash.as_ref().to_string());
+        self
+    }
+
+    /// Set when the application was built.
+    pub fn build_date<S>(mut self, build_date: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.build_date = Some(build_date.as_ref().to_string());
+        self
+    }
+
+    /// Add a key-value pair of build information.
+    pub fn extra<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        self.extra
+            .push((key.as_ref().to_string(), value.as_ref().to_string()));
+        self
+    }
+
+    /// The build information as key-value pairs, starting with the commit and the build date.
+    pub(crate) fn details(&self) -> Vec<(&str, &str)> {
+        let known = [
+            ("git hash", self.git_hash.as_deref()),
+            ("build date", self.build_date.as_deref()),
+        ];
+        let known = known
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, value?)));
+        let extra = self.extra.iter().map(|(k, v)| (k.as_str(), v.as_str()));
+        known.chain(extra).collect()
+    }
+
+    /// The metadata as a single record, the build information being its fields, i.e. to write it
+    /// to a structured log with [`wire::encode`](crate::wire).
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::Metadata;
+    ///
+    /// let record = Metadata::new("mytool", "1.2.0").git_hash("3f2a91c").record();
+    /// assert_eq!(record.fields().collect::<Vec<_>>(), [("git_hash", "3f2a91c")]);
+    /// ```
+    pub fn record(&self) -> Record {
+        let mut record = Record::new(&self.name, &self.version);
+        for (key, value) in self.details() {
+            record = record.field(key.replace(' ', "_"), value);
+        }
+        record
+    }
+
+    /// Read the metadata from the `CARGO_PKG_NAME` and `CARGO_PKG_VERSION` environment variables,
+    /// which cargo sets when running a binary with `cargo run`.
+    ///
+    /// Missing variables fall back to the executable name and an empty version.
+    pub fn from_env() -> Self {
+        let name = env::var("CARGO_PKG_NAME").unwrap_or_else(|_| {
+            env::current_exe()
+                .ok()
+                .and_then(|exe| exe.file_stem().map(|s| s.to_string_lossy().into_owned()))
+                .unwrap_or_default()
+        });
+        let version = env::var("CARGO_PKG_VERSION").unwrap_or_default();
+        Self {
+            name,
+            version,
+            ..Self::default()
+        }
+    }
+}
+
+/// The [`Metadata`] of the crate invoking the macro, captured at compile time.
+///
+/// The name and version come from `CARGO_PKG_NAME` and `CARGO_PKG_VERSION`. The commit and the
+/// build date are read from the `GIT_HASH` and `BUILD_DATE` variables, which a build script can
+/// set with `cargo:rustc-env=GIT_HASH=...`, and are `None` otherwise. The package description,
+/// authors and repository are kept as extra information when not empty.
+///
+/// ## Example
+/// ```
+/// let metadata = carlog::metadata_from_cargo!();
+/// assert_eq!(metadata.name, "carlog");
+/// ```
+#[macro_export]
+macro_rules! metadata_from_cargo {
+    () => {{
+        let mut metadata = $crate::Metadata::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+        metadata.git_hash = option_env!("GIT_HASH").map(str::to_string);
+        metadata.build_date = option_env!("BUILD_DATE").map(str::to_string);
+        for (key, value) in [
+            ("description", env!("CARGO_PKG_DESCRIPTION")),
+            ("authors", env!("CARGO_PKG_AUTHORS")),
+            ("repository", env!("CARGO_PKG_REPOSITORY")),
+        ] {
+            if !value.is_empty() {
+                metadata = metadata.extra(key, value);
+            }
+        }
+        metadata
+    }};
+}
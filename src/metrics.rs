@@ -0,0 +1,130 @@
+//! Counters of the messages printed, for export to a metrics system.
+//!
+//! Enabled with the `metrics` feature. The message macros are counted by kind and by level, and
+//! the messages that weren't printed because of the verbosity are counted as suppressed. These
+//! counters are atomics, so counting a message by kind and level never takes a lock.
+//!
+//! The breakdown by [target](crate::target_scope) is opt-in, see [`count_targets`]: it takes a
+//! lock for every message with a target, and allocates the first time a target is counted.
+//!
+//! ## Example
+//! ```
+//! let metrics = carlog::metrics::snapshot();
+//! println!("carlog_messages_total{{kind=\"warning\"}} {}", metrics.warning);
+//! ```
+
+use crate::Level;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+const LEVELS: [Level; 5] = [
+    Level::Trace,
+    Level::Debug,
+    Level::Info,
+    Level::Warning,
+    Level::Error,
+];
+
+static INFO: AtomicU64 = AtomicU64::new(0);
+static OK: AtomicU64 = AtomicU64::new(0);
+static WARNING: AtomicU64 = AtomicU64::new(0);
+static ERROR: AtomicU64 = AtomicU64::new(0);
+static SUPPRESSED: AtomicU64 = AtomicU64::new(0);
+static BY_LEVEL: [AtomicU64; 5] = [const { AtomicU64::new(0) }; 5];
+static COUNT_TARGETS: AtomicBool = AtomicBool::new(false);
+static TARGETS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+/// The counters at a point in time, see [`snapshot`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// The number of `carlog_info!` messages.
+    pub info: u64,
+
+    /// The number of `carlog_ok!` messages.
+    pub ok: u64,
+
+    /// The number of `carlog_warning!` messages.
+    pub warning: u64,
+
+    /// The number of `carlog_error!` messages.
+    pub error: u64,
+
+    /// The number of messages that weren't printed, i.e. fresh lines below the verbosity.
+    pub suppressed: u64,
+
+    /// The number of messages of every level, i.e. the `carlog_debug!` ones for `Debug`.
+    pub levels: BTreeMap<Level, u64>,
+
+    /// The number of messages by target, when [counted](count_targets). The messages without a
+    /// target aren't in it.
+    pub targets: BTreeMap<String, u64>,
+}
+
+/// The current value of the counters.
+pub fn snapshot() -> MetricsSnapshot {
+    MetricsSnapshot {
+        info: INFO.load(Ordering::Relaxed),
+        ok: OK.load(Ordering::Relaxed),
+        warning: WARNING.load(Ordering::Relaxed),
+        error: ERROR.load(Ordering::Relaxed),
+        suppressed: SUPPRESSED.load(Ordering::Relaxed),
+        levels: LEVELS
+            .iter()
+            .map(|&level| (level, BY_LEVEL[level as usize].load(Ordering::Relaxed)))
+            .collect(),
+        targets: TARGETS.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+    }
+}
+
+/// Reset every counter to zero.
+pub fn reset() {
+    for counter in [&INFO, &OK, &WARNING, &ERROR, &SUPPRESSED] {
+        counter.store(0, Ordering::Relaxed);
+    }
+    for counter in &BY_LEVEL {
+        counter.store(0, Ordering::Relaxed);
+    }
+    TARGETS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Count the messages by target too, off by default.
+///
+/// Unlike the other counters, the ones of the targets are behind a lock, which every message with
+/// a target takes.
+pub fn count_targets(count: bool) {
+    COUNT_TARGETS.store(count, Ordering::Relaxed);
+}
+
+/// Whether the messages are counted by target, see [`count_targets`].
+pub(crate) fn counting_targets() -> bool {
+    COUNT_TARGETS.load(Ordering::Relaxed)
+}
+
+pub(crate) fn record(kind: &str, level: Level, target: Option<&str>) {
+    let counter = match kind {
+        "info" => &INFO,
+        "ok" => &OK,
+        "warning" => &WARNING,
+        _ => &ERROR,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+    BY_LEVEL[level as usize].fetch_add(1, Ordering::Relaxed);
+    if !COUNT_TARGETS.load(Ordering::Relaxed) {
+        return;
+    }
+    let Some(target) = target else {
+        return;
+    };
+    let mut targets = TARGETS.lock().unwrap_or_else(|e| e.into_inner());
+    match targets.get_mut(target) {
+        Some(count) => *count += 1,
+        None => {
+            targets.insert(target.to_string(), 1);
+        }
+    }
+}
+
+pub(crate) fn record_suppressed() {
+    SUPPRESSED.fetch_add(1, Ordering::Relaxed);
+}
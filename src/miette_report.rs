@@ -0,0 +1,229 @@
+//! Rendering of `miette` diagnostics in the style of the crate.
+//!
+//! Enabled with the `miette` feature.
+
+use crate::diagnostic::{self, Span};
+use crate::{color, CargoColor, CarlogStream, Error, Format, Status};
+use colored::Colorize;
+use miette::{Diagnostic, LabeledSpan, Severity, SourceCode};
+
+/// Print a `miette` diagnostic like rustc would.
+///
+/// * The severity picks the status: `error`, `warning` or `note` for advice.
+/// * The code is shown next to the status, i.e. `error[E0425]: ...`.
+/// * Every label is shown under the source line of its span, with the span underlined.
+/// * The help is shown as a `help: ` line.
+/// * Related diagnostics are shown after it, indented.
+///
+/// With [`Format::Short`] only the first line is printed, located at the first label, i.e.
+/// `src/main.rs:2:18: error[E0308]: mismatched types`.
+///
+/// * `diagnostic`: The diagnostic to print.
+/// * `stream`: The stream where the diagnostic is printed.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+///
+/// let diagnostic = miette::MietteDiagnostic::new("unresolved import").with_code("E0432");
+/// let mut output = Vec::<u8>::new();
+/// carlog::report_diagnostic(&diagnostic, CarlogStream::Custom(&mut output)).unwrap();
+/// ```
+pub fn report_diagnostic(
+    diagnostic: &dyn Diagnostic,
+    mut stream: CarlogStream<'_>,
+) -> Result<(), Error> {
+    if crate::format() == Format::Short {
+        let (status, _) = render(diagnostic, "");
+        let status = match first_location(diagnostic) {
+            Some((name, line, column)) => status.location(name, line, column),
+            None => status,
+        };
+        return status.print_stream(&mut stream, format!(": {}", diagnostic));
+    }
+    let (status, body) = render(diagnostic, "");
+    status.print_stream(&mut stream, body)
+}
+
+/// The file, line and column of the first label of a diagnostic, all starting at 1.
+fn first_location(diagnostic: &dyn Diagnostic) -> Option<(String, u32, u32)> {
+    let source = diagnostic.source_code()?;
+    let (span, _) = label_span(source, &diagnostic.labels()?.next()?)?;
+    Some((span.path, span.line, span.column))
+}
+
+/// The status and the rest of the report of a diagnostic, with every line after the first
+/// prefixed with `indent`.
+fn render(diagnostic: &dyn Diagnostic, indent: &str) -> (Status, String) {
+    let (color, severity) = match diagnostic.severity().unwrap_or(Severity::Error) {
+        Severity::Error => (CargoColor::BrightRed, "error"),
+        Severity::Warning => (CargoColor::BrightYellow, "warning"),
+        Severity::Advice => (CargoColor::Cyan, "note"),
+    };
+    let status = match diagnostic.code() {
+        Some(code) => format!("{}[{}]", severity, code),
+        None => severity.to_string(),
+    };
+    let mut body = format!(": {}", diagnostic);
+    if let (Some(source), Some(labels)) = (diagnostic.source_code(), diagnostic.labels()) {
+        for label in labels {
+            snippet(&mut body, indent, source, &label, color);
+        }
+    }
+    if let Some(help) = diagnostic.help() {
+        body.push_str(&format!(
+            "\n{}{}: {}",
+            indent,
+            color::paint("help".bold()),
+            help
+        ));
+    }
+    if let Some(related) = diagnostic.related() {
+        let nested = format!("{}    ", indent);
+        for related in related {
+            let (status, rest) = render(related, &nested);
+            let mut line = Vec::<u8>::new();
+            // Rendering into memory can't fail.
+            let _ = status.print(&mut line, rest);
+            body.push_str(&format!("\n{}", nested));
+            body.push_str(String::from_utf8_lossy(&line).trim_end_matches('\n'));
+        }
+    }
+    (Status::plain().color(color).status(status), body)
+}
+
+/// Append the lines of source of a label with its span underlined, like the ones of a
+/// [`diagnostic`](crate::diagnostic).
+fn snippet(
+    body: &mut String,
+    indent: &str,
+    source: &dyn SourceCode,
+    label: &LabeledSpan,
+    color: CargoColor,
+) {
+    if let Some((span, lines)) = label_span(source, label) {
+        diagnostic::write_span(body, indent, &span, Some(&lines), label.label(), color);
+    }
+}
+
+/// The span of a label, with its columns and length in characters instead of bytes, and the
+/// source from the start of its line.
+fn label_span(source: &dyn SourceCode, label: &LabeledSpan) -> Option<(Span, String)> {
+    // The span alone gives its line, the context gives the whole lines around it.
+    let contents = source.read_span(label.inner(), 0, 0).ok()?;
+    let context = source.read_span(label.inner(), 1, 1).ok()?;
+    let data = std::str::from_utf8(context.data()).ok()?;
+    let start = label.offset().checked_sub(context.span().offset())?;
+    let end = (start + label.len()).min(data.len());
+    let (before, spanned) = (data.get(..start)?, data.get(start..end)?);
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    let span = Span {
+        path: contents.name().unwrap_or("<source>").to_string(),
+        line: contents.line() as u32 + 1,
+        column: before[line_start..].chars().count() as u32 + 1,
+        len: spanned.chars().count(),
+    };
+    Some((span, data[line_start..].to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::report_diagnostic;
+    use crate::test::{force_colors, global_lock};
+    use crate::{set_format, CarlogStream, Format};
+    use miette::{Diagnostic, NamedSource, SourceSpan};
+    use thiserror::Error;
+
+    #[derive(Debug, Diagnostic, Error)]
+    #[error("mismatched types")]
+    #[diagnostic(code(E0308), help("change the type of `x` to `u32`"))]
+    struct Mismatch {
+        #[source_code]
+        src: NamedSource<String>,
+        #[label("expected `u32`")]
+        span: SourceSpan,
+        #[related]
+        related: Vec<Unused>,
+    }
+
+    #[derive(Debug, Diagnostic, Error)]
+    #[error("unused variable")]
+    #[diagnostic(severity(Warning))]
+    struct Unused;
+
+    fn strip(output: Vec<u8>) -> String {
+        crate::fmt::strip_ansi(&String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_report() {
+        let _lock = global_lock();
+        force_colors();
+        let source = "fn main() {\n    let x: u32 = \"7\";\n}\n".to_string();
+        let diagnostic = Mismatch {
+            src: NamedSource::new("src/main.rs", source),
+            span: (29, 3).into(),
+            related: vec![Unused],
+        };
+        let mut output = Vec::<u8>::new();
+        report_diagnostic(&diagnostic, CarlogStream::Custom(&mut output)).unwrap();
+        assert!(output.starts_with(b"\x1b[91merror[E0308]\x1b[0m: mismatched types\n"));
+        assert_eq!(
+            strip(output),
+            "error[E0308]: mismatched types\n \
+             --> src/main.rs:2:18\n  \
+             |\n\
+             2 |     let x: u32 = \"7\";\n  \
+             |                  ^^^ expected `u32`\n\
+             help: change the type of `x` to `u32`\n    \
+             warning: unused variable\n"
+        );
+    }
+
+    #[test]
+    fn test_report_columns() {
+        let _lock = global_lock();
+        force_colors();
+        // The span starts 17 characters, 18 bytes, after the start of its line.
+        let source = "fn main() {\n\tlet caf\u{e9}: u32 = \"7\";\n}\n".to_string();
+        let diagnostic = Mismatch {
+            src: NamedSource::new("src/main.rs", source),
+            span: (30, 3).into(),
+            related: Vec::new(),
+        };
+        let mut output = Vec::<u8>::new();
+        report_diagnostic(&diagnostic, CarlogStream::Custom(&mut output)).unwrap();
+        assert_eq!(
+            strip(output),
+            "error[E0308]: mismatched types\n \
+             --> src/main.rs:2:18\n  \
+             |\n\
+             2 |     let caf\u{e9}: u32 = \"7\";\n  \
+             |                     ^^^ expected `u32`\n\
+             help: change the type of `x` to `u32`\n"
+        );
+    }
+
+    #[test]
+    fn test_short_report() {
+        let _lock = global_lock();
+        force_colors();
+        set_format(Format::Short);
+        let diagnostic = Mismatch {
+            src: NamedSource::new(
+                "src/main.rs",
+                "fn main() {\n    let x: u32 = \"7\";\n}\n".into(),
+            ),
+            span: (29, 3).into(),
+            related: vec![Unused],
+        };
+        let mut output = Vec::<u8>::new();
+        report_diagnostic(&diagnostic, CarlogStream::Custom(&mut output)).unwrap();
+        report_diagnostic(&Unused, CarlogStream::Custom(&mut output)).unwrap();
+        set_format(Format::Human);
+        assert_eq!(
+            strip(output),
+            "src/main.rs:2:18: error[E0308]: mismatched types\nwarning: unused variable\n"
+        );
+    }
+}
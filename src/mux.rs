@@ -0,0 +1,219 @@
+//! Labelled output of concurrent jobs.
+
+use crate::attributes::Attributes;
+use crate::{color, fmt, output, theme, CargoColor, Loggable, Status};
+use colored::Colorize;
+use std::io::{self, stdout, Write};
+use std::sync::{Arc, Mutex};
+
+/// The colors assigned to the jobs, in registration order.
+const PALETTE: [CargoColor; 4] = [
+    CargoColor::Cyan,
+    CargoColor::Green,
+    CargoColor::BrightYellow,
+    CargoColor::White,
+];
+
+struct Inner {
+    names: Vec<String>,
+    stream: Box<dyn Write + Send>,
+}
+
+/// Tags the lines of concurrent jobs with a colored label, like `docker-compose` does.
+///
+/// Every [job](Self::job) gets the next color of a rotating palette and its label is padded to the
+/// longest registered name, so the messages of interleaved lines stay aligned:
+/// ```text
+/// frontend |    Compiling app v0.1.0
+/// db       |      Running migrations
+/// ```
+///
+/// ## Example
+/// ```
+/// use carlog::Multiplexer;
+///
+/// let mux = Multiplexer::new();
+/// let frontend = mux.job("frontend");
+/// let db = mux.job("db");
+/// std::thread::spawn(move || frontend.info("Compiling", "app v0.1.0").unwrap());
+/// db.info("Running", "migrations").unwrap();
+/// ```
+#[derive(Clone)]
+pub struct Multiplexer {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Default for Multiplexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Multiplexer {
+    /// Create a multiplexer printing to stdout.
+    pub fn new() -> Self {
+        Self::with_stream(stdout())
+    }
+
+    /// Create a multiplexer printing to the specified stream.
+    pub fn with_stream<W>(stream: W) -> Self
+    where
+        W: Write + Send + 'static,
+    {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                names: Vec::new(),
+                stream: Box::new(stream),
+            })),
+        }
+    }
+
+    /// Register a job, returning the handle its lines are printed with.
+    pub fn job<S>(&self, name: S) -> JobHandle
+    where
+        S: AsRef<str>,
+    {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.names.push(name.as_ref().to_string());
+        JobHandle {
+            inner: Arc::clone(&self.inner),
+            index: inner.names.len() - 1,
+        }
+    }
+}
+
+/// Prints the lines of a job registered in a [`Multiplexer`].
+#[derive(Clone)]
+pub struct JobHandle {
+    inner: Arc<Mutex<Inner>>,
+    index: usize,
+}
+
+impl JobHandle {
+    /// The color of the job label.
+    pub fn color(&self) -> CargoColor {
+        PALETTE[self.index % PALETTE.len()]
+    }
+
+    /// Print the status after the label of the job.
+    ///
+    /// `status`: The status printed after the label.
+    /// `msg`: The message to be printed alongside the status.
+    pub fn print<S>(&self, status: Status, msg: S) -> io::Result<()>
+    where
+        S: Loggable,
+    {
+        let _output = output::lock();
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let width = inner.names.iter().map(|name| fmt::display_width(name));
+        let width = width.max().unwrap_or(0);
+        let name = &inner.names[self.index];
+        let label = Status::color_str(self.color(), Attributes::NONE, name);
+        let padding = " ".repeat(width - fmt::display_width(name));
+        let line = format!(
+            "{}{} {} {}",
+            label,
+            padding,
+            color::paint("|".dimmed()),
+            status.line(&msg.to_log_string())
+        );
+        // A single write keeps the line whole while other jobs print.
+        inner.stream.write_all(line.as_bytes())?;
+        inner.stream.flush()
+    }
+
+    /// Print an info like cargo message, see `carlog_info!`.
+    pub fn info<S, M>(&self, status: S, msg: M) -> io::Result<()>
+    where
+        S: AsRef<str>,
+        M: Loggable,
+    {
+        let status = Status::plain()
+            .bold()
+            .justify()
+            .color(theme().info)
+            .status(status);
+        self.print(status, format!(" {}", msg.to_log_string()))
+    }
+
+    /// Print an ok like cargo message, see `carlog_ok!`.
+    pub fn ok<S, M>(&self, status: S, msg: M) -> io::Result<()>
+    where
+        S: AsRef<str>,
+        M: Loggable,
+    {
+        let status = Status::plain()
+            .bold()
+            .justify()
+            .color(theme().ok)
+            .status(status);
+        self.print(status, format!(" {}", msg.to_log_string()))
+    }
+
+    /// Print a warning like cargo message, see `carlog_warning!`.
+    pub fn warning<M>(&self, msg: M) -> io::Result<()>
+    where
+        M: Loggable,
+    {
+        let status = Status::warning_with_label("warning");
+        self.print(status, format!(": {}", msg.to_log_string()))
+    }
+
+    /// Print an error like cargo message, see `carlog_error!`.
+    pub fn error<M>(&self, msg: M) -> io::Result<()>
+    where
+        M: Loggable,
+    {
+        let status = Status::error_with_label("error");
+        self.print(status, format!(": {}", msg.to_log_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Multiplexer;
+    use crate::test::{force_colors, global_lock, Shared};
+    use crate::CargoColor;
+    use std::thread;
+
+    #[test]
+    fn test_interleaved_jobs() {
+        let _lock = global_lock();
+        force_colors();
+        let output = Shared::default();
+        let mux = Multiplexer::with_stream(output.clone());
+        let jobs = [mux.job("frontend"), mux.job("db"), mux.job("api")];
+        assert_eq!(
+            jobs.iter().map(|job| job.color()).collect::<Vec<_>>(),
+            [
+                CargoColor::Cyan,
+                CargoColor::Green,
+                CargoColor::BrightYellow
+            ]
+        );
+        let threads: Vec<_> = jobs
+            .into_iter()
+            .map(|job| {
+                thread::spawn(move || {
+                    for n in 0..20 {
+                        job.info("Compiling", format!("unit{}", n)).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        let output = output.text();
+        let suffix = " \u{1b}[2m|\u{1b}[0m    \u{1b}[1;36mCompiling\u{1b}[0m unit";
+        let prefixes = [
+            format!("\u{1b}[36mfrontend\u{1b}[0m{}", suffix),
+            format!("\u{1b}[32mdb\u{1b}[0m      {}", suffix),
+            format!("\u{1b}[93mapi\u{1b}[0m     {}", suffix),
+        ];
+        assert_eq!(output.lines().count(), 60);
+        for prefix in &prefixes {
+            assert_eq!(output.lines().filter(|l| l.starts_with(prefix)).count(), 20);
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! The output lock, which keeps multi-line output whole when several threads print.
+
+use crate::{
+    emit_block_to, record, stream, theme, BlockStyle, CarlogStream, Error, Level, Loggable, Record,
+    Status, Target,
+};
+use std::cell::Cell;
+use std::io::{self, stderr, stdout, StderrLock, StdoutLock, Write};
+use std::marker::PhantomData;
+use std::sync::{Condvar, Mutex};
+
+static LOCKED: Mutex<bool> = Mutex::new(false);
+static RELEASED: Condvar = Condvar::new();
+
+thread_local! {
+    /// How many times the current thread holds the output lock.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The output lock held by the current thread, released when dropped.
+pub(crate) struct OutputGuard {
+    /// Whether the lock was taken, see [`lock`].
+    held: bool,
+    /// The lock belongs to the thread that took it.
+    _thread: PhantomData<*const ()>,
+}
+
+/// Take the output lock, waiting for other threads to release it.
+///
+/// The lock is reentrant: a thread holding it can take it again, i.e. to print a status inside a
+/// block of [`atomic`]. A thread whose thread-locals were already destroyed, i.e. printing from
+/// the destructor of one, prints without the lock rather than failing.
+pub(crate) fn lock() -> OutputGuard {
+    let held = DEPTH
+        .try_with(|depth| {
+            if depth.get() == 0 {
+                let mut locked = LOCKED.lock().unwrap_or_else(|e| e.into_inner());
+                while *locked {
+                    locked = RELEASED.wait(locked).unwrap_or_else(|e| e.into_inner());
+                }
+                *locked = true;
+            }
+            depth.set(depth.get() + 1);
+        })
+        .is_ok();
+    OutputGuard {
+        held,
+        _thread: PhantomData,
+    }
+}
+
+impl Drop for OutputGuard {
+    fn drop(&mut self) {
+        if !self.held {
+            return;
+        }
+        let released = DEPTH
+            .try_with(|depth| {
+                depth.set(depth.get() - 1);
+                depth.get() == 0
+            })
+            .unwrap_or(true);
+        if released {
+            *LOCKED.lock().unwrap_or_else(|e| e.into_inner()) = false;
+            RELEASED.notify_one();
+        }
+    }
+}
+
+/// The stream of a block of [`atomic`], where any number of statuses and blocks can be written
+/// without other threads printing in between.
+///
+/// Every [`Write`] is a block writer.
+pub trait BlockWriter: Write {
+    /// Print a status and its message, see [`Status::print`].
+    fn print(&mut self, status: Status, msg: &str) -> Result<(), Error> {
+        status.print(self, msg)
+    }
+
+    /// Print a status followed by a block of text, see [`emit_block_to`].
+    fn emit_block(&mut self, status: &Status, text: &str, style: BlockStyle) -> io::Result<()> {
+        emit_block_to(self, status, text, style)
+    }
+}
+
+impl<W> BlockWriter for W where W: Write + ?Sized {}
+
+/// Print several lines to stdout without other threads printing in between.
+///
+/// See [`atomic_to`].
+pub fn atomic<F, R>(block: F) -> R
+where
+    F: FnOnce(&mut dyn BlockWriter) -> R,
+{
+    let _output = lock();
+    block(&mut stdout().lock())
+}
+
+/// Print several lines to the specified stream without other threads printing in between.
+///
+/// The statuses printed by other threads wait until `block` returns. The thread of the block can
+/// keep printing, i.e. with `carlog_info!`, which writes to its own stream as usual. The block
+/// shouldn't wait for other threads that print, and `stream` shouldn't be a stream already locked
+/// by the caller, i.e. a `StdoutLock`, which would deadlock.
+///
+/// * `stream`: The stream where the block will be written.
+/// * `block`: Prints the lines through the [`BlockWriter`] it's given.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+///
+/// let mut output = Vec::<u8>::new();
+/// carlog::atomic_to(&mut output, |out| {
+///     let status = || Status::new().bold().justify().color(CargoColor::Red);
+///     out.print(status().status("Failed"), " unit 1")?;
+///     out.print(status().status("Failed"), " unit 2")
+/// })
+/// .unwrap();
+/// ```
+pub fn atomic_to<W, F, R>(mut stream: W, block: F) -> R
+where
+    W: Write,
+    F: FnOnce(&mut dyn BlockWriter) -> R,
+{
+    let _output = lock();
+    block(&mut stream)
+}
+
+/// A group of lines printed to one stream without other threads printing in between, flushed
+/// once when the session is dropped.
+///
+/// The session holds the output lock of the crate, like [`atomic_to`], and the lock of stdout or
+/// stderr when it prints there, so `println!` from other threads waits too. The messages are
+/// printed like the macros print them: counted, deduplicated, in the structured formats and to
+/// their [routes](crate::route_target), if any.
+///
+/// Statuses printed from other threads wait until the session is dropped. The thread of the
+/// session shouldn't wait for them meanwhile, which would deadlock.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::Session;
+///
+/// let mut output = Vec::<u8>::new();
+/// let mut session = Session::new(CarlogStream::Custom(&mut output));
+/// for member in ["carlog", "carlog-macros"] {
+///     session.info("Checking", member).unwrap();
+/// }
+/// session.warning("unused import: `std::io`").unwrap();
+/// session.ok("Finished", "dev [unoptimized] target(s)").unwrap();
+/// ```
+pub struct Session<'a> {
+    stream: CarlogStream<'a>,
+    _stdout: Option<StdoutLock<'static>>,
+    _stderr: Option<StderrLock<'static>>,
+    _output: OutputGuard,
+}
+
+impl<'a> Session<'a> {
+    /// Start a session printing to `stream`, waiting for other threads to finish their lines.
+    pub fn new(stream: CarlogStream<'a>) -> Self {
+        let output = lock();
+        let target = stream.target();
+        Self {
+            _stdout: (target == Target::Stdout).then(|| stdout().lock()),
+            _stderr: (target == Target::Stderr).then(|| stderr().lock()),
+            stream,
+            _output: output,
+        }
+    }
+
+    /// Print a status and its message, see [`Status::print_stream`].
+    pub fn status<S>(&mut self, status: &Status, msg: S) -> Result<(), Error>
+    where
+        S: Loggable,
+    {
+        stream::deferring_flush(|| status.print_stream(&mut self.stream, msg))
+    }
+
+    /// Print an info message, like `carlog_info!`.
+    pub fn info<S, M>(&mut self, status: S, msg: M) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        M: AsRef<str>,
+    {
+        self.record("info", Record::new(status, msg))
+    }
+
+    /// Print an ok message, like `carlog_ok!`.
+    pub fn ok<S, M>(&mut self, status: S, msg: M) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        M: AsRef<str>,
+    {
+        self.record("ok", Record::new(status, msg).color(theme().ok))
+    }
+
+    /// Print a warning, like `carlog_warning!`.
+    pub fn warning<M>(&mut self, msg: M) -> Result<(), Error>
+    where
+        M: AsRef<str>,
+    {
+        self.record("warning", Record::new("warning", msg).level(Level::Warning))
+    }
+
+    /// Print an error, like `carlog_error!`.
+    pub fn error<M>(&mut self, msg: M) -> Result<(), Error>
+    where
+        M: AsRef<str>,
+    {
+        self.record("error", Record::new("error", msg).level(Level::Error))
+    }
+
+    /// Print a record unless it's less severe than the max level, counted as `kind` in the metrics.
+    fn record(&mut self, kind: &str, record: Record) -> Result<(), Error> {
+        if !crate::__enabled(record.severity()) {
+            return Ok(());
+        }
+        stream::deferring_flush(|| record::print_in(kind, &record, &mut self.stream))
+    }
+}
+
+impl Drop for Session<'_> {
+    fn drop(&mut self) {
+        let _ = stream::flush_stream(&mut self.stream);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{atomic_to, BlockWriter, Session};
+    use crate::test::{auto_colors, force_colors, global_lock, Shared};
+    use crate::{settings, CarlogStream, Format, Status};
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    #[test]
+    fn test_no_interleaving() {
+        let _lock = global_lock();
+        let output = Shared::default();
+        let threads: Vec<_> = (0..8)
+            .map(|thread| {
+                let output = output.clone();
+                thread::spawn(move || {
+                    for block in 0..20 {
+                        if thread % 2 == 0 {
+                            Status::plain()
+                                .status("single")
+                                .print(output.clone(), format!(" {}", thread))
+                                .unwrap();
+                            continue;
+                        }
+                        atomic_to(output.clone(), |out: &mut dyn BlockWriter| {
+                            for line in 0..5 {
+                                let status = Status::plain().status("block");
+                                out.print(status, &format!(" {} {} {}", thread, block, line))?;
+                                thread::yield_now();
+                            }
+                            io::Result::Ok(())
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let output = output.text();
+        let output = crate::fmt::strip_ansi(&output);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4 * 20 + 4 * 20 * 5);
+        let mut i = 0;
+        while i < lines.len() {
+            if lines[i].starts_with("single") {
+                i += 1;
+                continue;
+            }
+            let block = lines[i].rsplit_once(' ').unwrap().0;
+            for line in 0..5 {
+                assert_eq!(lines[i + line], format!("{} {}", block, line));
+            }
+            i += 5;
+        }
+    }
+
+    /// Counts the flushes.
+    #[derive(Default)]
+    struct Counting {
+        written: Vec<u8>,
+        flushes: usize,
+    }
+
+    impl Write for Counting {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_session() {
+        let _lock = global_lock();
+        auto_colors();
+        let output = Arc::new(Mutex::new(Counting::default()));
+        let spammer = {
+            let output = Arc::clone(&output);
+            thread::spawn(move || {
+                let status = Status::plain().status("spam");
+                for i in 0..500 {
+                    let mut stream = CarlogStream::from(Arc::clone(&output));
+                    status.print_stream(&mut stream, format!(" {}", i)).unwrap();
+                }
+            })
+        };
+        while output.lock().unwrap().written.is_empty() {
+            thread::yield_now();
+        }
+        let mut session = Session::new(CarlogStream::from(Arc::clone(&output)));
+        for member in ["a", "b", "c"] {
+            session.info("Checking", member).unwrap();
+            thread::yield_now();
+        }
+        session.warning("unused import").unwrap();
+        session.error("could not compile").unwrap();
+        session.ok("Finished", "dev").unwrap();
+        drop(session);
+        spammer.join().unwrap();
+        force_colors();
+
+        let output = String::from_utf8(output.lock().unwrap().written.clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 500 + 6);
+        let start = lines
+            .iter()
+            .position(|line| !line.starts_with("spam"))
+            .unwrap();
+        assert_eq!(
+            lines[start..start + 6],
+            [
+                "    Checking a",
+                "    Checking b",
+                "    Checking c",
+                "warning: unused import",
+                "error: could not compile",
+                "    Finished dev",
+            ]
+        );
+
+        let mut output = Counting::default();
+        let mut session = Session::new(CarlogStream::Custom(&mut output));
+        session.info("Checking", "carlog").unwrap();
+        session.ok("Finished", "dev").unwrap();
+        drop(session);
+        assert_eq!(output.flushes, 1);
+        assert_eq!(
+            String::from_utf8(output.written).unwrap(),
+            "    \u{1b}[1;36mChecking\u{1b}[0m carlog\n    \u{1b}[1;32mFinished\u{1b}[0m dev\n"
+        );
+    }
+    #[test]
+    fn test_session_like_macros() {
+        let _lock = global_lock();
+        force_colors();
+        crate::reset_counts();
+        crate::set_format(Format::GithubActions);
+        let mut output = Vec::<u8>::new();
+        let mut session = Session::new(CarlogStream::Custom(&mut output));
+        session.warning("unused import").unwrap();
+        session.info("Checking", "carlog").unwrap();
+        drop(session);
+        settings::reset();
+        assert_eq!(crate::warning_count(), 1);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "::warning::unused import\n    \u{1b}[1;36mChecking\u{1b}[0m carlog\n"
+        );
+        crate::reset_counts();
+    }
+}
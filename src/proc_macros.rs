@@ -0,0 +1,147 @@
+//! Output of the message macros from procedural macros.
+//!
+//! Enabled with the `proc-macro` feature. Anything printed while a procedural macro is expanded
+//! ends up mixed in the output of the compiler, so when the macros are called from one without a
+//! stream:
+//! * `carlog_warning!` and `carlog_error!` are emitted as compiler diagnostics, when compiled on
+//!   nightly with `--cfg carlog_nightly`, or as plain `warning: ` and `error: ` lines on stderr.
+//! * `carlog_info!` and `carlog_ok!` are only printed to stderr from verbosity 1.
+//!
+//! See [`set_proc_macro_output`] to choose the output explicitly.
+
+extern crate proc_macro;
+
+use crate::verbosity;
+use std::io::{self, stderr, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static OUTPUT: AtomicU8 = AtomicU8::new(ProcMacroOutput::Auto as u8);
+
+/// Where the message macros print when called from a procedural macro.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ProcMacroOutput {
+    /// Use diagnostics or stderr only while a procedural macro is being expanded.
+    #[default]
+    Auto,
+    /// Always emit compiler diagnostics, falling back to stderr on stable or outside of procedural
+    /// macros.
+    Diagnostic,
+    /// Always print plain lines to stderr.
+    Stderr,
+    /// Always print like outside of procedural macros.
+    Disabled,
+}
+
+/// Choose where the message macros print when called from a procedural macro.
+pub fn set_proc_macro_output(output: ProcMacroOutput) {
+    OUTPUT.store(output as u8, Ordering::Relaxed);
+}
+
+/// Where the message macros print when called from a procedural macro.
+pub fn proc_macro_output() -> ProcMacroOutput {
+    match OUTPUT.load(Ordering::Relaxed) {
+        1 => ProcMacroOutput::Diagnostic,
+        2 => ProcMacroOutput::Stderr,
+        3 => ProcMacroOutput::Disabled,
+        _ => ProcMacroOutput::Auto,
+    }
+}
+
+/// Whether the messages are [emitted](emit) instead of printed, so the macros format them first.
+pub(crate) fn active() -> bool {
+    match proc_macro_output() {
+        ProcMacroOutput::Auto => proc_macro::is_available(),
+        ProcMacroOutput::Disabled => false,
+        ProcMacroOutput::Diagnostic | ProcMacroOutput::Stderr => true,
+    }
+}
+
+/// Emit a message according to the [output](set_proc_macro_output), returning whether it was
+/// handled.
+pub(crate) fn emit(status: &str, msg: &str) -> bool {
+    if !active() {
+        return false;
+    }
+    let expanding = proc_macro::is_available();
+    let output = match proc_macro_output() {
+        ProcMacroOutput::Auto | ProcMacroOutput::Diagnostic => ProcMacroOutput::Diagnostic,
+        output => output,
+    };
+    if output == ProcMacroOutput::Diagnostic && expanding && diagnostic(status, msg) {
+        return true;
+    }
+    // Nothing can be done about a failed write from a procedural macro.
+    let _ = emit_to(stderr().lock(), status, msg);
+    true
+}
+
+#[cfg(carlog_nightly)]
+fn diagnostic(status: &str, msg: &str) -> bool {
+    use proc_macro::{Diagnostic, Level};
+
+    let level = match status {
+        "warning" => Level::Warning,
+        "error" => Level::Error,
+        _ => return false,
+    };
+    Diagnostic::new(level, msg).emit();
+    true
+}
+
+#[cfg(not(carlog_nightly))]
+fn diagnostic(_: &str, _: &str) -> bool {
+    false
+}
+
+/// Print a message as a plain line, without colors that would be garbled in the build output.
+fn emit_to<W>(mut stream: W, status: &str, msg: &str) -> io::Result<()>
+where
+    W: Write,
+{
+    match status {
+        "warning" | "error" => writeln!(stream, "{}: {}", status, msg),
+        _ if verbosity() >= 1 => writeln!(stream, "{:>12} {}", status, msg),
+        _ => {
+            crate::record_suppressed();
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{emit, emit_to, proc_macro_output, set_proc_macro_output, ProcMacroOutput};
+    use crate::set_verbosity;
+    use crate::test::global_lock;
+
+    #[test]
+    fn test_plain_lines() {
+        let _lock = global_lock();
+        let mut output = Vec::<u8>::new();
+        emit_to(&mut output, "warning", "unused field").unwrap();
+        emit_to(&mut output, "error", "expected a struct").unwrap();
+        emit_to(&mut output, "Expanding", "derive(Builder)").unwrap();
+        set_verbosity(1);
+        emit_to(&mut output, "Expanding", "derive(Builder)").unwrap();
+        set_verbosity(0);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "warning: unused field\nerror: expected a struct\n   Expanding derive(Builder)\n"
+        );
+    }
+
+    #[test]
+    fn test_output_selection() {
+        let _lock = global_lock();
+        assert_eq!(proc_macro_output(), ProcMacroOutput::Auto);
+        // Tests don't run inside a procedural macro.
+        assert!(!emit("warning", "not expanding"));
+        set_proc_macro_output(ProcMacroOutput::Disabled);
+        assert!(!emit("warning", "disabled"));
+        set_proc_macro_output(ProcMacroOutput::Stderr);
+        assert_eq!(proc_macro_output(), ProcMacroOutput::Stderr);
+        assert!(emit("Expanding", "hidden below verbosity 1"));
+        set_proc_macro_output(ProcMacroOutput::Auto);
+    }
+}
@@ -0,0 +1,276 @@
+//! Interactive prompts.
+//!
+//! Prompts are printed to stderr so they don't end up in piped stdout. In
+//! [non-interactive](set_non_interactive) mode the default answer is taken without asking.
+
+use crate::{CargoColor, Status};
+use std::fmt::Display;
+use std::io::{self, stderr, stdin, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static NON_INTERACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Never wait for input, answering every prompt with its default, i.e. for `--yes` flags or CI.
+///
+/// Prompts without a default fail with [`io::ErrorKind::InvalidInput`].
+pub fn set_non_interactive(non_interactive: bool) {
+    NON_INTERACTIVE.store(non_interactive, Ordering::Relaxed);
+}
+
+/// Whether prompts are answered with their default without asking.
+pub fn non_interactive() -> bool {
+    NON_INTERACTIVE.load(Ordering::Relaxed)
+}
+
+/// Ask to choose one of `options`, returning the index of the chosen option.
+///
+/// See [`Select`].
+///
+/// ## Example
+/// ```no_run
+/// use carlog::prompt;
+///
+/// let profile = prompt::select("Which profile?", &["dev", "release"]).unwrap();
+/// ```
+pub fn select<S, T>(message: S, options: &[T]) -> io::Result<usize>
+where
+    S: AsRef<str>,
+    T: Display,
+{
+    Select::new(message, options).interact()
+}
+
+/// A prompt to choose one of several options.
+///
+/// The options are printed as a numbered list and the answer can be either the number of an option
+/// or a prefix of its text matching no other option. Invalid answers are reported and asked again,
+/// and an empty answer takes the default, if any.
+///
+/// ## Example
+/// ```no_run
+/// use carlog::prompt::Select;
+///
+/// let index = Select::new("Which profile?", &["dev", "release", "bench"])
+///     .default(0)
+///     .interact()
+///     .unwrap();
+/// ```
+pub struct Select<'a, T> {
+    message: String,
+    options: &'a [T],
+    default: Option<usize>,
+}
+
+impl<'a, T> Select<'a, T>
+where
+    T: Display,
+{
+    /// Create a prompt for the given options.
+    pub fn new<S>(message: S, options: &'a [T]) -> Self
+    where
+        S: AsRef<str>,
+    {
+        Self {
+            message: message.as_ref().to_string(),
+            options,
+            default: None,
+        }
+    }
+
+    /// Set the index of the option taken when the answer is empty or in non-interactive mode.
+    pub fn default(mut self, index: usize) -> Self {
+        self.default = Some(index);
+        self
+    }
+
+    /// Ask on stderr and read the answer from stdin.
+    pub fn interact(self) -> io::Result<usize> {
+        self.interact_on(stdin().lock(), stderr())
+    }
+
+    /// Ask on `output` and read the answer from `input`.
+    ///
+    /// Fails with [`io::ErrorKind::UnexpectedEof`] when the input ends without a valid answer and
+    /// there's no default.
+    pub fn interact_on<R, W>(self, mut input: R, mut output: W) -> io::Result<usize>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        let options: Vec<String> = self.options.iter().map(|o| o.to_string()).collect();
+        if options.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no options to select from",
+            ));
+        }
+        let default = self.default.filter(|&default| default < options.len());
+        if non_interactive() {
+            return default.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("`{}` needs an answer in non-interactive mode", self.message),
+                )
+            });
+        }
+        self.render(&options, default, &mut output)?;
+        let mut answer = String::new();
+        loop {
+            match default {
+                Some(default) => write!(
+                    output,
+                    "Choice [1-{}, default {}]: ",
+                    options.len(),
+                    default + 1
+                )?,
+                None => write!(output, "Choice [1-{}]: ", options.len())?,
+            }
+            output.flush()?;
+            answer.clear();
+            if input.read_line(&mut answer)? == 0 {
+                writeln!(output)?;
+                return default.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::UnexpectedEof, "no option selected")
+                });
+            }
+            match choose(&options, answer.trim(), default) {
+                Ok(index) => return Ok(index),
+                Err(reason) => Status::plain()
+                    .color(CargoColor::BrightYellow)
+                    .status("warning")
+                    .print(&mut output, format!(": {}", reason))?,
+            }
+        }
+    }
+
+    fn render<W>(
+        &self,
+        options: &[String],
+        default: Option<usize>,
+        output: &mut W,
+    ) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut list = String::new();
+        let width = options.len().to_string().len();
+        for (index, option) in options.iter().enumerate() {
+            let marker = if Some(index) == default {
+                " (default)"
+            } else {
+                ""
+            };
+            list.push_str(&format!("  {:>width$}) {}{}\n", index + 1, option, marker));
+        }
+        Status::plain()
+            .bold()
+            .color(CargoColor::Cyan)
+            .status("?")
+            .print(&mut *output, format!(" {}", self.message))?;
+        output.write_all(list.as_bytes())
+    }
+}
+
+/// The index of the option matching `answer`, or why there's none.
+fn choose(options: &[String], answer: &str, default: Option<usize>) -> Result<usize, String> {
+    if answer.is_empty() {
+        return default.ok_or_else(|| "an option must be selected".to_string());
+    }
+    if let Ok(number) = answer.parse::<usize>() {
+        return if (1..=options.len()).contains(&number) {
+            Ok(number - 1)
+        } else {
+            Err(format!(
+                "`{}` isn't between 1 and {}",
+                number,
+                options.len()
+            ))
+        };
+    }
+    let lowercase = answer.to_lowercase();
+    let matches: Vec<usize> = (0..options.len())
+        .filter(|&index| options[index].to_lowercase().starts_with(&lowercase))
+        .collect();
+    match matches[..] {
+        [index] => Ok(index),
+        [] => Err(format!("`{}` doesn't match any option", answer)),
+        _ => match matches
+            .iter()
+            .find(|&&index| options[index].to_lowercase() == lowercase)
+        {
+            Some(&index) => Ok(index),
+            None => Err(format!("`{}` matches several options", answer)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{set_non_interactive, Select};
+    use crate::test::{force_colors, global_lock};
+    use std::io;
+
+    fn select(input: &str, default: Option<usize>) -> (io::Result<usize>, String) {
+        force_colors();
+        let options = ["dev", "release", "release-lto"];
+        let mut select = Select::new("Which profile?", &options);
+        if let Some(default) = default {
+            select = select.default(default);
+        }
+        let mut output = Vec::<u8>::new();
+        let index = select.interact_on(input.as_bytes(), &mut output);
+        (index, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_numeric_and_prefix() {
+        let _lock = global_lock();
+        let (index, output) = select("2\n", None);
+        assert_eq!(index.unwrap(), 1);
+        assert_eq!(
+            output,
+            "\u{1b}[1;36m?\u{1b}[0m Which profile?\n  1) dev\n  2) release\n  3) release-lto\n\
+             Choice [1-3]: "
+        );
+        assert_eq!(select("d\n", None).0.unwrap(), 0);
+        assert_eq!(select("release-\n", None).0.unwrap(), 2);
+        assert_eq!(select("Release\n", None).0.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_re_ask() {
+        let _lock = global_lock();
+        let (index, output) = select("rel\n0\n4\nx\n3\n", Some(0));
+        assert_eq!(index.unwrap(), 2);
+        let warnings: Vec<&str> = output
+            .lines()
+            .filter_map(|line| line.split("\u{1b}[93mwarning\u{1b}[0m: ").nth(1))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                "`rel` matches several options",
+                "`0` isn't between 1 and 3",
+                "`4` isn't between 1 and 3",
+                "`x` doesn't match any option",
+            ]
+        );
+        assert!(output.contains("  1) dev (default)\n"));
+    }
+
+    #[test]
+    fn test_default() {
+        let _lock = global_lock();
+        assert_eq!(select("\n", Some(1)).0.unwrap(), 1);
+        assert_eq!(select("", Some(2)).0.unwrap(), 2);
+        let err = select("", None).0.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        set_non_interactive(true);
+        let (index, output) = select("", Some(1));
+        let err = select("", None).0.unwrap_err();
+        set_non_interactive(false);
+        assert_eq!(index.unwrap(), 1);
+        assert!(output.is_empty());
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,692 @@
+//! Messages kept as data, i.e. buffered or read back from a file, or built by custom frontends.
+
+use crate::{
+    cargo_json, color, dedup, github, route, sinks, stream, summary, theme, wire, CargoColor,
+    CarlogStream, Error, Level, Status,
+};
+use colored::Colorize;
+use std::borrow::Cow;
+use std::error;
+use std::fmt::{self, Debug};
+use std::mem;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::{self, ThreadId};
+use std::time::SystemTime;
+
+/// A message with the status it was printed with.
+///
+/// Records are printed by [`print_record`] like the macros print their messages, which makes
+/// them the entry point of custom macro layers.
+///
+/// ## Example
+/// ```
+/// use carlog::Record;
+/// use std::time::SystemTime;
+///
+/// let record = Record::new("Compiling", "carlog v0.1.0").timestamp(SystemTime::now()).sequence(1);
+/// assert_eq!(record.status(), "Compiling");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    status: String,
+    message: Message,
+    level: Level,
+    color: Option<CargoColor>,
+    location: Option<(String, u32, u32)>,
+    input: Option<(String, u32, u32)>,
+    fields: Vec<(String, String)>,
+    id: Option<String>,
+    code: Option<String>,
+    causes: Vec<String>,
+    target: Option<String>,
+    timestamp: Option<SystemTime>,
+    sequence: Option<u64>,
+}
+
+/// The most causes [`Record::from_error`] follows, in case of a cycle in the sources.
+const MAX_CAUSES: usize = 32;
+
+/// Formats a lazy message.
+type Format = Box<dyn FnOnce() -> String + Send>;
+
+/// Formats the lazy message of a macro, borrowing its arguments, on the thread of the macro.
+struct Scoped(ThreadId, Box<dyn FnOnce() -> String>);
+
+// SAFETY: the format only runs and is dropped on its thread, see `Message::get` and `Settle`.
+unsafe impl Send for Scoped {}
+
+/// How a lazy message is formatted, until it's read.
+enum Pending {
+    Owned(Format),
+    Scoped(Scoped),
+}
+
+/// A message formatted the first time it's read, shared by the clones of its record.
+struct Message(Arc<(OnceLock<String>, Mutex<Option<Pending>>)>);
+
+impl Message {
+    fn get(&self) -> &str {
+        let (text, pending) = &*self.0;
+        text.get_or_init(|| {
+            let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(Pending::Scoped(Scoped(thread, _))) = &*pending {
+                assert!(
+                    *thread == thread::current().id(),
+                    "the message of a macro was read from another thread"
+                );
+            }
+            let format: Box<dyn FnOnce() -> String> = match pending.take() {
+                Some(Pending::Owned(format)) => format,
+                Some(Pending::Scoped(Scoped(_, format))) => format,
+                None => return String::new(),
+            };
+            drop(pending);
+            format()
+        })
+    }
+
+    fn is_scoped(&self) -> bool {
+        let pending = self.0 .1.lock().unwrap_or_else(|e| e.into_inner());
+        matches!(*pending, Some(Pending::Scoped(_)))
+    }
+}
+
+impl Clone for Message {
+    fn clone(&self) -> Self {
+        // A clone could outlive the arguments of the macro, so it gets the formatted message.
+        if self.is_scoped() {
+            self.get();
+        }
+        Self(Arc::clone(&self.0))
+    }
+}
+
+/// Formats the scoped message of a record if it outlives its macro, drops it otherwise, before
+/// the arguments of the macro are.
+struct Settle(Arc<(OnceLock<String>, Mutex<Option<Pending>>)>);
+
+impl Drop for Settle {
+    fn drop(&mut self) {
+        if Arc::strong_count(&self.0) > 1 {
+            Message(Arc::clone(&self.0)).get();
+        }
+        let pending = self.0 .1.lock().unwrap_or_else(|e| e.into_inner()).take();
+        drop(pending);
+    }
+}
+
+impl Debug for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.get(), f)
+    }
+}
+
+impl PartialEq for Message {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl Eq for Message {}
+
+impl Record {
+    /// Create an info record without timestamp nor sequence number.
+    ///
+    /// * `status`: The status of the message, i.e. `Compiling`.
+    /// * `message`: The message, without the separator from the status.
+    pub fn new<S, M>(status: S, message: M) -> Self
+    where
+        S: AsRef<str>,
+        M: AsRef<str>,
+    {
+        let message = message.as_ref().to_string();
+        Self {
+            status: status.as_ref().to_string(),
+            message: Message(Arc::new((OnceLock::from(message), Mutex::new(None)))),
+            level: Level::Info,
+            color: None,
+            location: None,
+            input: None,
+            fields: Vec::new(),
+            id: None,
+            code: None,
+            causes: Vec::new(),
+            target: None,
+            timestamp: None,
+            sequence: None,
+        }
+    }
+
+    /// Create a record whose message is only formatted when it's first read, see
+    /// [`message`](Self::message).
+    ///
+    /// The clones of the record share the message, so it's formatted at most once.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::Record;
+    ///
+    /// let dependencies = vec!["colored", "libc"];
+    /// let record = Record::lazy("Resolved", move || dependencies.join(", "));
+    /// assert_eq!(record.message(), "colored, libc");
+    /// ```
+    pub fn lazy<S, F>(status: S, message: F) -> Self
+    where
+        S: AsRef<str>,
+        F: FnOnce() -> String + Send + 'static,
+    {
+        Self {
+            status: status.as_ref().to_string(),
+            message: Message(Arc::new((
+                OnceLock::new(),
+                Mutex::new(Some(Pending::Owned(Box::new(message)))),
+            ))),
+            level: Level::Info,
+            color: None,
+            location: None,
+            input: None,
+            fields: Vec::new(),
+            id: None,
+            code: None,
+            causes: Vec::new(),
+            target: None,
+            timestamp: None,
+            sequence: None,
+        }
+    }
+
+    /// Lend `print` a record whose message is formatted by `message` when it's first read, for the
+    /// macros.
+    ///
+    /// The message borrows the arguments of the macro, so it's formatted before this returns if
+    /// the record outlives `print`, and only on the current thread.
+    #[doc(hidden)]
+    pub fn __lazy<'a, S, F, P, R>(status: S, message: F, print: P) -> R
+    where
+        S: AsRef<str>,
+        F: FnOnce() -> String + 'a,
+        P: FnOnce(Self) -> R,
+    {
+        let format: Box<dyn FnOnce() -> String + 'a> = Box::new(message);
+        // SAFETY: `Settle` runs or drops the format before this returns, while its borrows are
+        // alive, and `Message::get` only runs it on this thread.
+        let format: Box<dyn FnOnce() -> String> = unsafe { mem::transmute(format) };
+        let mut record = Self::new(status, "");
+        record.message = Message(Arc::new((
+            OnceLock::new(),
+            Mutex::new(Some(Pending::Scoped(Scoped(
+                thread::current().id(),
+                format,
+            )))),
+        )));
+        let _settle = Settle(Arc::clone(&record.message.0));
+        print(record)
+    }
+
+    /// Create an error record of `error`, with its [sources](error::Error::source) as its
+    /// [causes](Self::cause).
+    ///
+    /// The chain of sources is followed for at most 32 causes, in case it loops.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::Record;
+    /// use std::io;
+    ///
+    /// let error = io::Error::other("failed to read `Cargo.toml`");
+    /// let record = Record::from_error(&error);
+    /// assert_eq!(record.message(), "failed to read `Cargo.toml`");
+    /// assert_eq!(record.causes().count(), 0);
+    /// ```
+    pub fn from_error(error: &dyn error::Error) -> Self {
+        let mut record = Self::new("error", error.to_string()).level(Level::Error);
+        let mut source = error.source();
+        while let Some(cause) = source.filter(|_| record.causes.len() < MAX_CAUSES) {
+            record = record.cause(cause.to_string());
+            source = cause.source();
+        }
+        record
+    }
+
+    /// Set the severity of the message, `Info` by default.
+    ///
+    /// The level decides the style the record is printed with: justified and bold for `Info`,
+    /// justified and dimmed for `Debug` and followed by `:` for `Warning` and `Error`, in the color
+    /// of the [`theme`](crate::theme).
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Print the status in `color` instead of the color of the theme for its level.
+    pub fn color(mut self, color: CargoColor) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Set where the message comes from, see [`Status::location`].
+    pub fn location<S>(mut self, path: S, line: u32, column: u32) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.location = Some((path.as_ref().to_string(), line, column));
+        self
+    }
+
+    /// Set the place of the input the message is about, i.e. the line of a manifest with an unknown
+    /// key, which the [GitHub Actions annotations](crate::github) point to.
+    pub fn input<S>(mut self, path: S, line: u32, column: u32) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.input = Some((path.as_ref().to_string(), line, column));
+        self
+    }
+
+    /// Add a field printed after the message as `key=value`.
+    pub fn field<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: AsRef<str>,
+        V: ToString,
+    {
+        self.fields
+            .push((key.as_ref().to_string(), value.to_string()));
+        self
+    }
+
+    /// Set the identifier of the message, i.e. the name of a [lint](crate::lint), printed dimmed
+    /// after the message as `[id]`.
+    pub fn id<S>(mut self, id: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.id = Some(id.as_ref().to_string());
+        self
+    }
+
+    /// Set the code of the message, i.e. `E0308`, printed in brackets after the status in its style
+    /// as `error[E0308]`.
+    pub fn code<S>(mut self, code: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.code = Some(code.as_ref().to_string());
+        self
+    }
+
+    /// Add a cause of the message, see [`Status::cause`].
+    pub fn cause<S>(mut self, cause: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.causes.push(cause.as_ref().to_string());
+        self
+    }
+
+    /// Set the target of the message, i.e. `net::http`, which picks its [route](crate::route_target)
+    /// instead of the [scope](crate::target_scope) of the thread.
+    pub fn target<S>(mut self, target: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.target = Some(target.as_ref().to_string());
+        self
+    }
+
+    /// Set when the message was emitted.
+    pub fn timestamp(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Set the position of the message in the output of its emitter.
+    pub fn sequence(mut self, sequence: u64) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+
+    /// The status of the message.
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+
+    /// The message, formatted now if it's [lazy](Self::lazy) and wasn't read yet.
+    pub fn message(&self) -> &str {
+        self.message.get()
+    }
+
+    /// When the message was emitted, if known.
+    pub fn time(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    /// The position of the message in the output of its emitter, if known.
+    pub fn sequence_number(&self) -> Option<u64> {
+        self.sequence
+    }
+
+    /// The severity of the message.
+    pub fn severity(&self) -> Level {
+        self.level
+    }
+
+    /// The color the status is printed with, if not the one of its severity in the [`theme`].
+    pub fn status_color(&self) -> Option<CargoColor> {
+        self.color
+    }
+
+    /// Where the message comes from, as path, line and column, if known.
+    pub fn source_location(&self) -> Option<(&str, u32, u32)> {
+        self.location
+            .as_ref()
+            .map(|(path, line, column)| (path.as_str(), *line, *column))
+    }
+
+    /// The place of the input the message is about, if any, see [`input`](Self::input).
+    pub fn input_location(&self) -> Option<(&str, u32, u32)> {
+        self.input
+            .as_ref()
+            .map(|(path, line, column)| (path.as_str(), *line, *column))
+    }
+
+    /// The identifier of the message, if any, see [`id`](Self::id).
+    pub fn identifier(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// The code of the message, if any, see [`code`](Self::code).
+    pub fn diagnostic_code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    /// The causes of the message, from the closest one, see [`cause`](Self::cause).
+    pub fn causes(&self) -> impl Iterator<Item = &str> {
+        self.causes.iter().map(String::as_str)
+    }
+
+    /// The target of the message, if any, see [`target`](Self::target).
+    pub fn log_target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    /// The fields of the message, in the order they were added.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// The status the record is printed with.
+    pub(crate) fn to_status(&self) -> Status {
+        let theme = theme();
+        let (color, status) = match self.level {
+            Level::Trace => (CargoColor::BrightBlack, Status::plain().justify().dimmed()),
+            Level::Debug => (theme.info, Status::plain().justify().dimmed()),
+            Level::Info => (theme.info, Status::plain().bold().justify()),
+            Level::Warning => (theme.warning, Status::plain()),
+            Level::Error => (theme.error, Status::plain()),
+        };
+        let status = status
+            .color(self.color.unwrap_or(color))
+            .status(&self.status)
+            .level(self.level);
+        let mut status = match &self.code {
+            Some(code) => status.code(code),
+            None => status,
+        };
+        for cause in &self.causes {
+            status = status.cause(cause);
+        }
+        match &self.location {
+            Some((path, line, column)) => status.location(path, *line, *column),
+            None => status,
+        }
+    }
+
+    /// The target the record is routed by, its own or the one of the current scope.
+    pub(crate) fn route_target(&self) -> Option<Cow<'_, str>> {
+        match &self.target {
+            Some(target) => Some(Cow::Borrowed(target)),
+            None => route::current_target().map(Cow::Owned),
+        }
+    }
+
+    /// The message printed after the status, with its separator and fields.
+    pub(crate) fn line_message(&self) -> String {
+        let separator = match self.level {
+            Level::Trace | Level::Debug | Level::Info => " ",
+            Level::Warning | Level::Error => ": ",
+        };
+        let mut message = format!("{}{}", separator, self.message());
+        for (key, value) in &self.fields {
+            message.push_str(&format!(" {}={}", key, value));
+        }
+        if let Some(id) = &self.id {
+            message.push(' ');
+            message.push_str(&color::paint(format!("[{}]", id).dimmed()));
+        }
+        message
+    }
+}
+
+/// Print a record like the macros print their messages: warnings and errors to stderr and the
+/// other records to stdout.
+///
+/// See [`print_record_to`].
+pub fn print_record(record: &Record) -> Result<(), Error> {
+    if !crate::__enabled(record.level) {
+        return Ok(());
+    }
+    if crate::__proc_macro_emit(&record.status, record.message()) {
+        return Ok(());
+    }
+    let stream = match record.level {
+        Level::Warning | Level::Error => crate::default_error_stream(),
+        Level::Trace | Level::Debug | Level::Info => CarlogStream::default(),
+    };
+    __print_record(kind(record.level), record, stream)
+}
+
+/// Print a record to the specified stream, like the macros print their messages.
+///
+/// Records less severe than the [max level](crate::set_max_level) are skipped, without
+/// formatting a [lazy](Record::lazy) message, and the others are counted in the metrics.
+///
+/// * `record`: The record to print.
+/// * `stream`: The stream where the record will be written.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::Record;
+///
+/// let record = Record::new("unused import", "`std::io`").level(Level::Warning).field("count", 2);
+/// let mut output = Vec::<u8>::new();
+/// carlog::print_record_to(&record, CarlogStream::Custom(&mut output)).unwrap();
+/// ```
+pub fn print_record_to(record: &Record, stream: CarlogStream<'_>) -> Result<(), Error> {
+    if !crate::__enabled(record.level) {
+        return Ok(());
+    }
+    __print_record(kind(record.level), record, stream)
+}
+
+/// The kind of message a record is counted as in the metrics.
+pub(crate) fn kind(level: Level) -> &'static str {
+    match level {
+        Level::Trace | Level::Debug | Level::Info => "info",
+        Level::Warning => "warning",
+        Level::Error => "error",
+    }
+}
+
+/// Print a record already checked against the max level, counted as `kind` in the metrics.
+#[doc(hidden)]
+pub fn __print_record(
+    kind: &str,
+    record: &Record,
+    mut stream: CarlogStream<'_>,
+) -> Result<(), Error> {
+    print_in(kind, record, &mut stream)
+}
+
+/// Print a record to a stream the caller keeps, like [`__print_record`].
+pub(crate) fn print_in(
+    kind: &str,
+    record: &Record,
+    stream: &mut CarlogStream<'_>,
+) -> Result<(), Error> {
+    let target = record.route_target();
+    crate::__record_message(kind, record.level, target.as_deref());
+    summary::count(record.level, record.code.as_deref());
+    if dedup::hold(record, stream)? {
+        return Ok(());
+    }
+    let structured = cargo_json::record_line(record)
+        .or_else(|| github::record_line(record))
+        .or_else(|| wire::record_line(record));
+    if let Some(line) = structured {
+        return sinks::with_level(Some(record.level), || {
+            let routed = route::write(target.as_deref(), |sink| sink.write_all(line.as_bytes()))?;
+            if routed && !stream.is_null() {
+                stream::write_line(stream, &line)?;
+            }
+            Ok(())
+        });
+    }
+    let routed = route::write(target.as_deref(), |sink| {
+        Ok(record.to_status().print(sink, record.line_message())?)
+    })?;
+    if routed && !stream.is_null() {
+        record
+            .to_status()
+            .print_stream(stream, record.line_message())?;
+    }
+    Ok(())
+}
+
+/// Print a `note:` or `help:` line of the macros, already checked against the max level, with
+/// its status `indent` columns from the start of the line.
+#[doc(hidden)]
+pub fn __print_note(
+    label: &str,
+    color: CargoColor,
+    indent: usize,
+    message: &str,
+    (path, line, column): (&str, u32, u32),
+    mut stream: CarlogStream<'_>,
+) -> Result<(), Error> {
+    let mut status = Status::plain()
+        .bold()
+        .color(color)
+        .status(label)
+        .location(path, line, column);
+    if indent > 0 {
+        // The padding of a justified status isn't styled.
+        status = status.justify_to(indent + crate::fmt::display_width(label));
+    }
+    let message = format!(": {}", message);
+    let routed = route::write(route::current_target().as_deref(), |sink| {
+        Ok(status.print(sink, &message)?)
+    })?;
+    if routed && !stream.is_null() {
+        status.print_stream(&mut stream, message)?;
+    }
+    Ok(())
+}
+
+/// Whether a message printed to `stream` is discarded, so the macros can skip formatting it.
+#[doc(hidden)]
+pub fn __discarded(stream: &CarlogStream<'_>) -> bool {
+    stream.is_null() && !route::routed(route::current_target().as_deref())
+}
+
+#[cfg(test)]
+mod test {
+    use super::Record;
+    use crate::test::{force_colors, global_lock};
+    use crate::{clear_routes, route_target_with, settings, CarlogStream, RouteMode};
+    use std::fmt::{self, Display};
+    use std::io;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Counts how many times it's formatted.
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Display for Counted {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            write!(f, "carlog v0.1.0")
+        }
+    }
+
+    fn lazy(count: &Arc<AtomicUsize>) -> Record {
+        let counted = Counted(Arc::clone(count));
+        Record::lazy("Compiling", move || format!("{}", counted)).sequence(1)
+    }
+
+    #[test]
+    fn test_lazy_message() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let unread = lazy(&count);
+        let clone = unread.clone();
+        assert_eq!(
+            (unread.status(), clone.sequence_number()),
+            ("Compiling", Some(1))
+        );
+        drop((unread, clone));
+        assert_eq!(count.load(Ordering::Relaxed), 0);
+
+        let record = lazy(&count);
+        let consumers = [record.clone(), record];
+        assert!(consumers.iter().all(|r| r.message() == "carlog v0.1.0"));
+        assert_eq!(
+            consumers[0],
+            Record::new("Compiling", "carlog v0.1.0").sequence(1)
+        );
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_macro_message() {
+        let _lock = global_lock();
+        force_colors();
+        let count = Arc::new(AtomicUsize::new(0));
+        let counted = Counted(Arc::clone(&count));
+        let status = Record::__lazy(
+            "Compiling",
+            || counted.to_string(),
+            |record| record.status().to_string(),
+        );
+        crate::try_carlog_info!("Compiling", counted, CarlogStream::Null).unwrap();
+        assert_eq!(
+            (status.as_str(), count.load(Ordering::Relaxed)),
+            ("Compiling", 0)
+        );
+
+        // The route and the stream both read the message.
+        route_target_with("lazy", io::sink(), RouteMode::Also);
+        let mut output = Vec::<u8>::new();
+        crate::try_carlog_info!(
+            target: "lazy",
+            "Compiling",
+            counted,
+            CarlogStream::Custom(&mut output)
+        )
+        .unwrap();
+        clear_routes();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "   \u{1b}[1;36mCompiling\u{1b}[0m carlog v0.1.0\n"
+        );
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+
+        // A record outliving its macro gets the message formatted before the arguments are gone.
+        let kept = Record::__lazy("Compiling", || counted.to_string(), |record| record.clone());
+        drop(counted);
+        assert_eq!(kept.message(), "carlog v0.1.0");
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+        settings::reset();
+    }
+}
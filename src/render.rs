@@ -0,0 +1,250 @@
+//! Pretty-printing of structured values inside messages.
+//!
+//! The rendered text is meant to be shown under a status with
+//! [`BlockStyle::Indented`](crate::BlockStyle::Indented):
+//! ```text
+//!       Loaded config
+//!              {
+//!                "name": "carlog",
+//!                "jobs": 4
+//!              }
+//! ```
+
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static MAX_DEPTH: AtomicUsize = AtomicUsize::new(8);
+static MAX_ITEMS: AtomicUsize = AtomicUsize::new(32);
+
+/// Collapse the values nested deeper than `max` to `{…}` or `[…]`.
+///
+/// `None` shows every level, the default is 8.
+pub fn set_max_depth(max: Option<usize>) {
+    MAX_DEPTH.store(max.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+/// Only show the first `max` items of objects and arrays, followed by a line like
+/// `… 3 items omitted …`.
+///
+/// `None` shows every item, the default is 32.
+pub fn set_max_items(max: Option<usize>) {
+    MAX_ITEMS.store(max.unwrap_or(usize::MAX), Ordering::Relaxed);
+}
+
+/// The depth above which values are collapsed, see [`set_max_depth`].
+pub(crate) fn max_depth() -> Option<usize> {
+    match MAX_DEPTH.load(Ordering::Relaxed) {
+        usize::MAX => None,
+        max => Some(max),
+    }
+}
+
+/// The number of items shown of objects and arrays, see [`set_max_items`].
+pub(crate) fn max_items() -> Option<usize> {
+    match MAX_ITEMS.load(Ordering::Relaxed) {
+        usize::MAX => None,
+        max => Some(max),
+    }
+}
+
+/// Pretty-print a value as JSON, with cyan keys, green strings, yellow numbers and literals and
+/// dimmed punctuation.
+///
+/// Enabled with the `serde` feature. Fails if the value can't be represented as JSON, i.e. a map
+/// with non string keys.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::BlockStyle;
+///
+/// let config = serde_json::json!({ "name": "carlog", "jobs": 4 });
+/// let status = Status::new().bold().justify().color(CargoColor::Green).status("Loaded config");
+/// let text = carlog::render::value(&config).unwrap();
+/// carlog::emit_block(&status, &text, BlockStyle::Indented).unwrap();
+/// ```
+#[cfg(feature = "serde")]
+pub fn value<T>(value: &T) -> std::io::Result<String>
+where
+    T: serde::Serialize + ?Sized,
+{
+    let value = serde_json::to_value(value)?;
+    let mut text = String::new();
+    json::render(&mut text, &value, 0);
+    Ok(text)
+}
+
+/// Pretty-print a value with its `Debug` implementation, for the types that aren't `Serialize`.
+///
+/// The depth limit of [`set_max_depth`] applies to the indentation of `{:#?}`, the item limit
+/// doesn't.
+///
+/// ## Example
+/// ```
+/// let text = carlog::render::debug(&vec![(1, "one"), (2, "two")]);
+/// assert!(text.starts_with("[\n    (\n        1,"));
+/// ```
+pub fn debug<T>(value: &T) -> String
+where
+    T: Debug + ?Sized,
+{
+    let max = MAX_DEPTH.load(Ordering::Relaxed);
+    let text = format!("{:#?}", value);
+    let mut lines = Vec::new();
+    let mut collapsed = false;
+    for line in text.lines() {
+        let depth = (line.len() - line.trim_start().len()) / 4;
+        if depth <= max {
+            lines.push(line.to_string());
+            collapsed = false;
+        } else if !collapsed {
+            lines.push(format!("{}\u{2026}", " ".repeat(max.saturating_add(1) * 4)));
+            collapsed = true;
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(feature = "serde")]
+mod json {
+    use super::{MAX_DEPTH, MAX_ITEMS};
+    use crate::color::paint;
+    use crate::fmt;
+    use colored::Colorize;
+    use serde_json::Value;
+    use std::sync::atomic::Ordering;
+
+    pub(super) fn render(text: &mut String, value: &Value, depth: usize) {
+        match value {
+            Value::Null | Value::Bool(_) | Value::Number(_) => {
+                text.push_str(&paint(value.to_string().yellow()))
+            }
+            Value::String(_) => text.push_str(&paint(value.to_string().green())),
+            Value::Array(items) => container(text, "[", "]", items.len(), depth, |text, n| {
+                render(text, &items[n], depth + 1)
+            }),
+            Value::Object(map) => {
+                let entries: Vec<_> = map.iter().collect();
+                container(text, "{", "}", entries.len(), depth, |text, n| {
+                    let (key, value) = entries[n];
+                    // Keys are escaped like strings.
+                    let key = Value::String(key.clone()).to_string();
+                    text.push_str(&format!("{}{} ", paint(key.cyan()), paint(":".dimmed())));
+                    render(text, value, depth + 1)
+                })
+            }
+        }
+    }
+
+    /// Render the `len` items of an array or an object between its delimiters, one per line.
+    fn container<F>(
+        text: &mut String,
+        open: &str,
+        close: &str,
+        len: usize,
+        depth: usize,
+        mut item: F,
+    ) where
+        F: FnMut(&mut String, usize),
+    {
+        if len == 0 {
+            text.push_str(&paint(format!("{}{}", open, close).dimmed()));
+            return;
+        }
+        if depth >= MAX_DEPTH.load(Ordering::Relaxed) {
+            text.push_str(&format!(
+                "{}\u{2026}{}",
+                paint(open.dimmed()),
+                paint(close.dimmed())
+            ));
+            return;
+        }
+        let shown = len.min(MAX_ITEMS.load(Ordering::Relaxed));
+        let indent = "  ".repeat(depth + 1);
+        text.push_str(&paint(open.dimmed()));
+        for n in 0..shown {
+            text.push('\n');
+            text.push_str(&indent);
+            item(text, n);
+            if n + 1 < len {
+                text.push_str(&paint(",".dimmed()));
+            }
+        }
+        if shown < len {
+            let omission = format!(
+                "\u{2026} {} omitted \u{2026}",
+                fmt::count(len - shown, "item")
+            );
+            text.push_str(&format!("\n{}{}", indent, paint(omission.dimmed())));
+        }
+        text.push_str(&format!(
+            "\n{}{}",
+            "  ".repeat(depth),
+            paint(close.dimmed())
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{debug, set_max_depth};
+    use crate::test::global_lock;
+
+    #[test]
+    fn test_debug() {
+        let _lock = global_lock();
+        let value = vec![Some(vec![1]), None];
+        assert_eq!(
+            debug(&value),
+            "[\n    Some(\n        [\n            1,\n        ],\n    ),\n    None,\n]"
+        );
+        set_max_depth(Some(1));
+        assert_eq!(
+            debug(&value),
+            "[\n    Some(\n        \u{2026}\n    ),\n    None,\n]"
+        );
+        set_max_depth(Some(8));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_value() {
+        use super::{set_max_items, value};
+        use crate::test::{auto_colors, force_colors};
+        use serde_json::json;
+
+        let _lock = global_lock();
+        let config = json!({
+            "name": "carlog \"cli\"",
+            "jobs": 4,
+            "targets": [{ "kind": ["lib"], "test": true }, null],
+            "features": {},
+        });
+        auto_colors();
+        let plain = value(&config).unwrap();
+        set_max_depth(Some(1));
+        set_max_items(Some(3));
+        let truncated = value(&config).unwrap();
+        set_max_depth(Some(8));
+        set_max_items(Some(32));
+        force_colors();
+        let colored = value(&json!({ "jobs": [4, "all"] })).unwrap();
+        assert_eq!(
+            plain,
+            "{\n  \"name\": \"carlog \\\"cli\\\"\",\n  \"jobs\": 4,\n  \"targets\": [\n    {\n      \
+             \"kind\": [\n        \"lib\"\n      ],\n      \"test\": true\n    },\n    null\n  ],\n  \
+             \"features\": {}\n}"
+        );
+        assert_eq!(
+            truncated,
+            "{\n  \"name\": \"carlog \\\"cli\\\"\",\n  \"jobs\": 4,\n  \"targets\": [\u{2026}],\n  \
+             \u{2026} 1 item omitted \u{2026}\n}"
+        );
+        assert_eq!(
+            colored,
+            "\u{1b}[2m{\u{1b}[0m\n  \u{1b}[36m\"jobs\"\u{1b}[0m\u{1b}[2m:\u{1b}[0m \
+             \u{1b}[2m[\u{1b}[0m\n    \u{1b}[33m4\u{1b}[0m\u{1b}[2m,\u{1b}[0m\n    \
+             \u{1b}[32m\"all\"\u{1b}[0m\n  \u{1b}[2m]\u{1b}[0m\n\u{1b}[2m}\u{1b}[0m"
+        );
+    }
+}
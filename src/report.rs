@@ -0,0 +1,210 @@
+//! Report of the terminal environment, to debug missing colors or garbled output.
+
+use crate::env::{EnvSource, ProcessEnv};
+use crate::{color, max_level, output, setting_sources, sys, verbosity};
+use crate::{CargoColor, CarlogStream, Status};
+use std::io::{self, stderr, stdout, IsTerminal, Write};
+
+/// The environment variables that affect the output.
+const VARIABLES: [&str; 6] = [
+    "TERM",
+    "COLORTERM",
+    "NO_COLOR",
+    "CLICOLOR",
+    "CLICOLOR_FORCE",
+    "CARGO_TERM_COLOR",
+];
+
+/// What was detected about the terminal.
+struct Terminal {
+    stdout: bool,
+    stderr: bool,
+    size: Option<(u16, u16)>,
+    colors: bool,
+}
+
+impl Terminal {
+    fn detect() -> Self {
+        Self {
+            stdout: stdout().is_terminal(),
+            stderr: stderr().is_terminal(),
+            size: sys::terminal_size(),
+            colors: color::enabled(),
+        }
+    }
+}
+
+/// Print everything the crate detected about the terminal to stderr.
+///
+/// See [`print_env_report_to`].
+pub fn print_env_report() -> io::Result<()> {
+    report(&ProcessEnv, &Terminal::detect(), &mut CarlogStream::Stderr)
+}
+
+/// Print everything the crate detected about the terminal to the specified stream.
+///
+/// The report shows whether stdout and stderr are terminals, the environment variables that
+/// affect colors, the detected color depth and terminal size and whether colors are enabled,
+/// followed by a sample line of every kind of message. Users can attach it to bug reports about
+/// missing colors or garbage characters.
+///
+/// * `stream`: The stream where the report will be written.
+///
+/// ## Example
+/// ```
+/// let mut output = Vec::<u8>::new();
+/// carlog::print_env_report_to(&mut output).unwrap();
+/// ```
+pub fn print_env_report_to<W>(mut stream: W) -> io::Result<()>
+where
+    W: Write,
+{
+    report(
+        &ProcessEnv,
+        &Terminal::detect(),
+        &mut CarlogStream::Custom(&mut stream),
+    )
+}
+
+/// Print the lines of the report as a whole, under the output lock.
+fn report<E>(env: &E, terminal: &Terminal, stream: &mut CarlogStream<'_>) -> io::Result<()>
+where
+    E: EnvSource,
+{
+    let _output = output::lock();
+    lines(env, terminal, stream)
+}
+
+/// The lines of the report, printed as a whole by [`report`].
+fn lines<E>(env: &E, terminal: &Terminal, stream: &mut CarlogStream<'_>) -> io::Result<()>
+where
+    E: EnvSource,
+{
+    let info = |status: &str| {
+        Status::plain()
+            .bold()
+            .justify()
+            .color(CargoColor::Cyan)
+            .status(status)
+    };
+    let tty = |tty: bool| if tty { "terminal" } else { "not a terminal" };
+    info("Stdout").print_stream(stream, format!(" {}", tty(terminal.stdout)))?;
+    info("Stderr").print_stream(stream, format!(" {}", tty(terminal.stderr)))?;
+    let size = match terminal.size {
+        Some((columns, rows)) => format!(" {} columns, {} rows", columns, rows),
+        None => " unknown".to_string(),
+    };
+    info("Size").print_stream(stream, size)?;
+    for name in VARIABLES {
+        let value = match env.var(name) {
+            Some(value) => format!("`{}`", value),
+            None => "unset".to_string(),
+        };
+        info("Env").print_stream(stream, format!(" {}: {}", name, value))?;
+    }
+    info("Depth").print_stream(stream, format!(" {}", color_depth(env)))?;
+    let colors = if terminal.colors {
+        "enabled"
+    } else {
+        "disabled"
+    };
+    info("Colors").print_stream(stream, format!(" {}", colors))?;
+    info("Verbosity").print_stream(stream, format!(" {}", verbosity()))?;
+    let level = max_level().as_str();
+    let source = setting_sources().max_level.as_str();
+    info("Level").print_stream(stream, format!(" {} ({})", level, source))?;
+
+    let sample = |status: &str, color| Status::plain().bold().justify().color(color).status(status);
+    sample("Compiling", CargoColor::Cyan).print_stream(stream, " sample info line")?;
+    sample("Finished", CargoColor::Green).print_stream(stream, " sample ok line")?;
+    Status::plain()
+        .justify()
+        .dimmed()
+        .status("Fresh")
+        .print_stream(stream, " sample fresh line")?;
+    Status::plain()
+        .color(CargoColor::BrightYellow)
+        .status("warning")
+        .print_stream(stream, ": sample warning line")?;
+    Status::plain()
+        .color(CargoColor::BrightRed)
+        .status("error")
+        .print_stream(stream, ": sample error line")?;
+    Ok(())
+}
+
+/// The number of colors the terminal claims to support.
+fn color_depth<E>(env: &E) -> &'static str
+where
+    E: EnvSource,
+{
+    let colorterm = env.var("COLORTERM").unwrap_or_default();
+    let term = env.var("TERM").unwrap_or_default();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        "truecolor"
+    } else if term.contains("256color") {
+        "256 colors"
+    } else if term.is_empty() || term == "dumb" {
+        "none"
+    } else {
+        "16 colors"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{color_depth, report, Terminal};
+    use crate::test::{force_colors, global_lock};
+    use crate::CarlogStream;
+
+    #[test]
+    fn test_report() {
+        let _lock = global_lock();
+        force_colors();
+        let env = [("TERM", "xterm-256color"), ("NO_COLOR", "1")];
+        let terminal = Terminal {
+            stdout: true,
+            stderr: false,
+            size: Some((120, 40)),
+            colors: false,
+        };
+        let mut output = Vec::<u8>::new();
+        report(&env, &terminal, &mut CarlogStream::Custom(&mut output)).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let strip = |line: &str| {
+            line.replace("\u{1b}[1;36m", "")
+                .replace("\u{1b}[0m", "")
+                .trim_start()
+                .to_string()
+        };
+        let lines: Vec<String> = output.lines().take(13).map(strip).collect();
+        assert_eq!(
+            lines,
+            [
+                "Stdout terminal",
+                "Stderr not a terminal",
+                "Size 120 columns, 40 rows",
+                "Env TERM: `xterm-256color`",
+                "Env COLORTERM: unset",
+                "Env NO_COLOR: `1`",
+                "Env CLICOLOR: unset",
+                "Env CLICOLOR_FORCE: unset",
+                "Env CARGO_TERM_COLOR: unset",
+                "Depth 256 colors",
+                "Colors disabled",
+                "Verbosity 0",
+                "Level info (default)",
+            ]
+        );
+        assert!(output.ends_with("error\u{1b}[0m: sample error line\n"));
+    }
+
+    #[test]
+    fn test_color_depth() {
+        assert_eq!(color_depth(&[("COLORTERM", "truecolor")]), "truecolor");
+        assert_eq!(color_depth(&[("TERM", "screen-256color")]), "256 colors");
+        assert_eq!(color_depth(&[("TERM", "xterm")]), "16 colors");
+        assert_eq!(color_depth(&[("TERM", "dumb")]), "none");
+        assert_eq!(color_depth(&[]), "none");
+    }
+}
@@ -0,0 +1,262 @@
+//! Routing of messages to their own sinks by target, i.e. the component that logs them.
+//!
+//! The target of a message is, in order:
+//! 1. The one of its [`Record`](crate::Record::target).
+//! 2. The innermost [`target_scope`] of the thread, which also applies to the macros, i.e.
+//!    `carlog_info!(target: "net", "Connecting", "crates.io")`.
+//! 3. The [component](crate::thread::component) of the thread.
+//!
+//! Targets are paths like `net::http`. The route of `net` applies to the targets `net` and
+//! `net::http`, but not to `network`, and the route of the longest matching target wins.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+static ROUTES: Mutex<Vec<Route>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static SCOPES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A sink shared by the messages of a route.
+type Sink = Arc<Mutex<dyn Write + Send>>;
+
+struct Route {
+    target: String,
+    mode: RouteMode,
+    sink: Sink,
+}
+
+/// Whether the messages of a routed target are also printed to their stream.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RouteMode {
+    /// The messages go to the sink of the route only (the default).
+    #[default]
+    Instead,
+    /// The messages go to the sink of the route and to their stream.
+    Also,
+}
+
+/// Print the messages of `target` and its sub-targets to `sink` instead of their stream.
+///
+/// See [`route_target_with`].
+///
+/// ## Example
+/// ```no_run
+/// use carlog::sinks::FileSink;
+///
+/// carlog::route_target("net", FileSink::new("net.log").unwrap());
+/// ```
+pub fn route_target<S, W>(target: S, sink: W)
+where
+    S: AsRef<str>,
+    W: Write + Send + 'static,
+{
+    route_target_with(target, sink, RouteMode::Instead);
+}
+
+/// Print the messages of `target` and its sub-targets to `sink`, instead of or in addition to
+/// their stream depending on `mode`, replacing the previous route of `target`, if any.
+///
+/// The sink gets the lines of the messages as printed to a custom stream. A message whose sink
+/// fails to write is still reported as failed, see [`Error`](crate::Error).
+pub fn route_target_with<S, W>(target: S, sink: W, mode: RouteMode)
+where
+    S: AsRef<str>,
+    W: Write + Send + 'static,
+{
+    let target = target.as_ref().to_string();
+    let mut routes = ROUTES.lock().unwrap_or_else(|e| e.into_inner());
+    routes.retain(|route| route.target != target);
+    routes.push(Route {
+        target,
+        mode,
+        sink: Arc::new(Mutex::new(sink)),
+    });
+}
+
+/// Remove the route of `target`, its messages go back to their stream.
+pub fn unroute_target<S>(target: S)
+where
+    S: AsRef<str>,
+{
+    let mut routes = ROUTES.lock().unwrap_or_else(|e| e.into_inner());
+    routes.retain(|route| route.target != target.as_ref());
+}
+
+/// Remove every route, see [`route_target`].
+pub fn clear_routes() {
+    ROUTES.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// The routed targets and their modes, without the sinks.
+pub(crate) fn routes() -> BTreeMap<String, RouteMode> {
+    let routes = ROUTES.lock().unwrap_or_else(|e| e.into_inner());
+    routes
+        .iter()
+        .map(|route| (route.target.clone(), route.mode))
+        .collect()
+}
+
+/// Keep only the routes of the targets in `modes`, with their mode there.
+///
+/// A sink can't be created from its target, so the targets without a route stay unrouted.
+pub(crate) fn set_routes(modes: &BTreeMap<String, RouteMode>) {
+    let mut routes = ROUTES.lock().unwrap_or_else(|e| e.into_inner());
+    routes.retain_mut(|route| match modes.get(&route.target) {
+        Some(&mode) => {
+            route.mode = mode;
+            true
+        }
+        None => false,
+    });
+}
+
+/// Give a target to the messages of the current thread until the returned guard is dropped.
+///
+/// Scopes nest, the innermost one wins. Other threads keep their target.
+///
+/// ## Example
+/// ```
+/// {
+///     let _net = carlog::target_scope("net::http");
+///     // Messages printed here go to the route of `net`, if any.
+/// }
+/// ```
+pub fn target_scope<S>(target: S) -> TargetGuard
+where
+    S: AsRef<str>,
+{
+    let depth = SCOPES.with(|scopes| {
+        let mut scopes = scopes.borrow_mut();
+        scopes.push(target.as_ref().to_string());
+        scopes.len() - 1
+    });
+    TargetGuard {
+        depth,
+        _thread: PhantomData,
+    }
+}
+
+/// Restores the previous target when dropped, see [`target_scope`].
+#[must_use = "the target is restored as soon as the guard is dropped"]
+pub struct TargetGuard {
+    depth: usize,
+    /// The scope belongs to the thread that created it.
+    _thread: PhantomData<*const ()>,
+}
+
+impl Drop for TargetGuard {
+    fn drop(&mut self) {
+        // Dropping an outer guard first also ends the scopes nested in it.
+        let _ = SCOPES.try_with(|scopes| scopes.borrow_mut().truncate(self.depth));
+    }
+}
+
+/// The target of the messages of the current thread without one of their own.
+pub(crate) fn current_target() -> Option<String> {
+    SCOPES
+        .try_with(|scopes| scopes.borrow().last().cloned())
+        .ok()
+        .flatten()
+        .or_else(crate::thread::component)
+}
+
+/// Whether the route of `route` applies to `target`.
+fn matches(route: &str, target: &str) -> bool {
+    match target.strip_prefix(route) {
+        Some(rest) => rest.is_empty() || rest.starts_with("::"),
+        None => false,
+    }
+}
+
+/// The sink and mode of the route of `target`, if any.
+fn find(target: &str) -> Option<(Sink, RouteMode)> {
+    let routes = ROUTES.lock().unwrap_or_else(|e| e.into_inner());
+    routes
+        .iter()
+        .filter(|route| matches(&route.target, target))
+        .max_by_key(|route| route.target.len())
+        .map(|route| (route.sink.clone(), route.mode))
+}
+
+/// Whether `target` has a route.
+pub(crate) fn routed(target: Option<&str>) -> bool {
+    target.and_then(find).is_some()
+}
+
+/// Flush the sink of every route, returning the first failure.
+pub(crate) fn flush() -> io::Result<()> {
+    let routes = ROUTES.lock().unwrap_or_else(|e| e.into_inner());
+    routes
+        .iter()
+        .map(|route| route.sink.lock().unwrap_or_else(|e| e.into_inner()).flush())
+        .fold(Ok(()), io::Result::and)
+}
+
+/// Write a line to the route of `target`, returning whether the line must still be printed to its
+/// stream.
+pub(crate) fn write<F>(target: Option<&str>, print: F) -> io::Result<bool>
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()>,
+{
+    let Some((sink, mode)) = target.and_then(find) else {
+        return Ok(true);
+    };
+    // The output lock is taken before the sink, like everywhere else.
+    let _output = crate::output::lock();
+    let mut sink = sink.lock().unwrap_or_else(|e| e.into_inner());
+    print(&mut *sink)?;
+    Ok(mode == RouteMode::Also)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{clear_routes, current_target, route_target, route_target_with, RouteMode};
+    use super::{matches, target_scope};
+    use crate::test::{auto_colors, global_lock, Shared};
+    use crate::{print_record_to, CarlogStream, Record};
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("net", "net"));
+        assert!(matches("net", "net::http"));
+        assert!(!matches("net", "network"));
+        assert!(!matches("net::http", "net"));
+    }
+
+    #[test]
+    fn test_routes() {
+        let _lock = global_lock();
+        auto_colors();
+        let (net, db) = (Shared::default(), Shared::default());
+        route_target("net", net.clone());
+        route_target_with("db", db.clone(), RouteMode::Also);
+        let mut output = Vec::<u8>::new();
+        let mut print = |record: Record| {
+            print_record_to(&record, CarlogStream::Custom(&mut output)).unwrap();
+        };
+        print(Record::new("Fetching", "index").target("net::http"));
+        print(Record::new("Migrating", "schema").target("db"));
+        print(Record::new("Compiling", "app").target("network"));
+        {
+            let _scope = target_scope("net");
+            assert_eq!(current_target().as_deref(), Some("net"));
+            print(Record::new("Connecting", "crates.io"));
+        }
+        print(Record::new("Finished", "dev"));
+        clear_routes();
+        crate::test::force_colors();
+
+        assert_eq!(net.text(), "    Fetching index\n  Connecting crates.io\n");
+        assert_eq!(db.text(), "   Migrating schema\n");
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "   Migrating schema\n   Compiling app\n    Finished dev\n"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Color scales to pick the status color from a value.
+
+use crate::CargoColor;
+
+/// How a normalized value is matched against the scale stops.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Mode {
+    /// The color of the highest stop the value reaches.
+    Threshold,
+    /// The color of the stop closest to the value.
+    Nearest,
+}
+
+/// Maps a value to a [`CargoColor`].
+///
+/// Values are first normalized to a ratio of the scale range (`0.0..=1.0` by default), flipped when
+/// the scale is [inverted](Self::inverted) so that lower values are better, and then matched against
+/// the color stops, which are expressed as ratios of the range.
+///
+/// The default scale maps ratios `>= 0.9` to green, `>= 0.6` to yellow and everything else
+/// (including NaN) to red.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+///
+/// let coverage = ColorScale::default();
+/// assert_eq!(coverage.color(0.73), CargoColor::BrightYellow);
+///
+/// // Latency in milliseconds, lower is better.
+/// let latency = ColorScale::new(0.8, 0.5).range(0.0, 500.0).inverted();
+/// assert_eq!(latency.color(20.0), CargoColor::Green);
+/// assert_eq!(latency.color(400.0), CargoColor::BrightRed);
+///
+/// let status = Status::new().status("Coverage").color_by(&coverage, 0.73);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorScale {
+    min: f64,
+    max: f64,
+    inverted: bool,
+    mode: Mode,
+    stops: Vec<(f64, CargoColor)>,
+}
+
+impl Default for ColorScale {
+    fn default() -> Self {
+        Self::new(0.9, 0.6)
+    }
+}
+
+impl ColorScale {
+    /// Create a green/yellow/red scale with custom thresholds.
+    ///
+    /// * `green`: The ratio from which values are green.
+    /// * `yellow`: The ratio from which values are yellow, values below it are red.
+    pub fn new(green: f64, yellow: f64) -> Self {
+        Self::with_mode(
+            Mode::Threshold,
+            [
+                (green, CargoColor::Green),
+                (yellow, CargoColor::BrightYellow),
+                (f64::NEG_INFINITY, CargoColor::BrightRed),
+            ],
+        )
+    }
+
+    /// Create a scale from custom thresholds: a value takes the color of the highest threshold it
+    /// reaches, or of the lowest threshold when it reaches none.
+    pub fn thresholds<I>(stops: I) -> Self
+    where
+        I: IntoIterator<Item = (f64, CargoColor)>,
+    {
+        Self::with_mode(Mode::Threshold, stops)
+    }
+
+    /// Create a scale from custom color stops: a value takes the color of the nearest stop.
+    pub fn stops<I>(stops: I) -> Self
+    where
+        I: IntoIterator<Item = (f64, CargoColor)>,
+    {
+        Self::with_mode(Mode::Nearest, stops)
+    }
+
+    fn with_mode<I>(mode: Mode, stops: I) -> Self
+    where
+        I: IntoIterator<Item = (f64, CargoColor)>,
+    {
+        let mut stops: Vec<(f64, CargoColor)> =
+            stops.into_iter().filter(|(at, _)| !at.is_nan()).collect();
+        stops.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Self {
+            min: 0.0,
+            max: 1.0,
+            inverted: false,
+            mode,
+            stops,
+        }
+    }
+
+    /// Use an absolute range instead of ratios: `min` maps to `0.0` and `max` to `1.0`.
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    /// Make lower values better, i.e. for latencies or disk usage.
+    pub fn inverted(mut self) -> Self {
+        self.inverted = true;
+        self
+    }
+
+    /// The color for `value`.
+    ///
+    /// Scales without stops always return the default color.
+    pub fn color(&self, value: f64) -> CargoColor {
+        let worst = match self.stops.last() {
+            Some((_, color)) => *color,
+            None => return CargoColor::default(),
+        };
+        let span = self.max - self.min;
+        let mut ratio = if span == 0.0 {
+            f64::from(u8::from(value >= self.max))
+        } else {
+            (value - self.min) / span
+        };
+        if self.inverted {
+            ratio = 1.0 - ratio;
+        }
+        if ratio.is_nan() {
+            return worst;
+        }
+        match self.mode {
+            Mode::Threshold => self
+                .stops
+                .iter()
+                .find(|(at, _)| ratio >= *at)
+                .map_or(worst, |(_, color)| *color),
+            Mode::Nearest => self
+                .stops
+                .iter()
+                .min_by(|a, b| (a.0 - ratio).abs().total_cmp(&(b.0 - ratio).abs()))
+                .map_or(worst, |(_, color)| *color),
+        }
+    }
+}
+
+impl CargoColor {
+    /// Pick a color from a ratio with the default [`ColorScale`]: `>= 0.9` is green, `>= 0.6`
+    /// yellow and anything else red.
+    ///
+    /// ## Example
+    /// ```
+    /// use carlog::prelude::*;
+    ///
+    /// assert_eq!(CargoColor::from_ratio(0.95), CargoColor::Green);
+    /// ```
+    pub fn from_ratio(ratio: f64) -> Self {
+        ColorScale::default().color(ratio)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ColorScale;
+    use crate::CargoColor;
+
+    #[test]
+    fn test_from_ratio_boundaries() {
+        assert_eq!(CargoColor::from_ratio(1.0), CargoColor::Green);
+        assert_eq!(CargoColor::from_ratio(0.9), CargoColor::Green);
+        assert_eq!(CargoColor::from_ratio(0.899), CargoColor::BrightYellow);
+        assert_eq!(CargoColor::from_ratio(0.6), CargoColor::BrightYellow);
+        assert_eq!(CargoColor::from_ratio(0.599), CargoColor::BrightRed);
+        assert_eq!(CargoColor::from_ratio(-3.0), CargoColor::BrightRed);
+        assert_eq!(CargoColor::from_ratio(f64::NAN), CargoColor::BrightRed);
+    }
+
+    #[test]
+    fn test_absolute_and_inverted() {
+        let disk = ColorScale::default().range(0.0, 200.0).inverted();
+        assert_eq!(disk.color(10.0), CargoColor::Green);
+        assert_eq!(disk.color(20.0), CargoColor::Green);
+        assert_eq!(disk.color(21.0), CargoColor::BrightYellow);
+        assert_eq!(disk.color(70.0), CargoColor::BrightYellow);
+        assert_eq!(disk.color(190.0), CargoColor::BrightRed);
+        let score = ColorScale::new(0.5, 0.25).range(0.0, 10.0);
+        assert_eq!(score.color(5.0), CargoColor::Green);
+        assert_eq!(score.color(2.5), CargoColor::BrightYellow);
+        assert_eq!(score.color(2.0), CargoColor::BrightRed);
+    }
+
+    #[test]
+    fn test_custom_stops() {
+        let scale = ColorScale::stops([
+            (0.0, CargoColor::BrightRed),
+            (0.5, CargoColor::BrightYellow),
+            (1.0, CargoColor::Cyan),
+        ]);
+        assert_eq!(scale.color(0.2), CargoColor::BrightRed);
+        assert_eq!(scale.color(0.3), CargoColor::BrightYellow);
+        assert_eq!(scale.color(0.74), CargoColor::BrightYellow);
+        assert_eq!(scale.color(0.8), CargoColor::Cyan);
+        assert_eq!(scale.color(7.0), CargoColor::Cyan);
+        let thresholds =
+            ColorScale::thresholds([(0.5, CargoColor::Cyan), (0.2, CargoColor::White)]);
+        assert_eq!(thresholds.color(0.6), CargoColor::Cyan);
+        assert_eq!(thresholds.color(0.3), CargoColor::White);
+        assert_eq!(thresholds.color(0.1), CargoColor::White);
+    }
+}
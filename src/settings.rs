@@ -0,0 +1,330 @@
+//! Global settings that are decided by the build profile, the environment or the program.
+
+use crate::env::{EnvSource, ProcessEnv};
+use crate::{Format, Level};
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+static MAX_LEVEL_SOURCE: AtomicU8 = AtomicU8::new(SettingSource::Default as u8);
+static LOCATIONS: AtomicBool = AtomicBool::new(true);
+static LOCATIONS_SOURCE: AtomicU8 = AtomicU8::new(SettingSource::Default as u8);
+static FORMAT_SOURCE: AtomicU8 = AtomicU8::new(SettingSource::Default as u8);
+static QUIET: AtomicBool = AtomicBool::new(false);
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// The number of live [`quiet_scope`]s of the thread.
+    static QUIET_SCOPES: Cell<usize> = const { Cell::new(0) };
+}
+
+/// What decided the value of a setting.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SettingSource {
+    /// The default of the crate or of the build profile, see [`init_default`].
+    #[default]
+    Default,
+    /// An environment variable, i.e. `CARLOG_LOG`.
+    Env,
+    /// The program, i.e. [`set_max_level`].
+    Programmatic,
+}
+
+impl SettingSource {
+    fn from_u8(source: u8) -> Self {
+        match source {
+            1 => SettingSource::Env,
+            2 => SettingSource::Programmatic,
+            _ => SettingSource::Default,
+        }
+    }
+
+    /// The name of the source, as shown by the [environment report](crate::print_env_report).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SettingSource::Default => "default",
+            SettingSource::Env => "env",
+            SettingSource::Programmatic => "programmatic",
+        }
+    }
+}
+
+/// What decided every setting, see [`setting_sources`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SettingSources {
+    /// The source of [`max_level`].
+    pub max_level: SettingSource,
+    /// The source of [`locations`].
+    pub locations: SettingSource,
+    /// The source of the [format](crate::format).
+    pub format: SettingSource,
+}
+
+/// What decided every setting.
+pub fn setting_sources() -> SettingSources {
+    SettingSources {
+        max_level: SettingSource::from_u8(MAX_LEVEL_SOURCE.load(Ordering::Relaxed)),
+        locations: SettingSource::from_u8(LOCATIONS_SOURCE.load(Ordering::Relaxed)),
+        format: SettingSource::from_u8(FORMAT_SOURCE.load(Ordering::Relaxed)),
+    }
+}
+
+/// Only print the messages at least as severe as `level`, `Info` by default.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+///
+/// carlog::set_max_level(Level::Warning);
+/// assert_eq!(carlog::max_level(), Level::Warning);
+/// # carlog::set_max_level(Level::Info);
+/// ```
+pub fn set_max_level(level: Level) {
+    store_max_level(level, SettingSource::Programmatic);
+}
+
+/// The least severe level printed, see [`set_max_level`].
+pub fn max_level() -> Level {
+    match MAX_LEVEL.load(Ordering::Relaxed) {
+        0 => Level::Trace,
+        1 => Level::Debug,
+        3 => Level::Warning,
+        4 => Level::Error,
+        _ => Level::Info,
+    }
+}
+
+/// Only print warnings and errors, as with `-q`: the info, ok and debug messages and the ones of
+/// `carlog!` are filtered out whatever the [max level](set_max_level) and [`set_verbose`].
+///
+/// ## Example
+/// ```
+/// carlog::set_quiet(true);
+/// carlog::carlog_info!("Compiling", "carlog v0.1.0"); // Not printed.
+/// carlog::carlog_warning!("unused import"); // Printed.
+/// # carlog::set_quiet(false);
+/// ```
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Whether only warnings and errors are printed in the current thread, see [`set_quiet`] and
+/// [`quiet_scope`].
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed) || QUIET_SCOPES.try_with(Cell::get).unwrap_or(0) > 0
+}
+
+/// Print the messages of every level, as with `-v`, i.e. the ones of `carlog_debug!` and
+/// `carlog_trace!`, unless [quiet](set_quiet).
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+/// Whether the messages of every level are printed, see [`set_verbose`].
+pub fn verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// Make the current thread [quiet](set_quiet) until the returned guard is dropped, i.e. in a
+/// library silencing the output of a step.
+///
+/// Scopes nest and are restored when unwinding from a panic. Other threads keep printing.
+///
+/// ## Example
+/// ```
+/// {
+///     let _quiet = carlog::quiet_scope();
+///     assert!(carlog::quiet());
+/// }
+/// assert!(!carlog::quiet());
+/// ```
+pub fn quiet_scope() -> QuietGuard {
+    QUIET_SCOPES.with(|scopes| scopes.set(scopes.get() + 1));
+    QuietGuard {
+        _thread: PhantomData,
+    }
+}
+
+/// Restores the previous quietness of the thread when dropped, see [`quiet_scope`].
+#[must_use = "the output is restored as soon as the guard is dropped"]
+pub struct QuietGuard {
+    /// The scope belongs to the thread that created it.
+    _thread: PhantomData<*const ()>,
+}
+
+impl Drop for QuietGuard {
+    fn drop(&mut self) {
+        let _ = QUIET_SCOPES.try_with(|scopes| scopes.set(scopes.get().saturating_sub(1)));
+    }
+}
+
+/// The least severe level printed after [`set_quiet`] and [`set_verbose`], quiet winning over
+/// verbose.
+pub(crate) fn threshold() -> Level {
+    if quiet() {
+        max_level().max(Level::Warning)
+    } else if verbose() {
+        Level::Trace
+    } else {
+        max_level()
+    }
+}
+
+/// Whether the location of the messages is printed with [`Format::Short`](crate::Format::Short),
+/// `true` by default.
+pub fn set_locations(locations: bool) {
+    store_locations(locations, SettingSource::Programmatic);
+}
+
+/// Whether the location of the messages is printed, see [`set_locations`].
+pub fn locations() -> bool {
+    LOCATIONS.load(Ordering::Relaxed)
+}
+
+fn store_max_level(level: Level, source: SettingSource) {
+    MAX_LEVEL.store(level as u8, Ordering::Relaxed);
+    MAX_LEVEL_SOURCE.store(source as u8, Ordering::Relaxed);
+}
+
+fn store_locations(locations: bool, source: SettingSource) {
+    LOCATIONS.store(locations, Ordering::Relaxed);
+    LOCATIONS_SOURCE.store(source as u8, Ordering::Relaxed);
+}
+
+/// Record what decided the [format](crate::format).
+pub(crate) fn store_format_source(source: SettingSource) {
+    FORMAT_SOURCE.store(source as u8, Ordering::Relaxed);
+}
+
+/// Configure the defaults of the build profile: chatty debug builds and quiet release builds.
+///
+/// | Setting         | Debug build | Release build |
+/// |-----------------|-------------|---------------|
+/// | [`max_level`]   | `Debug`     | `Info`        |
+/// | [`locations`]   | on          | off           |
+///
+/// Settings already configured by the program are kept, so the precedence is:
+/// 1. The program, i.e. [`set_max_level`] before or after this call.
+/// 2. The environment: `CARLOG_LOG` set to `error`, `warning`, `info`, `debug` or `trace` for the
+///    max level, and `GITHUB_ACTIONS` set to `true`, like in the workflows of GitHub, for the
+///    [GitHub Actions format](crate::Format::GithubActions).
+/// 3. The build profile.
+///
+/// See [`setting_sources`] for what decided every setting.
+///
+/// ## Example
+/// ```
+/// carlog::init_default();
+/// ```
+pub fn init_default() {
+    init(&ProcessEnv, cfg!(debug_assertions));
+}
+
+fn init<E>(env: &E, debug: bool)
+where
+    E: EnvSource,
+{
+    let sources = setting_sources();
+    if sources.max_level != SettingSource::Programmatic {
+        match env.var("CARLOG_LOG").and_then(|value| parse_level(&value)) {
+            Some(level) => store_max_level(level, SettingSource::Env),
+            None if debug => store_max_level(Level::Debug, SettingSource::Default),
+            None => store_max_level(Level::Info, SettingSource::Default),
+        }
+    }
+    if sources.locations != SettingSource::Programmatic {
+        store_locations(debug, SettingSource::Default);
+    }
+    if sources.format != SettingSource::Programmatic {
+        let format = match env.var("GITHUB_ACTIONS").as_deref() {
+            Some("true") => (Format::GithubActions, SettingSource::Env),
+            _ => (Format::Human, SettingSource::Default),
+        };
+        crate::store_format(format.0);
+        store_format_source(format.1);
+    }
+}
+
+fn parse_level(value: &str) -> Option<Level> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "error" => Some(Level::Error),
+        "warning" | "warn" => Some(Level::Warning),
+        "info" => Some(Level::Info),
+        "debug" => Some(Level::Debug),
+        "trace" => Some(Level::Trace),
+        _ => None,
+    }
+}
+
+/// Reset every setting to the default of the crate.
+#[cfg(test)]
+pub(crate) fn reset() {
+    store_max_level(Level::Info, SettingSource::Default);
+    store_locations(true, SettingSource::Default);
+    set_quiet(false);
+    set_verbose(false);
+    crate::store_format(Format::Human);
+    store_format_source(SettingSource::Default);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        init, locations, max_level, reset, set_locations, set_max_level, setting_sources,
+        SettingSource, SettingSources,
+    };
+    use crate::test::global_lock;
+    use crate::{format, set_format, Format, Level};
+
+    #[test]
+    fn test_init_precedence() {
+        let _lock = global_lock();
+        reset();
+        init(&[], true);
+        assert_eq!((max_level(), locations()), (Level::Debug, true));
+        init(&[], false);
+        assert_eq!((max_level(), locations()), (Level::Info, false));
+        assert_eq!(setting_sources(), SettingSources::default());
+
+        init(&[("CARLOG_LOG", "Warn")], true);
+        assert_eq!(max_level(), Level::Warning);
+        assert_eq!(setting_sources().max_level, SettingSource::Env);
+        init(&[("CARLOG_LOG", "chatty")], false);
+        assert_eq!(max_level(), Level::Info);
+        assert_eq!(setting_sources().max_level, SettingSource::Default);
+
+        set_max_level(Level::Error);
+        set_locations(true);
+        init(&[("CARLOG_LOG", "debug")], false);
+        assert_eq!((max_level(), locations()), (Level::Error, true));
+        assert_eq!(
+            setting_sources(),
+            SettingSources {
+                max_level: SettingSource::Programmatic,
+                locations: SettingSource::Programmatic,
+                format: SettingSource::Default,
+            }
+        );
+        reset();
+    }
+
+    #[test]
+    fn test_github_actions_detection() {
+        let _lock = global_lock();
+        reset();
+        init(&[("GITHUB_ACTIONS", "true")], false);
+        assert_eq!(format(), Format::GithubActions);
+        assert_eq!(setting_sources().format, SettingSource::Env);
+        init(&[("GITHUB_ACTIONS", "false")], false);
+        assert_eq!(format(), Format::Human);
+        assert_eq!(setting_sources().format, SettingSource::Default);
+
+        set_format(Format::Short);
+        init(&[("GITHUB_ACTIONS", "true")], false);
+        assert_eq!(format(), Format::Short);
+        assert_eq!(setting_sources().format, SettingSource::Programmatic);
+        reset();
+        assert_eq!(format(), Format::Human);
+    }
+}
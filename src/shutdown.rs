@@ -0,0 +1,111 @@
+//! The end of the output of the program, after which late messages still print as best they can.
+//!
+//! The global state of the crate is made of statics initialized on first use, so the macros
+//! work at any point of the program, i.e. from the initializers that run before `main`, without
+//! any call to [`init_default`](crate::init_default). Messages printed after [`shutdown`], i.e.
+//! from atexit handlers or the destructors of statics, degrade instead of aborting the program:
+//! * The messages to stdout, which may already be closed, go to stderr.
+//! * A message that fails to print is dropped, where the macros would otherwise pass the failure
+//!   to the [error handler](crate::set_error_handler).
+
+use std::io::{stderr, stdout, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static SHUT_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Flush the output and detach stdout, see the [module](self) for what happens to later messages.
+///
+/// Calling it again does nothing.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog_ok!("Finished", "dev [unoptimized] target(s)");
+/// carlog::shutdown();
+/// carlog_info!("Cleaning", "temporary files"); // Printed to stderr.
+/// carlog::shutdown();
+/// ```
+pub fn shutdown() {
+    if SHUT_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let _ = crate::dedup::flush();
+    let _output = crate::output::lock();
+    let _ = stdout().flush();
+    let _ = stderr().flush();
+}
+
+/// Whether [`shutdown`] was called.
+pub fn is_shut_down() -> bool {
+    SHUT_DOWN.load(Ordering::SeqCst)
+}
+
+/// Undo [`shutdown`].
+#[cfg(test)]
+pub(crate) fn reset() {
+    SHUT_DOWN.store(false, Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_shut_down, reset, shutdown};
+    use crate::test::global_lock;
+    use crate::{__printed, record_transcript, transcript, Status, Target};
+    use std::io;
+    use std::panic;
+    use std::thread;
+
+    #[test]
+    fn test_shutdown() {
+        let _lock = global_lock();
+        let failure = || Err(io::Error::from(io::ErrorKind::PermissionDenied).into());
+        assert!(panic::catch_unwind(|| __printed(failure())).is_err());
+
+        shutdown();
+        shutdown();
+        assert!(is_shut_down());
+        record_transcript(true);
+        Status::plain()
+            .status("Cleaning")
+            .print_stream(&mut crate::CarlogStream::Stdout, " temporary files")
+            .unwrap();
+        record_transcript(false);
+        __printed(failure());
+        reset();
+        let lines: Vec<(Target, String)> = transcript()
+            .into_iter()
+            .map(|entry| (entry.target, entry.line))
+            .collect();
+        assert_eq!(
+            lines,
+            [(Target::Stderr, "Cleaning temporary files".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_thread_local_destructor() {
+        struct Late;
+
+        impl Drop for Late {
+            fn drop(&mut self) {
+                let mut output = Vec::<u8>::new();
+                Status::new()
+                    .color(crate::theme().info)
+                    .status("Dropped")
+                    .print(&mut output, " late")
+                    .unwrap();
+                assert!(output.ends_with(b" late\n"));
+            }
+        }
+
+        thread_local! {
+            static LATE: Late = const { Late };
+        }
+
+        let _lock = global_lock();
+        thread::spawn(|| LATE.with(|_| ())).join().unwrap();
+    }
+}
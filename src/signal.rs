@@ -0,0 +1,64 @@
+//! Unix signal integration so external tools like logrotate can ask the file sinks to reopen their
+//! files.
+//!
+//! The signal handler does no work besides bumping an atomic generation counter; every
+//! [`FileSink`](crate::sinks::FileSink) compares the counter with the one it last saw on its next
+//! write and reopens its file when they differ.
+
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static REOPEN_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// Signals that can be used to request a reopen of the file sinks.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Signal {
+    Hup,
+    Usr1,
+    Usr2,
+}
+
+impl Signal {
+    fn number(self) -> libc::c_int {
+        match self {
+            Self::Hup => libc::SIGHUP,
+            Self::Usr1 => libc::SIGUSR1,
+            Self::Usr2 => libc::SIGUSR2,
+        }
+    }
+}
+
+/// Reopen every file sink on its next write whenever `signal` is received.
+///
+/// * `signal`: The signal to listen to, usually the one configured in the logrotate `postrotate`
+///   script.
+///
+/// ## Example
+/// ```no_run
+/// use carlog::Signal;
+///
+/// carlog::install_reopen_on(Signal::Hup).unwrap();
+/// ```
+pub fn install_reopen_on(signal: Signal) -> io::Result<()> {
+    extern "C" fn handler(_: libc::c_int) {
+        REOPEN_GENERATION.fetch_add(1, Ordering::SeqCst);
+    }
+
+    // SAFETY: the handler only touches an atomic, which is async-signal-safe, and the sigaction
+    // struct is fully initialized before being handed to the kernel.
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        if libc::sigaction(signal.number(), &action, std::ptr::null_mut()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// The number of reopen requests received so far.
+pub(crate) fn reopen_generation() -> usize {
+    REOPEN_GENERATION.load(Ordering::SeqCst)
+}
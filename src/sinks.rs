@@ -0,0 +1,1306 @@
+//! Writeable sinks that can be used as custom carlog streams.
+//!
+//! ## Example
+//! ```no_run
+//! use carlog::prelude::*;
+//! use carlog::sinks::{FileSink, MB};
+//!
+//! let mut sink = FileSink::builder("build.log")
+//!     .rotate_size(50 * MB)
+//!     .rotate_daily()
+//!     .keep(7)
+//!     .open()
+//!     .unwrap();
+//! let status = Status::new().bold().justify().color(CargoColor::Green).status("Compiled");
+//! status.print(&mut sink, " carlog v0.1.0").unwrap();
+//! ```
+
+#[cfg(unix)]
+use crate::signal::reopen_generation;
+use crate::time::{days_since_epoch, UtcDateTime};
+use crate::wire::Fields;
+use crate::{error, sys};
+use crate::{fmt, CargoColor, Format, Level, Metadata, Status};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+
+thread_local! {
+    /// The level of the message the thread is writing, see [`line_level`].
+    static LEVEL: Cell<Option<Level>> = const { Cell::new(None) };
+}
+
+/// One kibibyte, to be used with [`FileSinkBuilder::rotate_size`].
+pub const KB: u64 = 1024;
+
+/// One mebibyte, to be used with [`FileSinkBuilder::rotate_size`].
+pub const MB: u64 = 1024 * KB;
+
+/// One gibibyte, to be used with [`FileSinkBuilder::rotate_size`].
+pub const GB: u64 = 1024 * MB;
+
+/// Builder for a [`FileSink`].
+///
+/// ## Example
+/// ```no_run
+/// use carlog::sinks::{FileSink, MB};
+///
+/// let sink = FileSink::builder("build.log").rotate_size(10 * MB).keep(3).open();
+/// ```
+pub struct FileSinkBuilder {
+    path: PathBuf,
+    rotate_size: Option<u64>,
+    rotate_daily: bool,
+    keep: Option<usize>,
+    lock_per_line: bool,
+    lock_on_open: bool,
+    header: bool,
+    metadata: Option<Metadata>,
+    clock: fn() -> SystemTime,
+}
+
+impl FileSinkBuilder {
+    /// Rotate the file before a line would make it grow past `bytes`.
+    ///
+    /// A single line larger than the limit is still written in full to a fresh file.
+    pub fn rotate_size(mut self, bytes: u64) -> Self {
+        self.rotate_size = Some(bytes);
+        self
+    }
+
+    /// Rotate the file on the first line written after UTC midnight.
+    pub fn rotate_daily(mut self) -> Self {
+        self.rotate_daily = true;
+        self
+    }
+
+    /// Keep at most `count` rotated files, deleting the oldest ones.
+    ///
+    /// By default rotated files are never deleted.
+    pub fn keep(mut self, count: usize) -> Self {
+        self.keep = Some(count);
+        self
+    }
+
+    /// Take an advisory lock on the file around every line written.
+    ///
+    /// Use this when several processes append to the same file so their lines never interleave. If
+    /// the platform refuses the lock a warning is printed once and lines are written unlocked.
+    pub fn lock_per_line(mut self, lock: bool) -> Self {
+        self.lock_per_line = lock;
+        self
+    }
+
+    /// Take an exclusive advisory lock on the file for as long as the sink is open.
+    ///
+    /// Opening the sink fails with `ErrorKind::WouldBlock` while another process holds the lock.
+    /// This is cheaper than [`lock_per_line`](Self::lock_per_line), which is redundant when both are
+    /// set.
+    pub fn lock_on_open(mut self, lock: bool) -> Self {
+        self.lock_on_open = lock;
+        self
+    }
+
+    /// Whether to start every log file with a provenance header (enabled by default).
+    ///
+    /// The header is made of `#` comment lines with the application name and version, the command
+    /// line, the start time, the host name, the sink configuration and the global one from
+    /// [`config_snapshot`](crate::config_snapshot), i.e:
+    /// ```text
+    /// # mytool 1.2.0
+    /// # command: mytool build --release
+    /// # started: 2024-05-12T14:03:22Z
+    /// # host: buildbox
+    /// # carlog: rotate_size=52428800 rotate_daily=true keep=7 lock=none
+    /// # config: snapshot=3 format=Human max_level=Info verbosity=0 quiet=false verbose=false ...
+    /// ```
+    /// With the [JSON format](crate::Format::Json) the header is a single [wire](crate::wire)
+    /// record instead, with the status `carlog`, the identifier `carlog-header` and the same
+    /// information as fields, starting with `header_version`, [`HEADER_VERSION`] when written.
+    ///
+    /// It is written at the top of every new file, whether the sink opens it, starts it on a
+    /// rotation or on a reopen; appending to an existing log doesn't repeat it.
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Set the application metadata shown in the header.
+    ///
+    /// Defaults to [`Metadata::from_env`].
+    pub fn metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn clock(mut self, clock: fn() -> SystemTime) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Open (create or append to) the file and build the sink.
+    pub fn open(self) -> io::Result<FileSink> {
+        let file = open_append(&self.path)?;
+        let written = file.metadata()?.len();
+        let day = days_since_epoch((self.clock)());
+        let metadata = self
+            .header
+            .then(|| self.metadata.unwrap_or_else(Metadata::from_env));
+        let sink = FileSink {
+            path: self.path,
+            rotate_size: self.rotate_size,
+            rotate_daily: self.rotate_daily,
+            keep: self.keep,
+            lock_per_line: self.lock_per_line && !self.lock_on_open,
+            lock_on_open: self.lock_on_open,
+            metadata,
+            clock: self.clock,
+            state: Mutex::new(FileState {
+                file,
+                written,
+                header_len: 0,
+                day,
+                pending: Vec::new(),
+                rotate_warned: false,
+                rotate_failed_at: None,
+                lock_failed: false,
+                generation: reopen_generation(),
+            }),
+        };
+        {
+            let mut guard = sink.lock();
+            let state = &mut *guard;
+            sink.lock_opened(&state.file, &mut state.lock_failed)?;
+            if state.written == 0 {
+                sink.write_header(state)?;
+            }
+        }
+        Ok(sink)
+    }
+}
+
+/// The version of the header record of the JSON format, see [`FileSinkBuilder::header`].
+pub const HEADER_VERSION: u32 = 1;
+
+/// Sink that appends lines to a file, optionally rotating it.
+///
+/// The sink buffers partial lines and only ever hands whole lines to the file, so rotation always
+/// happens at a line boundary. Both `FileSink` and `&FileSink` implement `Write`; a shared sink can be
+/// written from several threads and every complete line lands in a single file untouched.
+///
+/// Partial lines are held until their newline arrives or the sink is dropped.
+///
+/// Rotated files are renamed to `<file name>.<UTC timestamp>` (with an extra `.<n>` index when
+/// several rotations happen within the same second) next to the original file.
+pub struct FileSink {
+    path: PathBuf,
+    rotate_size: Option<u64>,
+    rotate_daily: bool,
+    keep: Option<usize>,
+    lock_per_line: bool,
+    lock_on_open: bool,
+    metadata: Option<Metadata>,
+    clock: fn() -> SystemTime,
+    state: Mutex<FileState>,
+}
+
+struct FileState {
+    file: File,
+    written: u64,
+    header_len: u64,
+    day: i64,
+    pending: Vec<u8>,
+    rotate_warned: bool,
+    /// The size of the file when its rotation failed, see `rotate_if_needed`.
+    rotate_failed_at: Option<u64>,
+    lock_failed: bool,
+    generation: usize,
+}
+
+impl FileSink {
+    /// Open (create or append to) a file sink without rotation.
+    ///
+    /// * `path`: The path of the log file.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use carlog::sinks::FileSink;
+    ///
+    /// let sink = FileSink::new("build.log").unwrap();
+    /// ```
+    pub fn new<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::builder(path).open()
+    }
+
+    /// Create a builder for a file sink writing to `path`.
+    pub fn builder<P>(path: P) -> FileSinkBuilder
+    where
+        P: AsRef<Path>,
+    {
+        FileSinkBuilder {
+            path: path.as_ref().to_path_buf(),
+            rotate_size: None,
+            rotate_daily: false,
+            keep: None,
+            lock_per_line: false,
+            lock_on_open: false,
+            header: true,
+            metadata: None,
+            clock: SystemTime::now,
+        }
+    }
+
+    /// The path the sink writes to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Close and reopen the file at the sink's path.
+    ///
+    /// Use this after an external tool moved the file away: following lines are written to a new
+    /// file at the original path. Lines still waiting for their newline are kept and written to the
+    /// new file.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// use carlog::sinks::FileSink;
+    ///
+    /// let sink = FileSink::new("build.log").unwrap();
+    /// std::fs::rename("build.log", "build.log.1").unwrap();
+    /// sink.reopen().unwrap();
+    /// ```
+    pub fn reopen(&self) -> io::Result<()> {
+        let mut state = self.lock();
+        self.reopen_locked(&mut state)
+    }
+
+    fn reopen_locked(&self, state: &mut FileState) -> io::Result<()> {
+        state.file.flush()?;
+        let file = open_append(&self.path)?;
+        self.lock_opened(&file, &mut state.lock_failed)?;
+        state.written = file.metadata()?.len();
+        state.file = file;
+        if state.written == 0 {
+            self.write_header(state)?;
+        }
+        Ok(())
+    }
+
+    fn write_header(&self, state: &mut FileState) -> io::Result<()> {
+        let metadata = match &self.metadata {
+            Some(metadata) => metadata,
+            None => return Ok(()),
+        };
+        let name = format!("{} {}", metadata.name, metadata.version);
+        let name = name.trim_end();
+        let command: Vec<String> = std::env::args_os()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        let command = command.join(" ");
+        let now = (self.clock)();
+        let started = UtcDateTime::from_system_time(now).rfc3339();
+        let host = sys::hostname();
+        let sink = self.config_summary();
+        let config = crate::config_snapshot().summary();
+        let header = if crate::format() == Format::Json {
+            let version = HEADER_VERSION.to_string();
+            let mut fields = vec![
+                ("header_version", version.as_str()),
+                ("command", command.as_str()),
+                ("started", started.as_str()),
+            ];
+            fields.extend(host.as_deref().map(|host| ("host", host)));
+            fields.extend([("carlog", sink.as_str()), ("config", config.as_str())]);
+            let mut header = String::new();
+            Fields {
+                status: "carlog",
+                message: name,
+                fields,
+                id: Some("carlog-header"),
+                timestamp: Some(now),
+                ..Fields::default()
+            }
+            .write(&mut header);
+            header.push('\n');
+            header
+        } else {
+            let mut header = format!("# {}\n# command: {}\n", name, command);
+            header.push_str(&format!("# started: {}\n", started));
+            if let Some(host) = &host {
+                header.push_str(&format!("# host: {}\n", host));
+            }
+            header.push_str(&format!("# carlog: {}\n", sink));
+            header.push_str(&format!("# config: {}\n", config));
+            header
+        };
+        self.write_line(state, header.as_bytes())?;
+        state.written += header.len() as u64;
+        state.header_len = header.len() as u64;
+        Ok(())
+    }
+
+    fn config_summary(&self) -> String {
+        let lock = if self.lock_on_open {
+            "on-open"
+        } else if self.lock_per_line {
+            "per-line"
+        } else {
+            "none"
+        };
+        let rotate_size = self
+            .rotate_size
+            .map_or_else(|| "none".to_string(), |size| size.to_string());
+        let keep = self
+            .keep
+            .map_or_else(|| "all".to_string(), |keep| keep.to_string());
+        format!(
+            "rotate_size={} rotate_daily={} keep={} lock={}",
+            rotate_size, self.rotate_daily, keep, lock
+        )
+    }
+
+    /// Take the lock held while the file is open, if the sink was configured to.
+    fn lock_opened(&self, file: &File, lock_failed: &mut bool) -> io::Result<()> {
+        if !self.lock_on_open || *lock_failed {
+            return Ok(());
+        }
+        match sys::lock_file(file, false) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                format!(
+                    "log file `{}` is locked by another process",
+                    self.path.display()
+                ),
+            )),
+            Err(e) => {
+                self.lock_failed(lock_failed, e);
+                Ok(())
+            }
+        }
+    }
+
+    fn lock_failed(&self, lock_failed: &mut bool, e: io::Error) {
+        *lock_failed = true;
+        warn(format!(
+            "failed to lock log file `{}`, writing unlocked: {}",
+            self.path.display(),
+            e
+        ));
+    }
+
+    fn write_line(&self, state: &mut FileState, line: &[u8]) -> io::Result<()> {
+        let locked = self.lock_per_line && !state.lock_failed;
+        if locked {
+            if let Err(e) = sys::lock_file(&state.file, true) {
+                self.lock_failed(&mut state.lock_failed, e);
+                return state.file.write_all(line);
+            }
+        }
+        let result = state.file.write_all(line);
+        if locked {
+            let _ = sys::unlock_file(&state.file);
+        }
+        result
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FileState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write_lines(&self, state: &mut FileState) -> io::Result<()> {
+        let generation = reopen_generation();
+        if generation != state.generation {
+            state.generation = generation;
+            if let Err(e) = self.reopen_locked(state) {
+                warn(format!(
+                    "failed to reopen log file `{}`: {}",
+                    self.path.display(),
+                    e
+                ));
+            }
+        }
+        while let Some(end) = state.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = state.pending.drain(..=end).collect();
+            self.rotate_if_needed(state, line.len() as u64);
+            self.write_line(state, &line)?;
+            state.written += line.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn rotate_if_needed(&self, state: &mut FileState, next: u64) {
+        let today = days_since_epoch((self.clock)());
+        // After a failed rotation the file may grow by another limit before the next attempt.
+        let too_big = self.rotate_size.is_some_and(|limit| {
+            state.written + next > state.rotate_failed_at.unwrap_or_default() + limit
+        });
+        let new_day = self.rotate_daily && today != state.day;
+        if state.written > state.header_len && (too_big || new_day) {
+            match self.rotate(state) {
+                Ok(()) => state.rotate_failed_at = None,
+                Err(e) => {
+                    // Retrying on every line would rename, fail and warn for each of them, so
+                    // the next attempt waits for the next size limit or day.
+                    state.rotate_failed_at = Some(state.written);
+                    if !state.rotate_warned {
+                        state.rotate_warned = true;
+                        warn(format!(
+                            "failed to rotate log file `{}`: {}",
+                            self.path.display(),
+                            e
+                        ));
+                    }
+                }
+            }
+        }
+        state.day = today;
+    }
+
+    fn rotate(&self, state: &mut FileState) -> io::Result<()> {
+        state.file.flush()?;
+        let target = self.rotated_path();
+        if let Err(e) = fs::rename(&self.path, &target) {
+            if e.kind() == io::ErrorKind::NotFound {
+                // The file was deleted from under the sink, the next lines go to a new one rather
+                // than to the deleted file nobody can read.
+                state.header_len = 0;
+                let _ = self.reopen_locked(state);
+            }
+            return Err(e);
+        }
+        match open_append(&self.path).and_then(|file| {
+            self.lock_opened(&file, &mut state.lock_failed)
+                .map(|_| file)
+        }) {
+            Ok(file) => state.file = file,
+            Err(e) => {
+                // Keep writing to the renamed file rather than losing lines.
+                let _ = fs::rename(&target, &self.path);
+                return Err(e);
+            }
+        }
+        state.written = 0;
+        state.header_len = 0;
+        self.prune();
+        self.write_header(state)
+    }
+
+    fn rotated_path(&self) -> PathBuf {
+        let stamp = UtcDateTime::from_system_time((self.clock)()).compact();
+        let base = format!("{}.{}", self.file_name(), stamp);
+        let mut candidate = self.path.with_file_name(&base);
+        let mut index = 1;
+        while candidate.exists() {
+            candidate = self.path.with_file_name(format!("{}.{}", base, index));
+            index += 1;
+        }
+        candidate
+    }
+
+    fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// All the rotated files of this sink, oldest first.
+    pub fn rotated_files(&self) -> io::Result<Vec<PathBuf>> {
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let prefix = format!("{}.", self.file_name());
+        let mut rotated = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if let Some(suffix) = name.strip_prefix(&prefix) {
+                if let Some(key) = rotation_key(suffix) {
+                    rotated.push((key, entry.path()));
+                }
+            }
+        }
+        rotated.sort();
+        Ok(rotated.into_iter().map(|(_, path)| path).collect())
+    }
+
+    fn prune(&self) {
+        let keep = match self.keep {
+            Some(keep) => keep,
+            None => return,
+        };
+        if let Ok(rotated) = self.rotated_files() {
+            let excess = rotated.len().saturating_sub(keep);
+            for path in &rotated[..excess] {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+}
+
+impl Write for &FileSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.lock();
+        state.pending.extend_from_slice(buf);
+        self.write_lines(&mut state).map_err(|e| self.destined(e))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut state = self.lock();
+        self.write_lines(&mut state).map_err(|e| self.destined(e))?;
+        state.file.flush().map_err(|e| self.destined(e))
+    }
+}
+
+impl Write for FileSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+impl FileSink {
+    /// Describe the sink in an error, so that a failed print tells the path of the file.
+    fn destined(&self, error: io::Error) -> io::Error {
+        error::destined(error, format_args!("`{}`", self.path.display()))
+    }
+}
+
+impl Drop for FileSink {
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(|e| e.into_inner());
+        if !state.pending.is_empty() {
+            let _ = state.file.write_all(&state.pending);
+        }
+        let _ = state.file.flush();
+    }
+}
+
+#[cfg(not(unix))]
+fn reopen_generation() -> usize {
+    0
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Parse the `<timestamp>[.<n>]` suffix of a rotated file into a sortable key.
+fn rotation_key(suffix: &str) -> Option<(String, usize)> {
+    let (stamp, index) = match suffix.split_once('.') {
+        Some((stamp, index)) => (stamp, index.parse().ok()?),
+        None => (suffix, 0),
+    };
+    let valid = stamp.len() == 16 && stamp.ends_with('Z') && stamp.as_bytes()[8] == b'T';
+    valid.then(|| (stamp.to_string(), index))
+}
+
+fn warn(msg: String) {
+    let _ = Status::plain()
+        .color(CargoColor::BrightYellow)
+        .status("warning")
+        .print_stderr(format!(": {}", msg));
+}
+
+/// Builder for a [`RingBuffer`].
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::sinks::RingBuffer;
+///
+/// let buffer = RingBuffer::builder().capacity(500).always_keep(Level::Error, 100).build();
+/// ```
+pub struct RingBufferBuilder {
+    capacity: usize,
+    keep: Option<(Level, usize)>,
+}
+
+impl RingBufferBuilder {
+    /// Keep the last `lines` lines, 100 by default.
+    pub fn capacity(mut self, lines: usize) -> Self {
+        self.capacity = lines;
+        self
+    }
+
+    /// Also keep up to `lines` of the lines at least as severe as `level` after they are evicted,
+    /// the oldest of them are dropped first.
+    ///
+    /// The level of a line is the one of the message that wrote it, see [`Status::level`], and
+    /// `Info` for the lines written without one.
+    pub fn always_keep(mut self, level: Level, lines: usize) -> Self {
+        self.keep = Some((level, lines));
+        self
+    }
+
+    /// Create the buffer.
+    pub fn build(self) -> RingBuffer {
+        RingBuffer {
+            capacity: self.capacity,
+            keep: self.keep,
+            state: Mutex::new(RingState::default()),
+        }
+    }
+}
+
+/// Keeps the last lines written to it in memory, i.e. to dump them after a crash.
+///
+/// Like [`FileSink`], it can be written from several threads through a shared reference.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::sinks::RingBuffer;
+///
+/// let buffer = RingBuffer::new(2);
+/// for krate in ["a", "b", "c"] {
+///     let status = Status::new().justify().status("Compiling");
+///     status.print(&buffer, format!(" {}", krate)).unwrap();
+/// }
+/// assert_eq!(buffer.lines(), ["   Compiling b", "   Compiling c"]);
+/// ```
+pub struct RingBuffer {
+    capacity: usize,
+    keep: Option<(Level, usize)>,
+    state: Mutex<RingState>,
+}
+
+#[derive(Default)]
+struct RingState {
+    /// The last lines, without colors, with the level of their message.
+    recent: VecDeque<(String, Level)>,
+    /// The severe lines evicted from `recent`, all older than the lines in it.
+    pinned: VecDeque<String>,
+    pending: Vec<u8>,
+}
+
+impl RingBuffer {
+    /// Create a buffer keeping the last `capacity` lines.
+    pub fn new(capacity: usize) -> Self {
+        Self::builder().capacity(capacity).build()
+    }
+
+    /// Create a builder to configure the buffer.
+    pub fn builder() -> RingBufferBuilder {
+        RingBufferBuilder {
+            capacity: 100,
+            keep: None,
+        }
+    }
+
+    /// The kept lines without colors, in the order they were written.
+    pub fn lines(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let recent = state.recent.iter().map(|(line, _)| line);
+        state.pinned.iter().chain(recent).cloned().collect()
+    }
+
+    /// Write the kept lines to the specified stream.
+    pub fn dump<W>(&self, mut stream: W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        let mut dump = String::new();
+        for line in self.lines() {
+            dump.push_str(&line);
+            dump.push('\n');
+        }
+        stream.write_all(dump.as_bytes())?;
+        stream.flush()
+    }
+
+    fn push(&self, state: &mut RingState, line: String) {
+        state.recent.push_back((line, line_level()));
+        if state.recent.len() <= self.capacity {
+            return;
+        }
+        let evicted = state.recent.pop_front();
+        if let (Some((line, severity)), Some((level, keep))) = (evicted, self.keep) {
+            if severity >= level && keep > 0 {
+                if state.pinned.len() == keep {
+                    state.pinned.pop_front();
+                }
+                state.pinned.push_back(line);
+            }
+        }
+    }
+}
+
+/// Write the line of a message of `level` with `write`, so the sinks know its level.
+pub(crate) fn with_level<R, F>(level: Option<Level>, write: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let previous = LEVEL.replace(level);
+    let result = write();
+    LEVEL.set(previous);
+    result
+}
+
+/// The level of the line being written, `Info` for the lines written without one.
+pub(crate) fn line_level() -> Level {
+    LEVEL.get().unwrap_or(Level::Info)
+}
+
+impl Write for &RingBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.pending.extend_from_slice(buf);
+        while let Some(end) = state.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = state.pending.drain(..=end).collect();
+            let line = fmt::strip_ansi(String::from_utf8_lossy(&line[..end]).as_ref());
+            self.push(&mut state, line);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Write for RingBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+/// A stream described by a name, i.e. `` `build.log` `` or `the CI socket`, which a failed print
+/// reports as its destination in its [`Error`](crate::Error).
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::sinks::Named;
+///
+/// carlog::color::set_override(false);
+/// let mut output = Named::new("the report", Vec::<u8>::new());
+/// Status::new().status("warning").print(&mut output, ": unused import").unwrap();
+/// assert_eq!(output.get_ref(), b"warning: unused import\n");
+/// # carlog::color::unset_override();
+/// ```
+pub struct Named<W> {
+    name: String,
+    inner: W,
+}
+
+impl<W> Named<W>
+where
+    W: Write,
+{
+    /// Describe `inner` with `name`.
+    pub fn new<S>(name: S, inner: W) -> Self
+    where
+        S: AsRef<str>,
+    {
+        Self {
+            name: name.as_ref().to_string(),
+            inner,
+        }
+    }
+
+    /// The name of the stream.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The stream described.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The stream described, to write to it without describing its errors.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// The stream described, consuming the name.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W> Write for Named<W>
+where
+    W: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner
+            .write(buf)
+            .map_err(|e| error::destined(e, &self.name))
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.inner
+            .write_all(buf)
+            .map_err(|e| error::destined(e, &self.name))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner
+            .flush()
+            .map_err(|e| error::destined(e, &self.name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FileSink, FileSinkBuilder, RingBuffer, KB};
+    use crate::test::{force_colors, global_lock};
+    use crate::{
+        settings, CargoColor, CarlogStream, Format, Level, Metadata, Status, TimestampKind,
+    };
+    use std::fs;
+    use std::io::{self, Write};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn builder(path: &Path) -> FileSinkBuilder {
+        FileSink::builder(path).header(false)
+    }
+
+    fn line(n: usize) -> String {
+        format!("line {:03} {}\n", n, "x".repeat(20))
+    }
+
+    #[test]
+    fn test_rotate_size() {
+        let _lock = global_lock();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.log");
+        let mut sink = builder(&path).rotate_size(100).open().unwrap();
+        for n in 0..10 {
+            sink.write_all(line(n).as_bytes()).unwrap();
+        }
+        sink.flush().unwrap();
+        let rotated = sink.rotated_files().unwrap();
+        // Each line is 30 bytes, so 3 lines fit in every file.
+        assert_eq!(rotated.len(), 3);
+        assert_eq!(
+            fs::read_to_string(&rotated[0]).unwrap(),
+            line(0) + &line(1) + &line(2)
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), line(9));
+        let name = rotated[0]
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        assert!(name.starts_with("build.log.") && name.ends_with('Z'));
+    }
+
+    #[test]
+    fn test_rotate_keep() {
+        let _lock = global_lock();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.log");
+        let mut sink = builder(&path).rotate_size(KB).keep(2).open().unwrap();
+        let big = "y".repeat(1000) + "\n";
+        for _ in 0..5 {
+            sink.write_all(big.as_bytes()).unwrap();
+        }
+        sink.flush().unwrap();
+        let rotated = sink.rotated_files().unwrap();
+        assert_eq!(rotated.len(), 2);
+        for path in rotated.iter().chain(Some(&path)) {
+            assert_eq!(fs::read_to_string(path).unwrap(), big);
+        }
+    }
+
+    #[test]
+    fn test_rotate_daily() {
+        let _lock = global_lock();
+        static NOW: AtomicU64 = AtomicU64::new(86_400 * 10 + 3600);
+        fn clock() -> SystemTime {
+            UNIX_EPOCH + Duration::from_secs(NOW.load(Ordering::SeqCst))
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daily.log");
+        let mut sink = builder(&path).rotate_daily().clock(clock).open().unwrap();
+        sink.write_all(b"first\n").unwrap();
+        NOW.fetch_add(3600, Ordering::SeqCst);
+        sink.write_all(b"second\n").unwrap();
+        assert!(sink.rotated_files().unwrap().is_empty());
+        NOW.fetch_add(86_400, Ordering::SeqCst);
+        sink.write_all(b"third\n").unwrap();
+        let rotated = sink.rotated_files().unwrap();
+        assert_eq!(rotated.len(), 1);
+        assert!(rotated[0].ends_with("daily.log.19700112T020000Z"));
+        assert_eq!(fs::read_to_string(&rotated[0]).unwrap(), "first\nsecond\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third\n");
+    }
+
+    #[test]
+    fn test_partial_lines_and_threads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("threads.log");
+        let _lock = global_lock();
+        force_colors();
+        let sink = builder(&path).rotate_size(512).open().unwrap();
+        std::thread::scope(|scope| {
+            for t in 0..4 {
+                let sink = &sink;
+                scope.spawn(move || {
+                    for n in 0..50 {
+                        Status::new()
+                            .justify()
+                            .status("Worker")
+                            .print(sink, format!(" thread {} message {} done", t, n))
+                            .unwrap();
+                    }
+                });
+            }
+        });
+        drop(sink);
+        let mut lines = 0;
+        let dir_entries = fs::read_dir(dir.path()).unwrap();
+        for entry in dir_entries {
+            let contents = fs::read_to_string(entry.unwrap().path()).unwrap();
+            for line in contents.lines() {
+                assert!(
+                    line.starts_with("      \u{1b}[37mWorker\u{1b}[0m thread "),
+                    "{}",
+                    line
+                );
+                assert!(line.ends_with(" done"), "{}", line);
+                lines += 1;
+            }
+        }
+        assert_eq!(lines, 200);
+    }
+
+    #[test]
+    fn test_reopen() {
+        let _lock = global_lock();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.log");
+        let moved = dir.path().join("build.log.old");
+        let mut sink = builder(&path).open().unwrap();
+        sink.write_all(b"before\npartial").unwrap();
+        fs::rename(&path, &moved).unwrap();
+        sink.write_all(b" line\n").unwrap();
+        sink.reopen().unwrap();
+        sink.write_all(b"after\n").unwrap();
+        sink.flush().unwrap();
+        assert_eq!(
+            fs::read_to_string(&moved).unwrap(),
+            "before\npartial line\n"
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "after\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reopen_on_signal() {
+        use crate::Signal;
+
+        // The signal reopens every file sink of the process, which is why all the tests opening
+        // one take the global lock.
+        let _lock = global_lock();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signal.log");
+        let moved = dir.path().join("signal.log.1");
+        let mut sink = builder(&path).open().unwrap();
+        crate::install_reopen_on(Signal::Usr1).unwrap();
+        sink.write_all(b"before\n").unwrap();
+        fs::rename(&path, &moved).unwrap();
+        assert_eq!(unsafe { libc::raise(libc::SIGUSR1) }, 0);
+        sink.write_all(b"after\n").unwrap();
+        sink.flush().unwrap();
+        assert_eq!(fs::read_to_string(&moved).unwrap(), "before\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "after\n");
+    }
+
+    #[test]
+    fn test_lock_per_line() {
+        let _lock = global_lock();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shared.log");
+        let long = "z".repeat(64 * 1024);
+        std::thread::scope(|scope| {
+            for t in 0..2 {
+                let (path, long) = (&path, &long);
+                scope.spawn(move || {
+                    let mut sink = builder(path).lock_per_line(true).open().unwrap();
+                    for _ in 0..20 {
+                        writeln!(sink, "{}{}", t, long).unwrap();
+                    }
+                });
+            }
+        });
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 40);
+        for line in contents.lines() {
+            assert!(line == format!("0{}", long) || line == format!("1{}", long));
+        }
+    }
+
+    #[test]
+    fn test_lock_on_open() {
+        let _lock = global_lock();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("exclusive.log");
+        let sink = builder(&path).lock_on_open(true).open().unwrap();
+        let err = builder(&path).lock_on_open(true).open().err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+        assert!(err.to_string().contains("exclusive.log"));
+        drop(sink);
+        assert!(builder(&path).lock_on_open(true).open().is_ok());
+    }
+
+    #[test]
+    fn test_header() {
+        let _lock = global_lock();
+        fn clock() -> SystemTime {
+            UNIX_EPOCH + Duration::from_secs(1_715_522_602)
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("header.log");
+        let sink = FileSink::builder(&path)
+            .metadata(Metadata::new("mytool", "1.2.0"))
+            .rotate_size(400)
+            .clock(clock)
+            .open()
+            .unwrap();
+        std::thread::scope(|scope| {
+            for _ in 0..2 {
+                let mut sink = &sink;
+                scope.spawn(move || writeln!(sink, "record").unwrap());
+            }
+        });
+        drop(sink);
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines[0], "# mytool 1.2.0");
+        assert!(lines[1].starts_with("# command: "));
+        assert_eq!(lines[2], "# started: 2024-05-12T14:03:22Z");
+        assert!(lines[3].starts_with("# host: "));
+        assert_eq!(
+            lines[4],
+            "# carlog: rotate_size=400 rotate_daily=false keep=all lock=none"
+        );
+        assert!(lines[5].starts_with("# config: snapshot=3 format=Human max_level=Info "));
+        assert_eq!(&lines[6..], ["record", "record"]);
+        assert_eq!(contents.matches("# mytool").count(), 1);
+    }
+
+    #[test]
+    fn test_json_header() {
+        let _lock = global_lock();
+        fn clock() -> SystemTime {
+            UNIX_EPOCH + Duration::from_secs(1_715_522_602)
+        }
+        crate::set_format(Format::Json);
+        crate::route_target("net", io::sink());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("header.log");
+        let mut sink = FileSink::builder(&path)
+            .metadata(Metadata::new("mytool", "1.2.0"))
+            .clock(clock)
+            .open()
+            .unwrap();
+        writeln!(sink, "record").unwrap();
+        drop(sink);
+        crate::clear_routes();
+        settings::reset();
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(
+            r#"{"format_version":1,"status":"carlog","message":"mytool 1.2.0","level":"Info","#
+        ));
+        assert!(lines[0].contains(r#""fields":{"header_version":"1","command":"#));
+        assert!(lines[0].contains(r#""started":"2024-05-12T14:03:22Z","#));
+        assert!(lines[0].contains(r#""config":"snapshot=3 format=Json "#));
+        assert!(lines[0].contains(" routes=net "));
+        assert!(lines[0].contains(r#""id":"carlog-header","#));
+        assert!(lines[0].ends_with(r#""secs_since_epoch":1715522602,"nanos_since_epoch":0}}"#));
+        #[cfg(feature = "serde")]
+        {
+            let header = crate::wire::parse(lines[0]).unwrap();
+            assert_eq!(header.identifier(), Some("carlog-header"));
+        }
+        assert_eq!(lines[1], "record");
+    }
+
+    #[test]
+    fn test_header_once_per_file() {
+        let _lock = global_lock();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.log");
+        for record in ["first", "second"] {
+            let mut sink = FileSink::builder(&path)
+                .metadata(Metadata::new("mytool", "1.2.0"))
+                .open()
+                .unwrap();
+            writeln!(sink, "{}", record).unwrap();
+        }
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines[0], "# mytool 1.2.0");
+        assert_eq!(&lines[6..], ["first", "second"]);
+    }
+
+    #[test]
+    fn test_header_after_rotation() {
+        let _lock = global_lock();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.log");
+        let mut sink = FileSink::builder(&path)
+            .metadata(Metadata::new("mytool", "1.2.0"))
+            .rotate_size(1)
+            .open()
+            .unwrap();
+        writeln!(sink, "first").unwrap();
+        writeln!(sink, "second").unwrap();
+        sink.flush().unwrap();
+        let rotated = sink.rotated_files().unwrap();
+        assert_eq!(rotated.len(), 1);
+        for (path, record) in [(&rotated[0], "first"), (&path, "second")] {
+            let contents = fs::read_to_string(path).unwrap();
+            assert!(contents.starts_with("# mytool 1.2.0\n"));
+            assert_eq!(contents.lines().last(), Some(record));
+        }
+    }
+
+    #[test]
+    fn test_rename_failure_keeps_writing() {
+        let _lock = global_lock();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("build.log");
+        let mut sink = builder(&path).rotate_size(16).open().unwrap();
+        sink.write_all(b"0123456789\n").unwrap();
+        // Remove the file from under the sink so the rename fails.
+        fs::remove_file(&path).unwrap();
+        sink.write_all(b"lost file\n").unwrap();
+        assert!(sink.lock().rotate_warned);
+        assert_eq!(sink.lock().rotate_failed_at, Some(0));
+        sink.write_all(b"still\n").unwrap();
+        sink.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "lost file\nstill\n");
+
+        // The new file rotates like the first one.
+        sink.write_all(b"rotated\n").unwrap();
+        sink.flush().unwrap();
+        assert_eq!(sink.lock().rotate_failed_at, None);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "rotated\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+        // A rotation that keeps failing is retried once the file grew by another limit, not on
+        // every line.
+        fs::remove_dir_all(dir.path()).unwrap();
+        sink.write_all(b"0123456789\n").unwrap();
+        assert_eq!(sink.lock().rotate_failed_at, Some(8));
+        sink.write_all(b"kept\n").unwrap();
+        assert_eq!(sink.lock().rotate_failed_at, Some(8));
+        sink.write_all(b"retried\n").unwrap();
+        assert_eq!(sink.lock().rotate_failed_at, Some(24));
+    }
+
+    #[test]
+    fn test_ring_buffer_retention() {
+        let _lock = global_lock();
+        force_colors();
+        let severe = RingBuffer::builder()
+            .capacity(4)
+            .always_keep(Level::Warning, 2)
+            .build();
+        let errors = RingBuffer::builder()
+            .capacity(4)
+            .always_keep(Level::Error, 2)
+            .build();
+        let info = |n: usize| {
+            (
+                Status::plain().justify().status("Compiling"),
+                format!(" {}", n),
+            )
+        };
+        let warning = |msg: &str| {
+            let status = Status::plain()
+                .color(CargoColor::BrightYellow)
+                .status("warning")
+                .level(Level::Warning);
+            (status, format!(": {}", msg))
+        };
+        let error = |msg: &str| {
+            let status = Status::plain()
+                .color(CargoColor::BrightRed)
+                .status("error")
+                .level(Level::Error);
+            (status, format!(": {}", msg))
+        };
+        let mut messages = vec![error("a"), info(1), warning("b"), error("c")];
+        messages.extend((2..=8).map(info));
+        messages.push(error("d"));
+        for (status, msg) in messages {
+            status.print(&severe, &msg).unwrap();
+            status.print(&errors, &msg).unwrap();
+        }
+        let recent = [
+            "   Compiling 6",
+            "   Compiling 7",
+            "   Compiling 8",
+            "error: d",
+        ];
+        assert_eq!(severe.lines()[..2], ["warning: b", "error: c"]);
+        assert_eq!(severe.lines()[2..], recent);
+        assert_eq!(errors.lines()[..2], ["error: a", "error: c"]);
+        assert_eq!(errors.lines()[2..], recent);
+
+        let mut dump = Vec::<u8>::new();
+        RingBuffer::new(1).dump(&mut dump).unwrap();
+        assert!(dump.is_empty());
+        errors.dump(&mut dump).unwrap();
+        assert_eq!(String::from_utf8(dump).unwrap().lines().count(), 6);
+    }
+
+    #[test]
+    fn test_ring_buffer_levels() {
+        let _lock = global_lock();
+        force_colors();
+        crate::enable_timestamps(TimestampKind::Rfc3339);
+        crate::show_thread_names(true);
+        let buffer = RingBuffer::builder()
+            .capacity(1)
+            .always_keep(Level::Warning, 4)
+            .build();
+        crate::carlog_info!("errors", "none found", CarlogStream::Custom(&mut &buffer));
+        crate::carlog_warning!(label: "deprecated", "use of old API", CarlogStream::Custom(&mut &buffer));
+        crate::carlog_error!(code: "E0308", "mismatched types", CarlogStream::Custom(&mut &buffer));
+        (&buffer).write_all(b"error: not a message\n").unwrap();
+        crate::set_format(Format::Short);
+        crate::carlog_warning!("unused import", CarlogStream::Custom(&mut &buffer));
+        crate::set_format(Format::Json);
+        crate::carlog_error!("failed", CarlogStream::Custom(&mut &buffer));
+        crate::carlog_info!("Finished", "dev", CarlogStream::Custom(&mut &buffer));
+        crate::enable_timestamps(TimestampKind::None);
+        crate::show_thread_names(false);
+        crate::settings::reset();
+
+        let lines = buffer.lines();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].ends_with("deprecated: use of old API"));
+        assert!(lines[1].ends_with("error[E0308]: mismatched types"));
+        assert!(lines[2].ends_with("warning: unused import"));
+        assert!(lines[3].contains(r#""message":"failed""#));
+        assert!(lines[4].contains(r#""message":"dev""#));
+    }
+}
@@ -0,0 +1,450 @@
+//! The global configuration as data, to reproduce the setup of a program elsewhere.
+
+use crate::color::{self, ColorChoice, ColorDepth};
+use crate::lint::{self, LintLevel};
+#[cfg(feature = "metrics")]
+use crate::metrics;
+#[cfg(feature = "proc-macro")]
+use crate::proc_macros::{self, ProcMacroOutput};
+use crate::{block, dedup, library, link, prompt, render, route, settings, stream, theme, thread};
+use crate::{time, timing, transcript, FlushPolicy, Format, Level, RouteMode, StatusDefaults};
+use crate::{Target, Theme, TimestampKind};
+use std::collections::BTreeMap;
+use std::io;
+use std::time::Duration;
+
+/// The version of [`ConfigSnapshot`], increased whenever its fields change.
+pub const CONFIG_SNAPSHOT_VERSION: u32 = 3;
+
+/// Every global setting of the crate at a point in time, see [`config_snapshot`].
+///
+/// With the `serde` feature the snapshot can be serialized, i.e. pasted as JSON in a bug report,
+/// and deserialized back. Deserializing fails on unknown fields, so a snapshot of a newer version
+/// of the crate isn't silently applied in part.
+///
+/// The default is the configuration of a program that didn't change any setting.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct ConfigSnapshot {
+    /// The version of the snapshot, [`CONFIG_SNAPSHOT_VERSION`] when taken.
+    pub version: u32,
+    /// See [`set_verbosity`](crate::set_verbosity).
+    pub verbosity: u8,
+    /// See [`set_format`](crate::set_format).
+    pub format: Format,
+    /// See [`set_max_level`](crate::set_max_level).
+    pub max_level: Level,
+    /// See [`set_locations`](crate::set_locations).
+    pub locations: bool,
+    /// See [`set_theme`](crate::set_theme), without the scopes of the thread.
+    pub theme: Theme,
+    /// See [`set_status_defaults`](crate::set_status_defaults).
+    pub status_defaults: StatusDefaults,
+    /// See [`set_color_choice_for`](crate::set_color_choice_for) with [`Target::Stdout`].
+    pub stdout_color: ColorChoice,
+    /// See [`set_color_choice_for`](crate::set_color_choice_for) with [`Target::Stderr`].
+    pub stderr_color: ColorChoice,
+    /// See [`set_color_choice_for`](crate::set_color_choice_for) with [`Target::Custom`].
+    pub custom_color: ColorChoice,
+    /// See [`max_message_len`](crate::max_message_len).
+    pub max_message_len: usize,
+    /// See [`set_block_max_lines`](crate::set_block_max_lines).
+    pub block_max_lines: Option<usize>,
+    /// See [`render::set_max_depth`].
+    pub render_max_depth: Option<usize>,
+    /// See [`render::set_max_items`].
+    pub render_max_items: Option<usize>,
+    /// See [`set_slow_threshold`](crate::set_slow_threshold).
+    pub slow_threshold: Duration,
+    /// See [`set_warn_threshold`](crate::set_warn_threshold).
+    pub warn_threshold: Duration,
+    /// See [`set_timings_capacity`](crate::set_timings_capacity).
+    pub timings_capacity: usize,
+    /// See [`record_transcript`](crate::record_transcript).
+    pub transcript: bool,
+    /// See [`set_transcript_capacity`](crate::set_transcript_capacity).
+    pub transcript_capacity: usize,
+    /// See [`prompt::set_non_interactive`].
+    pub non_interactive: bool,
+    /// See [`set_flush_policy`](crate::set_flush_policy).
+    pub flush_policy: FlushPolicy,
+    /// See [`enable_timestamps`](crate::enable_timestamps).
+    pub timestamps: TimestampKind,
+    /// See [`show_thread_names`](crate::show_thread_names).
+    pub thread_names: bool,
+    /// See [`set_thread_tag_width`](crate::set_thread_tag_width).
+    pub thread_tag_width: usize,
+    /// See [`set_quiet`](crate::set_quiet), without the scopes of the thread.
+    pub quiet: bool,
+    /// See [`set_verbose`](crate::set_verbose).
+    pub verbose: bool,
+    /// See [`set_dedup_window`](crate::set_dedup_window).
+    pub dedup_window: Duration,
+    /// See [`set_hyperlinks`](crate::set_hyperlinks).
+    pub hyperlinks: bool,
+    /// See [`set_color_depth`](crate::set_color_depth), `None` when detected.
+    pub color_depth: Option<ColorDepth>,
+    /// The width of the statuses justified with [`Status::justify_auto`](crate::Status::justify_auto) so
+    /// far.
+    pub auto_justify_width: usize,
+    /// See [`lint::set_level`], without the declared lints.
+    pub lint_levels: BTreeMap<String, LintLevel>,
+    /// See [`route_target_with`](crate::route_target_with), the mode of each routed target.
+    ///
+    /// The sinks aren't part of the snapshot: applying it keeps the routes of these targets with
+    /// their mode and removes the others, but doesn't route a target without a route.
+    pub routes: BTreeMap<String, RouteMode>,
+    /// See [`enable_library_output`](crate::enable_library_output).
+    pub library_output: Vec<String>,
+    /// See [`metrics::count_targets`].
+    #[cfg(feature = "metrics")]
+    pub count_targets: bool,
+    /// See [`proc_macros::set_proc_macro_output`].
+    #[cfg(feature = "proc-macro")]
+    pub proc_macro_output: ProcMacroOutput,
+}
+
+impl Default for ConfigSnapshot {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_SNAPSHOT_VERSION,
+            verbosity: 0,
+            format: Format::Human,
+            max_level: Level::Info,
+            locations: true,
+            theme: Theme::default(),
+            status_defaults: StatusDefaults::default(),
+            stdout_color: ColorChoice::Auto,
+            stderr_color: ColorChoice::Auto,
+            custom_color: ColorChoice::Auto,
+            max_message_len: 64 * 1024,
+            block_max_lines: None,
+            render_max_depth: Some(8),
+            render_max_items: Some(32),
+            slow_threshold: Duration::ZERO,
+            warn_threshold: Duration::from_nanos(u64::MAX),
+            timings_capacity: 1000,
+            transcript: false,
+            transcript_capacity: 1000,
+            non_interactive: false,
+            flush_policy: FlushPolicy::EveryLine,
+            timestamps: TimestampKind::None,
+            thread_names: false,
+            thread_tag_width: 12,
+            quiet: false,
+            verbose: false,
+            dedup_window: Duration::ZERO,
+            hyperlinks: true,
+            color_depth: None,
+            auto_justify_width: 0,
+            lint_levels: BTreeMap::new(),
+            routes: BTreeMap::new(),
+            library_output: Vec::new(),
+            #[cfg(feature = "metrics")]
+            count_targets: false,
+            #[cfg(feature = "proc-macro")]
+            proc_macro_output: ProcMacroOutput::Auto,
+        }
+    }
+}
+
+impl ConfigSnapshot {
+    /// The settings that change what a log shows, as `key=value` pairs for the header of a
+    /// [`FileSink`](crate::sinks::FileSink).
+    pub(crate) fn summary(&self) -> String {
+        let names = |names: Vec<&str>| match names.is_empty() {
+            true => "none".to_string(),
+            false => names.join(","),
+        };
+        format!(
+            "snapshot={} format={:?} max_level={:?} verbosity={} quiet={} verbose={} \
+             locations={} timestamps={:?} dedup_window={:?} routes={} libraries={}",
+            self.version,
+            self.format,
+            self.max_level,
+            self.verbosity,
+            self.quiet,
+            self.verbose,
+            self.locations,
+            self.timestamps,
+            self.dedup_window,
+            names(self.routes.keys().map(String::as_str).collect()),
+            names(self.library_output.iter().map(String::as_str).collect()),
+        )
+    }
+}
+
+/// The current global configuration, see [`apply_snapshot`] to restore it.
+///
+/// ## Example
+/// ```
+/// let snapshot = carlog::config_snapshot();
+/// carlog::set_verbosity(2);
+/// carlog::apply_snapshot(&snapshot).unwrap();
+/// assert_eq!(carlog::verbosity(), 0);
+/// ```
+pub fn config_snapshot() -> ConfigSnapshot {
+    ConfigSnapshot {
+        version: CONFIG_SNAPSHOT_VERSION,
+        verbosity: crate::verbosity(),
+        format: crate::format(),
+        max_level: settings::max_level(),
+        locations: settings::locations(),
+        theme: theme::global_theme(),
+        status_defaults: crate::status_defaults(),
+        stdout_color: color::color_choice_for(Target::Stdout),
+        stderr_color: color::color_choice_for(Target::Stderr),
+        custom_color: color::color_choice_for(Target::Custom),
+        max_message_len: crate::message_len_limit(),
+        block_max_lines: block::block_max_lines(),
+        render_max_depth: render::max_depth(),
+        render_max_items: render::max_items(),
+        slow_threshold: timing::slow_threshold(),
+        warn_threshold: timing::warn_threshold(),
+        timings_capacity: timing::timings_capacity(),
+        transcript: transcript::recording(),
+        transcript_capacity: transcript::transcript_capacity(),
+        non_interactive: prompt::non_interactive(),
+        flush_policy: stream::flush_policy(),
+        timestamps: time::timestamps(),
+        thread_names: thread::thread_names(),
+        thread_tag_width: thread::thread_tag_width(),
+        quiet: settings::quiet(),
+        verbose: settings::verbose(),
+        dedup_window: dedup::dedup_window(),
+        hyperlinks: link::hyperlinks(),
+        color_depth: color::color_depth_override(),
+        auto_justify_width: crate::auto_width(),
+        lint_levels: lint::levels(),
+        routes: route::routes(),
+        library_output: library::enabled_patterns(),
+        #[cfg(feature = "metrics")]
+        count_targets: metrics::counting_targets(),
+        #[cfg(feature = "proc-macro")]
+        proc_macro_output: proc_macros::proc_macro_output(),
+    }
+}
+
+/// Configure the crate as described by a snapshot, i.e. one taken by a user reporting a bug.
+///
+/// Fails with [`io::ErrorKind::InvalidData`] without changing any setting if the snapshot was
+/// taken by a newer version of the crate.
+pub fn apply_snapshot(snapshot: &ConfigSnapshot) -> io::Result<()> {
+    if snapshot.version > CONFIG_SNAPSHOT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "configuration snapshot version {} is newer than the supported version {}",
+                snapshot.version, CONFIG_SNAPSHOT_VERSION
+            ),
+        ));
+    }
+    crate::set_verbosity(snapshot.verbosity);
+    crate::set_format(snapshot.format);
+    settings::set_max_level(snapshot.max_level);
+    settings::set_locations(snapshot.locations);
+    theme::set_theme(snapshot.theme);
+    crate::set_status_defaults(snapshot.status_defaults);
+    color::set_color_choice_for(Target::Stdout, snapshot.stdout_color);
+    color::set_color_choice_for(Target::Stderr, snapshot.stderr_color);
+    color::set_color_choice_for(Target::Custom, snapshot.custom_color);
+    crate::max_message_len(snapshot.max_message_len);
+    block::set_block_max_lines(snapshot.block_max_lines);
+    render::set_max_depth(snapshot.render_max_depth);
+    render::set_max_items(snapshot.render_max_items);
+    timing::set_slow_threshold(snapshot.slow_threshold);
+    timing::set_warn_threshold(snapshot.warn_threshold);
+    timing::set_timings_capacity(snapshot.timings_capacity);
+    // Starting to record clears the transcript, which shouldn't happen when it's already recording.
+    if snapshot.transcript != transcript::recording() {
+        transcript::record_transcript(snapshot.transcript);
+    }
+    transcript::set_transcript_capacity(snapshot.transcript_capacity);
+    prompt::set_non_interactive(snapshot.non_interactive);
+    stream::set_flush_policy(snapshot.flush_policy);
+    time::enable_timestamps(snapshot.timestamps);
+    thread::show_thread_names(snapshot.thread_names);
+    thread::set_thread_tag_width(snapshot.thread_tag_width);
+    settings::set_quiet(snapshot.quiet);
+    settings::set_verbose(snapshot.verbose);
+    dedup::set_dedup_window(snapshot.dedup_window);
+    link::set_hyperlinks(snapshot.hyperlinks);
+    color::set_color_depth_override(snapshot.color_depth);
+    crate::set_auto_width(snapshot.auto_justify_width);
+    lint::set_levels(snapshot.lint_levels.clone());
+    route::set_routes(&snapshot.routes);
+    library::set_enabled_patterns(snapshot.library_output.clone());
+    #[cfg(feature = "metrics")]
+    metrics::count_targets(snapshot.count_targets);
+    #[cfg(feature = "proc-macro")]
+    proc_macros::set_proc_macro_output(snapshot.proc_macro_output);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply_snapshot, config_snapshot, ConfigSnapshot, CONFIG_SNAPSHOT_VERSION};
+    use crate::test::{force_colors, global_lock};
+    use crate::{lint, route, settings, theme, CargoColor, ColorDepth, FlushPolicy, Format, Level};
+    use crate::{RouteMode, Status, StatusDefaults, Theme, TimestampKind};
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn clock() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_715_522_602)
+    }
+
+    fn configure() {
+        crate::set_verbosity(2);
+        crate::set_format(Format::Short);
+        settings::set_max_level(Level::Warning);
+        settings::set_locations(false);
+        theme::set_theme(Theme::warning_tint());
+        crate::set_status_defaults(StatusDefaults {
+            bold: true,
+            justify: true,
+            width: 14,
+            color: Some(CargoColor::Cyan),
+        });
+        crate::max_message_len(16);
+        crate::set_flush_policy(FlushPolicy::Manual);
+        crate::enable_timestamps(TimestampKind::Rfc3339);
+        crate::show_thread_names(true);
+        crate::set_thread_tag_width(6);
+        crate::set_quiet(true);
+        crate::set_verbose(true);
+        crate::set_dedup_window(Duration::from_secs(1));
+        crate::set_hyperlinks(false);
+        crate::set_color_depth(ColorDepth::Basic);
+        crate::set_auto_width(14);
+        crate::set_timings_capacity(10);
+        lint::declare("old-api");
+        lint::forbid("old-api");
+        crate::enable_library_output("tls-*");
+        crate::record_transcript(true);
+        #[cfg(feature = "metrics")]
+        crate::metrics::count_targets(true);
+    }
+
+    fn render() -> String {
+        let mut output = Vec::<u8>::new();
+        let long = " a message longer than the limit".to_string();
+        Status::new()
+            .status("Compiled")
+            .print(&mut output, &long)
+            .unwrap();
+        Status::plain()
+            .justify()
+            .color(theme::theme().info)
+            .status("Checking")
+            .location("src/lib.rs", 3, 5)
+            .print(&mut output, " carlog")
+            .unwrap();
+        Status::plain()
+            .justify_auto()
+            .color(CargoColor::Rgb(0xe5, 0xa0, 0x0d))
+            .status("Bundling")
+            .print(
+                &mut output,
+                format!(" {}", crate::link("docs", "https://docs.rs/carlog")),
+            )
+            .unwrap();
+        output.extend_from_slice(format!("{:?}\n", settings::max_level()).as_bytes());
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let _lock = global_lock();
+        force_colors();
+        crate::set_clock(clock);
+        let defaults = config_snapshot();
+        assert_eq!(defaults.version, CONFIG_SNAPSHOT_VERSION);
+        configure();
+        let snapshot = config_snapshot();
+        let configured = render();
+
+        apply_snapshot(&ConfigSnapshot::default()).unwrap();
+        force_colors();
+        assert_eq!(lint::level("old-api"), lint::LintLevel::Warn);
+        assert_ne!(render(), configured);
+        apply_snapshot(&snapshot).unwrap();
+        assert_eq!(config_snapshot(), snapshot);
+        assert_eq!(render(), configured);
+        assert_eq!(lint::level("old-api"), lint::LintLevel::Forbid);
+        assert!(crate::library_handle("tls-probe").enabled());
+        assert_eq!(snapshot.library_output, ["tls-*"]);
+        assert!(snapshot.transcript);
+        #[cfg(feature = "metrics")]
+        assert!(snapshot.count_targets);
+
+        let future = ConfigSnapshot {
+            version: CONFIG_SNAPSHOT_VERSION + 1,
+            verbosity: 0,
+            ..snapshot.clone()
+        };
+        assert!(apply_snapshot(&future).is_err());
+        assert_eq!(crate::verbosity(), 2);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&snapshot).unwrap();
+            let parsed: ConfigSnapshot = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, snapshot);
+            let unknown = json.replacen('{', "{\"wrap_width\":80,", 1);
+            assert!(serde_json::from_str::<ConfigSnapshot>(&unknown).is_err());
+
+            // The shape of a configuration file, where the unset options are left out.
+            let config = toml::to_string(&snapshot).unwrap();
+            assert!(
+                config.contains("\n[status_defaults]\nbold = true\njustify = true\nwidth = 14\n")
+            );
+            assert!(!config.contains("block_max_lines"));
+            assert_eq!(toml::from_str::<ConfigSnapshot>(&config).unwrap(), snapshot);
+            let unknown = format!("wrap_width = 80\n{}", config);
+            assert!(toml::from_str::<ConfigSnapshot>(&unknown).is_err());
+        }
+        apply_snapshot(&defaults).unwrap();
+        crate::set_clock(SystemTime::now);
+        settings::reset();
+    }
+
+    #[test]
+    fn test_routes() {
+        let _lock = global_lock();
+        crate::route_target_with("net", io::sink(), RouteMode::Also);
+        crate::route_target("build", io::sink());
+        let snapshot = config_snapshot();
+        let modes = BTreeMap::from([
+            ("build".to_string(), RouteMode::Instead),
+            ("net".to_string(), RouteMode::Also),
+        ]);
+        assert_eq!(snapshot.routes, modes);
+
+        let instead = BTreeMap::from([
+            ("net".to_string(), RouteMode::Instead),
+            ("cache".to_string(), RouteMode::Also),
+        ]);
+        apply_snapshot(&ConfigSnapshot {
+            routes: instead,
+            ..snapshot.clone()
+        })
+        .unwrap();
+        let net = BTreeMap::from([("net".to_string(), RouteMode::Instead)]);
+        assert_eq!(route::routes(), net);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&snapshot).unwrap();
+            assert!(json.contains(r#""routes":{"build":"Instead","net":"Also"}"#));
+            let parsed: ConfigSnapshot = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed.routes, modes);
+        }
+        crate::clear_routes();
+    }
+}
@@ -0,0 +1,439 @@
+//! The streams the macros print to when they aren't given one, i.e. to send every message of a
+//! program to a file or, in tests, to a buffer, and when the streams are flushed.
+
+use crate::{shutdown, CarlogStream, Target};
+use std::cell::Cell;
+use std::io::{self, stderr, stdout, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// The default stream and the default error stream.
+static DEFAULTS: RwLock<[Stored; 2]> = RwLock::new([Stored::Stdout, Stored::Stderr]);
+static FLUSH_POLICY: AtomicU8 = AtomicU8::new(FlushPolicy::EveryLine as u8);
+
+thread_local! {
+    /// Whether the lines printed by the thread are flushed later, i.e. by a
+    /// [`Session`](crate::Session).
+    static DEFERRED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// When the lines printed are flushed, see [`set_flush_policy`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FlushPolicy {
+    /// Every line is flushed once written (the default).
+    #[default]
+    EveryLine,
+    /// The lines aren't flushed, the writers are when they're dropped. The default streams
+    /// replaced by [`set_default_stream`] or [`with_default_stream`] are flushed then too.
+    OnDrop,
+    /// The lines are only flushed by [`flush`] and [`shutdown`](crate::shutdown).
+    Manual,
+}
+
+/// A default stream, kept in a form that can be shared between threads.
+#[derive(Clone)]
+pub(crate) enum Stored {
+    Stdout,
+    Stderr,
+    Shared(Arc<Mutex<dyn Write + Send>>),
+    Tee(Vec<Stored>),
+    Null,
+}
+
+impl Stored {
+    fn new(stream: CarlogStream<'static>) -> Self {
+        match stream {
+            CarlogStream::Stdout => Stored::Stdout,
+            CarlogStream::Stderr => Stored::Stderr,
+            CarlogStream::Custom(_) => {
+                panic!("a custom stream can't be a default stream, share its writer instead")
+            }
+            CarlogStream::File(file) => Stored::Shared(Arc::new(Mutex::new(file))),
+            CarlogStream::Boxed(writer) => Stored::Shared(Arc::new(Mutex::new(writer))),
+            CarlogStream::Shared(writer) => Stored::Shared(writer),
+            CarlogStream::Tee(streams) => Stored::Tee(streams.into_iter().map(Self::new).collect()),
+            CarlogStream::Null => Stored::Null,
+        }
+    }
+
+    /// A copy of `stream` that can still be written to after the call, `None` for the custom,
+    /// file and boxed streams.
+    pub(crate) fn retain(stream: &CarlogStream<'_>) -> Option<Self> {
+        match stream {
+            CarlogStream::Stdout => Some(Stored::Stdout),
+            CarlogStream::Stderr => Some(Stored::Stderr),
+            CarlogStream::Shared(writer) => Some(Stored::Shared(Arc::clone(writer))),
+            CarlogStream::Tee(streams) => streams
+                .iter()
+                .map(Self::retain)
+                .collect::<Option<_>>()
+                .map(Stored::Tee),
+            CarlogStream::Null => Some(Stored::Null),
+            CarlogStream::Custom(_) | CarlogStream::File(_) | CarlogStream::Boxed(_) => None,
+        }
+    }
+
+    pub(crate) fn stream<'a>(&self) -> CarlogStream<'a> {
+        match self {
+            Stored::Stdout => CarlogStream::Stdout,
+            Stored::Stderr => CarlogStream::Stderr,
+            Stored::Shared(writer) => CarlogStream::Shared(Arc::clone(writer)),
+            Stored::Tee(streams) => CarlogStream::Tee(streams.iter().map(Self::stream).collect()),
+            Stored::Null => CarlogStream::Null,
+        }
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        match self {
+            Stored::Stdout => stdout().flush(),
+            Stored::Stderr => stderr().flush(),
+            Stored::Shared(writer) => writer.lock().unwrap_or_else(|e| e.into_inner()).flush(),
+            Stored::Tee(streams) => streams
+                .iter()
+                .map(Self::flush)
+                .fold(Ok(()), io::Result::and),
+            Stored::Null => Ok(()),
+        }
+    }
+}
+
+/// Replace the default stream at `index`, returning the previous one.
+fn replace(index: usize, stream: Stored) -> Stored {
+    let mut defaults = DEFAULTS.write().unwrap_or_else(|e| e.into_inner());
+    let previous = std::mem::replace(&mut defaults[index], stream);
+    drop(defaults);
+    if flush_policy() == FlushPolicy::OnDrop {
+        let _ = previous.flush();
+    }
+    previous
+}
+
+/// The default stream at `index`.
+fn get<'a>(index: usize) -> CarlogStream<'a> {
+    DEFAULTS.read().unwrap_or_else(|e| e.into_inner())[index].stream()
+}
+
+/// Print the messages without a stream of their own, except the warnings and errors, to `stream`
+/// instead of stdout, in every thread.
+///
+/// This is the stream of [`CarlogStream::default`]. Files and boxed writers are shared by the
+/// threads from now on.
+///
+/// ## Panics
+/// If `stream` is or contains a [`CarlogStream::Custom`], which may not be shared between
+/// threads, see [`CarlogStream::from`] to share a writer instead.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// carlog::set_default_stream(CarlogStream::Stderr);
+/// carlog_info!("Compiling", "carlog v0.1.0"); // Printed to stderr.
+/// carlog::set_default_stream(CarlogStream::Stdout);
+/// ```
+pub fn set_default_stream(stream: CarlogStream<'static>) {
+    replace(0, Stored::new(stream));
+}
+
+/// Print the warnings and errors without a stream of their own to `stream` instead of stderr.
+///
+/// See [`set_default_stream`].
+pub fn set_default_error_stream(stream: CarlogStream<'static>) {
+    replace(1, Stored::new(stream));
+}
+
+/// The stream of the messages without one of their own, see [`set_default_stream`].
+pub fn default_stream<'a>() -> CarlogStream<'a> {
+    get(0)
+}
+
+/// The stream of the warnings and errors without one of their own, see
+/// [`set_default_error_stream`].
+pub fn default_error_stream<'a>() -> CarlogStream<'a> {
+    get(1)
+}
+
+/// Restores the previous default stream when dropped, also when unwinding from a panic.
+struct Restore(Option<Stored>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0.take() {
+            replace(0, previous);
+        }
+    }
+}
+
+/// Run `f` with `stream` as the [default stream](set_default_stream), restoring the previous one
+/// when `f` returns or panics.
+///
+/// The default stream is the one of every thread, not only the one running `f`.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+/// use std::sync::{Arc, Mutex};
+///
+/// let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+/// carlog::with_default_stream(CarlogStream::from(Arc::clone(&output)), || {
+///     carlog_info!("Compiling", "carlog v0.1.0");
+/// });
+/// assert!(!output.lock().unwrap().is_empty());
+/// ```
+pub fn with_default_stream<F, R>(stream: CarlogStream<'static>, f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let _restore = Restore(Some(replace(0, Stored::new(stream))));
+    f()
+}
+
+/// Decide when the lines printed are flushed, [`FlushPolicy::EveryLine`] by default.
+///
+/// Flushing after every line costs a system call per line to an unbuffered writer, which adds up
+/// when thousands of lines are printed. Stdout stays line buffered whatever the policy.
+///
+/// ## Example
+/// ```
+/// use carlog::FlushPolicy;
+///
+/// carlog::set_flush_policy(FlushPolicy::Manual);
+/// // Print thousands of lines...
+/// carlog::flush().unwrap();
+/// # carlog::set_flush_policy(FlushPolicy::EveryLine);
+/// ```
+pub fn set_flush_policy(policy: FlushPolicy) {
+    FLUSH_POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
+/// When the lines printed are flushed, see [`set_flush_policy`].
+pub fn flush_policy() -> FlushPolicy {
+    match FLUSH_POLICY.load(Ordering::Relaxed) {
+        1 => FlushPolicy::OnDrop,
+        2 => FlushPolicy::Manual,
+        _ => FlushPolicy::EveryLine,
+    }
+}
+
+/// Flush a line just written to `stream`, if the [policy](set_flush_policy) says so.
+pub(crate) fn flush_line<W>(stream: &mut W) -> io::Result<()>
+where
+    W: Write + ?Sized,
+{
+    let deferred = DEFERRED.try_with(Cell::get).unwrap_or(false);
+    match flush_policy() {
+        FlushPolicy::EveryLine if !deferred => stream.flush(),
+        _ => Ok(()),
+    }
+}
+
+/// Run `f` without flushing the lines it prints, see [`flush_line`].
+pub(crate) fn deferring_flush<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    let deferred = DEFERRED.try_with(|deferred| deferred.replace(true));
+    let result = f();
+    if let Ok(deferred) = deferred {
+        DEFERRED.with(|current| current.set(deferred));
+    }
+    result
+}
+
+/// Flush everything `stream` writes to.
+pub(crate) fn flush_stream(stream: &mut CarlogStream<'_>) -> io::Result<()> {
+    match stream {
+        CarlogStream::Stdout => stdout().flush(),
+        CarlogStream::Stderr => stderr().flush(),
+        CarlogStream::Custom(writer) => writer.flush(),
+        CarlogStream::File(file) => file.flush(),
+        CarlogStream::Boxed(writer) => writer.flush(),
+        CarlogStream::Shared(writer) => writer.lock().unwrap_or_else(|e| e.into_inner()).flush(),
+        CarlogStream::Tee(streams) => streams
+            .iter_mut()
+            .map(flush_stream)
+            .fold(Ok(()), io::Result::and),
+        CarlogStream::Null => Ok(()),
+    }
+}
+
+/// Write a whole line already rendered, newline included, to `stream` like the statuses are
+/// printed.
+pub(crate) fn write_line(stream: &mut CarlogStream<'_>, line: &str) -> io::Result<()> {
+    fn write<W>(writer: &mut W, target: Target, line: &str) -> io::Result<()>
+    where
+        W: Write + ?Sized,
+    {
+        crate::transcript::append(target, line);
+        // A single write keeps the line whole on streams shared between threads.
+        writer
+            .write_all(line.as_bytes())
+            .and_then(|_| flush_line(writer))
+    }
+
+    let _output = crate::output::lock();
+    match stream {
+        CarlogStream::Stdout if !shutdown::is_shut_down() => {
+            write(&mut stdout().lock(), Target::Stdout, line)
+        }
+        CarlogStream::Stdout | CarlogStream::Stderr => {
+            write(&mut stderr().lock(), Target::Stderr, line)
+        }
+        CarlogStream::Custom(writer) => write(writer, Target::Custom, line),
+        CarlogStream::File(file) => write(file, Target::Custom, line),
+        CarlogStream::Boxed(writer) => write(writer, Target::Custom, line),
+        CarlogStream::Shared(writer) => {
+            let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+            write(&mut *writer, Target::Custom, line)
+        }
+        CarlogStream::Tee(streams) => streams
+            .iter_mut()
+            .map(|stream| write_line(stream, line))
+            .fold(Ok(()), io::Result::and),
+        CarlogStream::Null => Ok(()),
+    }
+}
+
+/// Flush stdout, stderr, the [default streams](set_default_stream) and the sinks of the
+/// [routes](crate::route_target), returning the first failure once all of them were flushed.
+///
+/// The count of the repeated messages [held back](crate::set_dedup_window) is printed first.
+pub fn flush() -> io::Result<()> {
+    let released = crate::dedup::flush().map_err(io::Error::from);
+    let _output = crate::output::lock();
+    let defaults = DEFAULTS.read().unwrap_or_else(|e| e.into_inner());
+    [released]
+        .into_iter()
+        .chain(
+            [Stored::Stdout, Stored::Stderr]
+                .iter()
+                .chain(defaults.iter())
+                .map(Stored::flush),
+        )
+        .chain([crate::route::flush()])
+        .fold(Ok(()), io::Result::and)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        default_error_stream, default_stream, flush, set_default_error_stream, set_default_stream,
+        set_flush_policy, with_default_stream, FlushPolicy,
+    };
+    use crate::test::{force_colors, global_lock};
+    use crate::{CarlogStream, Record, Status};
+    use std::io::{self, Write};
+    use std::panic::{self, AssertUnwindSafe};
+    use std::sync::{Arc, Mutex};
+
+    /// Counts the lines written and the flushes.
+    #[derive(Default)]
+    struct Counting {
+        lines: usize,
+        flushes: usize,
+    }
+
+    impl Write for Counting {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.lines += buf.iter().filter(|&&byte| byte == b'\n').count();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    fn text(output: &Arc<Mutex<Vec<u8>>>) -> String {
+        String::from_utf8(output.lock().unwrap().clone()).unwrap()
+    }
+
+    #[test]
+    fn test_default_stream() {
+        let _lock = global_lock();
+        force_colors();
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let errors = Arc::new(Mutex::new(Vec::<u8>::new()));
+        set_default_error_stream(CarlogStream::from(Arc::clone(&errors)));
+        with_default_stream(CarlogStream::from(Arc::clone(&output)), || {
+            crate::carlog_info!("Compiling", "carlog v0.1.0");
+            crate::carlog_warning!("unused import");
+            crate::print_record(&Record::new("Finished", "dev")).unwrap();
+        });
+        set_default_error_stream(CarlogStream::Stderr);
+        assert_eq!(
+            text(&output),
+            "   \u{1b}[1;36mCompiling\u{1b}[0m carlog v0.1.0\n    \
+             \u{1b}[1;36mFinished\u{1b}[0m dev\n"
+        );
+        assert_eq!(text(&errors), "\u{1b}[93mwarning\u{1b}[0m: unused import\n");
+        assert!(matches!(default_stream(), CarlogStream::Stdout));
+        assert!(matches!(default_error_stream(), CarlogStream::Stderr));
+    }
+
+    #[test]
+    fn test_restore_on_panic() {
+        let _lock = global_lock();
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+            with_default_stream(CarlogStream::Null, || {
+                with_default_stream(CarlogStream::from(Arc::clone(&output)), || {
+                    panic!("build failed")
+                })
+            })
+        }));
+        assert!(panicked.is_err());
+        assert!(matches!(default_stream(), CarlogStream::Stdout));
+        assert!(matches!(CarlogStream::default(), CarlogStream::Stdout));
+    }
+
+    #[test]
+    #[should_panic(expected = "a custom stream can't be a default stream")]
+    fn test_custom_default_stream() {
+        let output: &'static mut Vec<u8> = Box::leak(Box::default());
+        with_default_stream(CarlogStream::Custom(output), || ());
+    }
+
+    #[test]
+    fn test_flush_policy() {
+        let _lock = global_lock();
+        let status = Status::new().bold().justify().status("Checking");
+        let mut counts = Vec::new();
+        for policy in [
+            FlushPolicy::EveryLine,
+            FlushPolicy::OnDrop,
+            FlushPolicy::Manual,
+        ] {
+            set_flush_policy(policy);
+            let counting = Arc::new(Mutex::new(Counting::default()));
+            set_default_stream(CarlogStream::from(Arc::clone(&counting)));
+            let mut stream = CarlogStream::default();
+            for file in 0..10_000 {
+                status
+                    .print_stream(&mut stream, format!(" src/{}.rs", file))
+                    .unwrap();
+            }
+            let printed = counting.lock().unwrap().flushes;
+            flush().unwrap();
+            let flushed = counting.lock().unwrap().flushes;
+            set_default_stream(CarlogStream::Stdout);
+            let counting = counting.lock().unwrap();
+            counts.push((counting.lines, printed, flushed, counting.flushes));
+        }
+        set_flush_policy(FlushPolicy::EveryLine);
+        assert_eq!(
+            counts,
+            [
+                (10_000, 10_000, 10_001, 10_001),
+                (10_000, 0, 1, 2),
+                (10_000, 0, 1, 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,289 @@
+//! Tallies of the warnings and errors printed, for a cargo like summary at the end of a run.
+//!
+//! Every warning and error printed by the macros, the [lints](crate::lint), the [logger](crate::init),
+//! the [diagnostics](crate::diagnostic) and [`print_record`](crate::print_record) is counted, from
+//! any thread. Messages filtered out by the max level aren't.
+//!
+//! ## Example
+//! ```
+//! #[macro_use] extern crate carlog;
+//!
+//! carlog_warning!("unused variable: `x`");
+//! carlog_warning!("unused import: `std::io`");
+//! carlog::print_summary(carlog::default_error_stream()).unwrap(); // "warning: 2 warnings emitted"
+//! ```
+
+use crate::{fmt, theme, CargoColor, CarlogStream, Error, Level, Status};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+static ERRORS: AtomicUsize = AtomicUsize::new(0);
+static DUPLICATES: AtomicUsize = AtomicUsize::new(0);
+/// The warnings and errors printed for every [code](crate::Record::code).
+static CODES: Mutex<BTreeMap<String, CodeCount>> = Mutex::new(BTreeMap::new());
+
+/// The warnings and errors printed with a [code](crate::Record::code), see [`code_counts`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CodeCount {
+    /// The warnings printed with the code.
+    pub warnings: usize,
+    /// The errors printed with the code.
+    pub errors: usize,
+}
+
+/// The number of warnings printed since the start or the last [`reset_counts`].
+pub fn warning_count() -> usize {
+    WARNINGS.load(Ordering::Relaxed)
+}
+
+/// The number of errors printed since the start or the last [`reset_counts`].
+pub fn error_count() -> usize {
+    ERRORS.load(Ordering::Relaxed)
+}
+
+/// The warnings and errors printed for every code since the start or the last [`reset_counts`],
+/// sorted by code.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// carlog::reset_counts();
+/// carlog_error!(code: "E0308", "mismatched types");
+/// let counts = carlog::code_counts();
+/// assert_eq!(counts[0].0, "E0308");
+/// assert_eq!(counts[0].1.errors, 1);
+/// ```
+pub fn code_counts() -> Vec<(String, CodeCount)> {
+    let codes = CODES.lock().unwrap_or_else(|e| e.into_inner());
+    codes
+        .iter()
+        .map(|(code, count)| (code.clone(), *count))
+        .collect()
+}
+
+/// Reset the warning, error and duplicate warning tallies to zero, and the ones of the codes.
+pub fn reset_counts() {
+    WARNINGS.store(0, Ordering::Relaxed);
+    ERRORS.store(0, Ordering::Relaxed);
+    DUPLICATES.store(0, Ordering::Relaxed);
+    CODES.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Print the summary of the warnings and errors to the specified stream.
+///
+/// The summary is a `warning: 3 warnings emitted` line when there were only warnings, or an
+/// `error: 2 errors and 1 warning emitted` line when there were errors. Nothing is printed when
+/// there were neither. The repeated warnings skipped by
+/// [`carlog_warn_once!`](crate::carlog_warn_once) are appended, i.e.
+/// `; 3999 duplicate warnings suppressed`. The summary itself isn't counted.
+///
+/// * `stream`: The stream where the summary will be written.
+pub fn print_summary(mut stream: CarlogStream<'_>) -> Result<(), Error> {
+    match summary() {
+        Some((status, message)) => status.print_stream(&mut stream, message),
+        None => Ok(()),
+    }
+}
+
+/// The status and the message of the summary, if there were warnings or errors.
+fn summary() -> Option<(Status, String)> {
+    let (warnings, errors) = (warning_count(), error_count());
+    let (label, color, message) = match (errors, warnings) {
+        (0, 0) => return None,
+        (0, _) => ("warning", theme().warning, fmt::count(warnings, "warning")),
+        (_, 0) => ("error", theme().error, fmt::count(errors, "error")),
+        _ => (
+            "error",
+            theme().error,
+            format!(
+                "{} and {}",
+                fmt::count(errors, "error"),
+                fmt::count(warnings, "warning")
+            ),
+        ),
+    };
+    let mut message = format!(": {} emitted", message);
+    let duplicates = DUPLICATES.load(Ordering::Relaxed);
+    if duplicates > 0 {
+        message.push_str(&format!(
+            "; {} suppressed",
+            fmt::count(duplicates, "duplicate warning")
+        ));
+    }
+    Some((Status::plain().color(color).status(label), message))
+}
+
+/// Print the summary like [`print_summary`], followed by a `note:` line for every code with its
+/// tallies, i.e. `note: E0308: 2 errors`, sorted by code.
+///
+/// * `stream`: The stream where the summary will be written.
+pub fn print_summary_by_code(mut stream: CarlogStream<'_>) -> Result<(), Error> {
+    if let Some((status, message)) = summary() {
+        status.print_stream(&mut stream, message)?;
+    }
+    let note = Status::plain()
+        .bold()
+        .color(CargoColor::BrightWhite)
+        .status("note");
+    for (code, count) in code_counts() {
+        let message = match (count.errors, count.warnings) {
+            (errors, 0) => fmt::count(errors, "error"),
+            (0, warnings) => fmt::count(warnings, "warning"),
+            (errors, warnings) => format!(
+                "{} and {}",
+                fmt::count(errors, "error"),
+                fmt::count(warnings, "warning")
+            ),
+        };
+        note.print_stream(&mut stream, format!(": {}: {}", code, message))?;
+    }
+    Ok(())
+}
+
+/// Count a warning of [`carlog_warn_once!`] skipped because its call site already printed one.
+#[doc(hidden)]
+pub fn __duplicate_warning() {
+    DUPLICATES.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count a message about to be printed if it's a warning or an error, for its code too if any.
+pub(crate) fn count(level: Level, code: Option<&str>) {
+    let counter = match level {
+        Level::Warning => &WARNINGS,
+        Level::Error => &ERRORS,
+        Level::Trace | Level::Debug | Level::Info => return,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+    if let Some(code) = code {
+        let mut codes = CODES.lock().unwrap_or_else(|e| e.into_inner());
+        let count = codes.entry(code.to_string()).or_default();
+        match level {
+            Level::Warning => count.warnings += 1,
+            _ => count.errors += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        code_counts, error_count, print_summary, print_summary_by_code, reset_counts,
+        warning_count, CodeCount,
+    };
+    use crate::test::{auto_colors, force_colors, global_lock};
+    use crate::{carlog_error, carlog_info, carlog_warning, set_max_level, CarlogStream, Level};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    fn summary() -> String {
+        let mut output = Vec::<u8>::new();
+        print_summary(CarlogStream::Custom(&mut output)).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_counts_across_threads() {
+        let _lock = global_lock();
+        force_colors();
+        reset_counts();
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let handles = (0..4)
+            .map(|i| {
+                let output = Arc::clone(&output);
+                thread::spawn(move || {
+                    for _ in 0..25 {
+                        carlog_warning!("unused variable", CarlogStream::from(Arc::clone(&output)));
+                        carlog_info!(
+                            "Compiling",
+                            "carlog",
+                            CarlogStream::from(Arc::clone(&output))
+                        );
+                    }
+                    if i % 2 == 0 {
+                        carlog_error!("mismatched types", CarlogStream::from(Arc::clone(&output)));
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!((warning_count(), error_count()), (100, 2));
+        assert_eq!(
+            summary(),
+            "\u{1b}[91merror\u{1b}[0m: 2 errors and 100 warnings emitted\n"
+        );
+        reset_counts();
+    }
+
+    #[test]
+    fn test_summary_wording() {
+        let _lock = global_lock();
+        auto_colors();
+        reset_counts();
+        let mut output = Vec::<u8>::new();
+        assert_eq!(summary(), "");
+        carlog_warning!("unused variable", CarlogStream::Custom(&mut output));
+        assert_eq!(summary(), "warning: 1 warning emitted\n");
+        carlog_warning!("unused import", CarlogStream::Custom(&mut output));
+        assert_eq!(summary(), "warning: 2 warnings emitted\n");
+        carlog_error!("mismatched types", CarlogStream::Custom(&mut output));
+        assert_eq!(summary(), "error: 1 error and 2 warnings emitted\n");
+        reset_counts();
+        carlog_error!("mismatched types", CarlogStream::Custom(&mut output));
+        carlog_error!("unresolved import", CarlogStream::Custom(&mut output));
+        assert_eq!(summary(), "error: 2 errors emitted\n");
+        set_max_level(Level::Error);
+        carlog_warning!("hidden", CarlogStream::Custom(&mut output));
+        assert_eq!((warning_count(), error_count()), (0, 2));
+        crate::settings::reset();
+        reset_counts();
+        force_colors();
+    }
+
+    #[test]
+    fn test_summary_by_code() {
+        let _lock = global_lock();
+        auto_colors();
+        reset_counts();
+        let mut output = Vec::<u8>::new();
+        carlog_error!(code: "E0308", "mismatched types", CarlogStream::Custom(&mut output));
+        carlog_error!(code: "E0308", "mismatched types", CarlogStream::Custom(&mut output));
+        carlog_warning!(code: "E0308", "mismatched types", CarlogStream::Custom(&mut output));
+        carlog_error!(code: "E0432", "unresolved import", CarlogStream::Custom(&mut output));
+        carlog_warning!("unused variable", CarlogStream::Custom(&mut output));
+        assert_eq!(
+            code_counts(),
+            [
+                (
+                    "E0308".to_string(),
+                    CodeCount {
+                        warnings: 1,
+                        errors: 2
+                    }
+                ),
+                (
+                    "E0432".to_string(),
+                    CodeCount {
+                        warnings: 0,
+                        errors: 1
+                    }
+                ),
+            ]
+        );
+        let mut summary = Vec::<u8>::new();
+        print_summary_by_code(CarlogStream::Custom(&mut summary)).unwrap();
+        assert_eq!(
+            String::from_utf8(summary).unwrap(),
+            "error: 3 errors and 2 warnings emitted\n\
+             note: E0308: 2 errors and 1 warning\n\
+             note: E0432: 1 error\n"
+        );
+        reset_counts();
+        assert!(code_counts().is_empty());
+        force_colors();
+    }
+}
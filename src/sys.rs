@@ -0,0 +1,215 @@
+//! Platform specific helpers.
+
+use std::fs::File;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The name of the machine, if it can be determined.
+#[cfg(unix)]
+pub(crate) fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer is valid for `buf.len()` bytes and gethostname never writes past it.
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return std::env::var("HOSTNAME").ok();
+    }
+    let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+/// The name of the machine, if it can be determined.
+#[cfg(not(unix))]
+pub(crate) fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+}
+
+/// Take an exclusive advisory lock on the whole file.
+///
+/// When `wait` is false the call fails with `ErrorKind::WouldBlock` if another handle holds the lock.
+#[cfg(unix)]
+pub(crate) fn lock_file(file: &File, wait: bool) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let operation = if wait {
+        libc::LOCK_EX
+    } else {
+        libc::LOCK_EX | libc::LOCK_NB
+    };
+    // SAFETY: the descriptor is owned by `file`, which outlives the call.
+    if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Release a lock taken with [`lock_file`].
+#[cfg(unix)]
+pub(crate) fn unlock_file(file: &File) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // SAFETY: the descriptor is owned by `file`, which outlives the call.
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_UN) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Take an exclusive advisory lock on the whole file.
+///
+/// When `wait` is false the call fails with `ErrorKind::WouldBlock` if another handle holds the lock.
+#[cfg(windows)]
+pub(crate) fn lock_file(file: &File, wait: bool) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::{ERROR_LOCK_VIOLATION, HANDLE};
+    use windows_sys::Win32::Storage::FileSystem::{
+        LockFileEx, LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY,
+    };
+
+    let mut flags = LOCKFILE_EXCLUSIVE_LOCK;
+    if !wait {
+        flags |= LOCKFILE_FAIL_IMMEDIATELY;
+    }
+    // SAFETY: the handle is owned by `file` and the overlapped struct is zero initialized as
+    // required for synchronous handles.
+    let ok = unsafe {
+        let mut overlapped = std::mem::zeroed();
+        LockFileEx(
+            file.as_raw_handle() as HANDLE,
+            flags,
+            0,
+            u32::MAX,
+            u32::MAX,
+            &mut overlapped,
+        )
+    };
+    if ok != 0 {
+        return Ok(());
+    }
+    let error = io::Error::last_os_error();
+    if error.raw_os_error() == Some(ERROR_LOCK_VIOLATION as i32) {
+        Err(io::Error::new(io::ErrorKind::WouldBlock, error))
+    } else {
+        Err(error)
+    }
+}
+
+/// Release a lock taken with [`lock_file`].
+#[cfg(windows)]
+pub(crate) fn unlock_file(file: &File) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::HANDLE;
+    use windows_sys::Win32::Storage::FileSystem::UnlockFileEx;
+
+    // SAFETY: see `lock_file`.
+    let ok = unsafe {
+        let mut overlapped = std::mem::zeroed();
+        UnlockFileEx(
+            file.as_raw_handle() as HANDLE,
+            0,
+            u32::MAX,
+            u32::MAX,
+            &mut overlapped,
+        )
+    };
+    if ok != 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn lock_file(_: &File, _: bool) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "file locking is not supported on this platform",
+    ))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn unlock_file(_: &File) -> io::Result<()> {
+    Ok(())
+}
+
+/// The offset of the local time zone from UTC at `time`, in seconds.
+#[cfg(unix)]
+pub(crate) fn utc_offset(time: SystemTime) -> i64 {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let Ok(secs) = libc::time_t::try_from(secs) else {
+        return 0;
+    };
+    // SAFETY: localtime_r only reads the time and writes a `tm` to the pointer.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+        return 0;
+    }
+    tm.tm_gmtoff as i64
+}
+
+/// The offset of the local time zone from UTC at `time`, in seconds.
+#[cfg(not(unix))]
+pub(crate) fn utc_offset(_: SystemTime) -> i64 {
+    0
+}
+
+/// Let the console of stdout, or of stderr if `stderr`, interpret escape sequences, returning
+/// whether it does.
+///
+/// Consoles older than Windows 10 can't, and the ones since have to be asked to with
+/// `ENABLE_VIRTUAL_TERMINAL_PROCESSING`.
+#[cfg(windows)]
+pub(crate) fn enable_virtual_terminal(stderr: bool) -> bool {
+    use windows_sys::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, SetConsoleMode, ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+        STD_ERROR_HANDLE, STD_OUTPUT_HANDLE,
+    };
+
+    let id = if stderr {
+        STD_ERROR_HANDLE
+    } else {
+        STD_OUTPUT_HANDLE
+    };
+    // SAFETY: the handle is the standard one of the process, which isn't closed, and the mode is
+    // only written through the pointer.
+    unsafe {
+        let handle = GetStdHandle(id);
+        let mut mode = 0;
+        if GetConsoleMode(handle, &mut mode) == 0 {
+            return false;
+        }
+        mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+            || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+    }
+}
+
+/// Let the terminal of stdout or stderr interpret escape sequences, which they always do outside
+/// of Windows.
+#[cfg(not(windows))]
+pub(crate) fn enable_virtual_terminal(_: bool) -> bool {
+    true
+}
+
+/// The size of the terminal attached to stdout or stderr as (columns, rows), if any.
+#[cfg(unix)]
+pub(crate) fn terminal_size() -> Option<(u16, u16)> {
+    [libc::STDOUT_FILENO, libc::STDERR_FILENO]
+        .into_iter()
+        .find_map(|fd| {
+            // SAFETY: TIOCGWINSZ only writes a `winsize` to the pointer.
+            let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+            let ok = unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut size) } == 0;
+            (ok && size.ws_col > 0).then_some((size.ws_col, size.ws_row))
+        })
+}
+
+/// The size of the terminal attached to stdout or stderr as (columns, rows), if any.
+#[cfg(not(unix))]
+pub(crate) fn terminal_size() -> Option<(u16, u16)> {
+    None
+}
@@ -0,0 +1,353 @@
+//! Forwarding of the messages to syslog, enabled with the `syslog` feature.
+//!
+//! A [`SyslogStream`] sends every message written to it as an RFC 5424 datagram, like
+//! `<12>1 2024-05-12T14:03:22Z builder carlog 4242 - - warning: unused import`, to the local
+//! syslog socket or to a UDP collector. Messages are plain text, without their escape sequences
+//! nor the spaces justifying their status. The severity comes from the [level](crate::Level) of
+//! the message: errors are `LOG_ERR`, warnings `LOG_WARNING` and the others, including the lines
+//! written without a level, `LOG_INFO`.
+//!
+//! Syslog being unreachable never fails a print: the first message that can't be sent is printed
+//! to stderr with a warning, and the next ones are dropped.
+//!
+//! ## Example
+//! ```no_run
+//! #[macro_use] extern crate carlog;
+//!
+//! use carlog::syslog::{Facility, SyslogStream};
+//!
+//! carlog::set_default_stream(SyslogStream::new(Facility::Local0, "builder").into());
+//! carlog_info!("Compiling", "carlog v0.1.0");
+//! ```
+
+use crate::time::{self, UtcDateTime};
+use crate::{fmt, sinks, sys, CargoColor, CarlogStream, Level, Status};
+use std::io::{self, Write};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+#[cfg(unix)]
+use std::path::Path;
+
+/// The facility of the messages, the kind of program sending them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Facility {
+    Kern,
+    User,
+    Mail,
+    Daemon,
+    Auth,
+    Syslog,
+    Lpr,
+    News,
+    Uucp,
+    Cron,
+    AuthPriv,
+    Ftp,
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7,
+}
+
+impl Facility {
+    /// The code of the facility, i.e. 16 for [`Local0`](Self::Local0).
+    pub fn code(self) -> u8 {
+        match self {
+            Facility::Kern => 0,
+            Facility::User => 1,
+            Facility::Mail => 2,
+            Facility::Daemon => 3,
+            Facility::Auth => 4,
+            Facility::Syslog => 5,
+            Facility::Lpr => 6,
+            Facility::News => 7,
+            Facility::Uucp => 8,
+            Facility::Cron => 9,
+            Facility::AuthPriv => 10,
+            Facility::Ftp => 11,
+            Facility::Local0 => 16,
+            Facility::Local1 => 17,
+            Facility::Local2 => 18,
+            Facility::Local3 => 19,
+            Facility::Local4 => 20,
+            Facility::Local5 => 21,
+            Facility::Local6 => 22,
+            Facility::Local7 => 23,
+        }
+    }
+}
+
+/// The socket the messages are sent through.
+enum Socket {
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+    Udp(UdpSocket),
+}
+
+impl Socket {
+    fn send(&self, datagram: &[u8]) -> io::Result<usize> {
+        match self {
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.send(datagram),
+            Socket::Udp(socket) => socket.send(datagram),
+        }
+    }
+}
+
+/// A stream writing its messages to syslog, see the [module](self).
+///
+/// It's a [`Write`], usable as a [boxed](CarlogStream::boxed) stream or as the default stream
+/// through its conversion to a [`CarlogStream`]. A message is what a write ends with a newline,
+/// so the continuation lines of a message printed at once stay with it.
+pub struct SyslogStream {
+    facility: Facility,
+    app_name: String,
+    hostname: String,
+    socket: io::Result<Socket>,
+    pending: Vec<u8>,
+    failed: bool,
+}
+
+impl SyslogStream {
+    /// Creates a stream sending to the local syslog socket, `/dev/log` or `/var/run/syslog`, or to
+    /// UDP port 514 of the local machine when there's none.
+    ///
+    /// * `facility`: The facility of the messages.
+    /// * `app_name`: The name of the program in the messages.
+    pub fn new<S>(facility: Facility, app_name: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        #[cfg(unix)]
+        for path in ["/dev/log", "/var/run/syslog"] {
+            if Path::new(path).exists() {
+                return Self::unix(facility, app_name, path);
+            }
+        }
+        Self::udp(facility, app_name, ("127.0.0.1", 514))
+    }
+
+    /// Creates a stream sending to the Unix datagram socket at `path`.
+    #[cfg(unix)]
+    pub fn unix<S, P>(facility: Facility, app_name: S, path: P) -> Self
+    where
+        S: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        let socket = UnixDatagram::unbound().and_then(|socket| {
+            socket.connect(path)?;
+            Ok(Socket::Unix(socket))
+        });
+        Self::with_socket(facility, app_name.as_ref(), socket)
+    }
+
+    /// Creates a stream sending to the UDP collector at `address`, i.e. `("logs.internal", 514)`.
+    pub fn udp<S, A>(facility: Facility, app_name: S, address: A) -> Self
+    where
+        S: AsRef<str>,
+        A: ToSocketAddrs,
+    {
+        let socket = address.to_socket_addrs().and_then(|mut addresses| {
+            let address = addresses.next().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the address resolves to nothing",
+                )
+            })?;
+            let local: SocketAddr = match address {
+                SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+                SocketAddr::V6(_) => ([0u16; 8], 0).into(),
+            };
+            let socket = UdpSocket::bind(local)?;
+            socket.connect(address)?;
+            Ok(Socket::Udp(socket))
+        });
+        Self::with_socket(facility, app_name.as_ref(), socket)
+    }
+
+    fn with_socket(facility: Facility, app_name: &str, socket: io::Result<Socket>) -> Self {
+        Self {
+            facility,
+            app_name: header_field(app_name, 48),
+            hostname: header_field(&sys::hostname().unwrap_or_default(), 255),
+            socket,
+            pending: Vec::new(),
+            failed: false,
+        }
+    }
+
+    /// Send a message, or fall back to stderr the first time it can't be.
+    fn send(&mut self, message: &str) {
+        if self.failed {
+            return;
+        }
+        let datagram = self.datagram(message);
+        let sent = match &self.socket {
+            Ok(socket) => socket.send(datagram.as_bytes()).map(drop),
+            Err(e) => Err(io::Error::new(e.kind(), e.to_string())),
+        };
+        if let Err(e) = sent {
+            self.failed = true;
+            let _ = Status::plain()
+                .color(CargoColor::BrightYellow)
+                .status("warning")
+                .print_stderr(format!(
+                    ": syslog is unreachable, the next messages are dropped: {}",
+                    e
+                ));
+            let _ = writeln!(io::stderr(), "{}", message);
+        }
+    }
+
+    /// The RFC 5424 datagram of `message`.
+    fn datagram(&self, message: &str) -> String {
+        let severity = match sinks::line_level() {
+            Level::Error => 3,
+            Level::Warning => 4,
+            Level::Trace | Level::Debug | Level::Info => 6,
+        };
+        let timestamp = UtcDateTime::from_system_time(time::now()).rfc3339();
+        format!(
+            "<{}>1 {} {} {} {} - - {}",
+            self.facility.code() * 8 + severity,
+            timestamp,
+            self.hostname,
+            self.app_name,
+            std::process::id(),
+            message
+        )
+    }
+}
+
+/// A field of the header: printable ASCII without spaces, at most `max` characters, `-` if empty.
+fn header_field(text: &str, max: usize) -> String {
+    let field: String = text
+        .chars()
+        .map(|c| match c {
+            '!'..='~' => c,
+            _ => '_',
+        })
+        .take(max)
+        .collect();
+    match field.is_empty() {
+        true => "-".to_string(),
+        false => field,
+    }
+}
+
+impl Write for SyslogStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        if let Some(end) = self.pending.iter().rposition(|&b| b == b'\n') {
+            let message: Vec<u8> = self.pending.drain(..=end).collect();
+            let message = fmt::strip_ansi(&String::from_utf8_lossy(&message[..end]));
+            self.send(message.trim_start());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl From<SyslogStream> for CarlogStream<'static> {
+    fn from(stream: SyslogStream) -> Self {
+        CarlogStream::boxed(stream)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{header_field, Facility, SyslogStream};
+    use crate::test::{force_colors, global_lock};
+    use crate::{carlog_error, carlog_info, carlog_ok, carlog_warning, set_clock, CarlogStream};
+    use std::net::UdpSocket;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn clock() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_715_522_602)
+    }
+
+    fn received(collector: &UdpSocket) -> String {
+        let mut buf = [0u8; 2048];
+        let len = collector.recv(&mut buf).unwrap();
+        String::from_utf8(buf[..len].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_messages() {
+        let _lock = global_lock();
+        force_colors();
+        set_clock(clock);
+        let collector = UdpSocket::bind("127.0.0.1:0").unwrap();
+        collector
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let address = collector.local_addr().unwrap();
+        let mut syslog = SyslogStream::udp(Facility::Local0, "car log", address);
+        carlog_ok!(
+            "Compiled",
+            "carlog v0.1.0",
+            CarlogStream::Custom(&mut syslog)
+        );
+        carlog_info!(
+            "Checking",
+            "carlog\n  at src/lib.rs",
+            CarlogStream::Custom(&mut syslog)
+        );
+        carlog_warning!("unused import", CarlogStream::Custom(&mut syslog));
+        carlog_error!("mismatched types", CarlogStream::Custom(&mut syslog));
+        set_clock(SystemTime::now);
+        let header = |pri: u8| {
+            format!(
+                "<{}>1 2024-05-12T14:03:22Z {} car_log {} - - ",
+                pri,
+                header_field(&crate::sys::hostname().unwrap_or_default(), 255),
+                std::process::id()
+            )
+        };
+        let messages: Vec<String> = (0..4).map(|_| received(&collector)).collect();
+        assert_eq!(
+            messages,
+            [
+                format!("{}Compiled carlog v0.1.0", header(134)),
+                format!("{}Checking carlog\n  at src/lib.rs", header(134)),
+                format!("{}warning: unused import", header(132)),
+                format!("{}error: mismatched types", header(131)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_header_fields() {
+        assert_eq!(header_field("carlog", 48), "carlog");
+        assert_eq!(header_field("", 48), "-");
+        assert_eq!(header_field("my app\u{e9}", 48), "my_app_");
+        assert_eq!(header_field(&"a".repeat(60), 48), "a".repeat(48));
+        assert_eq!(Facility::Kern.code(), 0);
+        assert_eq!(Facility::Local7.code(), 23);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreachable() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut stream = SyslogStream::unix(Facility::User, "carlog", dir.path().join("log"));
+        assert!(stream.socket.is_err());
+        crate::Status::new()
+            .status("Compiling")
+            .print(&mut stream, " carlog")
+            .unwrap();
+        assert!(stream.failed);
+        crate::Status::new()
+            .status("Compiling")
+            .print(&mut stream, " carlog")
+            .unwrap();
+    }
+}
@@ -0,0 +1,156 @@
+//! The colors of the message presets.
+
+use crate::CargoColor;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+static THEME: Mutex<Theme> = Mutex::new(Theme::CARGO);
+
+thread_local! {
+    static SCOPES: RefCell<Vec<Theme>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The colors of the statuses of the macros, i.e. `carlog_info!`, and of the equivalent methods
+/// of [`JobHandle`](crate::JobHandle).
+///
+/// Statuses built with [`Status::color`](crate::Status::color) keep their explicit color.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Theme {
+    /// The color of `carlog_info!`.
+    pub info: CargoColor,
+    /// The color of `carlog_ok!`.
+    pub ok: CargoColor,
+    /// The color of `carlog_warning!`.
+    pub warning: CargoColor,
+    /// The color of `carlog_error!`.
+    pub error: CargoColor,
+}
+
+impl Theme {
+    const CARGO: Self = Self {
+        info: CargoColor::Cyan,
+        ok: CargoColor::Green,
+        warning: CargoColor::BrightYellow,
+        error: CargoColor::BrightRed,
+    };
+
+    /// Every status in yellow except errors, for sections with destructive operations.
+    pub fn warning_tint() -> Self {
+        Self {
+            info: CargoColor::BrightYellow,
+            ok: CargoColor::BrightYellow,
+            ..Self::CARGO
+        }
+    }
+}
+
+impl Default for Theme {
+    /// The colors of cargo.
+    fn default() -> Self {
+        Self::CARGO
+    }
+}
+
+/// Set the theme of the whole program, see [`style_scope`] to override it temporarily.
+pub fn set_theme(theme: Theme) {
+    *THEME.lock().unwrap_or_else(|e| e.into_inner()) = theme;
+}
+
+/// The theme of the whole program, see [`set_theme`].
+pub(crate) fn global_theme() -> Theme {
+    *THEME.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The theme in effect in the current thread: the innermost [`style_scope`] or the one of
+/// [`set_theme`].
+pub fn theme() -> Theme {
+    SCOPES
+        .try_with(|scopes| scopes.borrow().last().copied())
+        .ok()
+        .flatten()
+        .unwrap_or_else(global_theme)
+}
+
+/// Override the theme in the current thread until the returned guard is dropped.
+///
+/// Scopes nest, the innermost one wins, and are restored when unwinding from a panic. Other
+/// threads keep their theme.
+///
+/// ## Example
+/// ```
+/// use carlog::Theme;
+///
+/// {
+///     let _danger = carlog::style_scope(Theme::warning_tint());
+///     assert_eq!(carlog::theme(), Theme::warning_tint());
+/// }
+/// assert_eq!(carlog::theme(), Theme::default());
+/// ```
+pub fn style_scope(theme: Theme) -> StyleGuard {
+    let depth = SCOPES.with(|scopes| {
+        let mut scopes = scopes.borrow_mut();
+        scopes.push(theme);
+        scopes.len() - 1
+    });
+    StyleGuard {
+        depth,
+        _thread: PhantomData,
+    }
+}
+
+/// Restores the previous theme when dropped, see [`style_scope`].
+#[must_use = "the theme is restored as soon as the guard is dropped"]
+pub struct StyleGuard {
+    depth: usize,
+    /// The scope belongs to the thread that created it.
+    _thread: PhantomData<*const ()>,
+}
+
+impl Drop for StyleGuard {
+    fn drop(&mut self) {
+        // Dropping an outer guard first also ends the scopes nested in it.
+        SCOPES.with(|scopes| scopes.borrow_mut().truncate(self.depth));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{style_scope, theme, Theme};
+    use crate::CargoColor;
+    use std::panic;
+    use std::thread;
+
+    #[test]
+    fn test_nested_scopes() {
+        let red = Theme {
+            info: CargoColor::BrightRed,
+            ..Theme::default()
+        };
+        let outer = style_scope(Theme::warning_tint());
+        {
+            let _inner = style_scope(red);
+            assert_eq!(theme(), red);
+            thread::spawn(|| assert_eq!(theme(), Theme::default()))
+                .join()
+                .unwrap();
+        }
+        assert_eq!(theme(), Theme::warning_tint());
+        let inner = style_scope(red);
+        drop(outer);
+        assert_eq!(theme(), Theme::default());
+        drop(inner);
+        assert_eq!(theme(), Theme::default());
+    }
+
+    #[test]
+    fn test_panic_restores() {
+        let result = panic::catch_unwind(|| {
+            let _scope = style_scope(Theme::warning_tint());
+            panic!("destructive operation failed");
+        });
+        assert!(result.is_err());
+        assert_eq!(theme(), Theme::default());
+    }
+}
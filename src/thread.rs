@@ -0,0 +1,422 @@
+//! Threads whose panics are reported as carlog errors.
+//!
+//! The panics of the threads spawned here aren't printed by the panic hook, they are reported
+//! when the thread is joined with `join_logged`, i.e.
+//! ```text
+//! error: thread 'worker' panicked at src/main.rs:4:9: index out of bounds
+//! ```
+//!
+//! The lines can also be tagged with the thread printing them, see [`show_thread_names`].
+
+use crate::{color, fmt, CargoColor, CarlogStream, Level, Status};
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::panic;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Once};
+use std::thread::{Builder, JoinHandle, Scope, ScopedJoinHandle};
+
+/// Where the panic hook stores the location of a panic of a logged thread.
+type Location = Arc<Mutex<Option<String>>>;
+
+struct Context {
+    component: String,
+    location: Location,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+static HOOK: Once = Once::new();
+static THREAD_NAMES: AtomicBool = AtomicBool::new(false);
+static THREAD_TAG_WIDTH: AtomicUsize = AtomicUsize::new(12);
+
+/// The component of the current thread, its name if spawned with [`spawn_logged`].
+pub fn component() -> Option<String> {
+    CONTEXT
+        .try_with(|context| context.borrow().as_ref().map(|c| c.component.clone()))
+        .ok()
+        .flatten()
+}
+
+/// Spawn a named thread whose panic is reported by [`LoggedHandle::join_logged`].
+///
+/// * `name`: The name of the thread, also its [`component`].
+/// * `f`: The function run by the thread.
+///
+/// ## Example
+/// ```
+/// let worker = carlog::thread::spawn_logged("worker", || 6 * 7).unwrap();
+/// assert_eq!(worker.join_logged().unwrap(), 42);
+/// ```
+pub fn spawn_logged<S, F, T>(name: S, f: F) -> io::Result<LoggedHandle<T>>
+where
+    S: AsRef<str>,
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (name, location, run) = prepare(name.as_ref(), f);
+    let handle = Builder::new().name(name.clone()).spawn(run)?;
+    Ok(LoggedHandle {
+        handle,
+        name,
+        location,
+    })
+}
+
+/// Spawn a named thread in a scope, see [`spawn_logged`].
+///
+/// ## Example
+/// ```
+/// let mut total = 0;
+/// std::thread::scope(|scope| {
+///     let worker = carlog::thread::spawn_scoped_logged(scope, "worker", || total += 1).unwrap();
+///     worker.join_logged().unwrap();
+/// });
+/// assert_eq!(total, 1);
+/// ```
+pub fn spawn_scoped_logged<'scope, 'env, S, F, T>(
+    scope: &'scope Scope<'scope, 'env>,
+    name: S,
+    f: F,
+) -> io::Result<ScopedLoggedHandle<'scope, T>>
+where
+    S: AsRef<str>,
+    F: FnOnce() -> T + Send + 'scope,
+    T: Send + 'scope,
+{
+    let (name, location, run) = prepare(name.as_ref(), f);
+    let handle = Builder::new().name(name.clone()).spawn_scoped(scope, run)?;
+    Ok(ScopedLoggedHandle {
+        handle,
+        name,
+        location,
+    })
+}
+
+/// Install the panic hook and wrap `f` to run in the context of a logged thread.
+fn prepare<F, T>(name: &str, f: F) -> (String, Location, impl FnOnce() -> T)
+where
+    F: FnOnce() -> T,
+{
+    HOOK.call_once(|| {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let logged = CONTEXT.with(|context| match &*context.borrow() {
+                Some(context) => {
+                    let location = info.location().map(|l| l.to_string());
+                    *context.location.lock().unwrap_or_else(|e| e.into_inner()) = location;
+                    true
+                }
+                None => false,
+            });
+            if !logged {
+                hook(info);
+            }
+        }));
+    });
+    let location = Location::default();
+    let context = Context {
+        component: name.to_string(),
+        location: Arc::clone(&location),
+    };
+    let run = move || {
+        CONTEXT.with(|c| *c.borrow_mut() = Some(context));
+        f()
+    };
+    (name.to_string(), location, run)
+}
+
+/// An owned permission to join a thread spawned with [`spawn_logged`].
+pub struct LoggedHandle<T> {
+    handle: JoinHandle<T>,
+    name: String,
+    location: Location,
+}
+
+impl<T> LoggedHandle<T> {
+    /// Wait for the thread to finish, printing an error to stderr if it panicked.
+    pub fn join_logged(self) -> std::thread::Result<T> {
+        // The thread may print to stderr until it finishes, so stderr is only locked afterwards.
+        let result = self.handle.join();
+        report(
+            &mut CarlogStream::Stderr,
+            &self.name,
+            &self.location,
+            result,
+        )
+    }
+
+    /// Wait for the thread to finish, printing an error to the specified stream if it panicked.
+    pub fn join_logged_to<W>(self, mut stream: W) -> std::thread::Result<T>
+    where
+        W: Write,
+    {
+        let result = self.handle.join();
+        report(
+            &mut CarlogStream::Custom(&mut stream),
+            &self.name,
+            &self.location,
+            result,
+        )
+    }
+
+    /// The underlying handle of the thread.
+    pub fn handle(&self) -> &JoinHandle<T> {
+        &self.handle
+    }
+}
+
+/// A permission to join a thread spawned with [`spawn_scoped_logged`].
+pub struct ScopedLoggedHandle<'scope, T> {
+    handle: ScopedJoinHandle<'scope, T>,
+    name: String,
+    location: Location,
+}
+
+impl<T> ScopedLoggedHandle<'_, T> {
+    /// Wait for the thread to finish, printing an error to stderr if it panicked.
+    pub fn join_logged(self) -> std::thread::Result<T> {
+        // The thread may print to stderr until it finishes, so stderr is only locked afterwards.
+        let result = self.handle.join();
+        report(
+            &mut CarlogStream::Stderr,
+            &self.name,
+            &self.location,
+            result,
+        )
+    }
+
+    /// Wait for the thread to finish, printing an error to the specified stream if it panicked.
+    pub fn join_logged_to<W>(self, mut stream: W) -> std::thread::Result<T>
+    where
+        W: Write,
+    {
+        let result = self.handle.join();
+        report(
+            &mut CarlogStream::Custom(&mut stream),
+            &self.name,
+            &self.location,
+            result,
+        )
+    }
+}
+
+fn report<T>(
+    stream: &mut CarlogStream<'_>,
+    name: &str,
+    location: &Location,
+    result: std::thread::Result<T>,
+) -> std::thread::Result<T> {
+    let payload = match result {
+        Ok(value) => return Ok(value),
+        Err(payload) => payload,
+    };
+    let mut msg = format!(": thread '{}' panicked", name);
+    if let Some(location) = &*location.lock().unwrap_or_else(|e| e.into_inner()) {
+        msg.push_str(&format!(" at {}", location));
+    }
+    msg.push_str(&format!(": {}", payload_str(&*payload)));
+    let target = crate::route::current_target();
+    crate::__record_message("error", Level::Error, target.as_deref());
+    // The panic is returned whether it could be printed or not.
+    let _ = Status::plain()
+        .color(CargoColor::BrightRed)
+        .status("error")
+        .print_stream(stream, msg);
+    Err(payload)
+}
+
+/// Tag every line with the name of the thread printing it, or its id when it has no name, in
+/// dimmed brackets before the status, i.e. `[worker-1]     Compiling carlog v0.1.0`.
+///
+/// The names are padded to the [tag width](set_thread_tag_width) so statuses stay aligned, and
+/// longer names are shortened with `…`. [`Status::thread_tag`] tags the lines of a single status.
+///
+/// ## Example
+/// ```
+/// use carlog::carlog_info;
+///
+/// carlog::show_thread_names(true);
+/// std::thread::Builder::new()
+///     .name("worker-1".to_string())
+///     .spawn(|| carlog_info!("Compiling", "carlog v0.1.0"))
+///     .unwrap()
+///     .join()
+///     .unwrap();
+/// # carlog::show_thread_names(false);
+/// ```
+pub fn show_thread_names(show: bool) {
+    THREAD_NAMES.store(show, Ordering::Relaxed);
+}
+
+/// Whether the lines are tagged with the name of their thread, see [`show_thread_names`].
+pub fn thread_names() -> bool {
+    THREAD_NAMES.load(Ordering::Relaxed)
+}
+
+/// Set the width in columns the names of the thread tags are padded or shortened to (default 12),
+/// 0 to print them whole without padding.
+pub fn set_thread_tag_width(width: usize) {
+    THREAD_TAG_WIDTH.store(width, Ordering::Relaxed);
+}
+
+/// The width of the names of the thread tags, see [`set_thread_tag_width`].
+pub fn thread_tag_width() -> usize {
+    THREAD_TAG_WIDTH.load(Ordering::Relaxed)
+}
+
+/// Write the tag of the current thread and its separator at the end of `line`.
+pub(crate) fn write_thread_tag(line: &mut String) {
+    let width = THREAD_TAG_WIDTH.load(Ordering::Relaxed);
+    let current = std::thread::current();
+    let start = line.len();
+    line.push('[');
+    let name = line.len();
+    // Writing to a string can't fail.
+    let _ = match current.name() {
+        Some(thread) => line.write_str(thread),
+        None => write!(line, "{:?}", current.id()),
+    };
+    let mut columns = fmt::display_width(&line[name..]);
+    if width > 0 && columns > width {
+        let mut kept = 0;
+        let end = line[name..]
+            .char_indices()
+            .find(|(_, c)| {
+                kept += fmt::char_width(*c);
+                kept > width - 1
+            })
+            .map_or(line.len(), |(i, _)| name + i);
+        line.truncate(end);
+        line.push('\u{2026}');
+        columns = fmt::display_width(&line[name..]);
+    }
+    line.push(']');
+    if color::enabled() {
+        line.insert_str(start, "\u{1b}[2m");
+        line.push_str("\u{1b}[0m");
+    }
+    line.extend(std::iter::repeat_n(' ', width.saturating_sub(columns) + 1));
+}
+
+/// The message of a panic payload.
+fn payload_str(payload: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        component, set_thread_tag_width, show_thread_names, spawn_logged, spawn_scoped_logged,
+    };
+    use crate::test::{auto_colors, force_colors, global_lock};
+
+    #[test]
+    fn test_join_logged() {
+        let _lock = global_lock();
+        force_colors();
+        let mut output = Vec::<u8>::new();
+        let worker = spawn_logged("worker", component).unwrap();
+        assert_eq!(worker.handle().thread().name(), Some("worker"));
+        let ok = worker.join_logged_to(&mut output).unwrap();
+        assert_eq!(ok.as_deref(), Some("worker"));
+        assert!(output.is_empty());
+
+        let line = line!() + 1;
+        let panicking = spawn_logged("panicking", || panic!("boom {}", 42)).unwrap();
+        assert!(panicking.join_logged_to(&mut output).is_err());
+        std::thread::scope(|scope| {
+            let scoped = spawn_scoped_logged(scope, "scoped", || panic!("bust")).unwrap();
+            assert!(scoped.join_logged_to(&mut output).is_err());
+        });
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            format!(
+                "\u{1b}[91merror\u{1b}[0m: thread 'panicking' panicked at {}:{}:54: boom 42",
+                file!(),
+                line
+            )
+        );
+        assert!(lines[1].starts_with("\u{1b}[91merror\u{1b}[0m: thread 'scoped' panicked at "));
+        assert!(lines[1].ends_with(": bust"));
+        assert_eq!(component(), None);
+    }
+
+    #[test]
+    fn test_join_logged_while_printing() {
+        let _lock = global_lock();
+        let worker = spawn_logged("printing", || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            crate::carlog_warning!("printed while being joined");
+        })
+        .unwrap();
+        assert!(worker.join_logged().is_ok());
+    }
+
+    #[test]
+    fn test_thread_tags() {
+        let _lock = global_lock();
+        auto_colors();
+        let status = crate::Status::plain().justify().status("Compiling");
+        let render = |name: Option<&str>, status: crate::Status| {
+            let builder = std::thread::Builder::new();
+            let builder = match name {
+                Some(name) => builder.name(name.to_string()),
+                None => builder,
+            };
+            let worker = builder.spawn(move || status.render(" carlog v0.1.0"));
+            worker.unwrap().join().unwrap()
+        };
+        assert_eq!(
+            render(Some("worker-1"), status.clone()),
+            "   Compiling carlog v0.1.0"
+        );
+        assert_eq!(
+            render(Some("worker-1"), status.clone().thread_tag()),
+            "[worker-1]        Compiling carlog v0.1.0"
+        );
+        show_thread_names(true);
+        let tagged = [
+            render(Some("worker-1"), status.clone()),
+            render(Some("a-very-long-worker"), status.clone()),
+            render(Some("ワーカー"), status.clone()),
+            render(Some("ワーカーワーカー"), status.clone()),
+        ];
+        let unnamed = render(None, status.clone());
+        set_thread_tag_width(0);
+        let whole = render(Some("a-very-long-worker"), status.clone());
+        set_thread_tag_width(12);
+        force_colors();
+        let colored = render(Some("worker-1"), status);
+        show_thread_names(false);
+        assert_eq!(
+            tagged,
+            [
+                "[worker-1]        Compiling carlog v0.1.0",
+                "[a-very-long…]    Compiling carlog v0.1.0",
+                "[ワーカー]        Compiling carlog v0.1.0",
+                "[ワーカーワ…]     Compiling carlog v0.1.0",
+            ]
+        );
+        assert!(unnamed.starts_with("[ThreadId("));
+        assert!(unnamed.ends_with("    Compiling carlog v0.1.0"));
+        assert_eq!(unnamed.chars().count(), tagged[0].chars().count());
+        assert_eq!(whole, "[a-very-long-worker]    Compiling carlog v0.1.0");
+        assert_eq!(
+            colored,
+            "\u{1b}[2m[worker-1]\u{1b}[0m        \u{1b}[37mCompiling\u{1b}[0m carlog v0.1.0"
+        );
+    }
+}
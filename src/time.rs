@@ -0,0 +1,273 @@
+//! Small calendar helpers shared by the features that need to print or compare dates, and the
+//! timestamps of the messages.
+//!
+//! The crate does not depend on a date/time library, so UTC civil dates are computed here from the
+//! unix epoch.
+
+use crate::sys;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+static TIMESTAMPS: AtomicU8 = AtomicU8::new(TimestampKind::None as u8);
+static CLOCK: RwLock<fn() -> SystemTime> = RwLock::new(SystemTime::now);
+static MONOTONIC_CLOCK: RwLock<fn() -> Instant> = RwLock::new(Instant::now);
+static TIMER: OnceLock<Instant> = OnceLock::new();
+
+/// The timestamp printed before each message, see [`enable_timestamps`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimestampKind {
+    /// No timestamp (the default), unless the status asks for one with
+    /// [`Status::timestamp`](crate::Status::timestamp).
+    #[default]
+    None,
+    /// The UTC date and time in RFC 3339, i.e. `2024-05-12T14:03:22Z`.
+    Rfc3339,
+    /// The local time of the day, i.e. `16:03:22`. The time is UTC on platforms other than unix.
+    LocalHms,
+    /// The time elapsed since [`init_timer`] in seconds, i.e. `[ 12.345s]`, like the verbose
+    /// output of some build tools. The width only grows after 999 seconds.
+    Elapsed,
+}
+
+/// Print a timestamp of `kind` before every message, dimmed and before the justified status so the
+/// statuses stay aligned.
+///
+/// ## Example
+/// ```
+/// use carlog::{carlog_info, TimestampKind};
+///
+/// carlog::enable_timestamps(TimestampKind::Rfc3339);
+/// carlog_info!("Compiling", "carlog v0.1.0");
+/// # carlog::enable_timestamps(TimestampKind::None);
+/// ```
+pub fn enable_timestamps(kind: TimestampKind) {
+    TIMESTAMPS.store(kind as u8, Ordering::Relaxed);
+}
+
+/// The timestamp printed before every message, see [`enable_timestamps`].
+pub fn timestamps() -> TimestampKind {
+    match TIMESTAMPS.load(Ordering::Relaxed) {
+        1 => TimestampKind::Rfc3339,
+        2 => TimestampKind::LocalHms,
+        3 => TimestampKind::Elapsed,
+        _ => TimestampKind::None,
+    }
+}
+
+/// Read the time of the timestamps from `clock` instead of [`SystemTime::now`], i.e. to test the
+/// printed lines.
+///
+/// ## Example
+/// ```
+/// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+///
+/// fn clock() -> SystemTime {
+///     UNIX_EPOCH + Duration::from_secs(1_715_522_602)
+/// }
+///
+/// carlog::set_clock(clock);
+/// # carlog::set_clock(SystemTime::now);
+/// ```
+pub fn set_clock(clock: fn() -> SystemTime) {
+    *CLOCK.write().unwrap_or_else(|e| e.into_inner()) = clock;
+}
+
+/// Start the timer of the [elapsed](TimestampKind::Elapsed) timestamps, at the start of the
+/// program.
+///
+/// Only the first call starts it, and the first elapsed timestamp does when it's never called.
+///
+/// ## Example
+/// ```
+/// use carlog::{carlog_info, Status};
+///
+/// carlog::init_timer();
+/// carlog_info!("Compiling", "carlog v0.1.0");
+/// Status::new().status("Finished").elapsed().print_stdout(" in 1.2s").unwrap();
+/// ```
+pub fn init_timer() {
+    timer();
+}
+
+/// Read the instants of the [elapsed](TimestampKind::Elapsed) timestamps from `clock` instead of
+/// [`Instant::now`], i.e. to test the printed lines.
+pub fn set_monotonic_clock(clock: fn() -> Instant) {
+    *MONOTONIC_CLOCK.write().unwrap_or_else(|e| e.into_inner()) = clock;
+}
+
+/// The instant the timer started at, started now if it wasn't, see [`init_timer`].
+pub(crate) fn timer() -> Instant {
+    *TIMER.get_or_init(monotonic_now)
+}
+
+/// The current time of the [clock](set_clock).
+pub(crate) fn now() -> SystemTime {
+    (*CLOCK.read().unwrap_or_else(|e| e.into_inner()))()
+}
+
+/// The current instant of the [monotonic clock](set_monotonic_clock).
+pub(crate) fn monotonic_now() -> Instant {
+    (*MONOTONIC_CLOCK.read().unwrap_or_else(|e| e.into_inner()))()
+}
+
+/// Write the timestamp of `kind` for the current time at the end of `line`, nothing for
+/// [`TimestampKind::None`].
+pub(crate) fn write_timestamp(line: &mut String, kind: TimestampKind) {
+    if kind == TimestampKind::Elapsed {
+        let start = timer();
+        let elapsed = monotonic_now().saturating_duration_since(start);
+        // Milliseconds are truncated, so the seconds never round up.
+        let _ = write!(
+            line,
+            "[{:>3}.{:03}s]",
+            elapsed.as_secs(),
+            elapsed.subsec_millis()
+        );
+        return;
+    }
+    let now = now();
+    // Writing to a string can't fail, and every field has a fixed width.
+    let _ = match kind {
+        TimestampKind::None | TimestampKind::Elapsed => Ok(()),
+        TimestampKind::Rfc3339 => {
+            let utc = UtcDateTime::from_system_time(now);
+            write!(
+                line,
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+                utc.year, utc.month, utc.day, utc.hour, utc.minute, utc.second
+            )
+        }
+        TimestampKind::LocalHms => {
+            let local = UtcDateTime::from_system_time(now).offset(sys::utc_offset(now));
+            write!(
+                line,
+                "{:02}:{:02}:{:02}",
+                local.hour, local.minute, local.second
+            )
+        }
+    };
+}
+
+/// A broken down UTC date and time.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct UtcDateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl UtcDateTime {
+    /// Convert a system time to its UTC calendar representation.
+    ///
+    /// Times before the unix epoch are clamped to the epoch.
+    pub fn from_system_time(time: SystemTime) -> Self {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0) as i64;
+        Self::from_secs(secs)
+    }
+
+    fn from_secs(secs: i64) -> Self {
+        let days = secs.div_euclid(86_400);
+        let rem = secs.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            year,
+            month,
+            day,
+            hour: (rem / 3600) as u32,
+            minute: (rem % 3600 / 60) as u32,
+            second: (rem % 60) as u32,
+        }
+    }
+
+    /// The same time `seconds` later, i.e. in another time zone.
+    pub fn offset(&self, seconds: i64) -> Self {
+        let days = days_from_civil(self.year, self.month, self.day);
+        let secs =
+            days * 86_400 + i64::from(self.hour * 3600 + self.minute * 60 + self.second) + seconds;
+        Self::from_secs(secs)
+    }
+
+    /// RFC 3339 representation, i.e. `2024-05-12T14:03:22Z`.
+    pub fn rfc3339(&self) -> String {
+        format!(
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+
+    /// Compact representation usable in file names, i.e. `20240512T140322Z`.
+    pub fn compact(&self) -> String {
+        format!(
+            "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Number of whole days elapsed since the unix epoch.
+pub(crate) fn days_since_epoch(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0) as i64
+        / 86_400
+}
+
+/// Convert days since the unix epoch to a (year, month, day) triple.
+///
+/// Algorithm from Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Convert a (year, month, day) triple to days since the unix epoch, the inverse of
+/// [`civil_from_days`].
+///
+/// Algorithm from Howard Hinnant's `days_from_civil`.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = i64::from((month + 9) % 12);
+    let doy = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod test {
+    use super::UtcDateTime;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_utc_date_time() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_715_522_602);
+        let utc = UtcDateTime::from_system_time(time);
+        assert_eq!(utc.compact(), "20240512T140322Z");
+        assert_eq!(utc.rfc3339(), "2024-05-12T14:03:22Z");
+        assert_eq!(
+            UtcDateTime::from_system_time(UNIX_EPOCH).compact(),
+            "19700101T000000Z"
+        );
+        assert_eq!(utc.offset(2 * 3600).rfc3339(), "2024-05-12T16:03:22Z");
+        assert_eq!(utc.offset(-15 * 3600).rfc3339(), "2024-05-11T23:03:22Z");
+        assert_eq!(utc.offset(0), utc);
+    }
+}
@@ -0,0 +1,322 @@
+//! Timing of build steps, with [`carlog_time!`](crate::carlog_time), a [`Stopwatch`] or, with
+//! the `macros` feature, the `#[carlog::timed]` attribute on a function.
+//!
+//! Every finished step is recorded in a process-global collector, see [`timings`], which keeps the
+//! [latest ones](set_timings_capacity). Whether a completion line is printed depends on how long the step took:
+//! * Below the [slow threshold](set_slow_threshold) nothing is printed.
+//! * Between the slow and the [warn threshold](set_warn_threshold) a `Finished` line is printed.
+//! * Above the warn threshold the step is reported with a warning.
+//!
+//! ## Example
+//! ```ignore
+//! #[macro_use] extern crate carlog;
+//!
+//! use std::time::Duration;
+//!
+//! carlog::set_slow_threshold(Duration::from_millis(100));
+//! carlog::set_warn_threshold(Duration::from_secs(30));
+//! let index = carlog_time!("build-index", build_index());
+//! ```
+
+use crate::{fmt, CargoColor, CarlogStream, Error, Status};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static SLOW_THRESHOLD: AtomicU64 = AtomicU64::new(0);
+static WARN_THRESHOLD: AtomicU64 = AtomicU64::new(u64::MAX);
+static TIMINGS: Mutex<Timings> = Mutex::new(Timings {
+    capacity: 1000,
+    entries: VecDeque::new(),
+});
+
+struct Timings {
+    capacity: usize,
+    entries: VecDeque<Timing>,
+}
+
+/// A finished step and how long it took.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Timing {
+    /// The name of the step.
+    pub name: String,
+
+    /// The time the step took.
+    pub elapsed: Duration,
+}
+
+/// Only print completion lines for steps taking at least `threshold`.
+///
+/// Faster steps are still recorded in [`timings`]. Defaults to zero, printing every step.
+pub fn set_slow_threshold(threshold: Duration) {
+    SLOW_THRESHOLD.store(as_nanos(threshold), Ordering::Relaxed);
+}
+
+/// Report steps taking longer than `threshold` with a warning, i.e. `warning: step X took 42.00s`.
+///
+/// Defaults to `Duration::MAX`, never warning.
+pub fn set_warn_threshold(threshold: Duration) {
+    WARN_THRESHOLD.store(as_nanos(threshold), Ordering::Relaxed);
+}
+
+/// The threshold of [`set_slow_threshold`].
+pub(crate) fn slow_threshold() -> Duration {
+    Duration::from_nanos(SLOW_THRESHOLD.load(Ordering::Relaxed))
+}
+
+/// The threshold of [`set_warn_threshold`].
+pub(crate) fn warn_threshold() -> Duration {
+    Duration::from_nanos(WARN_THRESHOLD.load(Ordering::Relaxed))
+}
+
+fn as_nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// The steps finished so far, in completion order.
+///
+/// Once the collector holds its [capacity](set_timings_capacity), every new step drops the
+/// oldest one.
+pub fn timings() -> Vec<Timing> {
+    let timings = TIMINGS.lock().unwrap_or_else(|e| e.into_inner());
+    timings.entries.iter().cloned().collect()
+}
+
+/// Forget every recorded step.
+pub fn clear_timings() {
+    TIMINGS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entries
+        .clear();
+}
+
+/// Keep at most `capacity` steps in [`timings`], 1000 by default.
+pub fn set_timings_capacity(capacity: usize) {
+    let mut timings = TIMINGS.lock().unwrap_or_else(|e| e.into_inner());
+    timings.capacity = capacity;
+    let excess = timings.entries.len().saturating_sub(capacity);
+    timings.entries.drain(..excess);
+}
+
+/// The number of steps kept, see [`set_timings_capacity`].
+pub(crate) fn timings_capacity() -> usize {
+    TIMINGS.lock().unwrap_or_else(|e| e.into_inner()).capacity
+}
+
+/// Record a finished step, dropping the oldest one when the collector is full.
+fn record(timing: Timing) {
+    let mut timings = TIMINGS.lock().unwrap_or_else(|e| e.into_inner());
+    if timings.capacity == 0 {
+        return;
+    }
+    if timings.entries.len() == timings.capacity {
+        timings.entries.pop_front();
+    }
+    timings.entries.push_back(timing);
+}
+
+/// Record a finished step and print its completion line according to the thresholds.
+///
+/// * `name`: The name of the step.
+/// * `elapsed`: The time the step took.
+/// * `stream`: The stream where the completion line is printed.
+pub fn report_timing<S>(
+    name: S,
+    elapsed: Duration,
+    mut stream: CarlogStream<'_>,
+) -> Result<(), Error>
+where
+    S: AsRef<str>,
+{
+    let name = name.as_ref();
+    record(Timing {
+        name: name.to_string(),
+        elapsed,
+    });
+    let nanos = as_nanos(elapsed);
+    if nanos > WARN_THRESHOLD.load(Ordering::Relaxed) {
+        let status = Status::plain()
+            .color(CargoColor::BrightYellow)
+            .status("warning");
+        let msg = format!(": step {} took {}", name, fmt::elapsed(elapsed));
+        status.print_stream(&mut stream, msg)
+    } else if nanos >= SLOW_THRESHOLD.load(Ordering::Relaxed) {
+        let status = Status::plain()
+            .bold()
+            .justify()
+            .color(CargoColor::Green)
+            .status("Finished");
+        let msg = format!(" {} in {}", name, fmt::elapsed(elapsed));
+        status.print_stream(&mut stream, msg)
+    } else {
+        Ok(())
+    }
+}
+
+/// Measures the time taken by a step.
+///
+/// ## Example
+/// ```
+/// use carlog::Stopwatch;
+///
+/// let stopwatch = Stopwatch::start("build-index");
+/// // ...
+/// stopwatch.finish().unwrap(); // "    Finished build-index in 0.00s"
+/// ```
+pub struct Stopwatch<'a> {
+    name: String,
+    start: Instant,
+    stream: CarlogStream<'a>,
+}
+
+impl<'a> Stopwatch<'a> {
+    /// Start measuring the step `name`.
+    pub fn start<S>(name: S) -> Self
+    where
+        S: AsRef<str>,
+    {
+        Self {
+            name: name.as_ref().to_string(),
+            start: Instant::now(),
+            stream: CarlogStream::default(),
+        }
+    }
+
+    /// Set the stream where the completion line is printed.
+    pub fn stream(mut self, stream: CarlogStream<'a>) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    /// The time elapsed since the stopwatch started.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Stop measuring and report the step, see [`report_timing`].
+    pub fn finish(self) -> Result<Duration, Error> {
+        let elapsed = self.elapsed();
+        report_timing(&self.name, elapsed, self.stream)?;
+        Ok(elapsed)
+    }
+}
+
+/// Reports the step of a function with the `#[carlog::timed]` attribute when its call returns.
+#[doc(hidden)]
+pub struct __Timed(Option<Stopwatch<'static>>);
+
+impl __Timed {
+    pub fn start(name: &str) -> Self {
+        Self(Some(Stopwatch::start(name)))
+    }
+}
+
+impl Drop for __Timed {
+    fn drop(&mut self) {
+        // A call that panicked didn't finish its step.
+        if let Some(stopwatch) = self.0.take().filter(|_| !std::thread::panicking()) {
+            crate::__printed(stopwatch.finish().map(drop));
+        }
+    }
+}
+
+/// Time an expression as a step, evaluating to the value of the expression.
+///
+/// ## Example
+/// ```
+/// #[macro_use] extern crate carlog;
+///
+/// use carlog::prelude::*;
+///
+/// let sum = carlog_time!("sum", (0..100).sum::<u32>());
+/// let mut output = Vec::<u8>::new();
+/// let sum = carlog_time!("sum", (0..100).sum::<u32>(), CarlogStream::Custom(&mut output));
+/// ```
+#[macro_export]
+macro_rules! carlog_time {
+    ($name:expr, $body:expr) => {
+        $crate::carlog_time!($name, $body, $crate::CarlogStream::default())
+    };
+    ($name:expr, $body:expr, $stream:expr) => {{
+        let stopwatch = $crate::Stopwatch::start($name).stream($stream);
+        let value = $body;
+        $crate::__printed(stopwatch.finish().map(drop));
+        value
+    }};
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        clear_timings, report_timing, set_slow_threshold, set_timings_capacity, set_warn_threshold,
+        timings,
+    };
+    use crate::test::{force_colors, global_lock};
+    use crate::CarlogStream;
+    use std::time::Duration;
+
+    #[test]
+    fn test_thresholds() {
+        let _lock = global_lock();
+        force_colors();
+        clear_timings();
+        set_slow_threshold(Duration::from_millis(100));
+        set_warn_threshold(Duration::from_secs(10));
+        let mut output = Vec::<u8>::new();
+        for (name, millis) in [("fast", 3), ("slow", 1500), ("stuck", 42_000)] {
+            let elapsed = Duration::from_millis(millis);
+            report_timing(name, elapsed, CarlogStream::Custom(&mut output)).unwrap();
+        }
+        set_slow_threshold(Duration::ZERO);
+        set_warn_threshold(Duration::MAX);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "    \u{1b}[1;32mFinished\u{1b}[0m slow in 1.50s\n\u{1b}[93mwarning\u{1b}[0m: step \
+             stuck took 42.00s\n"
+        );
+        let names: Vec<String> = timings().into_iter().map(|t| t.name).collect();
+        assert_eq!(names, ["fast", "slow", "stuck"]);
+        clear_timings();
+    }
+
+    #[test]
+    fn test_timings_capacity() {
+        let _lock = global_lock();
+        clear_timings();
+        set_slow_threshold(Duration::MAX);
+        for name in ["a", "b", "c"] {
+            report_timing(name, Duration::ZERO, CarlogStream::Custom(&mut Vec::new())).unwrap();
+        }
+        set_timings_capacity(2);
+        let names = || timings().into_iter().map(|t| t.name).collect::<Vec<_>>();
+        assert_eq!(names(), ["b", "c"]);
+        report_timing("d", Duration::ZERO, CarlogStream::Custom(&mut Vec::new())).unwrap();
+        assert_eq!(names(), ["c", "d"]);
+        set_timings_capacity(0);
+        report_timing("e", Duration::ZERO, CarlogStream::Custom(&mut Vec::new())).unwrap();
+        assert!(timings().is_empty());
+        set_timings_capacity(1000);
+        set_slow_threshold(Duration::ZERO);
+    }
+
+    #[test]
+    fn test_carlog_time() {
+        let _lock = global_lock();
+        force_colors();
+        clear_timings();
+        set_slow_threshold(Duration::MAX);
+        let mut output = Vec::<u8>::new();
+        let sum = carlog_time!(
+            "sum",
+            (1..=4).sum::<u32>(),
+            CarlogStream::Custom(&mut output)
+        );
+        set_slow_threshold(Duration::ZERO);
+        assert_eq!(sum, 10);
+        assert!(output.is_empty());
+        assert_eq!(timings()[0].name, "sum");
+        clear_timings();
+    }
+}
@@ -0,0 +1,187 @@
+//! The transcript of every line printed, whatever the stream it was printed to.
+
+use crate::{fmt, Target};
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+static RECORDING: AtomicBool = AtomicBool::new(false);
+static TRANSCRIPT: Mutex<Transcript> = Mutex::new(Transcript {
+    capacity: 1000,
+    next: 0,
+    entries: VecDeque::new(),
+});
+
+struct Transcript {
+    capacity: usize,
+    next: u64,
+    entries: VecDeque<TranscriptEntry>,
+}
+
+/// A line of the transcript, see [`transcript`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TranscriptEntry {
+    /// The position of the line in the transcript, counted from when the recording started.
+    pub sequence: u64,
+    /// Where the line was printed.
+    pub target: Target,
+    /// The line printed, without colors nor the newline.
+    pub line: String,
+}
+
+/// Start (`true`) or stop (`false`) recording the transcript, off by default.
+///
+/// Starting the recording clears the previous transcript. Once the transcript holds its
+/// [capacity](set_transcript_capacity), every new line drops the oldest one.
+///
+/// ## Example
+/// ```
+/// use carlog::prelude::*;
+/// use carlog::Target;
+///
+/// carlog::record_transcript(true);
+/// Status::new().status("warning").print_stderr(": unused import").unwrap();
+/// carlog::record_transcript(false);
+/// let transcript = carlog::transcript();
+/// assert_eq!(transcript[0].target, Target::Stderr);
+/// assert_eq!(transcript[0].line, "warning: unused import");
+/// ```
+pub fn record_transcript(record: bool) {
+    let mut transcript = TRANSCRIPT.lock().unwrap_or_else(|e| e.into_inner());
+    if record {
+        transcript.next = 0;
+        transcript.entries.clear();
+    }
+    RECORDING.store(record, Ordering::Relaxed);
+}
+
+/// Whether the lines printed are recorded, see [`record_transcript`].
+pub(crate) fn recording() -> bool {
+    RECORDING.load(Ordering::Relaxed)
+}
+
+/// Keep at most `capacity` lines in the transcript, 1000 by default.
+pub fn set_transcript_capacity(capacity: usize) {
+    let mut transcript = TRANSCRIPT.lock().unwrap_or_else(|e| e.into_inner());
+    transcript.capacity = capacity;
+    let excess = transcript.entries.len().saturating_sub(capacity);
+    transcript.entries.drain(..excess);
+}
+
+/// The number of lines kept, see [`set_transcript_capacity`].
+pub(crate) fn transcript_capacity() -> usize {
+    TRANSCRIPT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .capacity
+}
+
+/// The lines recorded, in the order they were printed, see [`record_transcript`].
+pub fn transcript() -> Vec<TranscriptEntry> {
+    let transcript = TRANSCRIPT.lock().unwrap_or_else(|e| e.into_inner());
+    transcript.entries.iter().cloned().collect()
+}
+
+/// Write the lines recorded to `stream`, one per line prefixed by their sequence number and
+/// target, i.e. `3 stderr: warning: unused import`.
+pub fn dump_transcript<W>(mut stream: W) -> io::Result<()>
+where
+    W: Write,
+{
+    let mut dump = String::new();
+    for entry in transcript() {
+        dump.push_str(&format!(
+            "{} {}: {}\n",
+            entry.sequence,
+            entry.target.as_str(),
+            entry.line
+        ));
+    }
+    stream.write_all(dump.as_bytes())?;
+    stream.flush()
+}
+
+/// Record a line printed to `target`, if the transcript is recorded.
+pub(crate) fn append(target: Target, line: &str) {
+    if !RECORDING.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut transcript = TRANSCRIPT.lock().unwrap_or_else(|e| e.into_inner());
+    if transcript.capacity == 0 {
+        return;
+    }
+    if transcript.entries.len() == transcript.capacity {
+        transcript.entries.pop_front();
+    }
+    let sequence = transcript.next;
+    transcript.next += 1;
+    transcript.entries.push_back(TranscriptEntry {
+        sequence,
+        target,
+        line: fmt::strip_ansi(line.strip_suffix('\n').unwrap_or(line)),
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::{dump_transcript, record_transcript, set_transcript_capacity, transcript, Target};
+    use crate::test::{force_colors, global_lock};
+    use crate::{CargoColor, CarlogStream, Status};
+
+    fn info(status: &str) -> Status {
+        Status::new()
+            .bold()
+            .justify()
+            .color(CargoColor::Cyan)
+            .status(status)
+    }
+
+    #[test]
+    fn test_transcript() {
+        let _lock = global_lock();
+        force_colors();
+        let mut file = Vec::<u8>::new();
+        record_transcript(true);
+        info("Compiling")
+            .print_stream(&mut CarlogStream::Stdout, " carlog v0.1.0")
+            .unwrap();
+        Status::warning_with_label("warning")
+            .print_stream(&mut CarlogStream::Stderr, ": unused import")
+            .unwrap();
+        Status::error_with_label("error")
+            .print_stream(&mut CarlogStream::Custom(&mut file), ": build failed")
+            .unwrap();
+        info("Finished").print_stdout(" dev").unwrap();
+        record_transcript(false);
+        info("Compiling").print_stdout(" not recorded").unwrap();
+
+        let recorded = transcript();
+        let lines: Vec<(u64, Target, &str)> = recorded
+            .iter()
+            .map(|entry| (entry.sequence, entry.target, entry.line.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                (0, Target::Stdout, "   Compiling carlog v0.1.0"),
+                (1, Target::Stderr, "warning: unused import"),
+                (2, Target::Custom, "error: build failed"),
+                (3, Target::Stdout, "    Finished dev"),
+            ]
+        );
+        assert_eq!(
+            String::from_utf8(file).unwrap(),
+            "\u{1b}[91merror\u{1b}[0m: build failed\n"
+        );
+
+        set_transcript_capacity(2);
+        let mut dump = Vec::<u8>::new();
+        dump_transcript(&mut dump).unwrap();
+        set_transcript_capacity(1000);
+        assert_eq!(
+            String::from_utf8(dump).unwrap(),
+            "2 custom: error: build failed\n3 stdout:     Finished dev\n"
+        );
+    }
+}
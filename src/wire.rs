@@ -0,0 +1,449 @@
+//! The structured form of records, one JSON object per line, read back by the replay and merge
+//! tools.
+//!
+//! Every object carries a `"format_version"` field, [`FORMAT_VERSION`] when written. The
+//! compatibility policy is:
+//! * The fields of a version never change their name nor their shape.
+//! * Optional fields may be added to a version, readers ignore the fields they don't know.
+//! * Anything else, i.e. removing a field or changing its type, is a new version. Readers reject
+//!   the lines of a version newer than theirs instead of misreading them.
+//!
+//! A line of version 1 looks like:
+//! ```json
+//! {"format_version":1,"status":"warning","message":"unused import","level":"Warning",
+//!  "color":"Yellow","location":{"path":"src/lib.rs","line":3,"column":5},
+//!  "fields":{"crate":"carlog"},"id":"unused-imports","code":"W0611",
+//!  "timestamp":{"secs_since_epoch":1700000000,"nanos_since_epoch":0},"sequence":7}
+//! ```
+//! Only `format_version`, `status`, `message` and `level` are always present.
+//!
+//! The lines of the [JSON format](crate::Format::Json) are lines of this form too, with a
+//! `timestamp` and a `color` always, plus the `bold` field of the status, `"truncated":true` when
+//! the message was [cut](crate::max_message_len) and `"lossy":true` when a
+//! [byte message](crate::Status::print_bytes) wasn't UTF-8 and had its invalid sequences replaced
+//! by `U+FFFD`. Readers of records ignore these three.
+//!
+//! Encoding and parsing need the `serde` feature.
+
+use crate::attributes::Attributes;
+use crate::{ansi, json, time, CargoColor, Format, Level, Record};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+#[cfg(feature = "serde")]
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The version of the structured form of records, see the [module](self) for the policy.
+pub const FORMAT_VERSION: u32 = 1;
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct Line {
+    format_version: u32,
+    status: String,
+    message: String,
+    level: Level,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<CargoColor>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    location: Option<Location>,
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    fields: serde_json::Map<String, serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    causes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<SystemTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sequence: Option<u64>,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct Location {
+    path: String,
+    line: u32,
+    column: u32,
+}
+
+/// The fields of a line, borrowed from a record or from a status and its message.
+#[derive(Default)]
+pub(crate) struct Fields<'a> {
+    pub status: &'a str,
+    pub message: &'a str,
+    pub level: Option<Level>,
+    pub color: Option<CargoColor>,
+    pub location: Option<(&'a str, u32, u32)>,
+    pub fields: Vec<(&'a str, &'a str)>,
+    pub id: Option<&'a str>,
+    pub code: Option<&'a str>,
+    pub causes: Vec<&'a str>,
+    pub timestamp: Option<SystemTime>,
+    pub sequence: Option<u64>,
+    pub bold: Option<bool>,
+    pub truncated: bool,
+    pub lossy: bool,
+}
+
+impl<'a> Fields<'a> {
+    /// The fields of `record`.
+    pub(crate) fn of(record: &'a Record) -> Self {
+        Self {
+            status: record.status(),
+            message: record.message(),
+            level: Some(record.severity()),
+            color: record.status_color(),
+            location: record.source_location(),
+            fields: record.fields().collect(),
+            id: record.identifier(),
+            code: record.diagnostic_code(),
+            causes: record.causes().collect(),
+            timestamp: record.time(),
+            sequence: record.sequence_number(),
+            ..Self::default()
+        }
+    }
+
+    /// Write the line, without the newline, at the end of `out`.
+    pub(crate) fn write(&self, out: &mut String) {
+        let start = out.len();
+        out.push('{');
+        json::push_key(out, start, "format_version");
+        out.push_str(&FORMAT_VERSION.to_string());
+        json::push_key(out, start, "status");
+        json::push_string(out, self.status);
+        json::push_key(out, start, "message");
+        json::push_string(out, self.message);
+        json::push_key(out, start, "level");
+        json::push_string(out, &format!("{:?}", self.level.unwrap_or(Level::Info)));
+        if let Some(color) = self.color {
+            json::push_key(out, start, "color");
+            push_color(out, color);
+        }
+        if let Some((path, line, column)) = self.location {
+            json::push_key(out, start, "location");
+            let location = out.len();
+            out.push('{');
+            json::push_key(out, location, "path");
+            json::push_string(out, path);
+            json::push_key(out, location, "line");
+            out.push_str(&line.to_string());
+            json::push_key(out, location, "column");
+            out.push_str(&column.to_string());
+            out.push('}');
+        }
+        if !self.fields.is_empty() {
+            json::push_key(out, start, "fields");
+            let fields = out.len();
+            out.push('{');
+            for (key, value) in &self.fields {
+                json::push_key(out, fields, key);
+                json::push_string(out, value);
+            }
+            out.push('}');
+        }
+        if let Some(id) = self.id {
+            json::push_key(out, start, "id");
+            json::push_string(out, id);
+        }
+        if let Some(code) = self.code {
+            json::push_key(out, start, "code");
+            json::push_string(out, code);
+        }
+        if !self.causes.is_empty() {
+            json::push_key(out, start, "causes");
+            out.push('[');
+            for (i, cause) in self.causes.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                json::push_string(out, cause);
+            }
+            out.push(']');
+        }
+        if let Some(timestamp) = self.timestamp {
+            // Like serde, as the time since the epoch, clamped to it.
+            let since = timestamp.duration_since(UNIX_EPOCH).unwrap_or_default();
+            json::push_key(out, start, "timestamp");
+            out.push_str(&format!(
+                "{{\"secs_since_epoch\":{},\"nanos_since_epoch\":{}}}",
+                since.as_secs(),
+                since.subsec_nanos()
+            ));
+        }
+        if let Some(sequence) = self.sequence {
+            json::push_key(out, start, "sequence");
+            out.push_str(&sequence.to_string());
+        }
+        if let Some(bold) = self.bold {
+            json::push_key(out, start, "bold");
+            out.push_str(if bold { "true" } else { "false" });
+        }
+        if self.truncated {
+            json::push_key(out, start, "truncated");
+            out.push_str("true");
+        }
+        if self.lossy {
+            json::push_key(out, start, "lossy");
+            out.push_str("true");
+        }
+        out.push('}');
+    }
+}
+
+/// The line of `record` printed with [`Format::Json`], with the newline.
+pub(crate) fn record_line(record: &Record) -> Option<String> {
+    if crate::format() != Format::Json {
+        return None;
+    }
+    let status = record.to_status();
+    let capped = crate::capped(record.message());
+    let message = ansi::strip(&capped);
+    let causes: Vec<Cow<'_, str>> = record.causes().map(ansi::strip).collect();
+    let mut fields = Fields::of(record);
+    fields.message = &message;
+    fields.causes = causes.iter().map(|cause| &**cause).collect();
+    fields.color = Some(status.color);
+    fields.location = fields.location.filter(|_| crate::locations());
+    fields.timestamp = fields.timestamp.or_else(|| Some(time::now()));
+    fields.bold = Some(status.attributes.contains(Attributes::BOLD));
+    fields.truncated = matches!(capped, Cow::Owned(_));
+    let mut line = String::new();
+    fields.write(&mut line);
+    line.push('\n');
+    Some(line)
+}
+
+/// Write `color` like serde, the name of its variant or the variant with its values.
+fn push_color(out: &mut String, color: CargoColor) {
+    match color {
+        CargoColor::Rgb(r, g, b) => out.push_str(&format!("{{\"Rgb\":[{},{},{}]}}", r, g, b)),
+        CargoColor::Ansi256(index) => out.push_str(&format!("{{\"Ansi256\":{}}}", index)),
+        color => json::push_string(out, &format!("{:?}", color)),
+    }
+}
+
+/// The line of a record, without the newline.
+///
+/// ## Example
+/// ```
+/// use carlog::Record;
+///
+/// let line = carlog::wire::encode(&Record::new("Compiling", "carlog v0.1.0"));
+/// assert_eq!(
+///     line,
+///     r#"{"format_version":1,"status":"Compiling","message":"carlog v0.1.0","level":"Info"}"#
+/// );
+/// ```
+#[cfg(feature = "serde")]
+pub fn encode(record: &Record) -> String {
+    let mut line = String::new();
+    Fields::of(record).write(&mut line);
+    line
+}
+
+#[cfg(feature = "serde")]
+impl Line {
+    /// The line of `record`.
+    fn of(record: &Record) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            status: record.status().to_string(),
+            message: record.message().to_string(),
+            level: record.severity(),
+            color: record.status_color(),
+            location: record
+                .source_location()
+                .map(|(path, line, column)| Location {
+                    path: path.to_string(),
+                    line,
+                    column,
+                }),
+            fields: record
+                .fields()
+                .map(|(key, value)| (key.to_string(), value.into()))
+                .collect(),
+            id: record.identifier().map(str::to_string),
+            code: record.diagnostic_code().map(str::to_string),
+            causes: record.causes().map(str::to_string).collect(),
+            timestamp: record.time(),
+            sequence: record.sequence_number(),
+        }
+    }
+
+    /// The record of the line.
+    fn into_record(self) -> Record {
+        let mut record = Record::new(self.status, self.message).level(self.level);
+        if let Some(color) = self.color {
+            record = record.color(color);
+        }
+        if let Some(location) = self.location {
+            record = record.location(location.path, location.line, location.column);
+        }
+        for (key, value) in self.fields {
+            record = match value {
+                serde_json::Value::String(value) => record.field(key, value),
+                value => record.field(key, value),
+            };
+        }
+        if let Some(id) = self.id {
+            record = record.id(id);
+        }
+        if let Some(code) = self.code {
+            record = record.code(code);
+        }
+        for cause in self.causes {
+            record = record.cause(cause);
+        }
+        if let Some(timestamp) = self.timestamp {
+            record = record.timestamp(timestamp);
+        }
+        if let Some(sequence) = self.sequence {
+            record = record.sequence(sequence);
+        }
+        record
+    }
+}
+
+/// Read back the record of a line.
+///
+/// Fails with [`io::ErrorKind::InvalidData`] if the line isn't a record, has no format version or
+/// was written by a newer version of the format, see the [module](self).
+///
+/// ## Example
+/// ```
+/// let line = r#"{"format_version":1,"status":"Compiling","message":"carlog","level":"Info"}"#;
+/// assert_eq!(carlog::wire::parse(line).unwrap().status(), "Compiling");
+/// let newer = line.replace("\"format_version\":1", "\"format_version\":2");
+/// assert!(carlog::wire::parse(&newer).is_err());
+/// ```
+#[cfg(feature = "serde")]
+pub fn parse(line: &str) -> io::Result<Record> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let value: serde_json::Value = serde_json::from_str(line).map_err(io::Error::from)?;
+    let version = value
+        .get("format_version")
+        .ok_or_else(|| invalid("the record has no `format_version`".to_string()))?
+        .as_u64()
+        .ok_or_else(|| invalid("the `format_version` isn't a number".to_string()))?;
+    if version > u64::from(FORMAT_VERSION) {
+        return Err(invalid(format!(
+            "record format version {} is newer than the supported version {}",
+            version, FORMAT_VERSION
+        )));
+    }
+    let line: Line = serde_json::from_value(value).map_err(io::Error::from)?;
+    Ok(line.into_record())
+}
+
+/// Records are serialized in their structured form, the one of [`encode`], which any serde
+/// format can hold. Like [`parse`], deserializing ignores the unknown fields and fails on the
+/// records of a newer version.
+///
+/// ## Example
+/// ```
+/// use carlog::Record;
+///
+/// let record = Record::new("Compiling", "carlog v0.1.0");
+/// let json = serde_json::to_string(&record).unwrap();
+/// assert_eq!(serde_json::from_str::<Record>(&json).unwrap(), record);
+/// ```
+#[cfg(feature = "serde")]
+impl Serialize for Record {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Line::of(self).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Record {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let line = Line::deserialize(deserializer)?;
+        if line.format_version > FORMAT_VERSION {
+            return Err(serde::de::Error::custom(format_args!(
+                "record format version {} is newer than the supported version {}",
+                line.format_version, FORMAT_VERSION
+            )));
+        }
+        Ok(line.into_record())
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod test {
+    use super::{encode, parse};
+    use crate::{CargoColor, Level, Record};
+    use std::io;
+    use std::time::{Duration, SystemTime};
+
+    /// Lines written by version 1, which must keep parsing to the same records.
+    const FIXTURE: &str = include_str!("../tests/fixtures/records-v1.jsonl");
+
+    #[test]
+    fn test_fixture() {
+        let records: Vec<Record> = FIXTURE.lines().map(|line| parse(line).unwrap()).collect();
+        let at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(
+            records,
+            [
+                Record::new("Compiling", "carlog v0.1.0"),
+                Record::new("warning", "unused import")
+                    .level(Level::Warning)
+                    .color(CargoColor::Yellow)
+                    .location("src/lib.rs", 3, 5)
+                    .field("crate", "carlog")
+                    .field("jobs", 4)
+                    .id("unused-imports")
+                    .timestamp(at)
+                    .sequence(7),
+            ]
+        );
+        // Writing the records gives the fixture back, so fields can't silently change shape.
+        let lines: Vec<String> = records.iter().map(encode).collect();
+        let fixture: Vec<&str> = FIXTURE.lines().collect();
+        assert_eq!(lines[0], fixture[0]);
+        assert_eq!(lines[1], fixture[1].replace("\"jobs\":4", "\"jobs\":\"4\""));
+    }
+
+    #[test]
+    fn test_code_and_causes() {
+        let record = Record::new("error", "mismatched types")
+            .level(Level::Error)
+            .code("E0308")
+            .cause("expected `u32`");
+        let line = encode(&record);
+        assert_eq!(
+            line,
+            r#"{"format_version":1,"status":"error","message":"mismatched types","level":"Error","code":"E0308","causes":["expected `u32`"]}"#
+        );
+        assert_eq!(parse(&line).unwrap(), record);
+    }
+
+    #[test]
+    fn test_versions() {
+        let line = encode(&Record::new("Finished", "dev"));
+        let unknown_field = line.replacen('{', "{\"host\":\"ci\",", 1);
+        assert_eq!(parse(&unknown_field).unwrap().status(), "Finished");
+        for invalid in [
+            line.replace("\"format_version\":1", "\"format_version\":2"),
+            line.replace("\"format_version\":1,", ""),
+            line.replace("\"message\":\"dev\"", "\"message\":[\"dev\"]"),
+            "Finished dev".to_string(),
+        ] {
+            assert_eq!(
+                parse(&invalid).unwrap_err().kind(),
+                io::ErrorKind::InvalidData
+            );
+        }
+    }
+}
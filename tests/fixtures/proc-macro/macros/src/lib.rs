@@ -0,0 +1,10 @@
+//! A derive macro reporting its progress and a deprecation with the macros of carlog.
+
+use proc_macro::TokenStream;
+
+#[proc_macro_derive(Checked)]
+pub fn derive_checked(_input: TokenStream) -> TokenStream {
+    carlog::carlog_info!("Expanding", "derive(Checked)");
+    carlog::carlog_warning!("derive(Checked) is deprecated, use derive(Verified)");
+    TokenStream::new()
+}
@@ -0,0 +1,4 @@
+//! A crate expanding the derive macro of the fixture.
+
+#[derive(carlog_fixture_macros::Checked)]
+pub struct Manifest;
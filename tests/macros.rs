@@ -0,0 +1,71 @@
+//! The exported macros expand outside of the crate, without anything of it in scope.
+
+#[test]
+fn test_macros_from_another_crate() {
+    carlog::color::set_override(false);
+    let mut output = Vec::<u8>::new();
+    carlog::carlog!(
+        "Packaged",
+        " carlog v0.1.0",
+        true,
+        true,
+        carlog::CargoColor::Green,
+        carlog::CarlogStream::Custom(&mut output)
+    );
+    carlog::carlog_info!(
+        "Compiling",
+        "carlog v0.1.0",
+        carlog::CarlogStream::Custom(&mut output)
+    );
+    carlog::carlog_ok!(
+        color: carlog::CargoColor::Cyan,
+        "Finished",
+        "dev",
+        carlog::CarlogStream::Custom(&mut output)
+    );
+    carlog::carlog_warning!(
+        label: "deprecated",
+        "old API",
+        carlog::CarlogStream::Custom(&mut output)
+    );
+    carlog::carlog_warning!(
+        id = "unused-key",
+        "unused key",
+        carlog::CarlogStream::Custom(&mut output)
+    );
+    carlog::carlog_error!(
+        target: "build",
+        "build failed",
+        carlog::CarlogStream::Custom(&mut output)
+    );
+    carlog::set_verbosity(1);
+    carlog::carlog_fresh!("carlog v0.1.0", carlog::CarlogStream::Custom(&mut output));
+    carlog::carlog_dirty!(
+        "colored v2.0.0",
+        "the file `src/lib.rs` has changed",
+        carlog::CarlogStream::Custom(&mut output)
+    );
+    let sum = carlog::carlog_time!(
+        "sum",
+        (0..4).sum::<u32>(),
+        carlog::CarlogStream::Custom(&mut output)
+    );
+    assert_eq!(sum, 6);
+
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        lines[..8],
+        [
+            "    Packaged carlog v0.1.0",
+            "   Compiling carlog v0.1.0",
+            "    Finished dev",
+            "deprecated: old API",
+            "warning: unused key [unused-key]",
+            "error: build failed",
+            "       Fresh carlog v0.1.0",
+            "       Dirty colored v2.0.0: the file `src/lib.rs` has changed",
+        ]
+    );
+    assert!(lines[8].starts_with("    Finished sum in "));
+}
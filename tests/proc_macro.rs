@@ -0,0 +1,40 @@
+//! The macros called from a procedural macro, with the crates of `tests/fixtures/proc-macro` built
+//! by cargo, i.e. with `cargo test --features proc-macro`.
+#![cfg(feature = "proc-macro")]
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn cargo(args: &[&str]) -> Output {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc-macro");
+    Command::new(env!("CARGO"))
+        .args(args)
+        .arg("--offline")
+        .current_dir(fixture)
+        .env(
+            "CARGO_TARGET_DIR",
+            Path::new(env!("CARGO_TARGET_TMPDIR")).join("proc-macro"),
+        )
+        // Without `--cfg carlog_nightly` the messages fall back to stderr, like on stable.
+        .env_remove("RUSTFLAGS")
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_stderr_fallback() {
+    // The derive only runs when the crate using it is compiled.
+    cargo(&["clean", "-p", "carlog-fixture-user"]);
+    let build = cargo(&["build", "-p", "carlog-fixture-user"]);
+    let stderr = String::from_utf8_lossy(&build.stderr);
+    assert!(build.status.success(), "{}", stderr);
+    assert!(
+        stderr
+            .lines()
+            .any(|line| line == "warning: derive(Checked) is deprecated, use derive(Verified)"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("Expanding"), "{}", stderr);
+}
@@ -0,0 +1,50 @@
+//! The macros compiled out by the `release_max_level_*` features, i.e. with
+//! `cargo test --release --features release_max_level_warn`.
+
+use carlog::{CarlogStream, Level, STATIC_MAX_LEVEL};
+use std::cell::Cell;
+
+#[test]
+fn test_static_max_level() {
+    carlog::color::set_override(false);
+    carlog::set_max_level(Level::Trace);
+    let evaluated = Cell::new(0);
+    let message = |text: &'static str| {
+        evaluated.set(evaluated.get() + 1);
+        text
+    };
+    let mut output = Vec::<u8>::new();
+    carlog::carlog_debug!(
+        "Resolving",
+        message("debug"),
+        CarlogStream::Custom(&mut output)
+    );
+    carlog::carlog_info!(
+        "Compiling",
+        message("info"),
+        CarlogStream::Custom(&mut output)
+    );
+    carlog::carlog_ok!("Finished", message("ok"), CarlogStream::Custom(&mut output));
+    carlog::carlog_warning!(message("warning"), CarlogStream::Custom(&mut output));
+    carlog::carlog_error!(message("error"), CarlogStream::Custom(&mut output));
+
+    let compiled = [
+        Level::Debug,
+        Level::Info,
+        Level::Info,
+        Level::Warning,
+        Level::Error,
+    ]
+    .into_iter()
+    .filter(|level| STATIC_MAX_LEVEL.is_some_and(|max| *level >= max))
+    .count();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(output.lines().count(), compiled);
+    assert_eq!(evaluated.get(), compiled);
+    if cfg!(debug_assertions) {
+        assert_eq!(STATIC_MAX_LEVEL, Some(Level::Trace));
+    }
+    if STATIC_MAX_LEVEL == Some(Level::Warning) {
+        assert_eq!(output, "warning: warning\nerror: error\n");
+    }
+}
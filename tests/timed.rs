@@ -0,0 +1,75 @@
+//! The `#[carlog::timed]` attribute, i.e. with `cargo test --features macros`.
+#![cfg(feature = "macros")]
+
+use carlog::{CarlogStream, Stopwatch};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[carlog::timed]
+fn index(crates: &[&str]) -> usize {
+    crates.len()
+}
+
+#[carlog::timed("fetch-crates")]
+fn fetch(name: &str) -> io::Result<&str> {
+    #![allow(clippy::needless_question_mark)]
+    if name.is_empty() {
+        return Err(io::Error::other("no crate"));
+    }
+    Ok(name.strip_prefix("carlog").ok_or(io::ErrorKind::NotFound)?)
+}
+
+struct Registry;
+
+impl Registry {
+    #[carlog::timed]
+    async fn r#resolve(&self) -> u32 {
+        7
+    }
+}
+
+fn names() -> Vec<String> {
+    carlog::timings()
+        .into_iter()
+        .map(|timing| timing.name)
+        .collect()
+}
+
+#[test]
+fn test_timed() {
+    carlog::color::set_override(false);
+    let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+    carlog::set_default_stream(CarlogStream::from(Arc::clone(&output)));
+    carlog::clear_timings();
+
+    assert_eq!(index(&["carlog", "colored"]), 2);
+    assert_eq!(fetch("carlog-macros").unwrap(), "-macros");
+    assert!(fetch("").is_err());
+    assert!(fetch("colored").is_err());
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    assert_eq!(runtime.block_on(Registry.resolve()), 7);
+    assert_eq!(
+        names(),
+        [
+            "index",
+            "fetch-crates",
+            "fetch-crates",
+            "fetch-crates",
+            "resolve"
+        ]
+    );
+
+    let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+    assert_eq!(output.lines().count(), 5);
+    assert!(output.starts_with("    Finished index in "));
+
+    // Like the other timing facilities, fast steps are recorded without a line.
+    carlog::set_slow_threshold(Duration::from_secs(60));
+    Stopwatch::start("manual").finish().unwrap();
+    index(&[]);
+    carlog::set_slow_threshold(Duration::ZERO);
+    assert_eq!(names()[5..], ["manual", "index"]);
+}